@@ -0,0 +1,182 @@
+use crate::engine::{Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::stats::Stats;
+use crate::GameEvent;
+use serde::Deserialize;
+
+// The bundled achievements, see `achievements.toml`.
+const ACHIEVEMENTS: &str = include_str!("../achievements.toml");
+// Two flaps closer together than this are flapping twice in a row.
+const DOUBLE_FLAP_TICKS: i32 = 15;
+
+// What it takes to unlock an achievement.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    // Points in one run.
+    Score(i32),
+    // Points in one run without ever flapping twice in a row.
+    ScoreWithoutDoubleFlaps(i32),
+    // Pipes passed over every run.
+    Pipes(u64),
+    Runs(u64),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Achievement {
+    // Kept in the stats once unlocked, so it mustn't change.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub goal: Goal,
+}
+
+impl Achievement {
+    // The name and description in the player's language, under the keys
+    // `achievement_<id>` and `achievement_<id>_about`.
+    fn label(&self) -> &str {
+        lang::lookup(&format!("achievement_{}", self.id)).unwrap_or(&self.name)
+    }
+
+    fn about(&self) -> &str {
+        lang::lookup(&format!("achievement_{}_about", self.id)).unwrap_or(&self.description)
+    }
+}
+
+#[derive(Deserialize)]
+struct List {
+    achievement: Vec<Achievement>,
+}
+
+fn parse(list: &str) -> Vec<Achievement> {
+    toml::from_str::<List>(list)
+        .map(|list| list.achievement)
+        .unwrap_or_else(|e| {
+            println!("can't read the achievements: {}", e);
+            Vec::new()
+        })
+}
+
+// Watches a run's events for achievements, unlocking each one for good in
+// the stats.
+pub struct Achievements {
+    list: Vec<Achievement>,
+    // The tick of the run's last flap, and whether two came in a row yet.
+    last_flap: Option<i32>,
+    double_flapped: bool,
+}
+
+impl Achievements {
+    pub fn new() -> Self {
+        Achievements {
+            list: parse(ACHIEVEMENTS),
+            last_flap: None,
+            double_flapped: false,
+        }
+    }
+
+    pub fn start_run(&mut self) {
+        self.last_flap = None;
+        self.double_flapped = false;
+    }
+
+    // Takes in an event of the run, at tick `frame` and with the run at
+    // `score`. Returns the names of the achievements it unlocked.
+    pub fn event(
+        &mut self,
+        event: &GameEvent,
+        frame: i32,
+        score: i32,
+        stats: &mut Stats,
+    ) -> Vec<String> {
+        if let GameEvent::Flapped = event {
+            if self
+                .last_flap
+                .is_some_and(|last| frame - last < DOUBLE_FLAP_TICKS)
+            {
+                self.double_flapped = true;
+            }
+            self.last_flap = Some(frame);
+        }
+        self.check(score, stats)
+    }
+
+    // Unlocks whatever the run at `score` and the lifetime stats have met
+    // and wasn't yet. Returns their names.
+    pub fn check(&self, score: i32, stats: &mut Stats) -> Vec<String> {
+        let mut unlocked = Vec::new();
+        for achievement in &self.list {
+            if stats.achievements.contains(&achievement.id) {
+                continue;
+            }
+            let met = match achievement.goal {
+                Goal::Score(points) => score >= points,
+                Goal::ScoreWithoutDoubleFlaps(points) => score >= points && !self.double_flapped,
+                Goal::Pipes(pipes) => stats.lifetime.pipes >= pipes,
+                Goal::Runs(runs) => stats.lifetime.runs >= runs,
+            };
+            if met {
+                stats.achievements.push(achievement.id.clone());
+                unlocked.push(achievement.label().to_string());
+            }
+        }
+        unlocked
+    }
+
+    // Every achievement in a panel filling `area`, the unlocked ones lit.
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, stats: &Stats, area: Rect) {
+        cmds.push(DrawCmd::Rect {
+            rect: area,
+            color: Color::new(0.1, 0.15, 0.25, 0.92),
+        });
+        let unlocked = |a: &&Achievement| stats.achievements.contains(&a.id);
+        cmds.push(DrawCmd::text(
+            lang::fill(
+                "achievements",
+                &[&self.list.iter().filter(unlocked).count(), &self.list.len()],
+            ),
+            Vec2::new(area.x + 20.0, area.y + 14.0),
+            24.0,
+            Color::new(0.9, 0.95, 1.0, 1.0),
+        ));
+        for (n, achievement) in self.list.iter().enumerate() {
+            let color = if unlocked(&achievement) {
+                Color::new(1.0, 0.9, 0.2, 1.0)
+            } else {
+                Color::new(0.6, 0.6, 0.65, 1.0)
+            };
+            cmds.push(DrawCmd::text(
+                format!("{}: {}", achievement.label(), achievement.about()),
+                Vec2::new(area.x + 20.0, area.y + 54.0 + 26.0 * n as f32),
+                16.0,
+                color,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flapping_twice_in_a_row_keeps_the_steady_one_locked() {
+        assert!(parse(ACHIEVEMENTS).len() >= 3);
+        let mut achievements = Achievements::new();
+        let mut stats = Stats::default();
+
+        let first = achievements.event(&GameEvent::Flapped, 10, 1, &mut stats);
+        assert_eq!(first, ["First flight"]);
+        // Unlocked ones stay unlocked without coming up again.
+        assert!(achievements.check(1, &mut stats).is_empty());
+
+        achievements.event(&GameEvent::Flapped, 20, 9, &mut stats);
+        assert!(achievements.check(10, &mut stats).is_empty());
+
+        achievements.start_run();
+        achievements.event(&GameEvent::Flapped, 40, 0, &mut stats);
+        achievements.event(&GameEvent::Flapped, 40 + DOUBLE_FLAP_TICKS, 9, &mut stats);
+        assert_eq!(achievements.check(10, &mut stats), ["Steady wings"]);
+        assert_eq!(stats.achievements, ["first_flight", "steady_wings"]);
+    }
+}
@@ -0,0 +1,3218 @@
+// The game itself: the window, the event loop and the command line.
+
+use crate::achievements::Achievements;
+use crate::assets;
+use crate::audio::Audio;
+use crate::best_run::{self, BestRuns, GhostPlayback, Recording};
+use crate::branding::Branding;
+use crate::caves::Caves;
+use crate::collectibles::{self, Collectibles};
+use crate::config::{self, Config};
+use crate::controller::{self, ControllerWatch, Screen};
+use crate::customize::{self, Customizer, Looks};
+use crate::dev;
+use crate::digits::Digits;
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2, Viewport};
+use crate::hazards::{self, Hazards};
+use crate::hotseat::HotSeat;
+use crate::hud::{Hud, HudSystem, ScorePopReader};
+use crate::inspector::Inspector;
+#[cfg(feature = "instant-replay")]
+use crate::instant_replay::InstantReplay;
+use crate::interpolate::Interpolation;
+use crate::lang;
+use crate::latency::LatencyProbe;
+use crate::leaderboard::Leaderboard;
+use crate::level::{self, Level};
+use crate::markers::DeathMarkers;
+use crate::medals::ResultsPanel;
+use crate::parallax;
+use crate::pipes::PipeSprites;
+use crate::policy::{self, AutopilotSystem, Observation, RulePolicy};
+use crate::profile::{self, Profile};
+use crate::profiler::{Profiler, Scope};
+use crate::replay_file::{InputLog, InputReplay, ReplayFile};
+use crate::rules::{self, RuleBook, Rules, RulesBuilder};
+use crate::schedule;
+use crate::seeds::{self, Choice, SeedBrowser};
+use crate::settings::{InputMap, Settings, SettingsScreen};
+use crate::shadow::{self, ShadowRace};
+use crate::share::{self, Card};
+use crate::skins::Skins;
+use crate::spacing::Spacing;
+use crate::speedrun::{self, Speedrun, SplitEvent};
+use crate::stats::{self, Session, Stats};
+use crate::streamer::{self, StreamOutput};
+use crate::tas::{self, TasPlayback, TasScript};
+use crate::telemetry::Telemetry;
+use crate::theme::{self, ThemeCycle};
+use crate::timeline::Timeline;
+use crate::tips::{self, Trail};
+use crate::tournament::{self, Replay, Run, TournamentPlay};
+use crate::tuning::{Preset, Tuning};
+use crate::wellbeing::SessionTime;
+use crate::whats_new::{self, WhatsNew};
+use crate::*;
+use ggez::event::{self, Axis, Button, GamepadId};
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::*;
+use specs::*;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::path;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Speeds `-` and `=` step through in practice.
+const PRACTICE_SPEEDS: [f32; 6] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0];
+
+struct State {
+    specs_world: World,
+    player_input: Direction,
+    // Every system of a playing tick, see `schedule::playing`.
+    playing: Dispatcher<'static, 'static>,
+    scroll_system: ScrollSystem,
+    animation_system: AnimationSystem,
+    dying_system: DyingSystem,
+    camera_system: CameraSystem,
+    particle_system: ParticleSystem,
+    idle_bob_system: IdleBobSystem,
+    hud_system: HudSystem,
+    stats: Stats,
+    show_stats: bool,
+    title: String,
+    // Draws what a bot policy would see, for debugging policies.
+    show_observation: bool,
+    inspector: Inspector,
+    // Frame timings, shown with the F3 overlay.
+    profiler: Profiler,
+    latency: LatencyProbe,
+    viewport: Viewport,
+    fullscreen: bool,
+    low_power: bool,
+    // When the last update started, to cap the frame rate in low power mode.
+    last_update: Instant,
+    // Set when the screen may have changed since it was last drawn, which
+    // low power mode waits for.
+    redraw: bool,
+    interpolation: Interpolation,
+    death_markers: Option<DeathMarkers>,
+    level: Option<Level>,
+    tournament: Option<TournamentPlay>,
+    hot_seat: Option<HotSeat>,
+    looks: Looks,
+    customizer: Customizer,
+    seeds: SeedBrowser,
+    whats_new: WhatsNew,
+    rule_book: RuleBook,
+    rules: RulesBuilder,
+    // An imported profile waiting for the player to merge or replace.
+    pending_profile: Option<Profile>,
+    // How the last profile export or import went, shown on the title screen.
+    profile_message: Option<String>,
+    // The bird's animation frames as loaded, and painted in the current
+    // player's look.
+    bird_sheet: SpriteSheet,
+    skins: Skins,
+    settings_screen: SettingsScreen,
+    painted_sheet: SpriteSheet,
+    // Plays instead of the player in demo mode and in the attract mode.
+    // The scenery and pipe theme, and when it changes.
+    themes: ThemeCycle,
+    demo: Option<AutopilotSystem>,
+    shadow: Option<ShadowRace>,
+    // The seekable timeline of a TAS script being watched.
+    timeline: Option<Timeline>,
+    // Only there with an endpoint in `config.toml`.
+    leaderboard: Option<Leaderboard>,
+    // Set while the menu shows off a bot run after sitting idle. Any input
+    // goes back to the menu, and so does the bot dying.
+    attract: bool,
+    // Ticks spent on the menu since the last input.
+    menu_idle: u32,
+    controllers: ControllerWatch,
+    // Controller only: no cursor, mouse input ignored and each screen shows
+    // the buttons it takes.
+    gamepad_only: bool,
+    // Keeps the score file up to date in streamer mode, which also keeps
+    // names and paths off the screen.
+    streamer: Option<StreamOutput>,
+    // None when no audio device could be opened.
+    audio: Option<Audio>,
+    // Where the sounds are up to in `GameEvents`, when there's audio.
+    sound_reader: Option<ReaderId<GameEvent>>,
+    // Where the lifetime stats are up to in `GameEvents`.
+    stats_reader: ReaderId<GameEvent>,
+    achievements: Achievements,
+    // Practice speed, applied as the time scale while not paused.
+    speed: f32,
+    // What kind of run this is, for per-mode best scores: "endless",
+    // "seeded", "marathon", "level", "tournament", "hot-seat", "tas" or
+    // "custom-" and the custom game's name, plus "-drift" in drift mode and
+    // "-flock" in flock mode.
+    mode: String,
+    // Set once the run is slowed below normal speed; its score then counts
+    // in the mode's "-assisted" variant.
+    assisted: bool,
+    // The development keys are on, see `dev`.
+    dev: bool,
+    #[cfg(feature = "livesplit")]
+    livesplit: Option<livesplit::LiveSplit>,
+    // Frames left of the white flash after a hit.
+    flash: u32,
+    #[cfg(feature = "hot-reload")]
+    tuning_watch: Option<tuning::TuningWatch>,
+    // With `--dev`, the assets and config to reload when they change.
+    #[cfg(feature = "hot-reload")]
+    asset_watch: Option<hot_reload::AssetWatch>,
+    // Rating and tip shown on the game over screen.
+    run_summary: Vec<String>,
+    // This sitting's runs, summed up when quitting and kept in the stats.
+    session: Session,
+    // Set while the session summary waits for Escape to quit.
+    quitting: bool,
+    // The game over screen's medal, score and best score.
+    results: ResultsPanel,
+    // The score while playing.
+    digits: Digits,
+    // The finished run's results card, saved on the next update.
+    card: Option<Card>,
+    // Runs without a seed of their own get a fresh one each, so any run can
+    // be flown again with `--seed`.
+    reseed: bool,
+    best_runs: BestRuns,
+    // Set by F12, to save the frame on screen before the next is drawn.
+    screenshot: bool,
+    // A line confirming something happened, shown for a moment at the
+    // bottom of the screen, and when it went up.
+    toast: Option<(String, Instant)>,
+    #[cfg(feature = "instant-replay")]
+    instant_replay: InstantReplay,
+}
+
+const FLASH_FRAMES: u32 = 12;
+// How long a toast stays up, fading out over the last half second.
+const TOAST_TIME: Duration = Duration::from_millis(2500);
+const TOAST_FADE: f32 = 0.5;
+// Idle time on the menu before the attract mode starts.
+const ATTRACT_TICKS: u32 = 8 * PHYSICS_FPS;
+
+const LOW_POWER_FRAME: Duration = Duration::from_nanos(1_000_000_000 / 30);
+const MAX_CATCH_UP_TICKS: u32 = 5;
+// Tells the second racer's bird apart from the first's.
+const SECOND_RACER_TINT: Color = Color::new(1.0, 0.6, 0.6, 1.0);
+
+impl State {
+    fn play_sounds(&mut self, ctx: &Context) {
+        let (audio, reader) = match (&mut self.audio, &mut self.sound_reader) {
+            (Some(audio), Some(reader)) => (audio, reader),
+            _ => return,
+        };
+
+        let events = self.specs_world.read_resource::<GameEvents>();
+        for event in events.read(reader) {
+            if let Err(e) = audio.play(ctx, event) {
+                println!("can't play sound: {}", e);
+            }
+        }
+    }
+
+    // Adds this tick's flaps and pipes to the lifetime stats, saved with
+    // the rest at the end of the run, and shows a toast for any achievement
+    // they unlock, which assisted runs never do.
+    fn count_events(&mut self) {
+        let events = self.specs_world.read_resource::<GameEvents>();
+        let game = self.specs_world.read_resource::<Game>();
+        let mut unlocked = Vec::new();
+        for event in events.read(&mut self.stats_reader) {
+            if self.attract {
+                continue;
+            }
+            match event {
+                GameEvent::Flapped => self.stats.lifetime.flaps += 1,
+                GameEvent::Passed { .. } => self.stats.lifetime.pipes += 1,
+                _ => (),
+            }
+            if !self.assisted {
+                unlocked.extend(self.achievements.event(
+                    event,
+                    game.frames,
+                    game.score,
+                    &mut self.stats,
+                ));
+            }
+        }
+        if let Some(toast) = unlocked_toast(&unlocked) {
+            self.toast = Some(toast);
+        }
+    }
+
+    // Hands the speedrun clock's events to external timers, if any.
+    fn forward_split_events(&mut self) {
+        let mut speedrun = match self.specs_world.try_fetch_mut::<Speedrun>() {
+            Some(speedrun) => speedrun,
+            None => return,
+        };
+
+        let events = speedrun.events.drain(..);
+        #[cfg(feature = "livesplit")]
+        {
+            if let Some(livesplit) = &self.livesplit {
+                events.for_each(|event| livesplit.send(event));
+            }
+        }
+        #[cfg(not(feature = "livesplit"))]
+        drop(events);
+    }
+
+    // Keeps the frames on screen while a run is under way, and saves the
+    // last few seconds of them once it's over.
+    #[cfg(feature = "instant-replay")]
+    fn record_instant_replay(&mut self, ctx: &Context, before: GameState) {
+        if self.attract {
+            return;
+        }
+        match *self.specs_world.read_resource::<GameState>() {
+            GameState::Playing | GameState::Dying => self.instant_replay.capture(ctx),
+            GameState::GameOver if before != GameState::GameOver => {
+                let path = self.instant_replay.save(ctx);
+                let message = lang::fill("instant_replay_saved", &[&path.display()]);
+                self.run_summary.push(self.private(message, &path));
+            }
+            _ => (),
+        }
+    }
+
+    fn mode(&self) -> String {
+        let mut mode = self.mode.clone();
+        if let Some(suffix) = self.specs_world.read_resource::<GameMode>().suffix() {
+            mode.push('-');
+            mode.push_str(suffix);
+        }
+        if self.assisted {
+            mode.push_str("-assisted");
+        }
+        mode
+    }
+
+    // Loads the best score for the current mode into the game, and tells
+    // the HUD whether the run is assisted.
+    fn sync_best(&mut self) {
+        let best = self.stats.best_score(&self.mode()).unwrap_or(0);
+        let mut game = self.specs_world.write_resource::<Game>();
+        game.best = best.max(game.score);
+        self.specs_world.write_resource::<Hud>().assisted = self.assisted;
+    }
+
+    // Puts the world back at the start of a new run, keeping the scenery,
+    // the sprites and every mode the game was started with.
+    fn restart(&mut self, ctx: &Context) {
+        if !self.begin_attempt() {
+            return;
+        }
+        let world = &mut self.specs_world;
+        {
+            let entities = world.entities();
+            let obstacles = world.read_storage::<ObstacleTag>();
+            let particles = world.read_storage::<Particle>();
+            let items = world.read_storage::<Collectible>();
+            for (entity, _) in (&entities, &obstacles).join() {
+                entities.delete(entity).unwrap();
+            }
+            for (entity, _) in (&entities, &items).join() {
+                entities.delete(entity).unwrap();
+            }
+            for (entity, _) in (&entities, &particles).join() {
+                entities.delete(entity).unwrap();
+            }
+        }
+        world.maintain();
+
+        let tuning = (*world.read_resource::<Tuning>()).clone();
+        spawn_obstacles(world, &tuning);
+        place_birds(world);
+        #[cfg(feature = "instant-replay")]
+        self.instant_replay.clear();
+        self.achievements.start_run();
+
+        let ready = self.ready_state();
+        let world = &mut self.specs_world;
+        world.insert(Game::new());
+        world.insert(ready);
+        world.insert(GetReady::default());
+        world.insert(Direction::new());
+        world.insert(Caves::default());
+        world.insert(Spacing::default());
+        world.insert(Hazards::default());
+        world.insert(Trail::default());
+        let seed = if self.reseed {
+            Some(rand::random())
+        } else {
+            world.try_fetch::<Challenge>().map(|c| c.seed)
+        };
+        if let Some(seed) = seed {
+            world.insert(Challenge::new(seed));
+        }
+        if let Some(mut ghost) = world.try_fetch_mut::<Ghost>() {
+            *ghost = Ghost::default();
+        }
+        if let Some(mut telegraph) = world.try_fetch_mut::<Telegraph>() {
+            *telegraph = Telegraph::default();
+        }
+        if let Some(mut practice) = world.try_fetch_mut::<Practice>() {
+            *practice = Practice::default();
+        }
+        if let Some(mut race) = world.try_fetch_mut::<Race>() {
+            *race = Race::default();
+        }
+        if let (true, Some(seed)) = (world.has_value::<Collectibles>(), seed) {
+            world.insert(Collectibles::new(seed));
+        }
+        if let Some(mut playback) = world.try_fetch_mut::<TasPlayback>() {
+            playback.rewind();
+        }
+        if let Some(mut replay) = world.try_fetch_mut::<InputReplay>() {
+            replay.rewind();
+        }
+        if let (Some(mut log), Some(seed)) = (world.try_fetch_mut::<InputLog>(), seed) {
+            log.begin(seed);
+        }
+        if let Some(mut replay) = world.try_fetch_mut::<Replay>() {
+            replay.flaps.clear();
+        }
+        if let Some(mut speedrun) = world.try_fetch_mut::<Speedrun>() {
+            let best_splits = self.stats.best_splits(speedrun.category);
+            speedrun.reset(best_splits);
+        }
+        world.write_resource::<Telemetry>().begin_run(&run_label());
+
+        if let Some(level) = &mut self.level {
+            level.reset();
+        }
+        if let Some(hot_seat) = &mut self.hot_seat {
+            hot_seat.next_turn();
+        }
+        if let (Some(markers), Some(seed)) = (&mut self.death_markers, seed) {
+            *markers = DeathMarkers::new(ctx, &self.stats, seed);
+        }
+        self.assisted = world.has_value::<Ghost>()
+            || world.has_value::<Telegraph>()
+            || world.has_value::<Practice>()
+            || self.dev;
+        self.player_input = Direction::new();
+        self.show_stats = false;
+        self.flash = 0;
+        if let Some(mut camera) = world.try_fetch_mut::<Camera>() {
+            *camera = Camera::default();
+        }
+        if let Some(shadow) = &mut self.shadow {
+            shadow.restart(world);
+        }
+        if let Some(timeline) = &mut self.timeline {
+            timeline.restart(world);
+        }
+        if let Some(leaderboard) = &mut self.leaderboard {
+            leaderboard.entering = false;
+            leaderboard.submitted = false;
+        }
+        if let Some(index) = self.themes.start_run() {
+            theme::show(world, index);
+        }
+        self.run_summary.clear();
+        self.sync_best();
+        self.dress_bird(ctx);
+        self.load_ghost();
+    }
+
+    // Clears the recording for the run about to start and brings out the
+    // ghost of the mode's best run, if there is one.
+    fn load_ghost(&mut self) {
+        if let Some(mut recording) = self.specs_world.try_fetch_mut::<Recording>() {
+            *recording = Recording::default();
+        }
+        let best = self.best_runs.get(&self.mode()).cloned();
+        if let Some(mut playback) = self.specs_world.try_fetch_mut::<GhostPlayback>() {
+            *playback = GhostPlayback(best);
+        }
+    }
+
+    // Only a plain menu shows off bot runs: tournament attempts, hot seat
+    // turns and speedrun timers belong to the players.
+    fn can_attract(&self) -> bool {
+        self.screen() == Screen::Game(GameState::Menu)
+            && self.tournament.is_none()
+            && self.hot_seat.is_none()
+            && !self.specs_world.has_value::<Speedrun>()
+    }
+
+    // Back to the menu from the attract mode, on a fresh world.
+    fn end_attract(&mut self, ctx: &Context) {
+        self.attract = false;
+        self.demo = None;
+        self.menu_idle = 0;
+        self.restart(ctx);
+        *self.specs_world.write_resource::<GameState>() = GameState::Menu;
+    }
+
+    // Runs start on the get ready screen, unless nobody needs to get ready:
+    // bots and TAS scripts start playing right away.
+    fn ready_state(&self) -> GameState {
+        if self.demo.is_some() || self.replaying() {
+            GameState::Playing
+        } else {
+            GameState::GetReady
+        }
+    }
+
+    // Uses up a tournament attempt for the run about to start. False if
+    // there's none left, and the run mustn't start.
+    fn begin_attempt(&mut self) -> bool {
+        self.tournament
+            .as_mut()
+            .is_none_or(TournamentPlay::begin_attempt)
+    }
+
+    // Space, a click or the A button: starts a run from the menu, respawns
+    // or starts over after dying, and flaps otherwise. A flap needs the
+    // button let go of since the last one. On the get ready screen the
+    // first flap after the countdown starts the run.
+    fn press_flap(&mut self, ctx: &Context) {
+        let state = *self.specs_world.read_resource::<GameState>();
+        match state {
+            GameState::Menu => {
+                if self.begin_attempt() {
+                    let ready = self.ready_state();
+                    *self.specs_world.write_resource::<GameState>() = ready;
+                }
+            }
+            GameState::GetReady => {
+                if self
+                    .specs_world
+                    .read_resource::<GetReady>()
+                    .countdown()
+                    .is_none()
+                {
+                    *self.specs_world.write_resource::<GameState>() = GameState::Playing;
+                    self.player_input.jump = true;
+                    self.player_input.release = false;
+                }
+            }
+            // No flapping on the way down.
+            GameState::Dying => (),
+            GameState::GameOver => {
+                let respawned = match &self.level {
+                    Some(level) => level.respawn(&mut self.specs_world),
+                    None => false,
+                };
+                if respawned {
+                    self.player_input = Direction::new();
+                    self.sync_best();
+                } else {
+                    self.restart(ctx);
+                }
+            }
+            _ => {
+                self.player_input.jump = true;
+                self.player_input.release = false;
+            }
+        }
+    }
+
+    // The second racer's flap. It starts the run like Space once the
+    // countdown is over, but only flaps the second bird.
+    fn press_second_flap(&mut self, ctx: &Context) {
+        let state = *self.specs_world.read_resource::<GameState>();
+        match state {
+            GameState::GetReady | GameState::Playing => {
+                if state == GameState::GetReady {
+                    let ready = self.specs_world.read_resource::<GetReady>();
+                    if ready.countdown().is_some() {
+                        return;
+                    }
+                }
+                *self.specs_world.write_resource::<GameState>() = GameState::Playing;
+                let mut race = self.specs_world.write_resource::<Race>();
+                race.second.jump = true;
+                race.second.release = false;
+            }
+            GameState::Paused => (),
+            _ => self.press_flap(ctx),
+        }
+    }
+
+    // Runs with a seed of the player's choosing, where the seed browser is
+    // offered: modes with a fixed seed of their own keep to it.
+    fn can_pick_seed(&self) -> bool {
+        ["endless", "seeded", "hot-seat", "race"]
+            .iter()
+            .any(|mode| self.mode.starts_with(mode))
+    }
+
+    // Runs the game mode can be picked for on the title screen.
+    fn can_pick_mode(&self) -> bool {
+        ["endless", "seeded", "custom-", "hot-seat"]
+            .iter()
+            .any(|mode| self.mode.starts_with(mode))
+    }
+
+    // Runs custom games can be played in instead. Flocks need the gaps
+    // they were made wider for.
+    fn can_customize(&self) -> bool {
+        ["endless", "seeded", "custom-"]
+            .iter()
+            .any(|mode| self.mode.starts_with(mode))
+            && !self.mode.contains("-flock")
+    }
+
+    // Starts a run with custom game `rules`, kept to on every run after.
+    fn play_rules(&mut self, ctx: &Context, rules: &Rules) {
+        self.rules.open = false;
+        self.mode = custom_mode(&self.mode, rules);
+        let mut tuning = (*self.specs_world.read_resource::<Tuning>()).clone();
+        rules.apply(&mut tuning);
+        apply_tuning(&mut self.specs_world, tuning);
+        rules.insert(&mut self.specs_world);
+        self.restart(ctx);
+    }
+
+    // Starts a run on `seed`, picked in the seed browser, and keeps to it
+    // on every run after like `--seed` does.
+    fn play_seed(&mut self, ctx: &Context, seed: u64) {
+        self.seeds.open = false;
+        self.reseed = false;
+        if self.mode.starts_with("endless") {
+            self.mode = self.mode.replacen("endless", "seeded", 1);
+        }
+        self.specs_world.insert(Challenge::new(seed));
+        self.restart(ctx);
+    }
+
+    // Saves the whole profile to one file to carry to another machine.
+    fn export_profile(&mut self, ctx: &Context) {
+        let path = profile::profile_path(ctx);
+        let message = match profile::export(&path, &self.stats, &self.looks) {
+            Ok(()) => lang::fill("profile_exported", &[&path.display()]),
+            Err(e) => e,
+        };
+        self.profile_message = Some(self.private(message, &path));
+    }
+
+    // The latency test takes over the keyboard, so a run is paused under
+    // it.
+    fn toggle_latency_probe(&mut self) {
+        self.latency.open = !self.latency.open;
+        let mut state = self.specs_world.write_resource::<GameState>();
+        if self.latency.open && *state == GameState::Playing {
+            *state = GameState::Paused;
+        }
+    }
+
+    // A message about a file as shown on screen, without the directory in
+    // streamer mode.
+    fn private(&self, text: String, path: &path::Path) -> String {
+        match self.streamer {
+            Some(_) => streamer::hide_dir(&text, path),
+            None => text,
+        }
+    }
+
+    // Reads a profile file, then asks whether to merge it into this one or
+    // to replace this one with it.
+    fn import_profile(&mut self, ctx: &Context) {
+        let path = profile::profile_path(ctx);
+        match profile::import(&path) {
+            Ok(imported) => {
+                self.pending_profile = Some(imported);
+                self.profile_message = None;
+            }
+            Err(e) => self.profile_message = Some(self.private(e, &path)),
+        }
+    }
+
+    fn answer_import(&mut self, ctx: &Context, key: Option<KeyCode>) {
+        let merge = match key {
+            Some(KeyCode::M) => true,
+            Some(KeyCode::R) => false,
+            Some(KeyCode::Escape) => {
+                self.pending_profile = None;
+                self.profile_message = Some(lang::tr("import_cancelled").to_string());
+                return;
+            }
+            _ => return,
+        };
+        let imported = match self.pending_profile.take() {
+            Some(imported) => imported,
+            None => return,
+        };
+
+        if merge {
+            self.stats.merge(imported.stats);
+            self.looks.merge(imported.looks);
+        } else {
+            self.stats.replace(imported.stats);
+            self.looks.replace(imported.looks);
+        }
+        self.stats.save();
+        self.looks.save();
+        self.sync_best();
+        self.skins.select(self.looks.skin.as_deref());
+        self.wear_skin(ctx);
+        self.profile_message = Some(
+            if merge {
+                lang::tr("profile_merged")
+            } else {
+                lang::tr("profile_replaced")
+            }
+            .to_string(),
+        );
+    }
+
+    fn bindings(&self) -> InputMap {
+        self.specs_world
+            .read_resource::<Settings>()
+            .bindings
+            .clone()
+    }
+
+    // Whose bird is flying: the hot seat player whose turn it is, or None
+    // for the profile's own bird.
+    fn look_owner(&self) -> Option<&str> {
+        self.hot_seat.as_ref().map(HotSeat::current)
+    }
+
+    // Swaps in the art of the skin picked, then dresses the bird in it.
+    fn wear_skin(&mut self, ctx: &Context) {
+        let skin = self.skins.current();
+        self.bird_sheet = skin.bird.clone();
+        let pipes = PipeSprites::from_art(skin.cap.clone(), skin.body.clone());
+        self.specs_world.insert(pipes);
+        self.dress_bird(ctx);
+    }
+
+    // Paints the bird in the current player's look.
+    fn dress_bird(&mut self, ctx: &Context) {
+        let look = self.looks.get(self.look_owner());
+        match look.paint(ctx, &self.bird_sheet) {
+            Ok(sheet) => self.painted_sheet = sheet,
+            Err(e) => {
+                println!("can't paint the bird: {}", e);
+                self.painted_sheet = self.bird_sheet.clone();
+            }
+        }
+        let mut sheets = self.specs_world.write_storage::<SpriteSheet>();
+        for sheet in (&mut sheets).join() {
+            let current_frame = sheet.current_frame;
+            *sheet = self.painted_sheet.clone();
+            sheet.current_frame = current_frame;
+        }
+    }
+
+    fn draw_menu<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>, ticks: u32) {
+        let center_x = engine::screen_width() / 2.0;
+        let look = self.looks.get(self.look_owner());
+        let frames = self.painted_sheet.frames();
+        let frame = &frames[(ticks / 4) as usize % frames.len()];
+        if self.customizer.open {
+            let owner = self.hot_seat.as_ref().map(HotSeat::current_label);
+            self.customizer
+                .draw(cmds, &look, owner.as_deref(), frame, ticks);
+            return;
+        }
+        if self.seeds.open {
+            self.seeds.draw(cmds);
+            return;
+        }
+        if self.whats_new.open {
+            self.whats_new.draw(cmds);
+            return;
+        }
+        if self.rules.open {
+            self.rules.draw(cmds);
+            return;
+        }
+        if self.show_stats {
+            let width = engine::screen_width();
+            if engine::narrow() {
+                self.stats.lifetime.draw(cmds, Vec2::new(center_x, 140.0));
+                self.achievements.draw(
+                    cmds,
+                    &self.stats,
+                    Rect::new(10.0, 280.0, width - 20.0, 190.0),
+                );
+                stats::draw_score_history(
+                    cmds,
+                    &self.stats,
+                    Rect::new(20.0, 500.0, width - 40.0, 80.0),
+                );
+            } else {
+                self.stats.lifetime.draw(cmds, Vec2::new(280.0, 250.0));
+                self.achievements
+                    .draw(cmds, &self.stats, Rect::new(560.0, 120.0, 420.0, 260.0));
+                stats::draw_score_history(cmds, &self.stats, Rect::new(312.0, 440.0, 400.0, 130.0));
+            }
+            return;
+        }
+        if self.settings_screen.open {
+            let settings = self.specs_world.read_resource::<Settings>();
+            self.settings_screen.draw(cmds, &settings);
+            return;
+        }
+
+        cmds.push(DrawCmd::centered_text(
+            self.title.as_str(),
+            Vec2::new(center_x, 200.0),
+            110.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+        if let Some(imported) = &self.pending_profile {
+            let lines = vec![
+                lang::fill("import_prompt", &[&imported.stats.scores.len()]),
+                lang::tr("import_choices").to_string(),
+                lang::tr("import_cancel").to_string(),
+            ];
+            for (n, line) in lines.into_iter().enumerate() {
+                cmds.push(DrawCmd::centered_text(
+                    line,
+                    Vec2::new(center_x, 360.0 + 40.0 * n as f32),
+                    26.0,
+                    Color::new(1.0, 1.0, 1.0, 1.0),
+                ));
+            }
+            return;
+        }
+
+        let prompt: Cow<str> = match &self.hot_seat {
+            Some(hot_seat) => lang::fill("hot_seat_play", &[&hot_seat.current_label()]).into(),
+            None => lang::tr("play").into(),
+        };
+        cmds.push(DrawCmd::centered_text(
+            prompt,
+            Vec2::new(center_x, 360.0),
+            30.0,
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        ));
+        if self.can_pick_mode() {
+            let game_mode = *self.specs_world.read_resource::<GameMode>();
+            cmds.push(DrawCmd::centered_text(
+                lang::fill("pick_mode", &[&game_mode.label()]),
+                Vec2::new(center_x, 392.0),
+                18.0,
+                Color::new(0.8, 0.8, 0.8, 1.0),
+            ));
+        }
+        customize::draw_preview(cmds, &look, frame, Vec2::new(center_x, 430.0), ticks);
+        if let Some(leaderboard) = &self.leaderboard {
+            leaderboard.draw_top(cmds);
+        }
+        let hint = match (self.can_pick_seed(), self.can_customize()) {
+            (true, true) => lang::tr("menu_hint_custom"),
+            (true, false) => lang::tr("menu_hint_seeds"),
+            _ => lang::tr("menu_hint"),
+        };
+        cmds.push(DrawCmd::centered_text(
+            hint,
+            Vec2::new(center_x, 490.0),
+            18.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+        if !self.skins.is_empty() {
+            cmds.push(DrawCmd::centered_text(
+                lang::fill("skin", &[&self.skins.current().name]),
+                Vec2::new(center_x, 510.0),
+                16.0,
+                Color::new(0.8, 0.8, 0.8, 1.0),
+            ));
+        }
+        if let Some(message) = &self.profile_message {
+            cmds.push(DrawCmd::centered_text(
+                message.as_str(),
+                Vec2::new(center_x, 530.0),
+                18.0,
+                Color::new(1.0, 0.9, 0.2, 1.0),
+            ));
+        }
+        cmds.push(DrawCmd::centered_text(
+            lang::fill("menu_footer", &[&whats_new::VERSION]),
+            Vec2::new(center_x, 575.0),
+            16.0,
+            Color::new(0.7, 0.7, 0.7, 1.0),
+        ));
+    }
+
+    fn draw_game_over<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>) {
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("game_over"),
+            Vec2::new(engine::screen_width() / 2.0, 110.0),
+            120.0,
+            Color::new(1.0, 0.0, 0.0, 1.0),
+        ));
+        let game = self.specs_world.read_resource::<Game>();
+        self.results.draw(
+            cmds,
+            Vec2::new(engine::screen_width() / 2.0, 280.0),
+            game.score,
+            game.best,
+        );
+        for (n, line) in self.run_summary.iter().enumerate() {
+            cmds.push(DrawCmd::centered_text(
+                line.as_str(),
+                Vec2::new(engine::screen_width() / 2.0, 400.0 + 30.0 * n as f32),
+                20.0,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+        }
+
+        let prompt: Cow<str> = if self.level.as_ref().is_some_and(|level| level.can_respawn()) {
+            lang::tr("restart_level").into()
+        } else if self
+            .tournament
+            .as_ref()
+            .is_some_and(|t| t.attempts_left() == 0)
+        {
+            lang::tr("no_attempts").into()
+        } else if let Some(hot_seat) = &self.hot_seat {
+            lang::fill("pass_to", &[&hot_seat.current_label()]).into()
+        } else if self.can_submit() {
+            lang::tr("play_again_or_submit").into()
+        } else {
+            lang::tr("play_again").into()
+        };
+        cmds.push(DrawCmd::centered_text(
+            prompt,
+            Vec2::new(engine::screen_width() / 2.0, 560.0),
+            20.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+        if let Some(leaderboard) = self.leaderboard.as_ref().filter(|l| l.entering) {
+            leaderboard.draw_entry(cmds);
+        }
+    }
+
+    // Picks up changed art and config with `--dev`. The tuning is only
+    // replaced when it came from the config, and the scenery starts over.
+    #[cfg(feature = "hot-reload")]
+    fn hot_reload(&mut self, ctx: &mut Context) {
+        let (changes, dir) = match &mut self.asset_watch {
+            Some(watch) => (watch.poll(), watch.dir.clone()),
+            None => return,
+        };
+        if changes == hot_reload::Changes::default() {
+            return;
+        }
+        let config = Config::load(path::Path::new(CONFIG_FILE));
+        if changes.config && self.tuning_watch.is_none() && !self.mode.starts_with("custom-") {
+            let mut tuning = config.tuning.clone();
+            self.specs_world
+                .read_resource::<Preset>()
+                .apply(&mut tuning);
+            if self.mode.contains("-flock") {
+                widen_for_flock(&mut tuning);
+            }
+            apply_tuning(&mut self.specs_world, tuning);
+            println!("reloaded the tuning from {}", CONFIG_FILE);
+        }
+
+        // The config has the scenery's layers.
+        let extra = Sprites::extra_images(&config.parallax);
+        let loaded = match assets::Assets::load(ctx, assets::manifest(&dir), &extra) {
+            Ok(loaded) => loaded,
+            Err(problems) => {
+                println!("keeping the current art: {}", problems.join(", "));
+                return;
+            }
+        };
+        let sprites = Sprites::load(&loaded, &config.parallax);
+        self.skins = Skins::scan(ctx, &sprites.bird, &sprites.pipes);
+        self.skins.select(self.looks.skin.as_deref());
+        self.wear_skin(ctx);
+        world_setup::replace_scenery(&mut self.specs_world, &sprites);
+        theme::show(&self.specs_world, self.themes.current());
+        self.digits = Digits::new(ctx);
+        self.digits
+            .resize(self.specs_world.read_resource::<Settings>().hud_scale);
+        self.interpolation.remember(&self.specs_world);
+        println!("reloaded the assets in {}", dir.display());
+    }
+
+    // Runs a single tick of a paused run, for `--dev`.
+    fn step_paused(&mut self) {
+        *self.specs_world.write_resource::<GameState>() = GameState::Playing;
+        self.interpolation.remember(&self.specs_world);
+        self.tick();
+        let mut state = self.specs_world.write_resource::<GameState>();
+        if *state == GameState::Playing {
+            *state = GameState::Paused;
+        }
+    }
+
+    // One physics tick, run PHYSICS_FPS times a second.
+    fn tick(&mut self) {
+        let state = *self.specs_world.read_resource::<GameState>();
+        let paused = state == GameState::Paused || self.controllers.is_lost();
+        if let Some(mut session) = self.specs_world.try_fetch_mut::<SessionTime>() {
+            session.tick(state == GameState::Playing && !paused && !self.attract);
+        }
+        if state == GameState::Playing && !paused && !self.attract {
+            self.session.ticks += 1;
+        }
+        let slow_motion = self
+            .specs_world
+            .try_fetch::<Collectibles>()
+            .map_or(1.0, |collectibles| collectibles.time_scale());
+        let time = TimeScale(if paused {
+            0.0
+        } else {
+            self.speed * slow_motion
+        });
+        self.specs_world.insert(time);
+        // The world stands still from the crash on, and the bird falls alone.
+        let scroll = match state {
+            GameState::Dying | GameState::GameOver => 0.0,
+            _ => 1.0,
+        };
+        self.specs_world.insert(ScrollSpeed(scroll));
+
+        match state {
+            GameState::Menu => {
+                self.scroll_system.run_now(&self.specs_world);
+                self.menu_idle += 1;
+                if self.menu_idle >= ATTRACT_TICKS && self.can_attract() {
+                    self.attract = true;
+                    self.demo = Some(AutopilotSystem::new(Box::new(RulePolicy)));
+                    *self.specs_world.write_resource::<GameState>() = GameState::Playing;
+                }
+                return;
+            }
+            GameState::GetReady => {
+                self.idle_bob_system.run_now(&self.specs_world);
+                self.animation_system.run_now(&self.specs_world);
+                return;
+            }
+            GameState::Paused => return,
+            GameState::Dying => {
+                self.dying_system.run_now(&self.specs_world);
+                self.camera_system.run_now(&self.specs_world);
+                self.particle_system.run_now(&self.specs_world);
+                self.specs_world.maintain();
+                return;
+            }
+            GameState::GameOver => {
+                self.camera_system.run_now(&self.specs_world);
+                self.particle_system.run_now(&self.specs_world);
+                self.specs_world.maintain();
+                return;
+            }
+            GameState::Playing => (),
+        }
+
+        if !paused {
+            self.specs_world.write_resource::<Game>().frames += 1;
+            if let Some(mut ghost) = self.specs_world.try_fetch_mut::<Ghost>() {
+                ghost.freeze = ghost.freeze.saturating_sub(1);
+            }
+            if let Some(mut collectibles) = self.specs_world.try_fetch_mut::<Collectibles>() {
+                collectibles.tick();
+            }
+        }
+        if self.speed < 1.0 && !self.assisted {
+            self.assisted = true;
+            self.sync_best();
+        }
+        if let (false, Some(autopilot)) = (paused, &mut self.demo) {
+            autopilot.run_now(&self.specs_world);
+        }
+
+        // The systems charge their own time to the profiler.
+        self.playing.dispatch(&self.specs_world);
+        let mut lap = Instant::now();
+        if let (false, Some(shadow)) = (paused, &mut self.shadow) {
+            shadow.step(time);
+        }
+        self.profiler.lap(Scope::Systems, &mut lap);
+
+        self.specs_world.maintain();
+        self.profiler.lap(Scope::Spawn, &mut lap);
+        self.count_events();
+        let score = self.specs_world.read_resource::<Game>().score;
+        if let Some(index) = self.themes.update(score) {
+            theme::show(&self.specs_world, index);
+        }
+        if let Some(level) = &mut self.level {
+            level.update(&self.specs_world);
+        }
+
+        let over = *self.specs_world.read_resource::<GameState>() == GameState::GameOver;
+        // A crash drops the bird to the floor before the results, other
+        // endings go straight to them.
+        if over && self.specs_world.read_resource::<Game>().death.is_some() {
+            *self.specs_world.write_resource::<GameState>() = GameState::Dying;
+            self.specs_world.insert(DeathFall::default());
+        }
+        let game = self.specs_world.read_resource::<Game>();
+        let ghost_hit = self
+            .specs_world
+            .try_fetch::<Ghost>()
+            .is_some_and(|ghost| ghost.freeze == GHOST_FREEZE_TICKS);
+        if (ghost_hit || over) && !self.low_power {
+            self.flash = FLASH_FRAMES;
+        }
+        // Attract runs aren't the player's, so nothing about them is kept.
+        if self.attract {
+            return;
+        }
+        if over {
+            self.session.record_run(game.score);
+            self.stats.record_run(game.score, game.frames as u64);
+            if !self.assisted {
+                let unlocked = self.achievements.check(game.score, &mut self.stats);
+                if let Some(toast) = unlocked_toast(&unlocked) {
+                    self.toast = Some(toast);
+                }
+            }
+            self.stats.record_best_score(&self.mode(), game.score);
+            self.run_summary = vec![tips::rating(game.score, &self.stats.scores)];
+            let game_mode = *self.specs_world.read_resource::<GameMode>();
+            if let Some(result) = game_mode.result(&game) {
+                self.run_summary.push(result);
+            }
+            if let Some(shadow) = &self.shadow {
+                self.run_summary.push(shadow.result(game.score));
+            }
+            if let Some(race) = self.specs_world.try_fetch::<Race>() {
+                self.run_summary.push(race.result(game.score));
+            }
+            if let Some(hot_seat) = &mut self.hot_seat {
+                hot_seat.record(game.score);
+            }
+            if let Some(log) = self.specs_world.try_fetch::<InputLog>() {
+                match log.file.save(&log.path) {
+                    Ok(()) => self
+                        .run_summary
+                        .push(lang::fill("replay_saved", &[&log.path.display()])),
+                    Err(e) => println!("{}", e),
+                }
+            }
+            if let Some(tournament) = &mut self.tournament {
+                tournament.finish(Run {
+                    score: game.score,
+                    frames: game.frames,
+                    flaps: self.specs_world.read_resource::<Replay>().flaps.clone(),
+                });
+            }
+            if let Some(death) = game.death {
+                self.stats.record_death(death);
+                let trail = self.specs_world.read_resource::<Trail>();
+                self.run_summary.push(tips::tip(&death, &game, &trail));
+            }
+            if let Some(challenge) = self.specs_world.try_fetch::<Challenge>() {
+                self.stats.record_seed(challenge.seed);
+                let seed = challenge.seed;
+                self.run_summary
+                    .push(lang::fill("seed_again", &[&seed, &seed]));
+            }
+            self.stats.save();
+            if let Some(mut recording) = self.specs_world.try_fetch_mut::<Recording>() {
+                let mut run = std::mem::take(&mut recording.0);
+                run.score = game.score;
+                if self.best_runs.record(&self.mode(), run) {
+                    self.best_runs.save();
+                }
+            }
+            self.card = Some(Card {
+                title: self.title.clone(),
+                score: game.score,
+                best: self.stats.best_score(&self.mode()).unwrap_or(game.score),
+                medal: self.results.medal(game.score),
+                mode: self.mode(),
+                seed: self.specs_world.try_fetch::<Challenge>().map(|c| c.seed),
+                look: self.looks.get(self.look_owner()),
+            });
+        }
+        if let Some(mut speedrun) = self.specs_world.try_fetch_mut::<Speedrun>() {
+            if over {
+                self.stats
+                    .record_splits(speedrun.category, &speedrun.splits);
+                if speedrun.finished && speedrun.target.is_some() {
+                    self.stats.record_marathon(speedrun.ticks);
+                } else {
+                    speedrun.events.push(SplitEvent::Reset);
+                }
+                self.stats.save();
+            }
+        }
+    }
+
+    fn shaking(&self) -> bool {
+        self.specs_world
+            .try_fetch::<Camera>()
+            .is_some_and(|camera| camera.shake > 0)
+    }
+
+    fn screen(&self) -> Screen {
+        let state = *self.specs_world.read_resource::<GameState>();
+        if state == GameState::Menu && self.customizer.open {
+            Screen::Customizer
+        } else if state == GameState::Menu && self.pending_profile.is_some() {
+            Screen::ImportPrompt
+        } else if state == GameState::Menu && self.seeds.open {
+            Screen::Seeds
+        } else if state == GameState::Menu && self.whats_new.open {
+            Screen::WhatsNew
+        } else if state == GameState::Menu && self.rules.open {
+            Screen::CustomGame
+        } else if state == GameState::Menu && self.settings_screen.open {
+            Screen::Settings
+        } else if self.quitting {
+            Screen::SessionSummary
+        } else if state == GameState::GameOver
+            && self.leaderboard.as_ref().is_some_and(|l| l.entering)
+        {
+            Screen::NameEntry
+        } else if self.break_reminder(state) {
+            Screen::BreakReminder
+        } else {
+            Screen::Game(state)
+        }
+    }
+
+    // Bound buttons stand in for their action's key in the game, leaving the
+    // overlays to the usual buttons. In a race the controller is the second
+    // player's, so its flap stands in for Up rather than Space while a run
+    // is on.
+    fn race_button_key(&self, btn: Button) -> Option<KeyCode> {
+        let screen = self.screen();
+        let key = match screen {
+            Screen::Game(_) => self
+                .bindings()
+                .button(btn)
+                .or_else(|| controller::button_key(btn, screen)),
+            _ => controller::button_key(btn, screen),
+        };
+        let racing = matches!(
+            screen,
+            Screen::Game(GameState::GetReady) | Screen::Game(GameState::Playing)
+        );
+        if racing && key == Some(KeyCode::Space) && self.specs_world.has_value::<Race>() {
+            return Some(KeyCode::Up);
+        }
+        key
+    }
+
+    // Whether the run on the game over screen can go on the leaderboard:
+    // once, and only when the player flew it unassisted.
+    fn can_submit(&self) -> bool {
+        *self.specs_world.read_resource::<GameState>() == GameState::GameOver
+            && self.leaderboard.as_ref().is_some_and(|l| !l.submitted)
+            && !self.assisted
+            && self.demo.is_none()
+            && !self.replaying()
+    }
+
+    // Whether the run plays back recorded input, from a TAS script or a
+    // replay file.
+    fn replaying(&self) -> bool {
+        self.specs_world.has_value::<TasPlayback>() || self.specs_world.has_value::<InputReplay>()
+    }
+
+    fn press_settings_key(&mut self, key: Option<KeyCode>) {
+        let key = match key {
+            Some(key) => key,
+            None => return,
+        };
+        let mut settings = self.specs_world.write_resource::<Settings>();
+        if !self.settings_screen.capturing && matches!(key, KeyCode::O | KeyCode::Escape) {
+            self.settings_screen.open = false;
+            settings.save();
+            return;
+        }
+        if self.settings_screen.press(&mut settings, key) {
+            if let Some(audio) = &mut self.audio {
+                audio.set_volume(settings.volume);
+            }
+            self.digits.resize(settings.hud_scale);
+        }
+    }
+
+    fn press_name_key(&mut self, key: Option<KeyCode>) {
+        let score = self.specs_world.read_resource::<Game>().score;
+        let mode = self.mode();
+        let leaderboard = match &mut self.leaderboard {
+            Some(leaderboard) => leaderboard,
+            None => return,
+        };
+        match key {
+            Some(KeyCode::Return) | Some(KeyCode::NumpadEnter)
+                if leaderboard.submit(score, &mode) =>
+            {
+                self.run_summary
+                    .push(lang::fill("score_sent", &[&leaderboard.name()]));
+            }
+            Some(KeyCode::Back) => leaderboard.delete_char(),
+            Some(KeyCode::Escape) => leaderboard.entering = false,
+            _ => (),
+        }
+    }
+
+    // Whether the break reminder is up over `state`'s screen.
+    fn break_reminder(&self, state: GameState) -> bool {
+        self.specs_world
+            .try_fetch::<SessionTime>()
+            .is_some_and(|session| session.reminding(state))
+    }
+
+    // Puts the break reminder away, if it's up. Returns whether it was.
+    fn dismiss_break_reminder(&mut self) -> bool {
+        let state = *self.specs_world.read_resource::<GameState>();
+        if !self.break_reminder(state) {
+            return false;
+        }
+        self.specs_world.write_resource::<SessionTime>().dismiss();
+        true
+    }
+
+    fn press_key(&mut self, ctx: &mut Context, key: Option<KeyCode>, repeat: bool) -> GameResult {
+        self.redraw = true;
+        self.menu_idle = 0;
+        if self.attract {
+            self.end_attract(ctx);
+            return Ok(());
+        }
+        if key == Some(KeyCode::F8) && !repeat {
+            self.toggle_latency_probe();
+            return Ok(());
+        }
+        if key == Some(KeyCode::F12) {
+            self.screenshot = !repeat;
+            return Ok(());
+        }
+        if self.latency.open {
+            if !repeat {
+                self.latency.press(Instant::now());
+            }
+            return Ok(());
+        }
+        if self.quitting {
+            if !repeat {
+                match key {
+                    Some(KeyCode::Escape) => ctx.request_quit(),
+                    _ => self.quitting = false,
+                }
+            }
+            return Ok(());
+        }
+        // Any key carries on, without also starting the next run.
+        if !repeat && self.dismiss_break_reminder() {
+            return Ok(());
+        }
+        let state = *self.specs_world.read_resource::<GameState>();
+        if self.settings_screen.open && state == GameState::Menu {
+            self.press_settings_key(key);
+            return Ok(());
+        }
+        if self.customizer.open && state == GameState::Menu {
+            let key = match key {
+                Some(KeyCode::Up) => customize::Key::Up,
+                Some(KeyCode::Down) => customize::Key::Down,
+                Some(KeyCode::Left) => customize::Key::Left,
+                Some(KeyCode::Right) => customize::Key::Right,
+                Some(KeyCode::C) | Some(KeyCode::Escape) => {
+                    self.customizer.open = false;
+                    self.looks.save();
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            };
+            let owner = self.hot_seat.as_ref().map(HotSeat::current);
+            if self.customizer.press(&mut self.looks, owner, key) {
+                self.dress_bird(ctx);
+            }
+            return Ok(());
+        }
+
+        if self.seeds.open && state == GameState::Menu {
+            let key = match key {
+                Some(KeyCode::Up) => seeds::Key::Up,
+                Some(KeyCode::Down) => seeds::Key::Down,
+                Some(KeyCode::Space) | Some(KeyCode::Return) => seeds::Key::Play,
+                Some(KeyCode::B) => seeds::Key::Bookmark,
+                Some(KeyCode::X) => seeds::Key::Share,
+                Some(KeyCode::S) | Some(KeyCode::Escape) => {
+                    self.seeds.open = false;
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            };
+            let tuning = (*self.specs_world.read_resource::<Tuning>()).clone();
+            match self.seeds.press(&mut self.stats, &tuning, key) {
+                Some(Choice::Play(seed)) => self.play_seed(ctx, seed),
+                Some(Choice::Share(seed)) => {
+                    let message = match share::share_seed(ctx, seed) {
+                        Ok(path) => {
+                            let text = lang::fill("seed_saved", &[&seed, &path.display()]);
+                            self.private(text, &path)
+                        }
+                        Err(e) => lang::fill("seed_failed", &[&e]),
+                    };
+                    self.seeds.message = Some(message);
+                }
+                None => (),
+            }
+            return Ok(());
+        }
+
+        if self.whats_new.open && state == GameState::Menu {
+            if let Some(KeyCode::Space)
+            | Some(KeyCode::Return)
+            | Some(KeyCode::Escape)
+            | Some(KeyCode::N) = key
+            {
+                self.whats_new.open = false;
+            }
+            return Ok(());
+        }
+
+        if self.rules.open && state == GameState::Menu {
+            let key = match key {
+                Some(KeyCode::Up) => rules::Key::Up,
+                Some(KeyCode::Down) => rules::Key::Down,
+                Some(KeyCode::Left) => rules::Key::Left,
+                Some(KeyCode::Right) => rules::Key::Right,
+                Some(KeyCode::Back) => rules::Key::Backspace,
+                Some(KeyCode::Delete) => rules::Key::Delete,
+                Some(KeyCode::Space) | Some(KeyCode::Return) => rules::Key::Play,
+                Some(KeyCode::Escape) => {
+                    self.rules.open = false;
+                    return Ok(());
+                }
+                _ => return Ok(()),
+            };
+            if let Some(rules) = self.rules.press(&mut self.rule_book, key) {
+                self.play_rules(ctx, &rules);
+            }
+            return Ok(());
+        }
+
+        if self.pending_profile.is_some() && state == GameState::Menu {
+            if !repeat {
+                self.answer_import(ctx, key);
+            }
+            return Ok(());
+        }
+
+        // Typing a name for the leaderboard takes every key.
+        if self.screen() == Screen::NameEntry {
+            if !repeat {
+                self.press_name_key(key);
+            }
+            return Ok(());
+        }
+        // Watching a script's run, the arrows seek through it.
+        let watching = !matches!(state, GameState::Menu | GameState::GetReady);
+        if let (true, Some(timeline), Some(step)) = (watching, &self.timeline, seek_step(key)) {
+            let frame = self.specs_world.read_resource::<Game>().frames as i64;
+            timeline.seek(&mut self.specs_world, frame + step);
+            self.interpolation.remember(&self.specs_world);
+            return Ok(());
+        }
+
+        if let (true, Some(key)) = (self.dev, key) {
+            if let Some(speed) = dev::speed(key) {
+                self.speed = speed;
+                return Ok(());
+            }
+            if key == dev::STEP_KEY && state == GameState::Paused {
+                self.step_paused();
+                return Ok(());
+            }
+        }
+
+        if !repeat {
+            match key {
+                Some(KeyCode::C) if state == GameState::Menu => {
+                    self.customizer.open = true;
+                    return Ok(());
+                }
+                Some(KeyCode::S) if state == GameState::Menu && self.can_pick_seed() => {
+                    let tuning = (*self.specs_world.read_resource::<Tuning>()).clone();
+                    self.seeds.show(&self.stats, &tuning);
+                    return Ok(());
+                }
+                Some(KeyCode::N) if state == GameState::Menu => {
+                    self.whats_new.show();
+                    return Ok(());
+                }
+                Some(KeyCode::T) if state == GameState::Menu && self.can_pick_mode() => {
+                    let next = self.specs_world.read_resource::<GameMode>().next();
+                    self.specs_world.insert(next);
+                    self.sync_best();
+                    return Ok(());
+                }
+                Some(KeyCode::G) if state == GameState::Menu && self.can_customize() => {
+                    self.rules.show(&self.rule_book);
+                    return Ok(());
+                }
+                Some(KeyCode::E) if state == GameState::Menu => {
+                    self.export_profile(ctx);
+                    return Ok(());
+                }
+                Some(KeyCode::I) if state == GameState::Menu => {
+                    self.import_profile(ctx);
+                    return Ok(());
+                }
+                Some(KeyCode::O) if state == GameState::Menu => {
+                    self.settings_screen.open = true;
+                    return Ok(());
+                }
+                Some(KeyCode::K) if state == GameState::Menu && !self.skins.is_empty() => {
+                    self.skins.next();
+                    self.looks.skin = self.skins.name().map(String::from);
+                    self.looks.save();
+                    self.wear_skin(ctx);
+                    return Ok(());
+                }
+                Some(KeyCode::Space) if self.controllers.is_lost() => {
+                    self.controllers.dismiss();
+                    return Ok(());
+                }
+                Some(KeyCode::Space) if state == GameState::Menu => {
+                    self.press_flap(ctx);
+                    return Ok(());
+                }
+                Some(KeyCode::R) if state == GameState::GameOver => {
+                    self.restart(ctx);
+                }
+                Some(KeyCode::Return) if self.can_submit() => {
+                    if let Some(leaderboard) = &mut self.leaderboard {
+                        leaderboard.entering = true;
+                    }
+                }
+                Some(KeyCode::Space) => self.press_flap(ctx),
+                Some(KeyCode::Up) if self.specs_world.has_value::<Race>() => {
+                    self.press_second_flap(ctx)
+                }
+                Some(KeyCode::Left) => {
+                    self.player_input.left = true;
+                }
+                Some(KeyCode::Right) => {
+                    self.player_input.right = true;
+                }
+                Some(KeyCode::Tab) => {
+                    self.show_stats = !self.show_stats;
+                }
+                Some(KeyCode::F3) => {
+                    self.show_observation = !self.show_observation;
+                }
+                Some(KeyCode::M) => {
+                    if let Some(audio) = &mut self.audio {
+                        audio.toggle_mute();
+                    }
+                }
+                Some(KeyCode::F4) => {
+                    self.inspector.open = !self.inspector.open;
+                }
+                Some(KeyCode::F7) => {
+                    self.viewport = self.viewport.with_filter(self.viewport.filter().toggled());
+                }
+                Some(KeyCode::F11) => {
+                    self.fullscreen = !self.fullscreen;
+                    ctx.gfx.set_fullscreen(fullscreen_type(self.fullscreen))?;
+                }
+                Some(KeyCode::P) => {
+                    let toggled = match state {
+                        GameState::Playing => GameState::Paused,
+                        GameState::Paused => GameState::Playing,
+                        other => other,
+                    };
+                    *self.specs_world.write_resource::<GameState>() = toggled;
+                }
+                Some(KeyCode::Minus) if self.tournament.is_none() => {
+                    self.speed = PRACTICE_SPEEDS
+                        .iter()
+                        .rev()
+                        .find(|&&s| s < self.speed)
+                        .map_or(self.speed, |&s| s);
+                }
+                Some(KeyCode::Equals) if self.tournament.is_none() => {
+                    self.speed = PRACTICE_SPEEDS
+                        .iter()
+                        .find(|&&s| s > self.speed)
+                        .map_or(self.speed, |&s| s);
+                }
+                // Sessions with runs in them get a summary before quitting,
+                // with the run paused behind it.
+                Some(KeyCode::Escape) if self.session.runs > 0 => {
+                    self.quitting = true;
+                    if state == GameState::Playing {
+                        *self.specs_world.write_resource::<GameState>() = GameState::Paused;
+                    }
+                }
+                Some(KeyCode::Escape) => {
+                    ctx.request_quit();
+                }
+                _ => (),
+            }
+        }
+
+        let mut input_state = self.specs_world.write_resource::<Direction>();
+        *input_state = self.player_input;
+        Ok(())
+    }
+
+    fn release_key(&mut self, key: Option<KeyCode>) {
+        match key {
+            Some(KeyCode::Space) => self.player_input.release = true,
+            Some(KeyCode::Up) => {
+                if let Some(mut race) = self.specs_world.try_fetch_mut::<Race>() {
+                    race.second.release = true;
+                }
+            }
+            Some(KeyCode::Left) => self.player_input.left = false,
+            Some(KeyCode::Right) => self.player_input.right = false,
+            _ => (),
+        }
+
+        let mut input_state = self.specs_world.write_resource::<Direction>();
+        *input_state = self.player_input;
+    }
+}
+
+impl ggez::event::EventHandler for State {
+    // Physics ticks at a fixed rate whatever the frame rate: as many ticks
+    // as the time since the last frame calls for, or none at all on fast
+    // displays. After a stall only a few ticks are caught up on and the
+    // rest of the backlog is dropped.
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.low_power {
+            // Physics catches up with two ticks a frame.
+            let elapsed = self.last_update.elapsed();
+            if elapsed < LOW_POWER_FRAME {
+                thread::sleep(LOW_POWER_FRAME - elapsed);
+            }
+            self.last_update = Instant::now();
+        }
+        self.latency.update(Instant::now());
+        if self.leaderboard.as_mut().is_some_and(Leaderboard::poll) {
+            self.redraw = true;
+        }
+        let before = *self.specs_world.read_resource::<GameState>();
+        let was_lost = self.controllers.is_lost();
+        self.controllers.check(ctx);
+
+        #[cfg(feature = "hot-reload")]
+        if let Some(tuning) = self.tuning_watch.as_mut().and_then(|w| w.poll()) {
+            println!("reloaded tuning: {:?}", tuning);
+            apply_tuning(&mut self.specs_world, tuning);
+        }
+        #[cfg(feature = "hot-reload")]
+        self.hot_reload(ctx);
+
+        let mut ticks = 0;
+        while ctx.time.check_update_time(PHYSICS_FPS) {
+            if ticks < MAX_CATCH_UP_TICKS {
+                self.interpolation.remember(&self.specs_world);
+                self.tick();
+                ticks += 1;
+            }
+        }
+        self.interpolation.alpha =
+            (ctx.time.remaining_update_time().as_secs_f32() * PHYSICS_FPS as f32).min(1.0);
+
+        if let Some(card) = self.card.take() {
+            match card.compose(ctx, &self.painted_sheet.frames()[0]) {
+                Ok(()) => {
+                    let path = share::card_path(ctx);
+                    let message = lang::fill("card_saved", &[&path.display()]);
+                    self.run_summary.push(self.private(message, &path));
+                }
+                Err(e) => println!("can't save the results card: {}", e),
+            }
+        }
+        // The frame on screen, as this runs between drawing them.
+        if self.screenshot {
+            self.screenshot = false;
+            let message = match share::screenshot(ctx) {
+                Ok(path) => {
+                    let text = lang::fill("screenshot_saved", &[&path.display()]);
+                    self.private(text, &path)
+                }
+                Err(e) => lang::fill("screenshot_failed", &[&e]),
+            };
+            self.toast = Some((message, Instant::now()));
+        }
+        #[cfg(feature = "instant-replay")]
+        self.record_instant_replay(ctx, before);
+        if self
+            .toast
+            .as_ref()
+            .is_some_and(|(_, shown)| shown.elapsed() >= TOAST_TIME)
+        {
+            self.toast = None;
+            self.redraw = true;
+        }
+
+        if self.attract && *self.specs_world.read_resource::<GameState>() == GameState::GameOver {
+            self.end_attract(ctx);
+        }
+
+        let state = *self.specs_world.read_resource::<GameState>();
+        let still = matches!(state, GameState::Paused | GameState::GameOver);
+        if state != before
+            || !still
+            || self.flash > 0
+            || self.shaking()
+            || self.controllers.is_lost() != was_lost
+            || self.latency.open
+            || self.toast.is_some()
+        {
+            self.redraw = true;
+        }
+
+        if let Some(streamer) = &mut self.streamer {
+            streamer.update(&self.specs_world.read_resource::<Game>());
+        }
+        if let Some(audio) = &mut self.audio {
+            audio.duck(still || self.controllers.is_lost());
+            audio.update(ctx.time.delta().as_secs_f32());
+        }
+        self.forward_split_events();
+        self.play_sounds(ctx);
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.low_power && !self.redraw {
+            // The last frame is shown again.
+            timer::yield_now();
+            return Ok(());
+        }
+        self.redraw = false;
+        let start = Instant::now();
+        if self.latency.open {
+            self.latency.frame(start);
+        }
+        self.hud_system.run_now(&self.specs_world);
+        let positions = self.specs_world.read_storage::<Position>();
+        let images = self.specs_world.read_storage::<Image>();
+        let zorders = self.specs_world.read_storage::<ZOrder>();
+        let sheets = self.specs_world.read_storage::<SpriteSheet>();
+        let boxes = self.specs_world.read_storage::<CollisionBox>();
+        let obstacles = self.specs_world.read_storage::<ObstacleTag>();
+        let game = self.specs_world.read_resource::<Game>();
+        let hud = self.specs_world.read_resource::<Hud>();
+        let state = *self.specs_world.read_resource::<GameState>();
+        let speedrun = self.specs_world.try_fetch::<Speedrun>();
+        let entities = self.specs_world.entities();
+        let lerp = &self.interpolation;
+        let (contrast, hud_scale) = {
+            let settings = self.specs_world.read_resource::<Settings>();
+            (settings.high_contrast, settings.hud_scale)
+        };
+        let scenery_tint = |distance| {
+            if contrast {
+                theme::CONTRAST_SCENERY
+            } else {
+                self.themes.scenery_tint(distance)
+            }
+        };
+        let mut cmds = Vec::new();
+
+        if state == GameState::Menu {
+            // Only the scenery, scrolling behind the title.
+            let tint = scenery_tint(game.distance);
+            for (e, at, i) in parallax::in_draw_order(&entities, &positions, &images, &zorders) {
+                cmds.push(DrawCmd::tinted_sprite(&i.image, lerp.at(e, at), tint));
+            }
+            let world_len = cmds.len();
+            self.draw_menu(&mut cmds, ctx.time.ticks() as u32);
+            let (world, ui) = cmds.split_at(world_len);
+            engine::render(
+                ctx,
+                &self.viewport,
+                Color::new(0.1, 0.1, 0.1, 1.0),
+                world,
+                ui,
+            )?;
+            timer::yield_now();
+            return Ok(());
+        }
+
+        // Only the scenery has plain images; it follows the time of day.
+        let tint = scenery_tint(game.distance);
+        for (e, at, i) in parallax::in_draw_order(&entities, &positions, &images, &zorders) {
+            cmds.push(DrawCmd::tinted_sprite(&i.image, lerp.at(e, at), tint));
+        }
+        let pipes = self.specs_world.try_fetch::<PipeSprites>();
+        let obstacle_boxes: Vec<(CollisionBox, &ObstacleTag)> = (&entities, &boxes, &obstacles)
+            .join()
+            .map(|(e, coll_box, obs)| {
+                let origin = lerp.at(e, coll_box.origin);
+                (
+                    CollisionBox {
+                        origin,
+                        ..*coll_box
+                    },
+                    obs,
+                )
+            })
+            .collect();
+        // Hazards first, so a pipe standing on one hides it.
+        for (coll_box, obs) in &obstacle_boxes {
+            if let Some(hazard) = obs.hazard {
+                hazards::draw(&mut cmds, coll_box, hazard);
+            }
+        }
+        let mut pipe_boxes = Vec::new();
+        for (coll_box, obs) in &obstacle_boxes {
+            if obs.hazard.is_some() {
+                continue;
+            } else if obs.cave {
+                caves::draw_segment(&mut cmds, coll_box);
+            } else {
+                pipe_boxes.push((*coll_box, obs.top, obs.color));
+            }
+        }
+        if let Some(pipes) = &pipes {
+            if contrast {
+                pipes.draw_flat(&mut cmds, &pipe_boxes, theme::CONTRAST_PIPES);
+            } else {
+                pipes.draw(&mut cmds, &pipe_boxes, self.themes.pipe_tint());
+            }
+            let debris = self.specs_world.read_storage::<Debris>();
+            let chunks: Vec<(Position, Debris)> = (&entities, &positions, &debris)
+                .join()
+                .map(|(e, p, d)| {
+                    let position = lerp.at(e, p.position);
+                    (Position { position, ..*p }, *d)
+                })
+                .collect();
+            pipes.draw_debris(&mut cmds, &chunks);
+        }
+        let items = self.specs_world.read_storage::<Collectible>();
+        for (e, coll_box, item) in (&entities, &boxes, &items).join() {
+            collectibles::draw(&mut cmds, lerp.at(e, coll_box.origin), item.kind);
+        }
+
+        if let Some(markers) = &self.death_markers {
+            markers.draw(&mut cmds, game.distance);
+        }
+
+        let look = self.looks.get(self.look_owner());
+        if !matches!(state, GameState::Dying | GameState::GameOver) && !self.low_power {
+            let trail = self.specs_world.read_resource::<Trail>();
+            let speed = self.specs_world.read_resource::<Difficulty>().speed;
+            let mut path: Vec<Vec2> = trail.positions().collect();
+            // The trail stays where the bird was as the world scrolls on.
+            let len = path.len();
+            for (n, point) in path.iter_mut().enumerate() {
+                point.x -= speed / PHYSICS_FPS as f32 * (len - 1 - n) as f32;
+            }
+            look.draw_trail(&mut cmds, &path);
+        }
+        if let Some(playback) = self.specs_world.try_fetch::<GhostPlayback>() {
+            if playback.flying(game.frames) {
+                let ghosts = self.specs_world.read_storage::<GhostBird>();
+                let frames = self.painted_sheet.frames();
+                let frame = &frames[(game.frames as u32 / ANIMATION_TICKS) as usize % frames.len()];
+                for (e, p, _) in (&entities, &positions, &ghosts).join() {
+                    best_run::draw(&mut cmds, frame, lerp.at(e, p.position));
+                }
+            }
+        }
+        if let (Some(shadow), GameState::Playing | GameState::Paused) = (&self.shadow, state) {
+            let frames = self.painted_sheet.frames();
+            shadow.draw_bird(
+                &mut cmds,
+                &frames[(game.frames as u32 / ANIMATION_TICKS) as usize % frames.len()],
+            );
+        }
+        let particles = self.specs_world.read_storage::<Particle>();
+        let texts = self.specs_world.read_storage::<FloatingText>();
+        for (e, p, particle, _) in (&entities, &positions, &particles, !&texts).join() {
+            let at = lerp.at(e, p.position);
+            let half = particle.size / 2.0;
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(at.x - half, at.y - half, particle.size, particle.size),
+                color: particle.color,
+            });
+        }
+        let shielded = self
+            .specs_world
+            .try_fetch::<Collectibles>()
+            .is_some_and(|collectibles| collectibles.shield);
+        let players = self.specs_world.read_storage::<PlayerTag>();
+        let ids = self.specs_world.read_storage::<PlayerId>();
+        let race = self.specs_world.try_fetch::<Race>();
+        // Blinks while invulnerable after losing a life.
+        let blink = game.invulnerable / 8 % 2 == 1;
+        let fall = match state {
+            GameState::Dying => self.specs_world.try_fetch::<DeathFall>().map(|fall| *fall),
+            _ => None,
+        };
+        for (e, p, sheet, id) in (&entities, &positions, &sheets, ids.maybe()).join() {
+            if blink && players.contains(e) {
+                continue;
+            }
+            // Racers that crashed are out of the picture.
+            if let (Some(race), Some(id)) = (&race, id) {
+                if !race.flying(id.0) {
+                    continue;
+                }
+            }
+            let at = lerp.at(e, p.position);
+            if let (true, Some(coll_box), true) = (shielded, boxes.get(e), players.contains(e)) {
+                collectibles::draw_shield(&mut cmds, coll_box, at);
+            }
+            let tint = match id {
+                Some(&PlayerId(n)) if n > 0 => SECOND_RACER_TINT,
+                _ => Color::WHITE,
+            };
+            let frame = sheet.frame();
+            match fall {
+                _ if contrast => {
+                    let size = Vec2::new(frame.width(), frame.height());
+                    theme::draw_flat_bird(&mut cmds, at, size, tint);
+                }
+                // The hat comes off in the crash.
+                Some(fall) if players.contains(e) => cmds.push(DrawCmd::Sprite {
+                    sprite: sheet.hit_frame(),
+                    dest: at,
+                    scale: 1.0,
+                    flip: false,
+                    tint,
+                    rotation: fall.rotation,
+                }),
+                _ => {
+                    cmds.push(DrawCmd::tinted_sprite(frame, at, tint));
+                    look.draw_hat(&mut cmds, at, 1.0);
+                }
+            }
+        }
+        // Popups go over the birds.
+        for (e, p, particle, text) in (&entities, &positions, &particles, &texts).join() {
+            let at = lerp.at(e, p.position);
+            cmds.push(DrawCmd::centered_text(
+                text.0,
+                at,
+                particle.size,
+                particle.color,
+            ));
+        }
+        let practice = self.specs_world.try_fetch::<Practice>();
+        if self.show_observation || practice.is_some() {
+            // Colliders as they are this tick, the bird's in green, or
+            // yellow while it's touching something in practice.
+            let touching = practice.as_ref().is_some_and(|practice| practice.touching);
+            for (e, coll_box) in (&entities, &boxes).join() {
+                let at = lerp.at(e, coll_box.origin);
+                let color = if players.contains(e) && touching {
+                    Color::new(1.0, 1.0, 0.3, 1.0)
+                } else if players.contains(e) {
+                    Color::new(0.3, 1.0, 0.3, 1.0)
+                } else {
+                    Color::new(1.0, 0.3, 0.3, 1.0)
+                };
+                let rect = Rect::new(at.x, at.y, coll_box.width, coll_box.height);
+                cmds.extend_from_slice(&DrawCmd::outline(rect, 1.0, color));
+            }
+        }
+        // Everything after this is UI.
+        let world_len = cmds.len();
+        if let Some(camera) = self.specs_world.try_fetch::<Camera>() {
+            for cmd in &mut cmds {
+                camera.view(cmd);
+            }
+        }
+
+        if self.show_observation {
+            if let Some(obs) = Observation::from_world(&self.specs_world) {
+                policy::draw_observation(&mut cmds, &obs);
+            }
+            let speed = (&positions, &players)
+                .join()
+                .next()
+                .map_or(Vec2::ZERO, |(p, _)| p.speed);
+            cmds.push(DrawCmd::text(
+                format!(
+                    "fps {:.0}  entities {}  bird vx {:.1} vy {:.1}",
+                    ctx.time.fps(),
+                    entities.join().count(),
+                    speed.x,
+                    speed.y
+                ),
+                Vec2::new(10.0, 512.0),
+                16.0,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+            let tuning = self.specs_world.read_resource::<Tuning>();
+            let difficulty = self.specs_world.read_resource::<Difficulty>();
+            cmds.push(DrawCmd::text(
+                format!(
+                    "gravity {}  flap {}  max rise {}  max fall {}  scroll {:.2}  spacing {:.0}  gap {:.0}",
+                    tuning.gravity,
+                    tuning.flap_impulse,
+                    tuning.max_rise_speed,
+                    tuning.max_fall_speed,
+                    difficulty.speed,
+                    difficulty.spacing,
+                    difficulty.gap
+                ),
+                Vec2::new(10.0, 575.0),
+                16.0,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+            self.profiler
+                .draw(&mut cmds, Rect::new(10.0, 540.0, 500.0, 10.0));
+        }
+        if let (Some(practice), Some(obs)) = (&practice, Observation::from_world(&self.specs_world))
+        {
+            policy::draw_centerline(&mut cmds, &obs);
+            let middle = (obs.ceiling + obs.floor) / 2.0;
+            cmds.push(DrawCmd::text(
+                format!(
+                    "vy {:+.0} px/s  next gap {:.0} px ahead, {:+.0} px off its middle  hits {}",
+                    obs.speed_y * PHYSICS_FPS as f32,
+                    obs.gap_dx,
+                    obs.bird_y + obs.bird_height / 2.0 - middle,
+                    practice.hits
+                ),
+                Vec2::new(10.0, 490.0),
+                16.0,
+                Color::new(1.0, 1.0, 0.3, 1.0),
+            ));
+        }
+
+        match state {
+            GameState::GameOver if self.show_stats => {
+                let width = engine::screen_width();
+                // A narrow screen has the graph across the bottom.
+                let (heatmap_x, history) = if engine::narrow() {
+                    (
+                        width / 2.0 - 100.0,
+                        Rect::new(20.0, 440.0, width - 40.0, 130.0),
+                    )
+                } else {
+                    (width / 2.0 - 150.0, Rect::new(600.0, 440.0, 400.0, 130.0))
+                };
+                stats::draw_death_heatmap(
+                    &mut cmds,
+                    &self.stats,
+                    Vec2::new(heatmap_x, engine::SCREEN_HEIGHT / 2.0),
+                );
+                stats::draw_score_history(&mut cmds, &self.stats, history);
+            }
+            GameState::GameOver => match &speedrun {
+                Some(speedrun) if speedrun.target.is_some() => {
+                    speedrun::draw_results(&mut cmds, speedrun, &self.stats.marathon_times);
+                }
+                // The level draws its own finish.
+                _ if self.level.as_ref().is_some_and(|level| level.finished) => (),
+                // So does the hot seat its ranking.
+                _ if self.hot_seat.as_ref().is_some_and(HotSeat::finished) => (),
+                // The crash plays out first.
+                _ if self.shaking() => (),
+                _ => self.draw_game_over(&mut cmds),
+            },
+            GameState::Menu
+            | GameState::GetReady
+            | GameState::Playing
+            | GameState::Paused
+            | GameState::Dying => {
+                if let Some(at) = hud.layout.score.position(hud.margin) {
+                    self.digits.draw(&mut cmds, game.score, at, hud.pop);
+                    let mut below = at + Vec2::new(0.0, self.digits.height() + 12.0);
+                    if hud.assisted {
+                        cmds.push(DrawCmd::centered_text(
+                            lang::tr("assisted"),
+                            below,
+                            18.0 * hud_scale,
+                            Color::new(1.0, 1.0, 1.0, 0.8),
+                        ));
+                        below.y += 22.0 * hud_scale;
+                    }
+                    let game_mode = *self.specs_world.read_resource::<GameMode>();
+                    if let (Some(goal), false) = (game_mode.goal(&game), state == GameState::Menu) {
+                        cmds.push(DrawCmd::centered_text(
+                            goal,
+                            below,
+                            22.0 * hud_scale,
+                            Color::new(1.0, 0.9, 0.2, 1.0),
+                        ));
+                    }
+                }
+            }
+        }
+        if state == GameState::GetReady {
+            let ready = self.specs_world.read_resource::<GetReady>();
+            draw_get_ready(&mut cmds, ready.countdown());
+        }
+        if let (Some(telegraph), GameState::Playing | GameState::Paused) =
+            (self.specs_world.try_fetch::<Telegraph>(), state)
+        {
+            if let Some(center) = telegraph.next {
+                let gap = self.specs_world.read_resource::<Difficulty>().gap;
+                draw_telegraph(&mut cmds, center, gap);
+            }
+        }
+        if let (Some(collectibles), GameState::Playing | GameState::Paused) =
+            (self.specs_world.try_fetch::<Collectibles>(), state)
+        {
+            collectibles.draw_status(&mut cmds, Vec2::new(engine::screen_width() - 224.0, 140.0));
+        }
+        if let (Some(wind), GameState::Playing | GameState::Paused) =
+            (self.specs_world.try_fetch::<Wind>(), state)
+        {
+            let push = wind.push(game.distance);
+            let blowing = if push.abs() < 0.1 * wind.strength {
+                "wind_calm"
+            } else if push < 0.0 {
+                "wind_up"
+            } else {
+                "wind_down"
+            };
+            cmds.push(DrawCmd::text(
+                lang::fill("wind", &[&lang::tr(blowing)]),
+                Vec2::new(engine::screen_width() - 224.0, 175.0),
+                20.0,
+                Color::new(0.8, 0.9, 1.0, 1.0),
+            ));
+        }
+        if let Some(level) = &self.level {
+            level.draw(&mut cmds, &game, state);
+        }
+        if let Some(tournament) = &self.tournament {
+            tournament.draw(&mut cmds, state);
+        }
+        if let Some(hot_seat) = &self.hot_seat {
+            hot_seat.draw(&mut cmds, state);
+        }
+        if let (Some(speedrun), Some(at)) = (&speedrun, hud.layout.timer.position(hud.margin)) {
+            speedrun::draw_timer(&mut cmds, speedrun, game.pipes_passed, at);
+        }
+        if self.attract {
+            cmds.push(DrawCmd::centered_text(
+                lang::tr("demo"),
+                Vec2::new(engine::screen_width() / 2.0, 120.0),
+                30.0,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+        }
+        if let Some(at) = hud
+            .layout
+            .best
+            .position(hud.margin)
+            .filter(|_| !hud.best.is_empty())
+        {
+            cmds.push(DrawCmd::text(
+                hud.best.as_str(),
+                at,
+                22.0 * hud_scale,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+        }
+        if let Some(at) = hud
+            .layout
+            .speed
+            .position(hud.margin)
+            .filter(|_| self.speed != 1.0)
+        {
+            cmds.push(DrawCmd::text(
+                format!("x{}", self.speed),
+                at,
+                22.0 * hud_scale,
+                Color::new(1.0, 1.0, 0.6, 1.0),
+            ));
+        }
+        if let Some(at) = hud
+            .layout
+            .lives
+            .position(hud.margin)
+            .filter(|_| !hud.lives.is_empty())
+        {
+            cmds.push(DrawCmd::text(
+                hud.lives.as_str(),
+                at,
+                22.0 * hud_scale,
+                Color::new(1.0, 0.6, 0.6, 1.0),
+            ));
+        }
+        if let (Some(shadow), GameState::Playing | GameState::Paused) = (&self.shadow, state) {
+            shadow.draw_standing(&mut cmds, game.score);
+        }
+        if let (Some(timeline), false) = (&self.timeline, state == GameState::Menu) {
+            timeline.draw(&mut cmds, game.frames as u64);
+        }
+        if let (Some(race), GameState::Playing | GameState::Paused) = (&race, state) {
+            let standing = |n: usize| {
+                let key = if race.flying(n) {
+                    "racer"
+                } else {
+                    "racer_crashed"
+                };
+                lang::fill(key, &[&(n + 1), &race.score(n, game.score)])
+            };
+            cmds.push(DrawCmd::centered_text(
+                format!("{}  -  {}", standing(0), standing(1)),
+                Vec2::new(engine::screen_width() / 2.0, 100.0),
+                20.0,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+        }
+        if state == GameState::Paused {
+            draw_paused(&mut cmds);
+        }
+        if let Some(session) = self.specs_world.try_fetch::<SessionTime>() {
+            if session.reminding(state) {
+                session.draw(&mut cmds);
+            }
+        }
+        if self.quitting {
+            self.session.draw(&mut cmds);
+        }
+        if self.flash > 0 {
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(0.0, 0.0, engine::screen_width(), engine::SCREEN_HEIGHT),
+                color: Color::new(1.0, 1.0, 1.0, 0.6 * self.flash as f32 / FLASH_FRAMES as f32),
+            });
+        }
+        if self.inspector.open {
+            self.inspector.draw(&mut cmds, &self.specs_world);
+        }
+        if self.gamepad_only {
+            controller::draw_legend(&mut cmds, self.screen());
+        }
+        self.controllers.draw_prompt(ctx, &mut cmds);
+        if self.latency.open {
+            self.latency.draw(&mut cmds);
+        }
+        if let Some((message, shown)) = &self.toast {
+            let left = TOAST_TIME.saturating_sub(shown.elapsed()).as_secs_f32();
+            let alpha = (left / TOAST_FADE).min(1.0);
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(
+                    0.0,
+                    engine::SCREEN_HEIGHT - 60.0,
+                    engine::screen_width(),
+                    36.0,
+                ),
+                color: Color::new(0.0, 0.0, 0.0, 0.6 * alpha),
+            });
+            cmds.push(DrawCmd::centered_text(
+                message.as_str(),
+                Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT - 42.0),
+                18.0,
+                Color::new(1.0, 1.0, 1.0, alpha),
+            ));
+        }
+        if self.specs_world.read_resource::<Settings>().show_fps {
+            cmds.push(DrawCmd::text(
+                format!("{:.0} fps", ctx.time.fps()),
+                Vec2::new(engine::screen_width() - 80.0, 8.0),
+                16.0,
+                Color::new(1.0, 1.0, 1.0, 0.8),
+            ));
+        }
+
+        let (world, ui) = cmds.split_at(world_len);
+        let text = engine::render(
+            ctx,
+            &self.viewport,
+            Color::new(0.1, 0.1, 0.1, 1.0),
+            world,
+            ui,
+        )?;
+        self.flash = self.flash.saturating_sub(1);
+        self.profiler.add(Scope::Text, text);
+        self.profiler
+            .add(Scope::Draw, start.elapsed().saturating_sub(text));
+        self.profiler.end_frame();
+
+        timer::yield_now();
+        Ok(())
+    }
+
+    // Bound keys stand in for the usual ones, except on the settings screen
+    // where they're bound.
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+        let key = match self.screen() {
+            Screen::Settings => input.keycode,
+            _ => self.bindings().resolve(input.keycode),
+        };
+        self.press_key(ctx, key, repeat)
+    }
+
+    // However the game is closed, a session with runs in it goes into the
+    // stats.
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        if self.session.runs > 0 {
+            self.stats.record_session(std::mem::take(&mut self.session));
+            self.stats.save();
+        }
+        Ok(false)
+    }
+
+    // Typing only goes anywhere while naming a custom game.
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        let state = *self.specs_world.read_resource::<GameState>();
+        if self.rules.open && state == GameState::Menu {
+            self.rules.type_char(character);
+            self.redraw = true;
+        }
+        if let (Screen::NameEntry, Some(leaderboard)) = (self.screen(), &mut self.leaderboard) {
+            leaderboard.type_char(character);
+            self.redraw = true;
+        }
+        Ok(())
+    }
+
+    // Clicks, and taps on touch screens which ggez turns into left clicks,
+    // flap like Space unless the inspector is open to pick entities. On a
+    // paused run they resume it instead, as touch screens have no `P`.
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: event::MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.redraw = true;
+        if self.gamepad_only {
+            return Ok(());
+        }
+        self.menu_idle = 0;
+        if self.attract {
+            self.end_attract(ctx);
+            return Ok(());
+        }
+        if self.inspector.open {
+            let point = self.viewport.to_screen(Vec2::new(x, y));
+            self.inspector.click(&self.specs_world, point);
+            return Ok(());
+        }
+        if self.quitting {
+            self.quitting = false;
+            return Ok(());
+        }
+        if self.dismiss_break_reminder() {
+            return Ok(());
+        }
+        let state = *self.specs_world.read_resource::<GameState>();
+        if let (true, event::MouseButton::Right, GameState::Playing | GameState::Paused) =
+            (self.dev, button, state)
+        {
+            let point = self.viewport.to_screen(Vec2::new(x, y));
+            dev::teleport(&self.specs_world, point);
+            self.interpolation.remember(&self.specs_world);
+            return Ok(());
+        }
+        if button != event::MouseButton::Left
+            || self.customizer.open
+            || self.seeds.open
+            || self.whats_new.open
+            || self.rules.open
+            || self.settings_screen.open
+            || self.screen() == Screen::NameEntry
+        {
+            return Ok(());
+        }
+        if self.controllers.is_lost() {
+            self.controllers.dismiss();
+            return Ok(());
+        }
+        let mut state = self.specs_world.write_resource::<GameState>();
+        if *state == GameState::Paused {
+            *state = GameState::Playing;
+            return Ok(());
+        }
+        drop(state);
+
+        self.press_flap(ctx);
+        *self.specs_world.write_resource::<Direction>() = self.player_input;
+        Ok(())
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: event::MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        if button == event::MouseButton::Left && !self.gamepad_only {
+            self.player_input.release = true;
+            *self.specs_world.write_resource::<Direction>() = self.player_input;
+        }
+        Ok(())
+    }
+
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.viewport = self.viewport.resized(width, height);
+        self.redraw = true;
+        Ok(())
+    }
+
+    // Switching away from the game, or the app being sent to the background
+    // on a phone, pauses the run. `P` resumes it as usual.
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) -> GameResult {
+        let mut state = self.specs_world.write_resource::<GameState>();
+        if !gained && *state == GameState::Playing {
+            *state = GameState::Paused;
+            self.redraw = true;
+        }
+        Ok(())
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult {
+        let key = self.bindings().resolve(input.keycode);
+        self.release_key(key);
+        Ok(())
+    }
+
+    // Buttons stand in for keys, see `controller::button_key`.
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        btn: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        self.redraw = true;
+        // Any button carries on after a lost controller.
+        let was_lost = self.controllers.is_lost();
+        self.controllers.used(id);
+        if was_lost {
+            return Ok(());
+        }
+        if self.settings_screen.capturing {
+            let mut settings = self.specs_world.write_resource::<Settings>();
+            self.settings_screen.press_button(&mut settings, btn);
+            return Ok(());
+        }
+
+        let key = self.race_button_key(btn);
+        self.press_key(ctx, key, false)
+    }
+
+    fn gamepad_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        btn: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        self.controllers.used(id);
+        let key = self.race_button_key(btn);
+        self.release_key(key);
+        Ok(())
+    }
+
+    fn gamepad_axis_event(
+        &mut self,
+        _ctx: &mut Context,
+        _axis: Axis,
+        _value: f32,
+        id: GamepadId,
+    ) -> GameResult {
+        self.controllers.used(id);
+        Ok(())
+    }
+}
+
+fn draw_get_ready(cmds: &mut Vec<DrawCmd>, countdown: Option<u32>) {
+    let center_x = engine::screen_width() / 2.0;
+    cmds.push(DrawCmd::centered_text(
+        lang::tr("get_ready"),
+        Vec2::new(center_x, 160.0),
+        80.0,
+        Color::new(1.0, 0.9, 0.2, 1.0),
+    ));
+    let (text, size): (Cow<str>, f32) = match countdown {
+        Some(n) => (n.to_string().into(), 120.0),
+        None => (lang::tr("flap_to_start").into(), 30.0),
+    };
+    cmds.push(DrawCmd::centered_text(
+        text,
+        Vec2::new(center_x, 300.0),
+        size,
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    ));
+}
+
+// The toast for achievements just unlocked, if any were.
+fn unlocked_toast(names: &[String]) -> Option<(String, Instant)> {
+    match names {
+        [] => None,
+        [name] => Some((lang::fill("achievement_unlocked", &[name]), Instant::now())),
+        _ => Some((
+            lang::fill("achievements_unlocked", &[&names.join(", ")]),
+            Instant::now(),
+        )),
+    }
+}
+
+// The gap telegraph's arrow at the right edge, pointing at the middle of
+// the next gap, with a bar as tall as the gap behind it.
+fn draw_telegraph(cmds: &mut Vec<DrawCmd>, center: f32, gap: f32) {
+    let x = engine::screen_width() - 8.0;
+    let color = Color::new(1.0, 0.9, 0.2, 0.8);
+    cmds.push(DrawCmd::Rect {
+        rect: Rect::new(x, center - gap / 2.0, 6.0, gap),
+        color: Color::new(1.0, 0.9, 0.2, 0.35),
+    });
+    for &dy in [-14.0, 14.0].iter() {
+        cmds.push(DrawCmd::Line {
+            from: Vec2::new(x - 30.0, center + dy),
+            to: Vec2::new(x - 12.0, center),
+            width: 5.0,
+            color,
+        });
+    }
+}
+
+// Dims the frozen run behind the pause text.
+fn draw_paused(cmds: &mut Vec<DrawCmd>) {
+    cmds.push(DrawCmd::Rect {
+        rect: Rect::new(0.0, 0.0, engine::screen_width(), engine::SCREEN_HEIGHT),
+        color: Color::new(0.0, 0.0, 0.0, 0.5),
+    });
+    let center = Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT / 2.0);
+    cmds.push(DrawCmd::centered_text(
+        lang::tr("paused"),
+        center,
+        80.0,
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    ));
+    cmds.push(DrawCmd::centered_text(
+        lang::tr("resume"),
+        center + Vec2::new(0.0, 60.0),
+        24.0,
+        Color::new(0.8, 0.8, 0.8, 1.0),
+    ));
+}
+
+// Label for the telemetry file of a run starting now.
+fn run_label() -> String {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+        .to_string()
+}
+
+// Switches a running world to new tuning. The difficulty is worked out
+// again right away, so the floor and obstacles pick up the new scroll speed
+// even in the menu.
+fn apply_tuning(world: &mut World, tuning: Tuning) {
+    {
+        let entities = world.entities();
+        let players = world.read_storage::<PlayerTag>();
+        let mut hitboxes = world.write_storage::<Hitbox>();
+        for (entity, _) in (&entities, &players).join() {
+            match Hitbox::bird(tuning.hitbox) {
+                Some(hitbox) => {
+                    hitboxes.insert(entity, hitbox).unwrap();
+                }
+                None => {
+                    hitboxes.remove(entity);
+                }
+            }
+        }
+    }
+    world.insert(tuning);
+    DifficultySystem.run_now(world);
+}
+
+// Gaps grow by the height the rest of the flock adds.
+fn widen_for_flock(tuning: &mut Tuning) {
+    let extra = FLOCK_SPACING * (FLOCK_SIZE - 1) as f32;
+    tuning.gap_curve.start += extra;
+    tuning.gap_curve.end += extra;
+}
+
+pub const ASSETS_DIR: &str = "./assets";
+const CONFIG_FILE: &str = "./config.toml";
+const GAME_ID: &str = "rusty_bird";
+const AUTHOR: &str = "Luis de Bethencourt";
+
+// Settings decide how the window opens, so they're found before there's a
+// context to ask for the user data directory.
+fn settings_path() -> Option<path::PathBuf> {
+    let fs = ggez::filesystem::Filesystem::new(GAME_ID, AUTHOR, "resources", "resources.zip");
+    fs.ok().map(|fs| fs.user_data_dir().join("settings.json"))
+}
+
+// Switches to the language picked in the settings, for the tools that open
+// a window of their own. Before the assets, as it can bring its own font.
+pub fn use_saved_language(assets: &path::Path) {
+    let settings = settings_path().map_or_else(Settings::default, Settings::load);
+    if let Some(language) = settings.lang.and_then(|code| lang::load(assets, &code)) {
+        lang::set(language);
+    }
+}
+
+pub fn load_branding(assets: &path::Path) -> Branding {
+    Branding::load(assets)
+}
+
+// Creates the game window, with the assets directory on the resource path.
+// Tools other than the game itself add their name to the title.
+pub fn open_window(
+    branding: &Branding,
+    assets: &path::Path,
+    window: config::Window,
+    tool: Option<&str>,
+) -> (Context, event::EventLoop<()>) {
+    let title = match tool {
+        Some(tool) => format!("{} - {}", branding.title, tool),
+        None => branding.title.clone(),
+    };
+    let mut conf = conf::Conf::new();
+    let win_setup = conf::WindowSetup {
+        title,
+        samples: conf::NumSamples::try_from(window.samples).unwrap_or(conf::NumSamples::One),
+        vsync: window.vsync,
+        icon: branding.window_icon(assets),
+        srgb: true,
+    };
+    conf.window_setup = win_setup;
+    conf.window_mode = conf::WindowMode::default()
+        .dimensions(window.width, window.height)
+        .resizable(true)
+        .fullscreen_type(fullscreen_type(window.fullscreen));
+
+    let (ctx, events) = ContextBuilder::new(GAME_ID, AUTHOR)
+        .default_conf(conf)
+        .add_resource_path(assets)
+        .build()
+        .unwrap();
+    if let Err(e) = branding.set_built_in_icon(&ctx, assets) {
+        println!("can't set the window icon: {}", e);
+    }
+    (ctx, events)
+}
+
+// Fullscreen takes over the desktop rather than changing the display mode,
+// so switching is instant.
+fn fullscreen_type(fullscreen: bool) -> conf::FullscreenType {
+    if fullscreen {
+        conf::FullscreenType::Desktop
+    } else {
+        conf::FullscreenType::Windowed
+    }
+}
+
+// `mode` with its kind of run swapped for the custom game's, keeping the
+// variants after it.
+fn custom_mode(mode: &str, rules: &Rules) -> String {
+    let kind_len = match mode.strip_prefix("custom-") {
+        Some(rest) => "custom-".len() + rest.find('-').unwrap_or(rest.len()),
+        None => mode.find('-').unwrap_or(mode.len()),
+    };
+    format!("{}{}", rules.mode(), &mode[kind_len..])
+}
+
+const USAGE: &str = "usage: rusty_bird [--fullscreen] [--resolution WxH] [--mute] \
+    [--seed N] [--difficulty easy|normal|hard] [--lang CODE] [--assets PATH] [--tuning FILE] ...
+       rusty_bird simulate|tas|tas-edit|tournament ...
+see the README for every option";
+
+// How many frames an arrow key seeks through a watched script: a second
+// either way with Left and Right, ten with Down and Up.
+fn seek_step(key: Option<KeyCode>) -> Option<i64> {
+    let second = PHYSICS_FPS as i64;
+    match key? {
+        KeyCode::Left => Some(-second),
+        KeyCode::Right => Some(second),
+        KeyCode::Down => Some(-10 * second),
+        KeyCode::Up => Some(10 * second),
+        _ => None,
+    }
+}
+
+// A window size given as `WIDTHxHEIGHT`, e.g. `1280x720`.
+fn parse_resolution(size: &str) -> Option<(f32, f32)> {
+    let (width, height) = size.split_once('x')?;
+    let (width, height) = (width.parse::<u32>().ok()?, height.parse::<u32>().ok()?);
+    (width > 0 && height > 0).then_some((width as f32, height as f32))
+}
+
+// Runs a tool when its name is the first argument, and otherwise the game.
+pub fn run() {
+    match std::env::args().nth(1).as_deref() {
+        Some("simulate") => return simulate::run(std::env::args().skip(2)),
+        Some("tas") => return tas::run(std::env::args().skip(2)),
+        Some("tas-edit") => return tas_editor::run(std::env::args().skip(2)),
+        Some("tournament") => return tournament::run(std::env::args().skip(2)),
+        _ => (),
+    }
+
+    let mut config = Config::load(path::Path::new(CONFIG_FILE));
+    let settings = settings_path().map_or_else(Settings::default, Settings::load);
+    config.window.vsync = settings.vsync;
+
+    let mut telemetry_dir = None;
+    let mut seed = None;
+    let mut marathon = false;
+    let mut timer = false;
+    let mut livesplit_address = None;
+    let mut tas_script = None;
+    let mut show_death_markers = false;
+    let mut speed = 1.0;
+    let mut drift = false;
+    let mut ghost = false;
+    let mut telegraph = false;
+    let mut practice = false;
+    let mut dev = false;
+    let mut collect = false;
+    let mut lives = None;
+    let mut flock = false;
+    let mut race = false;
+    let mut gamepad_only = false;
+    let mut muted = false;
+    let mut preset = None;
+    let mut language = None;
+    let mut assets = path::PathBuf::from(ASSETS_DIR);
+    let mut tuning_path = None;
+    let mut level = None;
+    let mut demo = None;
+    let mut shadow_skill = None;
+    let mut tournament_path = None;
+    let mut player = None;
+    let mut hot_seat = None;
+    let mut custom = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--telemetry" => telemetry_dir = args.next().map(path::PathBuf::from),
+            "--seed" => seed = args.next().and_then(|s| s.parse::<u64>().ok()),
+            "--death-markers" => show_death_markers = true,
+            "--drift" => drift = true,
+            "--ghost" => ghost = true,
+            "--telegraph" => telegraph = true,
+            "--practice" => practice = true,
+            "--dev" => dev = true,
+            "--collectibles" => collect = true,
+            "--lives" => {
+                let count = args.next_if(|next| !next.starts_with("--"));
+                match count.map_or(Ok(DEFAULT_LIVES), |n| n.parse::<u32>()) {
+                    Ok(count) if count > 0 => lives = Some(Lives(count)),
+                    _ => println!("--lives takes a number of lives, at least 1"),
+                }
+            }
+            "--flock" => flock = true,
+            "--race" => race = true,
+            "--gamepad" => gamepad_only = true,
+            "--streamer" => config.streamer.enabled = true,
+            "--fullscreen" => config.window.fullscreen = true,
+            "--resolution" => match args.next().as_deref().and_then(parse_resolution) {
+                Some((width, height)) => {
+                    config.window.width = width;
+                    config.window.height = height;
+                }
+                None => println!("--resolution needs a size like 1280x720"),
+            },
+            "--mute" => muted = true,
+            "--difficulty" => match args.next().map(|name| Preset::from_name(&name)) {
+                Some(Ok(p)) => preset = Some(p),
+                Some(Err(e)) => println!("ignoring --difficulty: {}", e),
+                None => println!("--difficulty needs easy, normal or hard"),
+            },
+            "--lang" => match args.next() {
+                Some(code) => language = Some(code),
+                None => println!("--lang needs a language code, like es"),
+            },
+            "--assets" => match args.next() {
+                Some(dir) => assets = path::PathBuf::from(dir),
+                None => println!("--assets needs a directory"),
+            },
+            "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--tuning" => tuning_path = args.next().map(path::PathBuf::from),
+            "--speed" => match args.next().and_then(|s| s.parse::<f32>().ok()) {
+                Some(s) if s > 0.0 => speed = s,
+                _ => println!("--speed needs a positive number"),
+            },
+            "--marathon" => marathon = true,
+            "--demo" | "--bot" => {
+                let name = args.next_if(|next| !next.starts_with("--"));
+                let name = name.unwrap_or_else(|| "rule".to_string());
+                match policy::from_name(&name) {
+                    Ok(bot) => demo = Some(AutopilotSystem::new(bot)),
+                    Err(e) => println!("ignoring {}: {}", arg, e),
+                }
+            }
+            "--shadow" => {
+                let skill = args.next_if(|next| !next.starts_with("--"));
+                match skill.map_or(Ok(shadow::DEFAULT_SKILL), |s| s.parse::<u32>()) {
+                    Ok(skill) if skill <= shadow::MAX_SKILL => shadow_skill = Some(skill),
+                    _ => println!("--shadow takes a skill from 0 to {}", shadow::MAX_SKILL),
+                }
+            }
+            "--level" => level = Some(Level::new(level::LEVEL_LENGTH)),
+            "--timer" => timer = true,
+            "--tournament" => tournament_path = args.next().map(path::PathBuf::from),
+            "--player" => player = args.next(),
+            "--rules" => match args.next() {
+                Some(name_or_code) => custom = Some(name_or_code),
+                None => println!("--rules needs a custom game's name or code"),
+            },
+            "--hot-seat" => match args.next().map(|names| HotSeat::parse(&names)) {
+                Some(Ok(players)) => hot_seat = Some(players),
+                Some(Err(e)) => println!("ignoring --hot-seat: {}", e),
+                None => println!("--hot-seat needs a comma separated list of names"),
+            },
+            "--tas" => match args.next().map(|p| TasScript::load(path::Path::new(&p))) {
+                Some(Ok(script)) => tas_script = Some(script),
+                Some(Err(e)) => println!("ignoring --tas: {}", e),
+                None => println!("--tas needs a script file"),
+            },
+            "--record" => match args.next() {
+                Some(path) => record = Some(path::PathBuf::from(path)),
+                None => println!("--record needs a file to save replays to"),
+            },
+            "--replay" => match args.next().map(|p| ReplayFile::load(path::Path::new(&p))) {
+                Some(Ok(file)) => replay = Some(file),
+                Some(Err(e)) => println!("ignoring --replay: {}", e),
+                None => println!("--replay needs a replay file"),
+            },
+            "--livesplit" => {
+                let address = args.next_if(|next| !next.starts_with("--"));
+                livesplit_address = Some(address.unwrap_or_else(|| "localhost:16834".to_string()));
+            }
+            _ => println!("ignoring unknown argument {}", arg),
+        }
+    }
+    #[cfg(not(feature = "livesplit"))]
+    if livesplit_address.is_some() {
+        println!("built without the livesplit feature, ignoring --livesplit");
+    }
+
+    // Tournament runs have to replay exactly, so they're played on the
+    // default tuning with nothing else changing the game.
+    let mut tournament = tournament_path.map(|path| {
+        let player = player.unwrap_or_else(|| {
+            println!("--tournament needs --player NAME");
+            process::exit(2);
+        });
+        TournamentPlay::open(&path, player).unwrap_or_else(|e| {
+            println!("{}", e);
+            process::exit(1);
+        })
+    });
+    if let Some(tournament) = &tournament {
+        if demo.is_some()
+            || tas_script.is_some()
+            || replay.is_some()
+            || marathon
+            || level.is_some()
+            || drift
+            || ghost
+            || telegraph
+            || practice
+            || collect
+            || lives.is_some()
+            || flock
+            || race
+            || shadow_skill.is_some()
+            || speed != 1.0
+            || preset.is_some()
+            || dev
+            || tuning_path.is_some()
+            || hot_seat.is_some()
+            || custom.is_some()
+        {
+            println!("a tournament is played as is, ignoring the other options");
+        }
+        demo = None;
+        tas_script = None;
+        replay = None;
+        hot_seat = None;
+        custom = None;
+        marathon = false;
+        level = None;
+        drift = false;
+        ghost = false;
+        telegraph = false;
+        practice = false;
+        dev = false;
+        collect = false;
+        lives = None;
+        flock = false;
+        race = false;
+        shadow_skill = None;
+        speed = 1.0;
+        preset = Some(Preset::Normal);
+        tuning_path = None;
+        seed = Some(tournament.tournament.seed);
+    }
+
+    // Bots can't pass the keyboard around.
+    if hot_seat.is_some() && (demo.is_some() || tas_script.is_some()) {
+        println!("ignoring --hot-seat in a demo or TAS run");
+        hot_seat = None;
+    }
+    // A replay is its own input, with a bot or a script there's nothing
+    // left for it to feed.
+    if replay.is_some() && (demo.is_some() || tas_script.is_some() || hot_seat.is_some() || race) {
+        println!("ignoring --replay with a bot, a script, the hot seat or a race");
+        replay = None;
+    }
+    if replay.is_some() && record.is_some() {
+        println!("ignoring --record while playing a replay");
+        record = None;
+    }
+    // Races need two players at the keys, each with a bird of their own.
+    if race && (hot_seat.is_some() || demo.is_some() || tas_script.is_some() || flock) {
+        println!("ignoring --race with another player, a bot, a script or a flock");
+        race = false;
+    }
+    // Every player in the hot seat gets the same pipes.
+    if hot_seat.is_some() {
+        seed = seed.or_else(|| Some(rand::random()));
+    }
+    if config.streamer.enabled {
+        if let Some(hot_seat) = &mut hot_seat {
+            hot_seat.hide_names = true;
+        }
+        if let Some(tournament) = &mut tournament {
+            tournament.hide_name = true;
+        }
+    }
+
+    // Tournament runs and scripts play on the landscape screen they're
+    // checked and made on, where the pipes come into view at the same time.
+    if let (Some(aspect), None, None) = (config.window.aspect(), &tournament, &tas_script) {
+        engine::set_aspect(aspect);
+        // A window left at its default size takes the screen's shape.
+        let window = &mut config.window;
+        if window.width == engine::LANDSCAPE_WIDTH && window.height == engine::SCREEN_HEIGHT {
+            window.width = engine::screen_width();
+        }
+    }
+
+    // Bots and scripts start playing right away. They play the way they
+    // were made, whatever the settings say.
+    let in_menu = demo.is_none() && tas_script.is_none() && replay.is_none();
+    // Before the assets, as a language can bring its own font.
+    let language = language.or_else(|| settings.lang.clone());
+    if let Some(language) = language.and_then(|code| lang::load(&assets, &code)) {
+        lang::set(language);
+    }
+    let preset = match preset {
+        Some(preset) => preset,
+        None if in_menu => settings.difficulty,
+        None => Preset::Normal,
+    };
+    let mut mode = if tournament.is_some() {
+        "tournament"
+    } else if hot_seat.is_some() {
+        "hot-seat"
+    } else if demo.is_some() {
+        "demo"
+    } else if tas_script.is_some() {
+        "tas"
+    } else if replay.is_some() {
+        "replay"
+    } else if race {
+        "race"
+    } else if marathon {
+        "marathon"
+    } else if level.is_some() {
+        "level"
+    } else if seed.is_some() {
+        "seeded"
+    } else {
+        "endless"
+    }
+    .to_string();
+    if drift {
+        mode.push_str("-drift");
+    }
+    if flock {
+        mode.push_str("-flock");
+    }
+    if preset != Preset::Normal {
+        mode.push('-');
+        mode.push_str(preset.name());
+    }
+    // Coins are worth extra points, so these runs keep bests of their own.
+    if collect {
+        mode.push_str("-collectibles");
+    }
+    // Every life is another try, so each count keeps its own best.
+    if let Some(Lives(count)) = lives {
+        mode.push_str(&format!("-lives{}", count));
+    }
+
+    let branding = load_branding(&assets);
+    println!("{}", branding.title);
+    let (mut ctx, event_loop) = open_window(&branding, &assets, config.window, None);
+    let (width, height) = ctx.gfx.drawable_size();
+    if gamepad_only {
+        ggez::input::mouse::set_cursor_hidden(&mut ctx, true);
+    }
+
+    let mut tuning = match &tuning_path {
+        _ if tournament.is_some() => Tuning::default(),
+        Some(path) => Tuning::from_file(path).unwrap_or_else(|e| {
+            println!("using the tuning from {}: {}", CONFIG_FILE, e);
+            config.tuning.clone()
+        }),
+        None => config.tuning.clone(),
+    };
+    preset.apply(&mut tuning);
+    let mut rule_book = RuleBook::load(rules::rule_book_path(&ctx));
+    let custom = custom.and_then(|name_or_code| {
+        if !["endless", "seeded"].iter().any(|m| mode.starts_with(m)) || flock {
+            println!("custom games are played endless or seeded, ignoring --rules");
+            return None;
+        }
+        rule_book
+            .find_or_import(&name_or_code)
+            .map_err(|e| println!("ignoring --rules: {}", e))
+            .ok()
+    });
+    if let Some(rules) = &custom {
+        rules.apply(&mut tuning);
+        mode = custom_mode(&mode, rules);
+    }
+    if flock {
+        widen_for_flock(&mut tuning);
+    }
+    let loaded = assets::load_or_exit(&mut ctx, &assets, &Sprites::extra_images(&config.parallax));
+    let sprites = Sprites::load(&loaded, &config.parallax);
+    let bird_sheet = sprites.bird.clone();
+    let mut world = build_world(tuning, Some(sprites));
+    let skins = Skins::scan(&ctx, &bird_sheet, &world.read_resource::<PipeSprites>());
+    // The first pipes were spawned before the preset was known.
+    world.insert(preset);
+    world.insert(config.theme.pipes);
+    world.insert(settings);
+    world_setup::repaint_obstacles(&mut world);
+    if flock {
+        spawn_flock(&mut world, FLOCK_SIZE);
+    }
+    if race {
+        spawn_race(&mut world);
+        world.insert(Race::default());
+    }
+    let mut stats = Stats::load(stats::stats_path(&ctx));
+    // Only players get to see it, the next time one starts the game.
+    let whats_new = if in_menu {
+        WhatsNew::on_launch(&mut stats)
+    } else {
+        WhatsNew::default()
+    };
+    let mut death_markers = None;
+    if let Some(script) = tas_script {
+        seed = Some(script.seed);
+        world.insert(TasPlayback::new(script));
+    }
+    if let Some(file) = replay {
+        seed = Some(file.seed);
+        world.insert(InputReplay::new(file));
+    }
+    if marathon {
+        seed = Some(speedrun::MARATHON_SEED);
+        world.insert(Speedrun::marathon(stats.best_splits("marathon")));
+    } else if timer {
+        world.insert(Speedrun::endless(stats.best_splits("endless")));
+    }
+    if level.is_some() {
+        seed = seed.or(Some(level::LEVEL_SEED));
+    }
+    if drift {
+        world.insert(Drift::default());
+    }
+    if ghost {
+        world.insert(Ghost::default());
+    }
+    if telegraph {
+        world.insert(Telegraph::default());
+    }
+    if practice {
+        world.insert(Practice::default());
+    }
+    if !config.window.low_power {
+        world.insert(Camera::default());
+        world.insert(ParticleEffects);
+    }
+    let reseed = seed.is_none();
+    let seed = seed.unwrap_or_else(rand::random);
+    world.insert(Challenge::new(seed));
+    if let Some(path) = record {
+        world.insert(InputLog::new(path, seed));
+    }
+    if collect {
+        world.insert(Collectibles::new(seed));
+    }
+    if let Some(lives) = lives {
+        world.insert(lives);
+    }
+    if let Some(rules) = &custom {
+        rules.insert(&mut world);
+    }
+    if config.wellbeing.break_reminder {
+        world.insert(SessionTime::new(config.wellbeing));
+    }
+    let shadow = shadow_skill.map(|skill| ShadowRace::new(skill, &world));
+    let timeline = world
+        .has_value::<TasPlayback>()
+        .then(|| Timeline::new(&world));
+    let leaderboard = Leaderboard::new(&config.leaderboard);
+    if show_death_markers {
+        death_markers = Some(DeathMarkers::new(&ctx, &stats, seed));
+    }
+    if let Some(dir) = telemetry_dir {
+        let mut telemetry = Telemetry::new(dir);
+        telemetry.begin_run(&run_label());
+        world.insert(telemetry);
+    }
+    let streamer = &config.streamer;
+    let margin = if streamer.enabled {
+        streamer.margin
+    } else {
+        0.0
+    };
+    world.insert(Hud::new(config.hud, margin));
+    let pop_reader = world.write_resource::<GameEvents>().register_reader();
+    world.insert(ScorePopReader(pop_reader));
+    if tournament.is_some() {
+        world.insert(Replay::default());
+    }
+    let player_input = Direction::new();
+
+    let audio = match Audio::new(&ctx, config.audio) {
+        Ok(mut audio) => {
+            audio.set_volume(world.read_resource::<Settings>().volume);
+            if muted {
+                audio.toggle_mute();
+            }
+            Some(audio)
+        }
+        Err(e) => {
+            println!("sound disabled: {}", e);
+            None
+        }
+    };
+    let sound_reader = audio
+        .as_ref()
+        .map(|_| world.write_resource::<GameEvents>().register_reader());
+    let stats_reader = world.write_resource::<GameEvents>().register_reader();
+
+    if in_menu {
+        world.insert(GameState::Menu);
+        // Only people race their best run.
+        world.insert(Recording::default());
+        world.insert(GhostPlayback::default());
+        world
+            .create_entity()
+            .with(Position {
+                position: Vec2::new(BIRD_HOME_X, BIRD_START_Y),
+                speed: Vec2::ZERO,
+            })
+            .with(GhostBird)
+            .build();
+    }
+
+    let profiler = Profiler::default();
+    let mut state = State {
+        specs_world: world,
+        player_input,
+        playing: schedule::playing(profiler.system_times()),
+        scroll_system: ScrollSystem,
+        animation_system: AnimationSystem::default(),
+        dying_system: DyingSystem,
+        camera_system: CameraSystem,
+        particle_system: ParticleSystem,
+        idle_bob_system: IdleBobSystem,
+        hud_system: HudSystem,
+        stats,
+        show_stats: false,
+        title: branding.title,
+        show_observation: false,
+        inspector: Inspector::default(),
+        profiler,
+        latency: LatencyProbe::default(),
+        viewport: Viewport::fit(width, height)
+            .with_render_scale(config.window.render_scale)
+            .with_filter(config.window.filter),
+        fullscreen: config.window.fullscreen,
+        low_power: config.window.low_power,
+        last_update: Instant::now(),
+        redraw: true,
+        interpolation: Interpolation::default(),
+        death_markers,
+        level,
+        tournament,
+        hot_seat,
+        looks: Looks::load(customize::looks_path(&ctx)),
+        customizer: Customizer::default(),
+        seeds: SeedBrowser::default(),
+        whats_new,
+        rule_book,
+        rules: RulesBuilder::default(),
+        pending_profile: None,
+        profile_message: None,
+        painted_sheet: bird_sheet.clone(),
+        bird_sheet,
+        skins,
+        settings_screen: SettingsScreen::new(lang::available(&assets)),
+        themes: ThemeCycle::new(config.theme),
+        demo,
+        shadow,
+        timeline,
+        leaderboard,
+        attract: false,
+        menu_idle: 0,
+        controllers: ControllerWatch::default(),
+        gamepad_only,
+        streamer: config
+            .streamer
+            .enabled
+            .then(|| StreamOutput::new(config.streamer.file.clone())),
+        audio,
+        sound_reader,
+        stats_reader,
+        achievements: Achievements::new(),
+        speed,
+        mode,
+        // Ghost and practice runs never end on a hit, and telegraphed ones
+        // see gaps coming, so they always count as assisted. So does
+        // everything with the development keys.
+        assisted: ghost || telegraph || practice || dev,
+        dev,
+        #[cfg(feature = "livesplit")]
+        livesplit: livesplit_address.map(livesplit::LiveSplit::connect),
+        flash: 0,
+        #[cfg(feature = "hot-reload")]
+        tuning_watch: tuning_path.map(tuning::TuningWatch::new),
+        #[cfg(feature = "hot-reload")]
+        asset_watch: dev
+            .then(|| hot_reload::AssetWatch::new(assets.clone(), path::PathBuf::from(CONFIG_FILE))),
+        run_summary: Vec::new(),
+        session: Session::start(),
+        quitting: false,
+        results: ResultsPanel::new(&ctx, config.medals),
+        digits: Digits::new(&ctx),
+        card: None,
+        reseed,
+        best_runs: BestRuns::load(best_run::best_runs_path(&ctx)),
+        screenshot: false,
+        toast: None,
+        #[cfg(feature = "instant-replay")]
+        instant_replay: InstantReplay::new(),
+    };
+    state.sync_best();
+    let hud_scale = state.specs_world.read_resource::<Settings>().hud_scale;
+    state.digits.resize(hud_scale);
+    if let Some(index) = state.themes.start_run() {
+        theme::show(&state.specs_world, index);
+    }
+    state.skins.select(state.looks.skin.as_deref());
+    state.wear_skin(&ctx);
+    state.load_ghost();
+
+    event::run(ctx, event_loop, state)
+}
@@ -0,0 +1,283 @@
+use crate::engine;
+use crate::lang;
+use crate::{Image, SpriteSheet};
+use ggez::Context;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+// `assets.toml` in the assets directory: the files the game loads, by the
+// name the code asks for them by. Paths are relative to the assets
+// directory, like "/floor.png", and animations are given by the base path
+// `SpriteSheet::load` takes. Names the file leaves out keep their default,
+// so a texture pack only lists what it moves:
+//
+//     [sprites]
+//     pipe_cap = "/pipes/cap.png"
+//
+//     [animations]
+//     bird = "/birds/robin"
+//
+// The sounds are synthesized, so there are none to list.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Manifest {
+    pub sprites: BTreeMap<String, String>,
+    pub animations: BTreeMap<String, String>,
+    pub fonts: BTreeMap<String, String>,
+}
+
+fn entries(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|&(name, path)| (name.to_string(), path.to_string()))
+        .collect()
+}
+
+impl Manifest {
+    // The files the game ships with.
+    pub fn builtin() -> Self {
+        Manifest {
+            sprites: entries(&[
+                ("pipe_cap", "/pipe_cap.png"),
+                ("pipe_body", "/pipe_body.png"),
+            ]),
+            animations: entries(&[("bird", "/player")]),
+            fonts: entries(&[("text", "/8bitOperatorPlus.ttf")]),
+        }
+    }
+
+    // The built-in manifest with `contents` laid over it.
+    fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        let file: Manifest = toml::from_str(contents)?;
+        let mut manifest = Manifest::builtin();
+        manifest.sprites.extend(file.sprites);
+        manifest.animations.extend(file.animations);
+        manifest.fonts.extend(file.fonts);
+        Ok(manifest)
+    }
+
+    // A missing file means the built-in manifest; an unreadable one is
+    // reported and ignored.
+    pub fn load(assets: &Path) -> Self {
+        let path = assets.join("assets.toml");
+        match fs::read_to_string(&path) {
+            Ok(contents) => Manifest::parse(&contents).unwrap_or_else(|e| {
+                println!("ignoring invalid {}: {}", path.display(), e);
+                Manifest::builtin()
+            }),
+            Err(_) => Manifest::builtin(),
+        }
+    }
+}
+
+// The files the game ships with, built in with the `embedded-assets`
+// feature. The high density variants aren't, to keep the binary small.
+#[cfg(feature = "embedded-assets")]
+const EMBEDDED: &[(&str, &[u8])] = &[
+    (
+        "/8bitOperatorPlus.ttf",
+        include_bytes!("../assets/8bitOperatorPlus.ttf"),
+    ),
+    (
+        "/background1.png",
+        include_bytes!("../assets/background1.png"),
+    ),
+    (
+        "/background2.png",
+        include_bytes!("../assets/background2.png"),
+    ),
+    ("/floor.png", include_bytes!("../assets/floor.png")),
+    ("/icon.png", include_bytes!("../assets/icon.png")),
+    ("/pipe_body.png", include_bytes!("../assets/pipe_body.png")),
+    ("/pipe_cap.png", include_bytes!("../assets/pipe_cap.png")),
+    ("/player1.png", include_bytes!("../assets/player1.png")),
+    ("/player2.png", include_bytes!("../assets/player2.png")),
+    ("/player3.png", include_bytes!("../assets/player3.png")),
+    ("/player4.png", include_bytes!("../assets/player4.png")),
+];
+
+// The built-in copy of the file at `path`, if there is one and the assets
+// directory doesn't have its own.
+pub fn embedded(ctx: &Context, path: &str) -> Option<&'static [u8]> {
+    if ctx.fs.exists(path) {
+        return None;
+    }
+    built_in(path)
+}
+
+// The built-in copy of the file at `path`, whether or not the assets
+// directory has one, for before there's a `Context` to look in it.
+pub fn built_in(path: &str) -> Option<&'static [u8]> {
+    #[cfg(feature = "embedded-assets")]
+    {
+        EMBEDDED
+            .iter()
+            .find(|&&(name, _)| name == path)
+            .map(|&(_, bytes)| bytes)
+    }
+    #[cfg(not(feature = "embedded-assets"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+// Whether `path` can be loaded, from the assets directory or built in.
+pub fn exists(ctx: &Context, path: &str) -> bool {
+    ctx.fs.exists(path) || embedded(ctx, path).is_some()
+}
+
+// Every image, animation and font the game draws with, loaded in one pass
+// at startup so a broken asset set is reported all at once instead of
+// panicking on the first missing file.
+pub struct Assets {
+    manifest: Manifest,
+    // Keyed by path, so images listed twice are loaded once.
+    images: HashMap<String, Image>,
+    animations: HashMap<String, SpriteSheet>,
+}
+
+impl Assets {
+    // Loads everything in `manifest` plus the `extra` images the config
+    // names by path, like the scenery layers. Fails with a line for every
+    // file that's missing or can't be loaded.
+    pub fn load(
+        ctx: &mut Context,
+        manifest: Manifest,
+        extra: &[&str],
+    ) -> Result<Self, Vec<String>> {
+        let mut problems = Vec::new();
+        let mut images = HashMap::new();
+        let paths = manifest.sprites.values().map(String::as_str);
+        for path in paths.chain(extra.iter().copied()) {
+            if images.contains_key(path) {
+                continue;
+            }
+            if !exists(ctx, path) {
+                problems.push(format!("{} is missing", path));
+                continue;
+            }
+            match engine::Sprite::load(ctx, path) {
+                Ok(image) => {
+                    images.insert(path.to_string(), Image { image });
+                }
+                Err(e) => problems.push(format!("{}: {}", path, e)),
+            }
+        }
+
+        let mut animations = HashMap::new();
+        for (name, base) in &manifest.animations {
+            let (atlas, first) = (format!("{}.json", base), format!("{}1.png", base));
+            if !ctx.fs.exists(&atlas) && !exists(ctx, &first) {
+                problems.push(format!("{} and {} are both missing", atlas, first));
+                continue;
+            }
+            match SpriteSheet::load(ctx, base) {
+                Ok(sheet) => {
+                    animations.insert(name.clone(), sheet);
+                }
+                Err(e) => problems.push(format!("{}: {}", base, e)),
+            }
+        }
+
+        for (name, path) in &manifest.fonts {
+            if !exists(ctx, path) {
+                problems.push(format!("{} is missing", path));
+            } else if let Err(e) = engine::load_font(ctx, name, path) {
+                problems.push(format!("{}: {}", path, e));
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(Assets {
+            manifest,
+            images,
+            animations,
+        })
+    }
+
+    // The sprite the manifest calls `name`.
+    pub fn sprite(&self, name: &str) -> &Image {
+        self.image(&self.manifest.sprites[name])
+    }
+
+    // An image loaded by path, from the manifest or the extras.
+    pub fn image(&self, path: &str) -> &Image {
+        &self.images[path]
+    }
+
+    pub fn animation(&self, name: &str) -> &SpriteSheet {
+        &self.animations[name]
+    }
+}
+
+// The manifest in `dir`, with the language's font if it has one.
+pub fn manifest(dir: &Path) -> Manifest {
+    let mut manifest = Manifest::load(dir);
+    if let Some(font) = lang::font() {
+        manifest.fonts.insert("text".to_string(), font.to_string());
+    }
+    manifest
+}
+
+// Loads the assets for a window opened on `dir`, or explains what's wrong
+// with them and quits.
+pub fn load_or_exit(ctx: &mut Context, dir: &Path, extra: &[&str]) -> Assets {
+    Assets::load(ctx, manifest(dir), extra).unwrap_or_else(|problems| {
+        println!(
+            "can't start, the assets in {} are incomplete:",
+            dir.display()
+        );
+        for problem in problems {
+            println!("  {}", problem);
+        }
+        println!("reinstall the game, or pass --assets with a complete set");
+        std::process::exit(1);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_manifest_only_replaces_what_it_names() {
+        let manifest = Manifest::parse(
+            r#"
+            [sprites]
+            pipe_cap = "/pipes/cap.png"
+            sun = "/sun.png"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.sprites["pipe_cap"], "/pipes/cap.png");
+        assert_eq!(manifest.sprites["pipe_body"], "/pipe_body.png");
+        assert_eq!(manifest.sprites["sun"], "/sun.png");
+        assert_eq!(manifest.animations, Manifest::builtin().animations);
+        assert!(Manifest::parse("sprites = 3").is_err());
+    }
+
+    #[cfg(feature = "embedded-assets")]
+    #[test]
+    fn every_default_file_is_built_in() {
+        let built_in = |path: &str| EMBEDDED.iter().any(|&(name, _)| name == path);
+        let manifest = Manifest::builtin();
+        let frames = manifest
+            .animations
+            .values()
+            .map(|base| format!("{}1.png", base));
+        let files = manifest.sprites.values().chain(manifest.fonts.values());
+
+        assert!(files.cloned().chain(frames).all(|path| built_in(&path)));
+        assert!(built_in(&crate::branding::Branding::default().icon));
+        let parallax = crate::parallax::Parallax::default();
+        assert!(crate::world_setup::Sprites::extra_images(&parallax)
+            .into_iter()
+            .all(built_in));
+    }
+}
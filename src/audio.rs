@@ -0,0 +1,336 @@
+use crate::GameEvent;
+use ggez::audio::{self, SoundSource};
+use ggez::{Context, GameResult};
+use serde::Deserialize;
+use std::f32::consts::PI;
+
+const SAMPLE_RATE: u32 = 22_050;
+// Every sound is rendered once per pan position, from hard left to hard
+// right, since playback can't pan on the fly.
+const PAN_STEPS: usize = 9;
+
+const FLAP_SECONDS: f32 = 0.12;
+const DING_SECONDS: f32 = 0.25;
+const WHOOSH_SECONDS: f32 = 0.35;
+const CRASH_SECONDS: f32 = 0.4;
+const PICKUP_SECONDS: f32 = 0.2;
+// How far the whoosh sweeps across the stereo field as the pipe goes by.
+const WHOOSH_SWEEP: f32 = 0.5;
+
+// The background tune, in semitones above A3, one note per beat. It loops
+// every `MUSIC_NOTES.len() * MUSIC_BEAT` seconds.
+const MUSIC_NOTES: [i32; 32] = [
+    0, 7, 12, 7, 3, 7, 10, 7, 0, 7, 12, 15, 12, 10, 7, 3, //
+    5, 12, 17, 12, 8, 12, 15, 12, 3, 10, 15, 10, 7, 5, 3, 2,
+];
+const MUSIC_BEAT: f32 = 0.25;
+const MUSIC_VOLUME: f32 = 0.3;
+
+// How the music backs off while the game is paused or over, from the
+// `[audio]` section of `config.toml`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    // Seconds to fade down when pausing or crashing.
+    pub duck_seconds: f32,
+    // Seconds to fade back up when play resumes.
+    pub restore_seconds: f32,
+    // The music's volume while ducked, as a fraction of its usual one.
+    pub ducked_volume: f32,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            duck_seconds: 0.5,
+            restore_seconds: 0.8,
+            ducked_volume: 0.2,
+        }
+    }
+}
+
+// A volume sliding linearly towards a target, so it never jumps.
+#[derive(Clone, Copy, Debug)]
+struct Envelope {
+    level: f32,
+    target: f32,
+    // Change per second.
+    rate: f32,
+}
+
+impl Envelope {
+    fn new(level: f32) -> Self {
+        Envelope {
+            level,
+            target: level,
+            rate: 0.0,
+        }
+    }
+
+    // Starts heading for `target`, getting there in `seconds` from the
+    // current level. Retargeting mid-fade carries on from where it is.
+    fn fade_to(&mut self, target: f32, seconds: f32) {
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        self.rate = if seconds > 0.0 {
+            (target - self.level).abs() / seconds
+        } else {
+            f32::INFINITY
+        };
+    }
+
+    fn step(&mut self, dt: f32) -> f32 {
+        let step = self.rate * dt;
+        self.level = if self.level < self.target {
+            (self.level + step).min(self.target)
+        } else {
+            (self.level - step).max(self.target)
+        };
+        self.level
+    }
+}
+
+pub struct Audio {
+    flap: audio::Source,
+    dings: Vec<audio::Source>,
+    whooshes: Vec<audio::Source>,
+    crash: audio::Source,
+    pickup: audio::Source,
+    music: audio::Source,
+    muted: bool,
+    // Of everything, from the settings.
+    volume: f32,
+    config: AudioConfig,
+    // Scales the music's volume, see `duck`.
+    ducking: Envelope,
+}
+
+impl Audio {
+    pub fn new(ctx: &Context, config: AudioConfig) -> GameResult<Self> {
+        let mut dings = Vec::with_capacity(PAN_STEPS);
+        let mut whooshes = Vec::with_capacity(PAN_STEPS);
+        for step in 0..PAN_STEPS {
+            let pan = step_pan(step);
+            dings.push(source(ctx, &render(DING_SECONDS, ding, |_| pan))?);
+            whooshes.push(source(
+                ctx,
+                &render(WHOOSH_SECONDS, whoosh(), |t| {
+                    pan + WHOOSH_SWEEP * (1.0 - 2.0 * t / WHOOSH_SECONDS)
+                }),
+            )?);
+        }
+
+        let flap = source(ctx, &render(FLAP_SECONDS, flap, |_| 0.0))?;
+        let crash = source(ctx, &render(CRASH_SECONDS, crash(), |_| 0.0))?;
+        let pickup = source(ctx, &render(PICKUP_SECONDS, pickup, |_| 0.0))?;
+
+        let music_seconds = MUSIC_NOTES.len() as f32 * MUSIC_BEAT;
+        let mut music = source(ctx, &render(music_seconds, music, |_| 0.0))?;
+        music.set_repeat(true);
+        music.set_volume(MUSIC_VOLUME);
+        music.play(ctx)?;
+
+        Ok(Audio {
+            flap,
+            dings,
+            whooshes,
+            crash,
+            pickup,
+            music,
+            muted: false,
+            volume: 1.0,
+            config,
+            ducking: Envelope::new(1.0),
+        })
+    }
+
+    // Fades the music down while `ducked`, e.g. paused or after a crash,
+    // and back up once it isn't. Takes effect over the following `update`s.
+    pub fn duck(&mut self, ducked: bool) {
+        let config = self.config;
+        if ducked {
+            self.ducking
+                .fade_to(config.ducked_volume.clamp(0.0, 1.0), config.duck_seconds);
+        } else {
+            self.ducking.fade_to(1.0, config.restore_seconds);
+        }
+    }
+
+    // Moves any fade on by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        let before = self.ducking.level;
+        let level = self.ducking.step(dt);
+        if level != before {
+            self.music.set_volume(MUSIC_VOLUME * level * self.volume);
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        self.music
+            .set_volume(MUSIC_VOLUME * self.ducking.level * volume);
+    }
+
+    // Silences, or brings back, the music and every sound effect.
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        if self.muted {
+            self.music.pause();
+        } else {
+            self.music.resume();
+        }
+    }
+
+    // The sound for `event`, if it has one. Fed every update from
+    // `GameEvents`, see `State::play_sounds`.
+    pub fn play(&mut self, ctx: &Context, event: &GameEvent) -> GameResult {
+        if self.muted {
+            return Ok(());
+        }
+
+        let volume = self.volume;
+        // A source's volume only reaches the sound it plays next.
+        let fire = |source: &mut audio::Source| {
+            source.set_volume(volume);
+            source.play_detached(ctx)
+        };
+        match event {
+            GameEvent::Flapped => fire(&mut self.flap),
+            GameEvent::Passed { pan } => {
+                let step = pan_step(*pan);
+                fire(&mut self.dings[step])?;
+                fire(&mut self.whooshes[step])
+            }
+            GameEvent::Crashed => fire(&mut self.crash),
+            GameEvent::PickedUp => fire(&mut self.pickup),
+            GameEvent::Died(_) => Ok(()),
+        }
+    }
+}
+
+fn step_pan(step: usize) -> f32 {
+    2.0 * step as f32 / (PAN_STEPS - 1) as f32 - 1.0
+}
+
+fn pan_step(pan: f32) -> usize {
+    ((pan.clamp(-1.0, 1.0) + 1.0) / 2.0 * (PAN_STEPS - 1) as f32).round() as usize
+}
+
+// A quick chirp sweeping upwards.
+fn flap(t: f32) -> f32 {
+    0.3 * (2.0 * PI * (400.0 + 2500.0 * t) * t).sin() * (-30.0 * t).exp()
+}
+
+fn ding(t: f32) -> f32 {
+    0.4 * (2.0 * PI * 880.0 * t).sin() * (-12.0 * t).exp()
+}
+
+// Low passed noise swelling in and out.
+fn whoosh() -> impl FnMut(f32) -> f32 {
+    let mut seed: u32 = 0x2545_f491;
+    let mut filtered = 0.0;
+    move |t| {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        let noise = (seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0;
+        filtered += 0.08 * (noise - filtered);
+        0.6 * filtered * (PI * t / WHOOSH_SECONDS).sin()
+    }
+}
+
+// Two quick rising notes, like a coin.
+fn pickup(t: f32) -> f32 {
+    let pitch = if t < 0.06 { 988.0 } else { 1319.0 };
+    0.3 * (2.0 * PI * pitch * t).sin() * (-10.0 * t).exp()
+}
+
+// A thud: a falling tone under a burst of noise, both dying out fast.
+fn crash() -> impl FnMut(f32) -> f32 {
+    let mut noise = whoosh();
+    move |t| {
+        let thud = (2.0 * PI * (120.0 - 150.0 * t) * t).sin();
+        (0.5 * thud + 2.0 * noise(t * WHOOSH_SECONDS / CRASH_SECONDS)) * (-8.0 * t).exp()
+    }
+}
+
+// A triangle wave lead playing `MUSIC_NOTES` over a sine bass that follows
+// the first note of every bar.
+fn music(t: f32) -> f32 {
+    const BAR: usize = 8;
+    let beat = (t / MUSIC_BEAT) as usize;
+    let bar = beat / BAR * BAR;
+    let semitone = |n: i32| 220.0 * 2f32.powf(n as f32 / 12.0);
+
+    let phase = (semitone(MUSIC_NOTES[beat % MUSIC_NOTES.len()]) * t).fract();
+    let lead =
+        (4.0 * (phase - 0.5).abs() - 1.0) * envelope(t - beat as f32 * MUSIC_BEAT, MUSIC_BEAT, 6.0);
+
+    let root = semitone(MUSIC_NOTES[bar % MUSIC_NOTES.len()] - 12);
+    let bass = (2.0 * PI * root * t).sin()
+        * envelope(t - bar as f32 * MUSIC_BEAT, BAR as f32 * MUSIC_BEAT, 1.5);
+
+    0.25 * lead + 0.3 * bass
+}
+
+// Volume `t` seconds into a note `length` long: a few milliseconds of
+// attack, an exponential decay and a fade to silence by the end, so notes
+// and the loop seam don't click.
+fn envelope(t: f32, length: f32, decay: f32) -> f32 {
+    (t * 200.0).min(1.0) * (-decay * t).exp() * (1.0 - t / length)
+}
+
+// Renders a mono sound as 16-bit stereo WAV, panned with equal power
+// between the channels according to `pan` at every instant.
+fn render(seconds: f32, mut sample: impl FnMut(f32) -> f32, pan: impl Fn(f32) -> f32) -> Vec<u8> {
+    let frames = (seconds * SAMPLE_RATE as f32) as u32;
+    let data_len = frames * 4;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 4).to_le_bytes());
+    wav.extend_from_slice(&4u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    for frame in 0..frames {
+        let t = frame as f32 / SAMPLE_RATE as f32;
+        let value = sample(t);
+        let angle = (pan(t).clamp(-1.0, 1.0) + 1.0) * PI / 4.0;
+        for gain in [angle.cos(), angle.sin()].iter() {
+            let pcm = (value * gain).clamp(-1.0, 1.0) * i16::MAX as f32;
+            wav.extend_from_slice(&(pcm as i16).to_le_bytes());
+        }
+    }
+    wav
+}
+
+fn source(ctx: &Context, wav: &[u8]) -> GameResult<audio::Source> {
+    audio::Source::from_data(ctx, audio::SoundData::from_bytes(wav))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_take_their_configured_time() {
+        let mut envelope = Envelope::new(1.0);
+        envelope.fade_to(0.2, 0.5);
+        assert!((envelope.step(0.25) - 0.6).abs() < 1e-5);
+        assert_eq!(envelope.step(0.5), 0.2);
+
+        // Resuming halfway down comes back up at the restore pace.
+        envelope.fade_to(1.0, 0.8);
+        assert!((envelope.step(0.4) - 0.6).abs() < 1e-5);
+        envelope.fade_to(0.2, 0.0);
+        assert_eq!(envelope.step(0.0), 0.2);
+    }
+}
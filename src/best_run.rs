@@ -0,0 +1,169 @@
+use crate::engine::{Color, DrawCmd, Sprite, Vec2};
+use crate::{Direction, Game, GhostBird, PlayerTag, Position, TimeScale};
+use ggez::Context;
+use serde::{Deserialize, Serialize};
+use specs::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+const GHOST_TINT: Color = Color::new(1.0, 1.0, 1.0, 0.4);
+const SHADOW_TINT: Color = Color::new(0.15, 0.15, 0.3, 0.6);
+
+// A run as the ghost bird replays it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BestRun {
+    pub score: i32,
+    // Where the bird was at the start of every tick played, as [x, y].
+    pub positions: Vec<[f32; 2]>,
+    // Ticks the bird flapped on, counted like `Game::frames`.
+    pub flaps: Vec<i32>,
+}
+
+// The best run of each mode, keyed by `State::mode`, persisted as JSON in
+// the user data directory.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BestRuns {
+    runs: HashMap<String, BestRun>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+pub fn best_runs_path(ctx: &Context) -> PathBuf {
+    ctx.fs.user_data_dir().join("best_runs.json")
+}
+
+impl BestRuns {
+    // Missing or unreadable files mean no best runs yet.
+    pub fn load(path: PathBuf) -> Self {
+        let mut runs = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                println!("ignoring corrupt best runs file {}: {}", path.display(), e);
+                BestRuns::default()
+            }),
+            Err(_) => BestRuns::default(),
+        };
+        runs.path = Some(path);
+        runs
+    }
+
+    pub fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string(self)?;
+                fs::write(path, json)
+            });
+        if let Err(e) = result {
+            println!("can't save best runs to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn get(&self, mode: &str) -> Option<&BestRun> {
+        self.runs.get(mode)
+    }
+
+    // Keeps `run` if it beats the mode's best. True if it did.
+    pub fn record(&mut self, mode: &str, run: BestRun) -> bool {
+        if self
+            .runs
+            .get(mode)
+            .is_some_and(|best| best.score >= run.score)
+        {
+            return false;
+        }
+        self.runs.insert(mode.to_string(), run);
+        true
+    }
+}
+
+// The run in progress, recorded by `ReplayRecorderSystem`. Only present in
+// runs played by a person.
+#[derive(Default)]
+pub struct Recording(pub BestRun);
+
+// Runs right before `MovementSystem`, like `ReplaySystem`, catching the
+// flaps it's about to do.
+pub struct ReplayRecorderSystem;
+
+impl<'a> System<'a> for ReplayRecorderSystem {
+    type SystemData = (
+        Option<Write<'a, Recording>>,
+        Read<'a, Direction>,
+        Read<'a, Game>,
+        Read<'a, TimeScale>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PlayerTag>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (recording, dir, game, time, positions, players) = data;
+        let mut recording = match recording {
+            Some(recording) if !time.is_paused() => recording,
+            _ => return,
+        };
+        if dir.jump && dir.release {
+            recording.0.flaps.push(game.frames);
+        }
+        for (pos, _) in (&positions, &players).join() {
+            recording.0.positions.push([pos.position.x, pos.position.y]);
+        }
+    }
+}
+
+// The best run being raced, if there is one for the mode.
+#[derive(Default)]
+pub struct GhostPlayback(pub Option<BestRun>);
+
+impl GhostPlayback {
+    // Whether the ghost is still flying at `frames` into the run.
+    pub fn flying(&self, frames: i32) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|run| frames >= 1 && (frames as usize) <= run.positions.len())
+    }
+}
+
+// Moves the ghost bird to where the best run was on this tick.
+pub struct GhostPlaybackSystem;
+
+impl<'a> System<'a> for GhostPlaybackSystem {
+    type SystemData = (
+        Option<Read<'a, GhostPlayback>>,
+        Read<'a, Game>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, GhostBird>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (playback, game, mut positions, ghosts) = data;
+        let run = match playback.as_ref().and_then(|p| p.0.as_ref()) {
+            Some(run) => run,
+            None => return,
+        };
+        let at = match run.positions.get((game.frames - 1).max(0) as usize) {
+            Some(&[x, y]) => Vec2::new(x, y),
+            None => return,
+        };
+        for (pos, _) in (&mut positions, &ghosts).join() {
+            pos.position = at;
+        }
+    }
+}
+
+// The ghost bird, translucent, in the frame the bird is showing.
+pub fn draw<'a>(cmds: &mut Vec<DrawCmd<'a>>, frame: &'a Sprite, at: Vec2) {
+    cmds.push(DrawCmd::tinted_sprite(frame, at, GHOST_TINT));
+}
+
+// The shadow race's bot, dark so it isn't taken for the best run's ghost.
+pub fn draw_shadow<'a>(cmds: &mut Vec<DrawCmd<'a>>, frame: &'a Sprite, at: Vec2) {
+    cmds.push(DrawCmd::tinted_sprite(frame, at, SHADOW_TINT));
+}
@@ -0,0 +1,83 @@
+use crate::assets;
+use ggez::graphics::Image;
+use ggez::winit::window::Icon;
+use ggez::{Context, GameError, GameResult};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// Name and window icon, read from `branding.toml` in the assets directory
+// so forks and skins can rebrand the game along with its art. Any field
+// missing from the file keeps its default value.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Branding {
+    pub title: String,
+    // Image in the assets directory, e.g. "/icon.png", or a built-in one.
+    // Empty for none.
+    pub icon: String,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Branding {
+            title: "Rusty Bird".to_string(),
+            icon: "/icon.png".to_string(),
+        }
+    }
+}
+
+impl Branding {
+    // A missing file means the default branding; an unreadable one is
+    // reported and ignored.
+    pub fn load(assets: &Path) -> Self {
+        let path = assets.join("branding.toml");
+        let mut branding = match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                println!("ignoring invalid {}: {}", path.display(), e);
+                Branding::default()
+            }),
+            Err(_) => Branding::default(),
+        };
+
+        if !branding.icon.is_empty()
+            && !branding.icon_on_disk(assets)
+            && assets::built_in(&branding.icon).is_none()
+        {
+            println!("icon {} not found in {}", branding.icon, assets.display());
+            branding.icon.clear();
+        }
+        branding
+    }
+
+    fn icon_on_disk(&self, assets: &Path) -> bool {
+        assets.join(self.icon.trim_start_matches('/')).exists()
+    }
+
+    // The icon for ggez to open the window with. It only looks in the assets
+    // directory, and refuses to open the window at all if the icon isn't
+    // there, so a built-in one is left to `set_built_in_icon`.
+    pub fn window_icon(&self, assets: &Path) -> String {
+        if self.icon_on_disk(assets) {
+            self.icon.clone()
+        } else {
+            String::new()
+        }
+    }
+
+    // Puts the built-in copy of the icon on a window opened without one.
+    pub fn set_built_in_icon(&self, ctx: &Context, assets: &Path) -> GameResult<()> {
+        if self.icon.is_empty() || self.icon_on_disk(assets) {
+            return Ok(());
+        }
+        let bytes = match assets::built_in(&self.icon) {
+            Some(bytes) => bytes,
+            None => return Ok(()),
+        };
+        let image = Image::from_bytes(ctx, bytes)?;
+        let icon = Icon::from_rgba(image.to_pixels(ctx)?, image.width(), image.height())
+            .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", self.icon, e)))?;
+        ctx.gfx.window().set_window_icon(Some(icon));
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::{BackgroundTag, CollisionBox, Difficulty, ObstacleTag, PipeColor, Position};
+use rand::{Rng, RngCore};
+use specs::world::EntitiesRes;
+use specs::LazyUpdate;
+
+// One in this many pipe slots starts a cave.
+const CAVE_CHANCE: u32 = 8;
+// A cave lasts this many pipe slots, upper bound excluded. The last one is
+// left open, so there's a full slot of air before the next pipe pair.
+const CAVE_SLOTS: (u32, u32) = (4, 7);
+
+const SEGMENT_WIDTH: f32 = 32.0;
+
+// The gap starts wide and closes in by GAP_STEP every segment. A flap
+// lifts the bird about 170 pixels, so even the narrowest gap leaves room
+// for a full flap above the bird's 72 pixel height.
+const START_GAP: f32 = 400.0;
+const MIN_GAP: f32 = 290.0;
+const GAP_STEP: f32 = 4.0;
+// How far the gap's center can wander between two segments.
+const JAG: f32 = 12.0;
+const CEILING_MIN: f32 = 30.0;
+const FLOOR_MAX: f32 = 500.0;
+
+// Spawn state for cave sections: a cave replaces pipes for a few slots and
+// is built as a run of jagged ceiling and floor segments.
+#[derive(Clone, Default)]
+pub struct Caves {
+    slots_left: u32,
+    segments: u32,
+    center: f32,
+    // World x where the last segment built so far ends.
+    end: f32,
+}
+
+impl Caves {
+    // Decides whether the slot being spawned is part of a cave, possibly
+    // starting a new one.
+    pub fn roll(&mut self, rng: &mut dyn RngCore) -> bool {
+        if self.slots_left == 0 && rng.gen_ratio(1, CAVE_CHANCE) {
+            *self = Caves {
+                slots_left: rng.gen_range(CAVE_SLOTS.0, CAVE_SLOTS.1),
+                segments: 0,
+                center: (CEILING_MIN + FLOOR_MAX) / 2.0,
+                end: 0.0,
+            };
+        }
+        self.slots_left > 0
+    }
+
+    // Builds the cave for the slot at screen x `spawn_x`, reaching the
+    // difficulty's spacing ahead so the next slot continues it without a
+    // hole. The slot itself is an invisible top obstacle without a collision
+    // box, so it triggers the next spawn and counts as passed just like a
+    // top pipe.
+    pub(crate) fn spawn_slot(
+        &mut self,
+        spawn_x: f32,
+        distance: f32,
+        difficulty: &Difficulty,
+        rng: &mut dyn RngCore,
+        entities: &EntitiesRes,
+        updater: &LazyUpdate,
+    ) {
+        self.slots_left -= 1;
+
+        let slot = entities.create();
+        updater.insert(
+            slot,
+            Position {
+                position: Vec2::new(spawn_x, 0.0),
+                speed: Vec2::ZERO,
+            },
+        );
+        updater.insert(
+            slot,
+            BackgroundTag {
+                velocity: difficulty.speed,
+                width: 64.0,
+                num_copies: 1,
+            },
+        );
+        updater.insert(
+            slot,
+            ObstacleTag {
+                top: true,
+                passed: false,
+                scored: false,
+                cave: true,
+                hazard: None,
+                color: PipeColor::Green,
+            },
+        );
+
+        let start = distance + spawn_x;
+        let reach = if self.slots_left == 0 {
+            0.0
+        } else {
+            difficulty.spacing
+        };
+        let mut x = self.end.max(start);
+        while x < start + reach {
+            let gap = (START_GAP - GAP_STEP * self.segments as f32).max(MIN_GAP);
+            self.center = (self.center + rng.gen_range(-JAG, JAG))
+                .clamp(CEILING_MIN + gap / 2.0, FLOOR_MAX - gap / 2.0);
+            let ceiling = self.center - gap / 2.0;
+            let floor = self.center + gap / 2.0;
+
+            let screen_x = x - distance;
+            for (y, height) in [(0.0, ceiling), (floor, engine::SCREEN_HEIGHT - floor)].iter() {
+                let segment = entities.create();
+                updater.insert(
+                    segment,
+                    Position {
+                        position: Vec2::new(screen_x, *y),
+                        speed: Vec2::ZERO,
+                    },
+                );
+                updater.insert(
+                    segment,
+                    BackgroundTag {
+                        velocity: difficulty.speed,
+                        width: SEGMENT_WIDTH,
+                        num_copies: 1,
+                    },
+                );
+                updater.insert(
+                    segment,
+                    ObstacleTag {
+                        top: false,
+                        passed: false,
+                        scored: false,
+                        cave: true,
+                        hazard: None,
+                        color: PipeColor::Green,
+                    },
+                );
+                updater.insert(
+                    segment,
+                    CollisionBox {
+                        origin: Vec2::new(screen_x, *y),
+                        height: *height,
+                        width: SEGMENT_WIDTH,
+                    },
+                );
+            }
+
+            x += SEGMENT_WIDTH;
+            self.segments += 1;
+        }
+        self.end = x;
+    }
+}
+
+// Draws one ceiling or floor segment as rock with a lighter edge facing
+// the gap.
+pub(crate) fn draw_segment(cmds: &mut Vec<DrawCmd>, coll_box: &CollisionBox) {
+    let edge = 6.0;
+    let ceiling = coll_box.origin.y <= 0.0;
+
+    cmds.push(DrawCmd::Rect {
+        rect: Rect::new(
+            coll_box.origin.x,
+            coll_box.origin.y,
+            coll_box.width,
+            coll_box.height,
+        ),
+        color: Color::new(0.35, 0.27, 0.22, 1.0),
+    });
+    let edge_y = if ceiling {
+        coll_box.origin.y + coll_box.height - edge
+    } else {
+        coll_box.origin.y
+    };
+    cmds.push(DrawCmd::Rect {
+        rect: Rect::new(coll_box.origin.x, edge_y, coll_box.width, edge),
+        color: Color::new(0.55, 0.45, 0.35, 1.0),
+    });
+}
@@ -0,0 +1,235 @@
+use crate::engine::{Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::{
+    BackgroundTag, Collectible, CollectibleKind, CollisionBox, Difficulty, Game, Position,
+    VerticalOscillator, PHYSICS_FPS,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use specs::world::EntitiesRes;
+use specs::LazyUpdate;
+
+pub const COLLECTIBLE_SIZE: f32 = 28.0;
+// Chance a pipe pair has something in its gap.
+const SPAWN_CHANCE: f64 = 0.3;
+// How likely each kind is, relative to the others.
+const WEIGHTS: [(CollectibleKind, u32); 3] = [
+    (CollectibleKind::Coin, 6),
+    (CollectibleKind::Shield, 1),
+    (CollectibleKind::SlowMotion, 1),
+];
+// Points a coin is worth on top of the pipes'.
+const COIN_BONUS: i32 = 2;
+const SLOW_MOTION_TICKS: u32 = 5 * PHYSICS_FPS;
+const SLOW_MOTION_SCALE: f32 = 0.6;
+// Ticks after a shield breaks that nothing else can hit, to get clear of
+// whatever broke it.
+const SHIELD_GRACE_TICKS: u32 = 3 * PHYSICS_FPS / 4;
+// Mixed into the run's seed, so the pickups don't follow the pipes' draws.
+const SEED_SALT: u64 = 0x636f_696e;
+
+// Collectibles mode: coins, shields and slow motion float in the gaps of
+// some pipe pairs. A coin is worth `COIN_BONUS` points, a shield survives
+// the next hit, breaking the pipe like a ghost hit, and slow motion runs
+// the game slower for a few seconds. Pickups come from a generator of their
+// own, seeded from the run's, so seeded runs get the same pipes and the
+// same pickups either way.
+#[derive(Clone)]
+pub struct Collectibles {
+    rng: StdRng,
+    pub shield: bool,
+    // Ticks of slow motion left.
+    pub slow_motion: u32,
+    // Ticks left before the bird can be hit again after a shield broke.
+    pub grace: u32,
+}
+
+impl Collectibles {
+    pub fn new(seed: u64) -> Self {
+        Collectibles {
+            rng: StdRng::seed_from_u64(seed ^ SEED_SALT),
+            shield: false,
+            slow_motion: 0,
+            grace: 0,
+        }
+    }
+
+    // What, if anything, goes in the gap of the pair being spawned.
+    pub(crate) fn roll(&mut self) -> Option<CollectibleKind> {
+        if !self.rng.gen_bool(SPAWN_CHANCE) {
+            return None;
+        }
+        let total: u32 = WEIGHTS.iter().map(|&(_, weight)| weight).sum();
+        let mut pick = self.rng.gen_range(0, total);
+        for &(kind, weight) in WEIGHTS.iter() {
+            if pick < weight {
+                return Some(kind);
+            }
+            pick -= weight;
+        }
+        None
+    }
+
+    pub(crate) fn pick_up(&mut self, kind: CollectibleKind, game: &mut Game) {
+        match kind {
+            CollectibleKind::Coin => {
+                game.score += COIN_BONUS;
+                game.best = game.best.max(game.score);
+            }
+            CollectibleKind::Shield => self.shield = true,
+            CollectibleKind::SlowMotion => self.slow_motion = SLOW_MOTION_TICKS,
+        }
+    }
+
+    // Uses up the shield on a hit, if there is one.
+    pub(crate) fn break_shield(&mut self) -> bool {
+        let shielded = self.shield;
+        if shielded {
+            self.shield = false;
+            self.grace = SHIELD_GRACE_TICKS;
+        }
+        shielded
+    }
+
+    // Multiplied into the time scale while slow motion lasts.
+    pub fn time_scale(&self) -> f32 {
+        if self.slow_motion > 0 {
+            SLOW_MOTION_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    // Counts the effects down, once every unpaused tick.
+    pub fn tick(&mut self) {
+        self.slow_motion = self.slow_motion.saturating_sub(1);
+        self.grace = self.grace.saturating_sub(1);
+    }
+
+    // The effects in play, for the HUD, at `at`.
+    pub fn draw_status(&self, cmds: &mut Vec<DrawCmd>, at: Vec2) {
+        let mut lines = Vec::new();
+        if self.shield {
+            lines.push(lang::tr("shield").to_string());
+        }
+        if self.slow_motion > 0 {
+            let seconds = self.slow_motion.div_ceil(PHYSICS_FPS);
+            lines.push(lang::fill("slow_motion", &[&seconds]));
+        }
+        for (n, line) in lines.into_iter().enumerate() {
+            cmds.push(DrawCmd::text(
+                line,
+                at + Vec2::new(0.0, 22.0 * n as f32),
+                20.0,
+                Color::new(1.0, 0.9, 0.3, 1.0),
+            ));
+        }
+    }
+}
+
+// Puts a `kind` pickup centered on `center`, the middle of a pipe pair's
+// gap. With a `swing` it moves along with the pair.
+pub(crate) fn spawn(
+    kind: CollectibleKind,
+    center: Vec2,
+    difficulty: &Difficulty,
+    swing: Option<VerticalOscillator>,
+    entities: &EntitiesRes,
+    updater: &LazyUpdate,
+) {
+    let x = center.x - COLLECTIBLE_SIZE / 2.0;
+    let y = center.y - COLLECTIBLE_SIZE / 2.0;
+    let offset = swing.map_or(0.0, |swing| swing.amplitude * swing.phase.sin());
+
+    let entity = entities.create();
+    updater.insert(
+        entity,
+        Position {
+            position: Vec2::new(x, y + offset),
+            speed: Vec2::ZERO,
+        },
+    );
+    updater.insert(
+        entity,
+        BackgroundTag {
+            velocity: difficulty.speed,
+            width: COLLECTIBLE_SIZE,
+            num_copies: 1,
+        },
+    );
+    updater.insert(entity, Collectible { kind });
+    updater.insert(
+        entity,
+        CollisionBox {
+            origin: Vec2::new(x, y + offset),
+            height: COLLECTIBLE_SIZE,
+            width: COLLECTIBLE_SIZE,
+        },
+    );
+    if let Some(swing) = swing {
+        updater.insert(entity, VerticalOscillator { base_y: y, ..swing });
+    }
+}
+
+// Draws a pickup whose top left corner is at `at`.
+pub(crate) fn draw(cmds: &mut Vec<DrawCmd>, at: Vec2, kind: CollectibleKind) {
+    let radius = COLLECTIBLE_SIZE / 2.0;
+    let center = at + Vec2::new(radius, radius);
+    let white = Color::new(1.0, 1.0, 1.0, 0.9);
+    match kind {
+        CollectibleKind::Coin => {
+            cmds.push(DrawCmd::Circle {
+                center,
+                radius,
+                color: Color::new(0.85, 0.6, 0.1, 1.0),
+            });
+            cmds.push(DrawCmd::Circle {
+                center,
+                radius: radius - 4.0,
+                color: Color::new(1.0, 0.85, 0.2, 1.0),
+            });
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(center.x - 2.0, center.y - 7.0, 4.0, 14.0),
+                color: Color::new(0.85, 0.6, 0.1, 1.0),
+            });
+        }
+        CollectibleKind::Shield => {
+            cmds.push(DrawCmd::Circle {
+                center,
+                radius,
+                color: Color::new(0.3, 0.7, 1.0, 0.9),
+            });
+            cmds.push(DrawCmd::Circle {
+                center,
+                radius: radius - 5.0,
+                color: Color::new(0.6, 0.9, 1.0, 0.9),
+            });
+        }
+        CollectibleKind::SlowMotion => {
+            cmds.push(DrawCmd::Circle {
+                center,
+                radius,
+                color: Color::new(0.6, 0.35, 0.9, 1.0),
+            });
+            // Clock hands.
+            for &to in [Vec2::new(0.0, -9.0), Vec2::new(6.0, 0.0)].iter() {
+                cmds.push(DrawCmd::Line {
+                    from: center,
+                    to: center + to,
+                    width: 3.0,
+                    color: white,
+                });
+            }
+        }
+    }
+}
+
+// A bubble around a shielded bird whose box is `coll_box`, drawn at `at`.
+pub(crate) fn draw_shield(cmds: &mut Vec<DrawCmd>, coll_box: &CollisionBox, at: Vec2) {
+    let half = Vec2::new(coll_box.width / 2.0, coll_box.height / 2.0);
+    cmds.push(DrawCmd::Circle {
+        center: at + half,
+        radius: half.x.max(half.y) + 6.0,
+        color: Color::new(0.4, 0.8, 1.0, 0.35),
+    });
+}
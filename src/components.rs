@@ -0,0 +1,381 @@
+use crate::assets;
+use crate::engine::{Color, Rect, Sprite, Vec2};
+use crate::tuning::HitboxShape;
+use ggez::{Context, GameError, GameResult};
+use serde::Deserialize;
+use specs::*;
+use specs_derive::*;
+use std::io::Read;
+
+#[derive(Component, Debug, Clone)]
+#[storage(VecStorage)]
+pub struct Image {
+    pub image: Sprite,
+}
+
+#[derive(Component, Clone, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct Position {
+    pub position: Vec2,
+    pub speed: Vec2,
+}
+
+// Animation frames cut from a single texture, so they share its memory and
+// draw in one batch.
+#[derive(Component, Clone, Debug)]
+#[storage(VecStorage)]
+pub struct SpriteSheet {
+    pub current_frame: u32,
+    sheet: Sprite,
+    // Where each frame is on the sheet, in its size on screen.
+    rects: Vec<Rect>,
+    frames: Vec<Sprite>,
+    // Where the frame shown after a crash is, if the sheet has one.
+    hit: Option<(Rect, Sprite)>,
+}
+
+// A sprite sheet's layout, in `<name>.json` next to its image: either
+// every frame's `[x, y, width, height]`, in order,
+//
+//     {"image": "/player.png", "frames": [[0, 0, 58, 72], [58, 0, 58, 72]]}
+//
+// or a grid of equal frames, read row by row.
+//
+//     {"image": "/player.png", "columns": 4, "rows": 1}
+//
+// Either can add a `"hit": [x, y, width, height]` frame, left out of the
+// animation and shown once the bird crashes.
+#[derive(Deserialize)]
+struct Atlas {
+    image: String,
+    #[serde(default)]
+    frames: Vec<[f32; 4]>,
+    #[serde(default)]
+    hit: Option<[f32; 4]>,
+    #[serde(default = "one")]
+    columns: u32,
+    #[serde(default = "one")]
+    rows: u32,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl SpriteSheet {
+    pub fn new(sheet: Sprite, rects: Vec<Rect>) -> Self {
+        let frames = rects.iter().map(|&rect| sheet.region(rect)).collect();
+        SpriteSheet {
+            current_frame: 0,
+            sheet,
+            rects,
+            frames,
+            hit: None,
+        }
+    }
+
+    pub fn with_hit(mut self, rect: Rect) -> Self {
+        self.hit = Some((rect, self.sheet.region(rect)));
+        self
+    }
+
+    pub fn grid(sheet: Sprite, columns: u32, rows: u32) -> Self {
+        let width = sheet.width() / columns as f32;
+        let height = sheet.height() / rows as f32;
+        let rects = (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| {
+                    Rect::new(column as f32 * width, row as f32 * height, width, height)
+                })
+            })
+            .collect();
+        SpriteSheet::new(sheet, rects)
+    }
+
+    // Loads the sheet described by `<base_path>.json`, or else joins the
+    // loose frames `<base_path>1.png`, `<base_path>2.png` and so on into one,
+    // with `<base_path>_hit.png` after them if there is one.
+    pub fn load(ctx: &Context, base_path: &str) -> GameResult<Self> {
+        let atlas_path = format!("{}.json", base_path);
+        if !ctx.fs.exists(&atlas_path) {
+            let mut frames = Vec::new();
+            while assets::exists(ctx, &format!("{}{}.png", base_path, frames.len() + 1)) {
+                let path = format!("{}{}.png", base_path, frames.len() + 1);
+                frames.push(Sprite::load(ctx, &path)?);
+            }
+            let hit_path = format!("{}_hit.png", base_path);
+            let has_hit = assets::exists(ctx, &hit_path);
+            if has_hit {
+                frames.push(Sprite::load(ctx, &hit_path)?);
+            }
+            let sheet = Sprite::strip(ctx, &frames)?;
+            let mut sheet = SpriteSheet::grid(sheet, frames.len() as u32, 1);
+            if has_hit {
+                let rect = sheet.rects.pop().unwrap();
+                sheet.frames.pop();
+                sheet = sheet.with_hit(rect);
+            }
+            return sheet.with_frames(base_path);
+        }
+
+        let mut json = String::new();
+        ctx.fs.open(&atlas_path)?.read_to_string(&mut json)?;
+        let atlas: Atlas = serde_json::from_str(&json)
+            .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", atlas_path, e)))?;
+        if atlas.columns == 0 || atlas.rows == 0 {
+            return Err(GameError::ResourceLoadError(format!(
+                "{}: columns and rows must be at least 1",
+                atlas_path
+            )));
+        }
+        let sheet = Sprite::load(ctx, &atlas.image)?;
+        let sheet = if atlas.frames.is_empty() {
+            SpriteSheet::grid(sheet, atlas.columns, atlas.rows)
+        } else {
+            let rects = atlas
+                .frames
+                .iter()
+                .map(|&[x, y, w, h]| Rect::new(x, y, w, h))
+                .collect();
+            SpriteSheet::new(sheet, rects)
+        };
+        match atlas.hit {
+            Some([x, y, w, h]) => sheet.with_hit(Rect::new(x, y, w, h)),
+            None => sheet,
+        }
+        .with_frames(&atlas_path)
+    }
+
+    // The sheet, unless it has no frames to animate, which `frame` and
+    // `hit_frame` can't do without.
+    fn with_frames(self, path: &str) -> GameResult<Self> {
+        if self.frames.is_empty() {
+            return Err(GameError::ResourceLoadError(format!("{}: no frames", path)));
+        }
+        Ok(self)
+    }
+
+    pub fn frame(&self) -> &Sprite {
+        &self.frames[self.current_frame as usize]
+    }
+
+    // The frame shown once the bird has crashed, the first one for sheets
+    // without one of its own.
+    pub fn hit_frame(&self) -> &Sprite {
+        self.hit.as_ref().map_or(&self.frames[0], |(_, hit)| hit)
+    }
+
+    pub fn frames(&self) -> &[Sprite] {
+        &self.frames
+    }
+
+    // The same frames on a copy of the sheet with every RGBA pixel passed
+    // through `recolor`.
+    pub fn recolored(
+        &self,
+        ctx: &Context,
+        recolor: impl Fn([u8; 4]) -> [u8; 4],
+    ) -> GameResult<Self> {
+        let mut sheet = SpriteSheet::new(self.sheet.recolored(ctx, recolor)?, self.rects.clone());
+        sheet.current_frame = self.current_frame;
+        if let Some((rect, _)) = self.hit {
+            sheet = sheet.with_hit(rect);
+        }
+        Ok(sheet)
+    }
+}
+
+#[derive(Component, Clone)]
+#[storage(VecStorage)]
+pub struct BackgroundTag {
+    pub velocity: f32,
+    pub width: f32,
+    pub num_copies: u32,
+}
+
+// Which of the theme's background layers a scenery entity shows, counted
+// from the back. See `theme::show`.
+#[derive(Component, Clone, Copy, Debug)]
+#[storage(VecStorage)]
+pub struct Backdrop(pub usize);
+
+// Draw order of the scenery: higher is drawn over lower.
+#[derive(Component, Clone, Copy, Debug, Default)]
+#[storage(VecStorage)]
+pub struct ZOrder(pub i32);
+
+#[derive(Component, Clone, Default)]
+#[storage(VecStorage)]
+pub struct ObstacleTag {
+    // The obstacle that owns its slot: it spawns the next slot when it
+    // leaves the screen and counts as passed. The top pipe of a pair, or an
+    // invisible marker for cave slots.
+    pub top: bool,
+    pub passed: bool,
+    // Set once the bird's center is past the middle of the slot.
+    pub scored: bool,
+    // Part of a cave section rather than a pipe.
+    pub cave: bool,
+    // Lying on the floor rather than a pipe.
+    pub hazard: Option<Hazard>,
+    // What the pipe is painted, picked when it spawns.
+    pub color: PipeColor,
+}
+
+// The pipes' paint: green on normal, icy blue on easy and red on hard, so
+// the difficulty can be told at a glance. Blue only comes up with the
+// theme's random pipe colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PipeColor {
+    #[default]
+    Green,
+    Ice,
+    Red,
+    Blue,
+}
+
+impl PipeColor {
+    // The tint over the green pipe art.
+    pub fn tint(self) -> Color {
+        match self {
+            PipeColor::Green => Color::WHITE,
+            PipeColor::Ice => Color::new(0.6, 0.8, 1.0, 1.0),
+            PipeColor::Red => Color::new(1.0, 0.45, 0.4, 1.0),
+            PipeColor::Blue => Color::new(0.4, 0.55, 1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hazard {
+    Spikes,
+    Puddle,
+}
+
+// Something floating in a pipe pair's gap for the bird to fly through and
+// pick up, see `collectibles`. Never lethal; `CollisionSystem` picks it up.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct Collectible {
+    pub kind: CollectibleKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectibleKind {
+    Coin,
+    Shield,
+    SlowMotion,
+}
+
+// Bobs an obstacle up and down, `amplitude` pixels either side of
+// `base_y`, once every `period` ticks. Both pipes of a moving pair share
+// the phase, so their gap keeps its height. Moved in `MovementSystem`.
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[storage(DenseVecStorage)]
+pub struct VerticalOscillator {
+    pub base_y: f32,
+    pub amplitude: f32,
+    pub period: f32,
+    // Radians into the swing.
+    pub phase: f32,
+}
+
+#[derive(Component, Default)]
+#[storage(NullStorage)]
+pub struct PlayerTag;
+
+// Which player a bird belongs to in race mode, see `Race`.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct PlayerId(pub usize);
+
+// The translucent bird replaying the best run, see `best_run`. It never
+// collides with anything.
+#[derive(Component, Default)]
+#[storage(NullStorage)]
+pub struct GhostBird;
+
+// A chunk of a pipe broken by a ghost hit, tumbling off the screen, see
+// `DebrisSystem`. `piece` picks the part of the pipe art it shows: 0 and 1
+// are the halves of the cap, 2 to 5 quarters of a body segment.
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct Debris {
+    pub piece: usize,
+    // In radians, turning about the chunk's center.
+    pub rotation: f32,
+    // Radians a second.
+    pub spin: f32,
+}
+
+// A feather or speck of dust, moved and faded out by `ParticleSystem`. Ages
+// in ticks scaled by the time scale, and falls with `gravity` in pixels a
+// second squared, its speed per second like everything else that moves.
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct Particle {
+    pub age: f32,
+    pub lifetime: f32,
+    pub gravity: f32,
+    pub size: f32,
+    pub color: Color,
+}
+
+// Text that drifts and fades like a particle, such as the "+1" over the bird
+// for each pipe passed. Goes on an entity along with its `Particle`, whose
+// size is the text's.
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[storage(DenseVecStorage)]
+pub struct FloatingText(pub &'static str);
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct CollisionBox {
+    pub origin: Vec2,
+    pub height: f32,
+    pub width: f32,
+}
+
+// A capsule the bird collides with instead of its `CollisionBox`: the
+// points within `radius` of the segment from `from` to `to`, both relative
+// to the bird's position. The segment is horizontal or vertical; a circle
+// when both ends are the same.
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[storage(VecStorage)]
+pub struct Hitbox {
+    pub from: Vec2,
+    pub to: Vec2,
+    pub radius: f32,
+}
+
+impl Hitbox {
+    // Fitted to the bird's body in its sprites. None for the plain box.
+    pub fn bird(shape: HitboxShape) -> Option<Self> {
+        match shape {
+            HitboxShape::Box => None,
+            HitboxShape::Circle => Some(Hitbox {
+                from: Vec2::new(44.0, 28.0),
+                to: Vec2::new(44.0, 28.0),
+                radius: 22.0,
+            }),
+            HitboxShape::Capsule => Some(Hitbox {
+                from: Vec2::new(30.0, 28.0),
+                to: Vec2::new(50.0, 28.0),
+                radius: 20.0,
+            }),
+        }
+    }
+
+    // Whether the hitbox of a bird at `pos` overlaps `other`.
+    pub fn hits(&self, pos: Vec2, other: &CollisionBox) -> bool {
+        let (from, to) = (pos + self.from, pos + self.to);
+        // Gap between the segment and the box along each axis.
+        let dx = (other.origin.x - from.x.max(to.x))
+            .max(from.x.min(to.x) - (other.origin.x + other.width))
+            .max(0.0);
+        let dy = (other.origin.y - from.y.max(to.y))
+            .max(from.y.min(to.y) - (other.origin.y + other.height))
+            .max(0.0);
+        dx * dx + dy * dy < self.radius * self.radius
+    }
+}
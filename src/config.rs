@@ -0,0 +1,193 @@
+use crate::audio::AudioConfig;
+use crate::engine::{self, Filter};
+use crate::hud::HudLayout;
+use crate::leaderboard::LeaderboardConfig;
+use crate::medals::Medals;
+use crate::parallax::Parallax;
+use crate::streamer::StreamerConfig;
+use crate::theme::ThemeConfig;
+use crate::tuning::Tuning;
+use crate::wellbeing::WellbeingConfig;
+use ggez::conf::NumSamples;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+// Player settings, read from `config.toml` in the working directory at
+// startup. Any field missing from the file keeps its default value:
+//
+//     [window]
+//     width = 1280.0
+//     height = 750.0
+//     aspect = [9, 16]
+//     low_power = true
+//     render_scale = 0.75
+//     samples = 4
+//     filter = "nearest"
+//
+//     [tuning]
+//     gravity = 900.0
+//     scroll_speed = 210.0
+//
+//     [hud.best]
+//     visible = false
+//
+//     [medals]
+//     gold = 25
+//
+//     [theme]
+//     change = "score"
+//     every = 10
+//     pipes = "random"
+//
+//     [[parallax]]
+//     image = "/background1.png"
+//     speed = 2.0
+//     z = 0
+//
+//     [audio]
+//     duck_seconds = 0.5
+//     ducked_volume = 0.2
+//
+//     [wellbeing]
+//     break_reminder = true
+//     play_minutes = 45.0
+//
+//     [leaderboard]
+//     endpoint = "http://scores.example.com/rusty_bird"
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub window: Window,
+    // Physics, speeds and spacing, as in a `--tuning` file, which takes
+    // precedence over these.
+    pub tuning: Tuning,
+    pub hud: HudLayout,
+    pub medals: Medals,
+    pub streamer: StreamerConfig,
+    pub theme: ThemeConfig,
+    pub parallax: Parallax,
+    pub audio: AudioConfig,
+    pub wellbeing: WellbeingConfig,
+    pub leaderboard: LeaderboardConfig,
+}
+
+// The game is laid out for `engine::screen_width()` by `SCREEN_HEIGHT` and
+// scaled to fit other sizes, see `engine::Viewport`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct Window {
+    pub width: f32,
+    pub height: f32,
+    // The screen's shape, width to height, such as [9, 16] to fill a phone
+    // held upright. The screen is always as tall, so this sets its width:
+    // how far ahead the pipes come into view and how the HUD and menus are
+    // spread out. Left out, it's 1024 by 600.
+    pub aspect: Option<[u32; 2]>,
+    pub fullscreen: bool,
+    // Saves battery: 30 frames a second, no hit flash or trails, and
+    // static screens like the pause and game over ones aren't redrawn.
+    pub low_power: bool,
+    // Fraction of the window's resolution the world is drawn at, e.g. 0.5
+    // on weak GPUs. Text is always drawn at full resolution.
+    pub render_scale: f32,
+    // Multisample anti-aliasing: 1 for none or 4. Only read when the
+    // window opens.
+    pub samples: u8,
+    // "linear" smooths the world's images when they're scaled, "nearest"
+    // keeps their pixels sharp. F7 switches between them while playing.
+    pub filter: Filter,
+    // Waits for the display between frames. Set on the settings screen
+    // rather than in the file.
+    #[serde(skip)]
+    pub vsync: bool,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window {
+            width: engine::screen_width(),
+            height: engine::SCREEN_HEIGHT,
+            aspect: None,
+            fullscreen: false,
+            low_power: false,
+            render_scale: 1.0,
+            samples: 1,
+            filter: Filter::Linear,
+            vsync: true,
+        }
+    }
+}
+
+impl Config {
+    // A missing file means the default config; an unreadable one is
+    // reported and ignored.
+    pub fn load(path: &Path) -> Self {
+        let mut config = match fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                println!("ignoring invalid {}: {}", path.display(), e);
+                Config::default()
+            }),
+            Err(_) => Config::default(),
+        };
+
+        if config.window.width < 1.0 || config.window.height < 1.0 {
+            println!(
+                "ignoring window size {}x{}",
+                config.window.width, config.window.height
+            );
+            config.window = Window::default();
+        }
+        if !(config.window.render_scale > 0.0 && config.window.render_scale <= 1.0) {
+            println!(
+                "ignoring render scale {}, it must be above 0 and at most 1",
+                config.window.render_scale
+            );
+            config.window.render_scale = 1.0;
+        }
+        if let (Some([width, height]), None) = (config.window.aspect, config.window.aspect()) {
+            println!(
+                "ignoring aspect {}:{}, it must be from 1:2 to 21:9",
+                width, height
+            );
+            config.window.aspect = None;
+        }
+        if NumSamples::try_from(config.window.samples).is_err() {
+            println!(
+                "ignoring {} samples, anti-aliasing takes 1 or 4",
+                config.window.samples
+            );
+            config.window.samples = 1;
+        }
+        config
+    }
+}
+
+impl Window {
+    // How many times wider than tall the screen is laid out, if the config
+    // picks its shape.
+    pub fn aspect(&self) -> Option<f32> {
+        self.aspect.and_then(ratio)
+    }
+}
+
+// Width over height, from tall 1:2 to wide 21:9 so the HUD and the bird's
+// view ahead still fit.
+fn ratio([width, height]: [u32; 2]) -> Option<f32> {
+    Some(width as f32 / height as f32).filter(|ratio| (0.5..=21.0 / 9.0).contains(ratio))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aspects_are_width_to_height_within_limits() {
+        let config: Config = toml::from_str("[window]\naspect = [9, 16]").unwrap();
+        assert_eq!(config.window.aspect(), Some(0.5625));
+        assert_eq!(ratio([16, 9]), Some(16.0 / 9.0));
+        assert_eq!(ratio([1, 3]), None);
+        assert_eq!(ratio([9, 0]), None);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::GameState;
+use ggez::event::{Button, GamepadId};
+use ggez::input::keyboard::KeyCode;
+use ggez::*;
+
+// Keeps track of the controller the player is using so the game can pause
+// when it is unplugged instead of silently losing input.
+#[derive(Default)]
+pub struct ControllerWatch {
+    // The last controller that sent any input.
+    active: Option<GamepadId>,
+    // Set while waiting for a controller that disconnected mid-game.
+    lost: Option<GamepadId>,
+}
+
+impl ControllerWatch {
+    pub fn used(&mut self, id: GamepadId) {
+        self.active = Some(id);
+        self.lost = None;
+    }
+
+    // Notices the active controller going away; the game stays paused until
+    // the player carries on with any controller or the keyboard.
+    pub fn check(&mut self, ctx: &Context) {
+        if let Some(id) = self.active {
+            if !ctx.gamepad.gamepad(id).is_connected() {
+                self.active = None;
+                self.lost = Some(id);
+            }
+        }
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost.is_some()
+    }
+
+    pub fn dismiss(&mut self) {
+        self.lost = None;
+    }
+
+    // Dims the screen and tells the player how to carry on.
+    pub fn draw_prompt(&self, ctx: &Context, cmds: &mut Vec<DrawCmd>) {
+        let lost = match self.lost {
+            Some(id) => id,
+            None => return,
+        };
+
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(0.0, 0.0, engine::screen_width(), engine::SCREEN_HEIGHT),
+            color: Color::new(0.0, 0.0, 0.0, 0.6),
+        });
+
+        let hint = if ctx.gamepad.gamepad(lost).is_connected() {
+            lang::tr("controller_reconnected")
+        } else {
+            lang::tr("controller_reconnect")
+        };
+        cmds.push(DrawCmd::centered_text(
+            format!("{}\n{}", lang::tr("controller_disconnected"), hint),
+            Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT / 2.0),
+            30.0,
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        ));
+    }
+}
+
+// Which controls are live, for mapping buttons and drawing the legend.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Screen {
+    Game(GameState),
+    Customizer,
+    ImportPrompt,
+    Seeds,
+    WhatsNew,
+    BreakReminder,
+    CustomGame,
+    SessionSummary,
+    NameEntry,
+    Settings,
+}
+
+// The key each button stands in for on a screen, so everything the
+// keyboard reaches is reachable with a controller too.
+pub fn button_key(btn: Button, screen: Screen) -> Option<KeyCode> {
+    let key = match (btn, screen) {
+        (Button::South, Screen::NameEntry) => KeyCode::Return,
+        (Button::South, _) => KeyCode::Space,
+        (Button::DPadUp, _) => KeyCode::Up,
+        (Button::DPadDown, _) => KeyCode::Down,
+        (Button::DPadLeft, _) => KeyCode::Left,
+        (Button::DPadRight, _) => KeyCode::Right,
+        (Button::East, Screen::Customizer)
+        | (Button::East, Screen::ImportPrompt)
+        | (Button::East, Screen::Seeds)
+        | (Button::East, Screen::WhatsNew)
+        | (Button::East, Screen::CustomGame)
+        | (Button::East, Screen::NameEntry)
+        | (Button::East, Screen::Settings) => KeyCode::Escape,
+        (Button::East, Screen::Game(GameState::GameOver)) => KeyCode::R,
+        (Button::West, Screen::ImportPrompt) => KeyCode::M,
+        (Button::West, Screen::Seeds) => KeyCode::B,
+        (Button::West, Screen::CustomGame) | (Button::West, Screen::Settings) => KeyCode::Delete,
+        (Button::West, _) => KeyCode::C,
+        (Button::North, Screen::ImportPrompt) => KeyCode::R,
+        (Button::North, Screen::Seeds) => KeyCode::X,
+        (Button::North, Screen::Game(GameState::Menu)) => KeyCode::S,
+        (Button::RightThumb, Screen::Game(GameState::Menu)) => KeyCode::G,
+        (Button::LeftThumb, Screen::Game(GameState::Menu)) => KeyCode::K,
+        (Button::Start, Screen::Game(GameState::Menu)) => KeyCode::O,
+        (Button::RightThumb, Screen::Game(GameState::GameOver)) => KeyCode::Return,
+        (Button::North, _) => KeyCode::Tab,
+        (Button::LeftTrigger, _) => KeyCode::E,
+        (Button::RightTrigger, _) => KeyCode::I,
+        (Button::LeftTrigger2, _) => KeyCode::Minus,
+        (Button::RightTrigger2, _) => KeyCode::Equals,
+        (Button::Start, _) => KeyCode::P,
+        (Button::Select, _) => KeyCode::Escape,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// The buttons that do something on a screen, for `--gamepad`, with the
+// string table keys of what they do.
+pub fn legend(screen: Screen) -> &'static [(Button, &'static str)] {
+    match screen {
+        Screen::Customizer => &[
+            (Button::DPadUp, "do_pick"),
+            (Button::DPadLeft, "do_change"),
+            (Button::East, "do_done"),
+        ],
+        Screen::ImportPrompt => &[
+            (Button::West, "do_merge"),
+            (Button::North, "do_replace"),
+            (Button::East, "do_cancel"),
+        ],
+        Screen::Seeds => &[
+            (Button::DPadUp, "do_pick"),
+            (Button::South, "do_play"),
+            (Button::West, "do_bookmark"),
+            (Button::North, "do_share"),
+            (Button::East, "do_back"),
+        ],
+        Screen::WhatsNew => &[(Button::East, "do_back")],
+        Screen::BreakReminder => &[(Button::South, "do_carry_on")],
+        Screen::SessionSummary => &[(Button::South, "do_carry_on"), (Button::Select, "do_quit")],
+        Screen::NameEntry => &[(Button::South, "do_send"), (Button::East, "do_cancel")],
+        Screen::Settings => &[
+            (Button::DPadUp, "do_pick"),
+            (Button::DPadLeft, "do_change"),
+            (Button::West, "do_clear"),
+            (Button::East, "do_done"),
+        ],
+        Screen::CustomGame => &[
+            (Button::DPadUp, "do_pick"),
+            (Button::DPadLeft, "do_change"),
+            (Button::South, "do_play"),
+            (Button::West, "do_delete"),
+            (Button::East, "do_back"),
+        ],
+        Screen::Game(GameState::Menu) => &[
+            (Button::South, "do_play"),
+            (Button::West, "do_customize"),
+            (Button::North, "do_seeds"),
+            (Button::RightThumb, "do_custom_game"),
+            (Button::LeftThumb, "do_skin"),
+            (Button::Start, "do_settings"),
+            (Button::LeftTrigger, "do_export"),
+            (Button::RightTrigger, "do_import"),
+            (Button::Select, "do_quit"),
+        ],
+        Screen::Game(GameState::GetReady) => &[(Button::South, "do_flap")],
+        Screen::Game(GameState::Playing) => &[
+            (Button::South, "do_flap"),
+            (Button::Start, "do_pause"),
+            (Button::LeftTrigger2, "do_slower"),
+            (Button::RightTrigger2, "do_faster"),
+        ],
+        Screen::Game(GameState::Paused) => {
+            &[(Button::Start, "do_resume"), (Button::Select, "do_quit")]
+        }
+        // Nothing to do but watch the bird fall.
+        Screen::Game(GameState::Dying) => &[],
+        Screen::Game(GameState::GameOver) => &[
+            (Button::South, "do_play_again"),
+            (Button::East, "do_restart"),
+            (Button::North, "do_stats"),
+            (Button::RightThumb, "do_leaderboard"),
+            (Button::Select, "do_quit"),
+        ],
+    }
+}
+
+fn button_name(btn: Button) -> &'static str {
+    match btn {
+        Button::South => "A",
+        Button::East => "B",
+        Button::West => "X",
+        Button::North => "Y",
+        Button::DPadUp | Button::DPadDown => "Up/Down",
+        Button::DPadLeft | Button::DPadRight => "Left/Right",
+        Button::LeftTrigger => "LB",
+        Button::RightTrigger => "RB",
+        Button::LeftTrigger2 => "LT",
+        Button::RightTrigger2 => "RT",
+        Button::Start => "Start",
+        Button::Select => "Select",
+        Button::RightThumb => "RS",
+        Button::LeftThumb => "LS",
+        _ => "?",
+    }
+}
+
+pub fn draw_legend(cmds: &mut Vec<DrawCmd>, screen: Screen) {
+    let text = legend(screen)
+        .iter()
+        .map(|&(btn, action)| format!("{} {}", button_name(btn), lang::tr(action)))
+        .collect::<Vec<_>>()
+        .join("   ");
+    cmds.push(DrawCmd::Rect {
+        rect: Rect::new(
+            0.0,
+            engine::SCREEN_HEIGHT - 34.0,
+            engine::screen_width(),
+            34.0,
+        ),
+        color: Color::new(0.0, 0.0, 0.0, 0.6),
+    });
+    cmds.push(DrawCmd::centered_text(
+        text,
+        Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT - 17.0),
+        20.0,
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_legend_button_does_something() {
+        let screens = vec![
+            Screen::Customizer,
+            Screen::ImportPrompt,
+            Screen::Seeds,
+            Screen::WhatsNew,
+            Screen::BreakReminder,
+            Screen::CustomGame,
+            Screen::SessionSummary,
+            Screen::NameEntry,
+            Screen::Settings,
+            Screen::Game(GameState::Menu),
+            Screen::Game(GameState::GetReady),
+            Screen::Game(GameState::Playing),
+            Screen::Game(GameState::Paused),
+            Screen::Game(GameState::Dying),
+            Screen::Game(GameState::GameOver),
+        ];
+        for screen in screens {
+            for &(btn, action) in legend(screen) {
+                assert!(
+                    button_key(btn, screen).is_some(),
+                    "{:?} has no key for {}",
+                    screen,
+                    action
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Sprite, Vec2};
+use crate::lang;
+use crate::SpriteSheet;
+use ggez::{Context, GameResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+// Body colors. The bird's blue feathers are swapped for another hue,
+// keeping their shading; the beak and outline stay as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Body {
+    Sky,
+    Ember,
+    Leaf,
+    Gold,
+    Violet,
+    Pink,
+}
+
+impl Body {
+    const ALL: [Body; 6] = [
+        Body::Sky,
+        Body::Ember,
+        Body::Leaf,
+        Body::Gold,
+        Body::Violet,
+        Body::Pink,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Body::Sky => lang::tr("body_sky"),
+            Body::Ember => lang::tr("body_ember"),
+            Body::Leaf => lang::tr("body_leaf"),
+            Body::Gold => lang::tr("body_gold"),
+            Body::Violet => lang::tr("body_violet"),
+            Body::Pink => lang::tr("body_pink"),
+        }
+    }
+
+    // The hue, in degrees, the feathers are swapped to. None for the
+    // original blue.
+    fn hue(self) -> Option<f32> {
+        match self {
+            Body::Sky => None,
+            Body::Ember => Some(5.0),
+            Body::Leaf => Some(110.0),
+            Body::Gold => Some(45.0),
+            Body::Violet => Some(275.0),
+            Body::Pink => Some(325.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hat {
+    None,
+    Cap,
+    Topper,
+    Crown,
+}
+
+impl Hat {
+    const ALL: [Hat; 4] = [Hat::None, Hat::Cap, Hat::Topper, Hat::Crown];
+
+    fn label(self) -> &'static str {
+        match self {
+            Hat::None => lang::tr("hat_none"),
+            Hat::Cap => lang::tr("hat_cap"),
+            Hat::Topper => lang::tr("hat_topper"),
+            Hat::Crown => lang::tr("hat_crown"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trail {
+    None,
+    Sparkles,
+    Rainbow,
+    Smoke,
+}
+
+impl Trail {
+    const ALL: [Trail; 4] = [Trail::None, Trail::Sparkles, Trail::Rainbow, Trail::Smoke];
+
+    fn label(self) -> &'static str {
+        match self {
+            Trail::None => lang::tr("trail_none"),
+            Trail::Sparkles => lang::tr("trail_sparkles"),
+            Trail::Rainbow => lang::tr("trail_rainbow"),
+            Trail::Smoke => lang::tr("trail_smoke"),
+        }
+    }
+}
+
+// The option after or before `current` in `all`, wrapping around.
+fn cycle<T: Copy + PartialEq>(all: &[T], current: T, step: isize) -> T {
+    let n = all.iter().position(|&o| o == current).unwrap_or(0) as isize;
+    all[(n + step).rem_euclid(all.len() as isize) as usize]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BirdLook {
+    pub body: Body,
+    pub hat: Hat,
+    pub trail: Trail,
+}
+
+impl Default for BirdLook {
+    fn default() -> Self {
+        BirdLook {
+            body: Body::Sky,
+            hat: Hat::None,
+            trail: Trail::None,
+        }
+    }
+}
+
+// Where the top of the bird's head is in its sprite, for seating hats.
+const HEAD_TOP: Vec2 = Vec2::new(52.0, 5.0);
+// Where the trail leaves the bird, by its tail.
+const TAIL: Vec2 = Vec2::new(8.0, 30.0);
+
+impl BirdLook {
+    // The bird's animation frames in this body color.
+    pub fn paint(&self, ctx: &Context, sheet: &SpriteSheet) -> GameResult<SpriteSheet> {
+        match self.body.hue() {
+            Some(hue) => sheet.recolored(ctx, |pixel| swap_hue(pixel, hue)),
+            None => Ok(sheet.clone()),
+        }
+    }
+
+    // The hat for a bird drawn at `pos`, `scale` times its sprite's size.
+    pub fn draw_hat(&self, cmds: &mut Vec<DrawCmd>, pos: Vec2, scale: f32) {
+        let head = pos + HEAD_TOP * scale;
+        // Parts are laid out around the top of the head.
+        let rect = |x: f32, y: f32, w: f32, h: f32, (r, g, b)| DrawCmd::Rect {
+            rect: Rect::new(head.x + x * scale, head.y + y * scale, w * scale, h * scale),
+            color: Color::new(r, g, b, 1.0),
+        };
+        match self.hat {
+            Hat::None => (),
+            Hat::Cap => {
+                let red = (0.85, 0.15, 0.15);
+                cmds.push(rect(-10.0, -10.0, 20.0, 10.0, red));
+                cmds.push(rect(4.0, -4.0, 14.0, 4.0, (0.6, 0.1, 0.1)));
+            }
+            Hat::Topper => {
+                let black = (0.1, 0.1, 0.1);
+                cmds.push(rect(-14.0, -4.0, 28.0, 4.0, black));
+                cmds.push(rect(-9.0, -24.0, 18.0, 20.0, black));
+                cmds.push(rect(-9.0, -9.0, 18.0, 3.0, (0.8, 0.1, 0.1)));
+            }
+            Hat::Crown => {
+                let gold = (1.0, 0.8, 0.1);
+                cmds.push(rect(-12.0, -8.0, 24.0, 8.0, gold));
+                for n in 0..3 {
+                    cmds.push(rect(-12.0 + 9.5 * n as f32, -16.0, 5.0, 8.0, gold));
+                }
+                cmds.push(DrawCmd::Circle {
+                    center: head + Vec2::new(0.0, -4.0) * scale,
+                    radius: 2.5 * scale,
+                    color: Color::new(0.9, 0.1, 0.3, 1.0),
+                });
+            }
+        }
+    }
+
+    // The trail through `path`, the bird's latest positions on screen,
+    // oldest first.
+    pub fn draw_trail(&self, cmds: &mut Vec<DrawCmd>, path: &[Vec2]) {
+        let len = path.len();
+        for (n, &pos) in path.iter().enumerate().step_by(3) {
+            // 0.0 for the oldest point, 1.0 at the bird.
+            let fresh = (n + 1) as f32 / len as f32;
+            let at = pos + TAIL;
+            let cmd = match self.trail {
+                Trail::None => return,
+                Trail::Sparkles => DrawCmd::Circle {
+                    center: at + Vec2::new(0.0, ((n * 7) % 11) as f32 - 5.0),
+                    radius: 1.5 + fresh * 2.0,
+                    color: Color::new(1.0, 1.0, 0.7, fresh),
+                },
+                Trail::Rainbow => {
+                    let (r, g, b) = hsv_to_rgb((n as f32 * 12.0) % 360.0, 0.8, 1.0);
+                    DrawCmd::Circle {
+                        center: at,
+                        radius: 4.0,
+                        color: Color::new(r, g, b, fresh * 0.8),
+                    }
+                }
+                Trail::Smoke => DrawCmd::Circle {
+                    center: at,
+                    radius: 3.0 + (1.0 - fresh) * 8.0,
+                    color: Color::new(0.7, 0.7, 0.7, fresh * 0.4),
+                },
+            };
+            cmds.push(cmd);
+        }
+    }
+}
+
+// Moves bluish pixels to `hue`. Only clearly colored pixels in the blue
+// range change, which leaves the outline, beak and eye alone.
+fn swap_hue([r, g, b, a]: [u8; 4], hue: f32) -> [u8; 4] {
+    let (h, s, v) = rgb_to_hsv(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    if !(165.0..=225.0).contains(&h) || s < 0.2 {
+        return [r, g, b, a];
+    }
+    let (r, g, b) = hsv_to_rgb(hue, s, v);
+    [
+        (r * 255.0).round() as u8,
+        (g * 255.0).round() as u8,
+        (b * 255.0).round() as u8,
+        a,
+    ]
+}
+
+fn rgb_to_hsv(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+    (h, s, max)
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    (r + m, g + m, b + m)
+}
+
+// Every look on this profile: the default one, and one per hot seat player
+// who made their own. Persisted as JSON in the user data directory.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Looks {
+    pub bird: BirdLook,
+    pub players: BTreeMap<String, BirdLook>,
+    // The skin picked on the menu, for every player. None for the usual
+    // look.
+    pub skin: Option<String>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+pub fn looks_path(ctx: &Context) -> PathBuf {
+    ctx.fs.user_data_dir().join("bird.json")
+}
+
+impl Looks {
+    // Missing or unreadable files mean the default bird.
+    pub fn load(path: PathBuf) -> Self {
+        let mut looks = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                println!("ignoring corrupt bird file {}: {}", path.display(), e);
+                Looks::default()
+            }),
+            Err(_) => Looks::default(),
+        };
+        looks.path = Some(path);
+        looks
+    }
+
+    pub fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string(self)?;
+                fs::write(path, json)
+            });
+        if let Err(e) = result {
+            println!("can't save the bird to {}: {}", path.display(), e);
+        }
+    }
+
+    // Adds the hot seat players of another profile who have no look here,
+    // keeping this profile's own bird.
+    pub fn merge(&mut self, other: Looks) {
+        for (player, look) in other.players {
+            self.players.entry(player).or_insert(look);
+        }
+    }
+
+    // Takes the looks of another profile instead, still saving here.
+    pub fn replace(&mut self, other: Looks) {
+        let path = self.path.take();
+        *self = other;
+        self.path = path;
+    }
+
+    // The look of hot seat `player`, or the default one.
+    pub fn get(&self, player: Option<&str>) -> BirdLook {
+        player
+            .and_then(|player| self.players.get(player))
+            .copied()
+            .unwrap_or(self.bird)
+    }
+
+    fn get_mut(&mut self, player: Option<&str>) -> &mut BirdLook {
+        match player {
+            Some(player) => {
+                let default = self.bird;
+                self.players.entry(player.to_string()).or_insert(default)
+            }
+            None => &mut self.bird,
+        }
+    }
+}
+
+const ROWS: [&str; 3] = ["part_body", "part_hat", "part_trail"];
+
+// The bird editor on the title screen: Up and Down pick a part, Left and
+// Right cycle through its options.
+#[derive(Default)]
+pub struct Customizer {
+    pub open: bool,
+    row: usize,
+}
+
+impl Customizer {
+    // Applies a key to the look of `player`. Returns whether it changed.
+    pub fn press(&mut self, looks: &mut Looks, player: Option<&str>, key: Key) -> bool {
+        let step = match key {
+            Key::Up => {
+                self.row = (self.row + ROWS.len() - 1) % ROWS.len();
+                return false;
+            }
+            Key::Down => {
+                self.row = (self.row + 1) % ROWS.len();
+                return false;
+            }
+            Key::Left => -1,
+            Key::Right => 1,
+        };
+        let look = looks.get_mut(player);
+        match self.row {
+            0 => look.body = cycle(&Body::ALL, look.body, step),
+            1 => look.hat = cycle(&Hat::ALL, look.hat, step),
+            _ => look.trail = cycle(&Trail::ALL, look.trail, step),
+        }
+        true
+    }
+
+    // The editor, with a live preview of the bird in `frame`.
+    pub fn draw<'a>(
+        &self,
+        cmds: &mut Vec<DrawCmd<'a>>,
+        look: &BirdLook,
+        player: Option<&str>,
+        frame: &'a Sprite,
+        ticks: u32,
+    ) {
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let center_x = engine::screen_width() / 2.0;
+        let title = match player {
+            Some(player) => lang::fill("players_bird", &[&player]),
+            None => lang::tr("your_bird").to_string(),
+        };
+        cmds.push(DrawCmd::centered_text(
+            title,
+            Vec2::new(center_x, 120.0),
+            50.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+
+        draw_preview(cmds, look, frame, Vec2::new(center_x, 220.0), ticks);
+
+        let values = [look.body.label(), look.hat.label(), look.trail.label()];
+        for (n, (row, value)) in ROWS.iter().zip(values.iter()).enumerate() {
+            let color = if n == self.row {
+                Color::new(1.0, 0.9, 0.2, 1.0)
+            } else {
+                white
+            };
+            cmds.push(DrawCmd::centered_text(
+                format!("{}   < {} >", lang::tr(row), value),
+                Vec2::new(center_x, 320.0 + 40.0 * n as f32),
+                28.0,
+                color,
+            ));
+        }
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("customize_hint"),
+            Vec2::new(center_x, 460.0),
+            18.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// The bird bobbing in place centered on `center`, trail and all, as it
+// would fly. `ticks` drives the bobbing.
+pub fn draw_preview<'a>(
+    cmds: &mut Vec<DrawCmd<'a>>,
+    look: &BirdLook,
+    frame: &'a Sprite,
+    center: Vec2,
+    ticks: u32,
+) {
+    let bob = |t: f32| (t / 12.0).sin() * 8.0;
+    let pos = center - Vec2::new(frame.width() / 2.0, frame.height() / 2.0);
+    let path: Vec<Vec2> = (0..30)
+        .rev()
+        .map(|age| {
+            let t = ticks as f32 - age as f32;
+            Vec2::new(pos.x - 4.0 * age as f32, pos.y + bob(t))
+        })
+        .collect();
+    look.draw_trail(cmds, &path);
+    let pos = Vec2::new(pos.x, pos.y + bob(ticks as f32));
+    cmds.push(DrawCmd::sprite(frame, pos));
+    look.draw_hat(cmds, pos, 1.0);
+}
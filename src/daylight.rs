@@ -0,0 +1,33 @@
+use crate::engine::Color;
+
+// Distance flown in one full day, about three minutes at the default
+// scroll speed.
+const DAY_LENGTH: f32 = 43_200.0;
+
+// Tints for the scenery through the day, evenly spaced and blended in
+// between. Runs start at noon.
+const GRADES: [(f32, f32, f32); 4] = [
+    // Noon
+    (1.0, 1.0, 1.0),
+    // Dusk, cool
+    (0.7, 0.75, 1.0),
+    // Night, dark
+    (0.3, 0.32, 0.5),
+    // Dawn, warm
+    (1.0, 0.8, 0.65),
+];
+
+// The color the background layers are modulated with at `distance` into
+// the run.
+pub fn tint(distance: f32) -> Color {
+    let phase = (distance / DAY_LENGTH).fract() * GRADES.len() as f32;
+    let from = GRADES[phase as usize % GRADES.len()];
+    let to = GRADES[(phase as usize + 1) % GRADES.len()];
+    let t = phase.fract();
+    Color::new(
+        from.0 + (to.0 - from.0) * t,
+        from.1 + (to.1 - from.1) * t,
+        from.2 + (to.2 - from.2) * t,
+        1.0,
+    )
+}
@@ -0,0 +1,67 @@
+use crate::engine::Vec2;
+use crate::{CollisionBox, PlayerId, PlayerTag, Position};
+use ggez::input::keyboard::KeyCode;
+use specs::*;
+
+// Keys for tuning the spawner and collisions, only with `--dev`: `1` to
+// `4` run the simulation at a quarter, half, normal and double speed, `.`
+// steps one physics tick while paused and a right click puts the bird
+// where it's pointing. Every run counts as assisted with them.
+const SPEEDS: [(KeyCode, f32); 4] = [
+    (KeyCode::Key1, 0.25),
+    (KeyCode::Key2, 0.5),
+    (KeyCode::Key3, 1.0),
+    (KeyCode::Key4, 2.0),
+];
+
+pub const STEP_KEY: KeyCode = KeyCode::Period;
+
+// The speed a key picks, if it's one of them.
+pub fn speed(key: KeyCode) -> Option<f32> {
+    SPEEDS
+        .iter()
+        .find(|&&(speed_key, _)| speed_key == key)
+        .map(|&(_, speed)| speed)
+}
+
+// Moves the first player's bird so it's centered on `at`, at a standstill.
+pub fn teleport(world: &World, at: Vec2) {
+    let players = world.read_storage::<PlayerTag>();
+    let ids = world.read_storage::<PlayerId>();
+    let mut positions = world.write_storage::<Position>();
+    let mut boxes = world.write_storage::<CollisionBox>();
+    let birds = (&players, &mut positions, &mut boxes, ids.maybe()).join();
+    for (_, pos, coll_box, id) in birds {
+        if id.is_some_and(|id| id.0 > 0) {
+            continue;
+        }
+        let top_left = at - Vec2::new(coll_box.width, coll_box.height) * 0.5;
+        let offset = coll_box.origin - pos.position;
+        pos.position = top_left - offset;
+        pos.speed = Vec2::ZERO;
+        coll_box.origin = top_left;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::Tuning;
+    use crate::world_setup::build_world;
+
+    #[test]
+    fn a_teleported_bird_stops_where_it_was_put() {
+        let world = build_world(Tuning::default(), None);
+        teleport(&world, Vec2::new(500.0, 200.0));
+
+        let players = world.read_storage::<PlayerTag>();
+        let positions = world.read_storage::<Position>();
+        let boxes = world.read_storage::<CollisionBox>();
+        let (_, pos, coll_box) = (&players, &positions, &boxes).join().next().unwrap();
+        assert_eq!(pos.speed, Vec2::ZERO);
+        assert_eq!(coll_box.origin.x + coll_box.width / 2.0, 500.0);
+        assert_eq!(coll_box.origin.y + coll_box.height / 2.0, 200.0);
+        assert_eq!(speed(KeyCode::Key2), Some(0.5));
+        assert_eq!(speed(KeyCode::Key5), None);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::assets;
+use crate::engine::{Color, DrawCmd, Rect, Sprite, Vec2};
+use crate::hud::POP_TICKS;
+use ggez::Context;
+
+// 0 to 9, each drawn with a dark outline around it.
+const DIGITS: [[&str; 7]; 10] = [
+    [
+        ".###.", "##.##", "##.##", "##.##", "##.##", "##.##", ".###.",
+    ],
+    [
+        "..##.", ".###.", "..##.", "..##.", "..##.", "..##.", ".####",
+    ],
+    [
+        ".###.", "##.##", "...##", "..##.", ".##..", "##...", "#####",
+    ],
+    [
+        ".###.", "##.##", "...##", "..##.", "...##", "##.##", ".###.",
+    ],
+    [
+        "...##", "..###", ".#.##", "#..##", "#####", "...##", "...##",
+    ],
+    [
+        "#####", "##...", "####.", "...##", "...##", "##.##", ".###.",
+    ],
+    [
+        ".###.", "##...", "####.", "##.##", "##.##", "##.##", ".###.",
+    ],
+    [
+        "#####", "...##", "..##.", "..##.", ".##..", ".##..", ".##..",
+    ],
+    [
+        ".###.", "##.##", "##.##", ".###.", "##.##", "##.##", ".###.",
+    ],
+    [
+        ".###.", "##.##", "##.##", "##.##", ".####", "...##", ".###.",
+    ],
+];
+// A glyph and its outline.
+const CELL_WIDTH: usize = 7;
+const CELL_HEIGHT: usize = 9;
+const FILL: [u8; 4] = [255, 255, 255, 255];
+const OUTLINE: [u8; 4] = [40, 30, 30, 255];
+// Screen pixels per glyph pixel.
+const SCALE: f32 = 5.0;
+// How much bigger the score is the moment it goes up.
+const POP: f32 = 0.35;
+// Texture packs can draw their own, ten digits of the same width side by
+// side.
+const ATLAS: &str = "/digits.png";
+
+// Whether the glyph pixel at `x`, `y` of a cell is lit.
+fn lit(digit: usize, x: isize, y: isize) -> bool {
+    let (x, y) = (x - 1, y - 1);
+    if x < 0 || y < 0 || x >= 5 || y >= 7 {
+        return false;
+    }
+    DIGITS[digit][y as usize].as_bytes()[x as usize] == b'#'
+}
+
+// The ten outlined digits side by side, as RGBA rows.
+fn atlas_pixels() -> Vec<u8> {
+    let width = CELL_WIDTH * DIGITS.len();
+    let mut rgba = Vec::with_capacity(width * CELL_HEIGHT * 4);
+    for y in 0..CELL_HEIGHT as isize {
+        for x in 0..width as isize {
+            let (digit, x) = ((x / CELL_WIDTH as isize) as usize, x % CELL_WIDTH as isize);
+            let near = (-1..=1).any(|dy| (-1..=1).any(|dx| lit(digit, x + dx, y + dy)));
+            rgba.extend_from_slice(if lit(digit, x, y) {
+                &FILL
+            } else if near {
+                &OUTLINE
+            } else {
+                &[0, 0, 0, 0]
+            });
+        }
+    }
+    rgba
+}
+
+// The score in big pixel art digits at the top of the screen, as in the
+// original game, popping out a little whenever it goes up.
+pub struct Digits {
+    glyphs: Vec<Sprite>,
+    // Of the glyphs to the usual size, and that times the HUD's scale.
+    base: f32,
+    scale: f32,
+}
+
+impl Digits {
+    pub fn new(ctx: &Context) -> Self {
+        let atlas = if assets::exists(ctx, ATLAS) {
+            Sprite::load(ctx, ATLAS)
+                .map_err(|e| println!("using the built-in digits, {}: {}", ATLAS, e))
+                .ok()
+        } else {
+            None
+        };
+        match atlas {
+            // Drawn the height of the built-in digits.
+            Some(atlas) => {
+                let scale = CELL_HEIGHT as f32 * SCALE / atlas.height();
+                Digits::cut(&atlas, scale)
+            }
+            None => {
+                let width = (CELL_WIDTH * DIGITS.len()) as u32;
+                let atlas = Sprite::from_rgba8(ctx, width, CELL_HEIGHT as u32, &atlas_pixels());
+                Digits::cut(&atlas, SCALE)
+            }
+        }
+    }
+
+    fn cut(atlas: &Sprite, scale: f32) -> Self {
+        let width = atlas.width() / DIGITS.len() as f32;
+        let glyphs = (0..DIGITS.len())
+            .map(|n| atlas.region(Rect::new(width * n as f32, 0.0, width, atlas.height())))
+            .collect();
+        Digits {
+            glyphs,
+            base: scale,
+            scale,
+        }
+    }
+
+    // Draws them `by` times the usual size, for the HUD scale setting.
+    pub fn resize(&mut self, by: f32) {
+        self.scale = self.base * by;
+    }
+
+    // `score` centered on `top.x`, below `top.y`. `pop` is the ticks left
+    // of the pop since it last went up.
+    pub fn draw<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>, score: i32, top: Vec2, pop: u32) {
+        let t = pop.min(POP_TICKS) as f32 / POP_TICKS as f32;
+        let scale = self.scale * (1.0 + POP * t * t);
+        let glyphs: Vec<&Sprite> = score
+            .max(0)
+            .to_string()
+            .bytes()
+            .map(|digit| &self.glyphs[(digit - b'0') as usize])
+            .collect();
+        let width: f32 = glyphs.iter().map(|glyph| glyph.width()).sum::<f32>() * scale;
+
+        // It grows about its middle.
+        let mut x = top.x - width / 2.0;
+        let y = top.y + self.glyphs[0].height() * (self.scale - scale) / 2.0;
+        for glyph in glyphs {
+            cmds.push(DrawCmd::Sprite {
+                sprite: glyph,
+                dest: Vec2::new(x, y),
+                scale,
+                flip: false,
+                tint: Color::WHITE,
+                rotation: 0.0,
+            });
+            x += glyph.width() * scale;
+        }
+    }
+
+    // Of the digits when they aren't popping.
+    pub fn height(&self) -> f32 {
+        self.glyphs[0].height() * self.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_digit_is_outlined() {
+        let rgba = atlas_pixels();
+        let width = CELL_WIDTH * DIGITS.len();
+        let pixel = |x: usize, y: usize| &rgba[(y * width + x) * 4..][..4];
+        for digit in 0..DIGITS.len() {
+            let cell = (0..CELL_WIDTH).map(|x| x + digit * CELL_WIDTH);
+            let lit: Vec<_> = cell
+                .flat_map(|x| (0..CELL_HEIGHT).map(move |y| (x, y)))
+                .filter(|&(x, y)| pixel(x, y) == FILL)
+                .collect();
+            assert!(lit.len() > 10, "{} is nearly blank", digit);
+            // No lit pixel borders the transparent background.
+            for (x, y) in lit {
+                for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                    assert_ne!(pixel(nx, ny)[3], 0, "{} has a gap in its outline", digit);
+                }
+            }
+        }
+    }
+}
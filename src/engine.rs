@@ -0,0 +1,834 @@
+// Thin layer between the game and ggez. Game code keeps positions in `Vec2`,
+// holds images as `Sprite`s and describes each frame as a list of
+// `DrawCmd`s; only this module talks to the ggez graphics API, so engine
+// upgrades stay contained here.
+
+use crate::assets;
+use ggez::graphics::{self, Canvas, DrawParam, FontData, ImageFormat, Sampler};
+use ggez::{Context, GameError, GameResult};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::ops::{Add, AddAssign, Mul, Sub, SubAssign};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+pub use ggez::graphics::{Color, Rect};
+
+pub const SCREEN_HEIGHT: f32 = 600.0;
+// The screen's width unless `[window] aspect` in the config picks another.
+pub const LANDSCAPE_WIDTH: f32 = 1024.0;
+
+static SCREEN_WIDTH: OnceLock<f32> = OnceLock::new();
+
+// The width the game is laid out for, always `SCREEN_HEIGHT` tall. Pipes
+// come in at its right edge, the backgrounds are tiled across it and the
+// HUD and menus are placed along it.
+pub fn screen_width() -> f32 {
+    SCREEN_WIDTH.get().copied().unwrap_or(LANDSCAPE_WIDTH)
+}
+
+// Makes the screen `aspect` times as wide as it's tall, e.g. 0.5625 for a
+// portrait phone. Only the first call counts, before any world is built.
+pub fn set_aspect(aspect: f32) {
+    let _ = SCREEN_WIDTH.set((SCREEN_HEIGHT * aspect).round());
+}
+
+// Narrower than the landscape screen the menus were first laid out on,
+// so side by side panels get stacked instead.
+pub fn narrow() -> bool {
+    screen_width() < LANDSCAPE_WIDTH
+}
+
+// A `width` by `height` panel centered on `center`, narrowed to the
+// screen when that's narrower.
+pub fn panel(center: Vec2, width: f32, height: f32) -> Rect {
+    let width = width.min(screen_width());
+    Rect::new(
+        center.x - width / 2.0,
+        center.y - height / 2.0,
+        width,
+        height,
+    )
+}
+
+// The manifest's font all text is drawn in, see `assets::Manifest`.
+const FONT: &str = "text";
+// Space kept clear at the sides of the screen by centered text.
+const TEXT_MARGIN: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Vec2 { x, y }
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, scale: f32) -> Vec2 {
+        Vec2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl From<Vec2> for ggez::mint::Point2<f32> {
+    fn from(v: Vec2) -> Self {
+        ggez::mint::Point2 { x: v.x, y: v.y }
+    }
+}
+
+impl From<Vec2> for ggez::mint::Vector2<f32> {
+    fn from(v: Vec2) -> Self {
+        ggez::mint::Vector2 { x: v.x, y: v.y }
+    }
+}
+
+// Displays at or above this scale factor use the @2x asset set.
+const HIGH_DENSITY_SCALE: f64 = 1.5;
+
+static NEXT_SPRITE_ID: AtomicU64 = AtomicU64::new(0);
+
+// A loaded image. Cloning is cheap; the pixels are shared.
+#[derive(Clone, Debug)]
+pub struct Sprite {
+    image: graphics::Image,
+    // Shared by clones and regions, so sprites of the same pixels can be
+    // batched.
+    id: u64,
+    // The part of the image drawn, in fractions of its size.
+    src: Rect,
+    // Scaled with nearest neighbour filtering instead of smoothing.
+    pixelated: bool,
+    // Image pixels per screen pixel: 2.0 for @2x assets, which are drawn at
+    // half scale so they cover the same area as their @1x versions.
+    density: f32,
+}
+
+impl Sprite {
+    // `path` is relative to the resource directory, e.g. "/floor.png". On
+    // high density displays "/floor@2x.png" is loaded instead when the asset
+    // set has it. Falls back on the built-in copy, see `assets::embedded`.
+    pub fn load(ctx: &Context, path: &str) -> GameResult<Self> {
+        if ctx.gfx.window().scale_factor() >= HIGH_DENSITY_SCALE {
+            let hi_res = match path.rfind('.') {
+                Some(dot) => format!("{}@2x{}", &path[..dot], &path[dot..]),
+                None => format!("{}@2x", path),
+            };
+            if ctx.fs.exists(&hi_res) {
+                return Ok(Sprite {
+                    image: graphics::Image::from_path(ctx, hi_res)?,
+                    id: next_sprite_id(),
+                    src: Rect::one(),
+                    pixelated: false,
+                    density: 2.0,
+                });
+            }
+        }
+
+        let image = match assets::embedded(ctx, path) {
+            Some(bytes) => graphics::Image::from_bytes(ctx, bytes)?,
+            None => graphics::Image::from_path(ctx, path)?,
+        };
+        Ok(Sprite {
+            image,
+            id: next_sprite_id(),
+            src: Rect::one(),
+            pixelated: false,
+            density: 1.0,
+        })
+    }
+
+    // Size on screen at scale 1.
+    pub fn width(&self) -> f32 {
+        self.image.width() as f32 * self.src.w / self.density
+    }
+
+    pub fn height(&self) -> f32 {
+        self.image.height() as f32 * self.src.h / self.density
+    }
+
+    // The part of this sprite within `rect`, in its size on screen at scale
+    // 1, sharing its pixels.
+    pub fn region(&self, rect: Rect) -> Self {
+        let (width, height) = (self.width(), self.height());
+        Sprite {
+            src: Rect::new(
+                self.src.x + self.src.w * rect.x / width,
+                self.src.y + self.src.h * rect.y / height,
+                self.src.w * rect.w / width,
+                self.src.h * rect.h / height,
+            ),
+            ..self.clone()
+        }
+    }
+
+    // Whole sprites laid side by side in one new image, aligned at the top.
+    pub fn strip(ctx: &Context, sprites: &[Sprite]) -> GameResult<Self> {
+        let first = match sprites.first() {
+            Some(first) => first,
+            None => {
+                return Err(GameError::ResourceLoadError(
+                    "no sprites to join".to_string(),
+                ))
+            }
+        };
+        let width: u32 = sprites.iter().map(|s| s.image.width()).sum();
+        let height = sprites.iter().map(|s| s.image.height()).max().unwrap_or(0);
+        let mut rgba = vec![0; (width * height * 4) as usize];
+
+        let mut left = 0;
+        for sprite in sprites {
+            let row = (sprite.image.width() * 4) as usize;
+            let pixels = sprite.image.to_pixels(ctx)?;
+            for (y, line) in pixels.chunks_exact(row).enumerate() {
+                let start = (y * width as usize + left) * 4;
+                rgba[start..start + row].copy_from_slice(line);
+            }
+            left += sprite.image.width() as usize;
+        }
+
+        Ok(Sprite {
+            image: graphics::Image::from_pixels(ctx, &rgba, first.image.format(), width, height),
+            id: next_sprite_id(),
+            src: Rect::one(),
+            pixelated: first.pixelated,
+            density: first.density,
+        })
+    }
+
+    // A copy with every RGBA pixel passed through `recolor`.
+    pub fn recolored(
+        &self,
+        ctx: &Context,
+        recolor: impl Fn([u8; 4]) -> [u8; 4],
+    ) -> GameResult<Self> {
+        let mut pixels = self.image.to_pixels(ctx)?;
+        for pixel in pixels.chunks_exact_mut(4) {
+            let new = recolor([pixel[0], pixel[1], pixel[2], pixel[3]]);
+            pixel.copy_from_slice(&new);
+        }
+        Ok(Sprite {
+            image: graphics::Image::from_pixels(
+                ctx,
+                &pixels,
+                self.image.format(),
+                self.image.width(),
+                self.image.height(),
+            ),
+            id: next_sprite_id(),
+            src: self.src,
+            pixelated: self.pixelated,
+            density: self.density,
+        })
+    }
+
+    // A pixel art sprite from raw RGBA bytes, `width * height * 4` long.
+    pub fn from_rgba8(ctx: &Context, width: u32, height: u32, rgba: &[u8]) -> Self {
+        Sprite {
+            image: graphics::Image::from_pixels(
+                ctx,
+                rgba,
+                ImageFormat::Rgba8UnormSrgb,
+                width,
+                height,
+            ),
+            id: next_sprite_id(),
+            src: Rect::one(),
+            pixelated: true,
+            density: 1.0,
+        }
+    }
+}
+
+fn next_sprite_id() -> u64 {
+    NEXT_SPRITE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+// One thing to draw, in screen space. Commands are drawn in order. Sprites
+// are borrowed, and text can be, so building a frame's commands copies no
+// images and only formats the text that changes.
+#[derive(Clone, Debug)]
+pub enum DrawCmd<'a> {
+    // With `flip` set the sprite is drawn upside down, still covering the
+    // area below `dest`. Every pixel is multiplied by `tint`. `rotation`, in
+    // radians, turns it about its center.
+    Sprite {
+        sprite: &'a Sprite,
+        dest: Vec2,
+        scale: f32,
+        flip: bool,
+        tint: Color,
+        rotation: f32,
+    },
+    Rect {
+        rect: Rect,
+        color: Color,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+        color: Color,
+    },
+    Line {
+        from: Vec2,
+        to: Vec2,
+        width: f32,
+        color: Color,
+    },
+    // With `centered` set, `dest` is the center of the text block instead
+    // of its top left corner.
+    Text {
+        text: Cow<'a, str>,
+        dest: Vec2,
+        size: f32,
+        color: Color,
+        centered: bool,
+    },
+}
+
+impl<'a> DrawCmd<'a> {
+    pub fn sprite(sprite: &'a Sprite, dest: Vec2) -> Self {
+        DrawCmd::Sprite {
+            sprite,
+            dest,
+            scale: 1.0,
+            flip: false,
+            tint: Color::WHITE,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn tinted_sprite(sprite: &'a Sprite, dest: Vec2, tint: Color) -> Self {
+        DrawCmd::Sprite {
+            sprite,
+            dest,
+            scale: 1.0,
+            flip: false,
+            tint,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn rotated_sprite(sprite: &'a Sprite, dest: Vec2, rotation: f32) -> Self {
+        DrawCmd::Sprite {
+            sprite,
+            dest,
+            scale: 1.0,
+            flip: false,
+            tint: Color::WHITE,
+            rotation,
+        }
+    }
+
+    pub fn flipped_sprite(sprite: &'a Sprite, dest: Vec2) -> Self {
+        DrawCmd::Sprite {
+            sprite,
+            dest,
+            scale: 1.0,
+            flip: true,
+            tint: Color::WHITE,
+            rotation: 0.0,
+        }
+    }
+
+    pub fn text(text: impl Into<Cow<'a, str>>, dest: Vec2, size: f32, color: Color) -> Self {
+        DrawCmd::Text {
+            text: text.into(),
+            dest,
+            size,
+            color,
+            centered: false,
+        }
+    }
+
+    pub fn centered_text(
+        text: impl Into<Cow<'a, str>>,
+        dest: Vec2,
+        size: f32,
+        color: Color,
+    ) -> Self {
+        DrawCmd::Text {
+            text: text.into(),
+            dest,
+            size,
+            color,
+            centered: true,
+        }
+    }
+
+    // The four sides of `rect`, `width` thick.
+    pub fn outline(rect: Rect, width: f32, color: Color) -> [Self; 4] {
+        let corners = [
+            Vec2::new(rect.x, rect.y),
+            Vec2::new(rect.x + rect.w, rect.y),
+            Vec2::new(rect.x + rect.w, rect.y + rect.h),
+            Vec2::new(rect.x, rect.y + rect.h),
+        ];
+        let side = |n: usize| DrawCmd::Line {
+            from: corners[n],
+            to: corners[(n + 1) % 4],
+            width,
+            color,
+        };
+        [side(0), side(1), side(2), side(3)]
+    }
+
+    // Scales the command `by` times about `about`, e.g. to zoom the camera.
+    pub fn zoom(&mut self, about: Vec2, by: f32) {
+        let at = |point: Vec2| about + (point - about) * by;
+        match self {
+            DrawCmd::Sprite { dest, scale, .. } => {
+                *dest = at(*dest);
+                *scale *= by;
+            }
+            DrawCmd::Text { dest, size, .. } => {
+                *dest = at(*dest);
+                *size *= by;
+            }
+            DrawCmd::Rect { rect, .. } => {
+                let corner = at(Vec2::new(rect.x, rect.y));
+                *rect = Rect::new(corner.x, corner.y, rect.w * by, rect.h * by);
+            }
+            DrawCmd::Circle { center, radius, .. } => {
+                *center = at(*center);
+                *radius *= by;
+            }
+            DrawCmd::Line {
+                from, to, width, ..
+            } => {
+                *from = at(*from);
+                *to = at(*to);
+                *width *= by;
+            }
+        }
+    }
+
+    // Moves the command by `by`, e.g. to shake the camera.
+    pub fn shift(&mut self, by: Vec2) {
+        match self {
+            DrawCmd::Sprite { dest, .. } | DrawCmd::Text { dest, .. } => *dest += by,
+            DrawCmd::Rect { rect, .. } => {
+                rect.x += by.x;
+                rect.y += by.y;
+            }
+            DrawCmd::Circle { center, .. } => *center += by,
+            DrawCmd::Line { from, to, .. } => {
+                *from += by;
+                *to += by;
+            }
+        }
+    }
+}
+
+// Registers the font at `path` as `name`. Done by `Assets::load` once
+// after creating the context.
+pub fn load_font(ctx: &mut Context, name: &str, path: &str) -> GameResult<()> {
+    let font = match assets::embedded(ctx, path) {
+        Some(bytes) => FontData::from_slice(bytes)?,
+        None => FontData::from_path(ctx, path)?,
+    };
+    ctx.gfx.add_font(name, font);
+    Ok(())
+}
+
+// How the world's images are sampled when scaled: smoothed, or as sharp
+// square pixels for the pixel art look.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Filter {
+    Linear,
+    Nearest,
+}
+
+impl Filter {
+    pub fn toggled(self) -> Self {
+        match self {
+            Filter::Linear => Filter::Nearest,
+            Filter::Nearest => Filter::Linear,
+        }
+    }
+
+    fn sampler(self) -> Sampler {
+        match self {
+            Filter::Linear => Sampler::default(),
+            Filter::Nearest => Sampler::nearest_clamp(),
+        }
+    }
+}
+
+// Where the screen sits in a window of any size: scaled as large as fits
+// without changing its aspect ratio, and centered, with black bars filling
+// the rest. Game code only ever sees screen coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    // Window pixels per screen unit.
+    scale: f32,
+    // The whole window, in screen coordinates.
+    window: Rect,
+    // Fraction of the window's resolution the world is drawn at.
+    render_scale: f32,
+    filter: Filter,
+}
+
+impl Viewport {
+    // The viewport for a window `width` by `height` pixels, as passed to
+    // `resize_event`.
+    pub fn fit(width: f32, height: f32) -> Self {
+        // A minimized window can report no size at all.
+        let (width, height) = (width.max(1.0), height.max(1.0));
+        let screen_width = screen_width();
+        let scale = (width / screen_width).min(height / SCREEN_HEIGHT);
+        let (w, h) = (width / scale, height / scale);
+        Viewport {
+            scale,
+            window: Rect::new((screen_width - w) / 2.0, (SCREEN_HEIGHT - h) / 2.0, w, h),
+            render_scale: 1.0,
+            filter: Filter::Linear,
+        }
+    }
+
+    // Draws the world at `render_scale` times the window's resolution and
+    // scales it up, which is lighter on weak GPUs. Text stays sharp.
+    pub fn with_render_scale(self, render_scale: f32) -> Self {
+        Viewport {
+            render_scale,
+            ..self
+        }
+    }
+
+    pub fn with_filter(self, filter: Filter) -> Self {
+        Viewport { filter, ..self }
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    // The viewport after the window was resized, at the same render scale
+    // and filter.
+    pub fn resized(&self, width: f32, height: f32) -> Self {
+        Viewport::fit(width, height)
+            .with_render_scale(self.render_scale)
+            .with_filter(self.filter)
+    }
+
+    // Converts a point in window pixels, such as a mouse click, to screen
+    // coordinates.
+    pub fn to_screen(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            self.window.x + point.x / self.scale,
+            self.window.y + point.y / self.scale,
+        )
+    }
+
+    // The parts of the window outside the screen.
+    fn bars(&self) -> Vec<Rect> {
+        let Rect { x, y, w, h } = self.window;
+        [
+            Rect::new(x, y, -x, h),
+            Rect::new(screen_width(), y, -x, h),
+            Rect::new(x, y, w, -y),
+            Rect::new(x, SCREEN_HEIGHT, w, -y),
+        ]
+        .iter()
+        .copied()
+        .filter(|bar| bar.w > 0.0 && bar.h > 0.0)
+        .collect()
+    }
+}
+
+thread_local! {
+    // What the world is drawn on below full resolution, kept for as long
+    // as its size doesn't change.
+    static WORLD_IMAGE: RefCell<Option<graphics::Image>> = const { RefCell::new(None) };
+}
+
+// Clears the screen to `background`, draws `world` and then `ui` in order
+// and presents. The world is drawn at the viewport's render scale and
+// filter, the UI always at full resolution and smoothed. Returns the part
+// of the time spent laying out and queuing text.
+pub fn render(
+    ctx: &mut Context,
+    viewport: &Viewport,
+    background: Color,
+    world: &[DrawCmd],
+    ui: &[DrawCmd],
+) -> GameResult<Duration> {
+    let mut text_time = Duration::ZERO;
+    let mut canvas = if viewport.render_scale < 1.0 {
+        let pixels = |size: f32| ((size * viewport.scale * viewport.render_scale) as u32).max(1);
+        let (width, height) = (pixels(viewport.window.w), pixels(viewport.window.h));
+        let image = WORLD_IMAGE.with(|cached| {
+            let mut cached = cached.borrow_mut();
+            match &*cached {
+                Some(image) if image.width() == width && image.height() == height => image.clone(),
+                _ => {
+                    let format = ctx.gfx.surface_format();
+                    let image = graphics::Image::new_canvas_image(ctx, format, width, height, 1);
+                    *cached = Some(image.clone());
+                    image
+                }
+            }
+        });
+
+        let mut world_canvas = Canvas::from_image(ctx, image.clone(), background);
+        world_canvas.set_screen_coordinates(viewport.window);
+        text_time += draw_cmds(ctx, &mut world_canvas, world, viewport.filter)?;
+        world_canvas.finish(ctx)?;
+
+        let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
+        canvas.set_screen_coordinates(viewport.window);
+        canvas.set_sampler(viewport.filter.sampler());
+        canvas.draw(
+            &image,
+            DrawParam::default()
+                .dest(Vec2::new(viewport.window.x, viewport.window.y))
+                .scale(Vec2::new(
+                    viewport.window.w / width as f32,
+                    viewport.window.h / height as f32,
+                )),
+        );
+        canvas
+    } else {
+        let mut canvas = Canvas::from_frame(ctx, background);
+        canvas.set_screen_coordinates(viewport.window);
+        text_time += draw_cmds(ctx, &mut canvas, world, viewport.filter)?;
+        canvas
+    };
+    text_time += draw_cmds(ctx, &mut canvas, ui, Filter::Linear)?;
+
+    // Covers whatever was drawn past the edges of the screen, like pipes
+    // about to scroll in.
+    let bars = viewport.bars();
+    if !bars.is_empty() {
+        let mut shapes = graphics::MeshBuilder::new();
+        for bar in bars {
+            shapes.rectangle(graphics::DrawMode::fill(), bar, Color::BLACK)?;
+        }
+        flush_shapes(ctx, &mut canvas, &mut shapes)?;
+    }
+
+    canvas.finish(ctx)?;
+    Ok(text_time)
+}
+
+// Draws `cmds` off screen on a `width` by `height` image, in its pixels,
+// and saves it as a PNG at `path` in the user config directory.
+pub fn render_png(
+    ctx: &mut Context,
+    width: u32,
+    height: u32,
+    background: Color,
+    cmds: &[DrawCmd],
+    path: &str,
+) -> GameResult {
+    let image =
+        graphics::Image::new_canvas_image(ctx, ImageFormat::Rgba8UnormSrgb, width, height, 1);
+    let mut canvas = Canvas::from_image(ctx, image.clone(), background);
+    draw_cmds(ctx, &mut canvas, cmds, Filter::Linear)?;
+    canvas.finish(ctx)?;
+    image.encode(ctx, graphics::ImageEncodingFormat::Png, path)
+}
+
+// Draws `cmds` in order with `filter`, returning the time spent laying out
+// text.
+fn draw_cmds(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    cmds: &[DrawCmd],
+    filter: Filter,
+) -> GameResult<Duration> {
+    canvas.set_sampler(filter.sampler());
+    let mut text_time = Duration::ZERO;
+    // Consecutive shapes are batched into one mesh, and consecutive sprites
+    // of the same image into one instance array.
+    let mut shapes = graphics::MeshBuilder::new();
+    let mut pending = false;
+    let mut sprites: Option<(&Sprite, Vec<DrawParam>)> = None;
+
+    for cmd in cmds {
+        let same_image = match (&sprites, cmd) {
+            (Some((batched, _)), DrawCmd::Sprite { sprite, .. }) => batched.id == sprite.id,
+            _ => false,
+        };
+        if !same_image {
+            if let Some((sprite, params)) = sprites.take() {
+                flush_sprites(ctx, canvas, sprite, params, filter);
+            }
+        }
+        if pending
+            && !matches!(
+                cmd,
+                DrawCmd::Rect { .. } | DrawCmd::Circle { .. } | DrawCmd::Line { .. }
+            )
+        {
+            flush_shapes(ctx, canvas, &mut shapes)?;
+            pending = false;
+        }
+
+        match cmd {
+            DrawCmd::Sprite {
+                sprite,
+                dest,
+                scale,
+                flip,
+                tint,
+                rotation,
+            } => {
+                let (dest, scale_y) = if *flip {
+                    (*dest + Vec2::new(0.0, sprite.height() * scale), -scale)
+                } else {
+                    (*dest, *scale)
+                };
+                let density = sprite.density;
+                let mut param = DrawParam::default()
+                    .src(sprite.src)
+                    .dest(dest)
+                    .color(*tint)
+                    .scale(Vec2::new(scale / density, scale_y / density));
+                if *rotation != 0.0 {
+                    let half = Vec2::new(sprite.width(), sprite.height()) * (*scale / 2.0);
+                    param = param
+                        .dest(dest + half)
+                        .offset(Vec2::new(0.5, 0.5))
+                        .rotation(*rotation);
+                }
+                sprites
+                    .get_or_insert_with(|| (*sprite, Vec::new()))
+                    .1
+                    .push(param);
+            }
+            DrawCmd::Rect { rect, color } => {
+                shapes.rectangle(graphics::DrawMode::fill(), *rect, *color)?;
+                pending = true;
+            }
+            DrawCmd::Circle {
+                center,
+                radius,
+                color,
+            } => {
+                shapes.circle(graphics::DrawMode::fill(), *center, *radius, 0.5, *color)?;
+                pending = true;
+            }
+            DrawCmd::Line {
+                from,
+                to,
+                width,
+                color,
+            } => {
+                // Zero length lines don't tessellate.
+                if from != to {
+                    shapes.line(&[*from, *to], *width, *color)?;
+                    pending = true;
+                }
+            }
+            DrawCmd::Text {
+                text,
+                dest,
+                size,
+                color,
+                centered,
+            } => {
+                let start = Instant::now();
+                let mut text = graphics::Text::new(
+                    graphics::TextFragment::new(text.as_ref())
+                        .font(FONT)
+                        .scale(*size)
+                        .color(*color),
+                );
+                let mut dest = *dest;
+                if *centered {
+                    let size = text.measure(ctx)?;
+                    let room = screen_width() - 2.0 * TEXT_MARGIN;
+                    if size.x > room {
+                        // Too long for a narrow screen, so it wraps, each
+                        // line centered on `dest`.
+                        text.set_bounds([room, f32::INFINITY])
+                            .set_layout(graphics::TextLayout::center());
+                    } else {
+                        dest -= Vec2::new(size.x / 2.0, size.y / 2.0);
+                    }
+                }
+                canvas.draw(&text, DrawParam::default().dest(dest));
+                text_time += start.elapsed();
+            }
+        }
+    }
+    if pending {
+        flush_shapes(ctx, canvas, &mut shapes)?;
+    }
+    if let Some((sprite, params)) = sprites {
+        flush_sprites(ctx, canvas, sprite, params, filter);
+    }
+    Ok(text_time)
+}
+
+fn flush_sprites(
+    ctx: &Context,
+    canvas: &mut Canvas,
+    sprite: &Sprite,
+    params: Vec<DrawParam>,
+    filter: Filter,
+) {
+    if sprite.pixelated {
+        canvas.set_sampler(Sampler::nearest_clamp());
+    }
+    if let [param] = params[..] {
+        canvas.draw(&sprite.image, param);
+    } else {
+        let mut batch = graphics::InstanceArray::new(ctx, sprite.image.clone());
+        batch.set(params);
+        canvas.draw(&batch, DrawParam::default());
+    }
+    if sprite.pixelated {
+        canvas.set_sampler(filter.sampler());
+    }
+}
+
+fn flush_shapes(
+    ctx: &Context,
+    canvas: &mut Canvas,
+    shapes: &mut graphics::MeshBuilder,
+) -> GameResult<()> {
+    let mesh = graphics::Mesh::from_data(ctx, shapes.build());
+    canvas.draw(&mesh, DrawParam::default());
+    *shapes = graphics::MeshBuilder::new();
+    Ok(())
+}
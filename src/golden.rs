@@ -0,0 +1,107 @@
+// Golden replays: every `tests/replays/NAME.tas` script is played headlessly
+// and has to end exactly as recorded in `NAME.golden` next to it. A failure
+// means physics or spawning changed; if that was on purpose, rerun with
+// `UPDATE_GOLDEN=1 cargo test` to record the new results and check them in.
+
+use crate::tas::{self, TasScript};
+use crate::{Game, GameState};
+use specs::*;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Long enough for a run to clear a few dozen pipes.
+const MAX_FRAMES: i32 = 3000;
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    score: i32,
+    // The frame the bird died on, or None if it lasted until `MAX_FRAMES`.
+    death_frame: Option<i32>,
+}
+
+impl Outcome {
+    fn play(script: TasScript) -> Self {
+        let world = tas::play(script, None, MAX_FRAMES);
+        let game = world.read_resource::<Game>();
+        let over = *world.read_resource::<GameState>() == GameState::GameOver;
+        Outcome {
+            score: game.score,
+            death_frame: if over { Some(game.frames) } else { None },
+        }
+    }
+
+    // The golden file format, one `key value` pair per line:
+    //
+    //     score 12
+    //     death 1534
+    //
+    // with `death none` for runs that survive.
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut score = None;
+        let mut death_frame = None;
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("score"), Some(value)) => score = value.parse().ok(),
+                (Some("death"), Some("none")) => death_frame = Some(None),
+                (Some("death"), Some(value)) => death_frame = value.parse().ok().map(Some),
+                (None, _) => (),
+                _ => return Err(format!("unexpected line `{}`", line)),
+            }
+        }
+
+        match (score, death_frame) {
+            (Some(score), Some(death_frame)) => Ok(Outcome { score, death_frame }),
+            _ => Err("needs a `score` and a `death` line".to_string()),
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let death = match self.death_frame {
+            Some(frame) => frame.to_string(),
+            None => "none".to_string(),
+        };
+        format!("score {}\ndeath {}\n", self.score, death)
+    }
+}
+
+#[test]
+fn golden_replays() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replays");
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+
+    let mut scripts: Vec<_> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("can't read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "tas"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no replays in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for path in &scripts {
+        let outcome = Outcome::play(TasScript::load(path).unwrap());
+        let golden_path = path.with_extension("golden");
+
+        if update {
+            fs::write(&golden_path, outcome.to_text()).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden_path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Outcome::parse(&text));
+        match expected {
+            Ok(expected) if expected == outcome => (),
+            Ok(expected) => failures.push(format!(
+                "{}: expected {:?}, got {:?}",
+                path.display(),
+                expected,
+                outcome
+            )),
+            Err(e) => failures.push(format!("{}: {}", golden_path.display(), e)),
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
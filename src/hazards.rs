@@ -0,0 +1,129 @@
+use crate::engine::{Color, DrawCmd, Rect, Vec2};
+use crate::world_setup::FLOOR_Y;
+use crate::{BackgroundTag, CollisionBox, Difficulty, Hazard, ObstacleTag, PipeColor, Position};
+use rand::{Rng, RngCore};
+use specs::world::EntitiesRes;
+use specs::LazyUpdate;
+
+// World distance between two hazards, upper bound excluded.
+const HAZARD_EVERY: (f32, f32) = (900.0, 2400.0);
+// How far into a run the first hazard shows up.
+const FIRST_HAZARD: f32 = 1500.0;
+
+const SPIKES_WIDTH: f32 = 48.0;
+const SPIKES_HEIGHT: f32 = 18.0;
+const PUDDLE_WIDTH: f32 = 96.0;
+const PUDDLE_HEIGHT: f32 = 10.0;
+
+// Spawn state for floor hazards: where in the world the next one lies.
+#[derive(Clone)]
+pub struct Hazards {
+    next_at: f32,
+}
+
+impl Default for Hazards {
+    fn default() -> Self {
+        Hazards {
+            next_at: FIRST_HAZARD,
+        }
+    }
+}
+
+impl Hazards {
+    // Whether the next hazard's spot, `distance` being the world distance
+    // at the left edge of the screen, reached `spawn_x` on screen.
+    pub fn due(&self, distance: f32, spawn_x: f32) -> bool {
+        distance + spawn_x >= self.next_at
+    }
+
+    // Puts a random hazard on the floor at screen x `spawn_x` and picks the
+    // spot of the one after.
+    pub(crate) fn spawn(
+        &mut self,
+        spawn_x: f32,
+        difficulty: &Difficulty,
+        rng: &mut dyn RngCore,
+        entities: &EntitiesRes,
+        updater: &LazyUpdate,
+    ) {
+        let (hazard, width, height) = if rng.gen() {
+            (Hazard::Spikes, SPIKES_WIDTH, SPIKES_HEIGHT)
+        } else {
+            (Hazard::Puddle, PUDDLE_WIDTH, PUDDLE_HEIGHT)
+        };
+        self.next_at += rng.gen_range(HAZARD_EVERY.0, HAZARD_EVERY.1);
+
+        let y = FLOOR_Y - height;
+        let entity = entities.create();
+        updater.insert(
+            entity,
+            Position {
+                position: Vec2::new(spawn_x, y),
+                speed: Vec2::ZERO,
+            },
+        );
+        updater.insert(
+            entity,
+            BackgroundTag {
+                velocity: difficulty.speed,
+                width,
+                num_copies: 1,
+            },
+        );
+        updater.insert(
+            entity,
+            ObstacleTag {
+                hazard: Some(hazard),
+                color: PipeColor::Green,
+                ..ObstacleTag::default()
+            },
+        );
+        updater.insert(
+            entity,
+            CollisionBox {
+                origin: Vec2::new(spawn_x, y),
+                height,
+                width,
+            },
+        );
+    }
+}
+
+// Draws a hazard sitting on the floor in `coll_box`.
+pub(crate) fn draw(cmds: &mut Vec<DrawCmd>, coll_box: &CollisionBox, hazard: Hazard) {
+    let Vec2 { x, y } = coll_box.origin;
+    match hazard {
+        Hazard::Spikes => {
+            let color = Color::new(0.75, 0.75, 0.8, 1.0);
+            let spike = 12.0;
+            let mut left = x;
+            while left + spike <= x + coll_box.width {
+                // Narrowing slabs make up each spike.
+                for step in 0..4 {
+                    let inset = spike / 2.0 * step as f32 / 4.0;
+                    let slab = coll_box.height / 4.0;
+                    cmds.push(DrawCmd::Rect {
+                        rect: Rect::new(
+                            left + inset,
+                            y + coll_box.height - slab * (step + 1) as f32,
+                            spike - 2.0 * inset,
+                            slab,
+                        ),
+                        color,
+                    });
+                }
+                left += spike;
+            }
+        }
+        Hazard::Puddle => {
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x, y, coll_box.width, coll_box.height),
+                color: Color::new(0.35, 0.8, 0.2, 0.9),
+            });
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x + 8.0, y + 2.0, coll_box.width / 3.0, 2.0),
+                color: Color::new(0.7, 1.0, 0.5, 0.9),
+            });
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often the files are looked at.
+const INTERVAL: Duration = Duration::from_millis(500);
+
+// What changed since the last look.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Changes {
+    pub assets: bool,
+    pub config: bool,
+}
+
+// Watches the assets directory and `config.toml` while the game runs with
+// `--dev`, so sprites, fonts and tuning can be changed without restarting.
+// It compares modification times every `INTERVAL`, like `TuningWatch`
+// does for the `--tuning` file.
+pub struct AssetWatch {
+    pub dir: PathBuf,
+    config: PathBuf,
+    assets: HashMap<PathBuf, SystemTime>,
+    config_modified: Option<SystemTime>,
+    next: Instant,
+}
+
+impl AssetWatch {
+    pub fn new(dir: PathBuf, config: PathBuf) -> Self {
+        let mut watch = AssetWatch {
+            dir,
+            config,
+            assets: HashMap::new(),
+            config_modified: None,
+            next: Instant::now(),
+        };
+        watch.check();
+        watch
+    }
+
+    pub fn poll(&mut self) -> Changes {
+        let now = Instant::now();
+        if now < self.next {
+            return Changes::default();
+        }
+        self.next = now + INTERVAL;
+        self.check()
+    }
+
+    fn check(&mut self) -> Changes {
+        let mut assets = HashMap::new();
+        scan(&self.dir, &mut assets);
+        let config_modified = modified(&self.config);
+        let changes = Changes {
+            assets: assets != self.assets,
+            config: config_modified != self.config_modified,
+        };
+        self.assets = assets;
+        self.config_modified = config_modified;
+        changes
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Every file under `dir` with when it was last changed.
+fn scan(dir: &Path, files: &mut HashMap<PathBuf, SystemTime>) {
+    let entries = fs::read_dir(dir).into_iter().flatten();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            scan(&path, files);
+        } else if let Some(modified) = modified(&path) {
+            files.insert(path, modified);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sees_new_and_changed_files() {
+        let dir = std::env::temp_dir().join(format!("rusty_bird_watch_{}", std::process::id()));
+        let assets = dir.join("assets");
+        fs::create_dir_all(assets.join("birds")).unwrap();
+        let config = dir.join("config.toml");
+        fs::write(&config, "").unwrap();
+
+        let mut watch = AssetWatch::new(assets.clone(), config.clone());
+        assert_eq!(watch.check(), Changes::default());
+        fs::write(assets.join("birds").join("robin1.png"), "").unwrap();
+        let changes = watch.check();
+        assert!(changes.assets && !changes.config);
+        fs::remove_file(&config).unwrap();
+        assert_eq!(
+            watch.check(),
+            Changes {
+                assets: false,
+                config: true
+            }
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
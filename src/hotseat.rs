@@ -0,0 +1,143 @@
+use crate::engine::{self, Color, DrawCmd, Vec2};
+use crate::lang;
+use crate::streamer;
+use crate::GameState;
+
+const MIN_PLAYERS: usize = 2;
+const MAX_PLAYERS: usize = 8;
+
+// Pass-the-keyboard party mode: named players take turns at one run each,
+// all on the same pipes, and once everyone has flown the round ends with a
+// ranking. The next round starts over with the same players.
+pub struct HotSeat {
+    players: Vec<String>,
+    // Scores of the players who have had their turn this round, in turn
+    // order.
+    scores: Vec<i32>,
+    // Streamer mode shows players by number instead of by name.
+    pub hide_names: bool,
+}
+
+impl HotSeat {
+    // From a comma separated list of names, e.g. "ana,ben,cleo".
+    pub fn parse(names: &str) -> Result<Self, String> {
+        let players: Vec<String> = names
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players.len()) {
+            return Err(format!(
+                "the hot seat needs {} to {} players, got {}",
+                MIN_PLAYERS,
+                MAX_PLAYERS,
+                players.len()
+            ));
+        }
+        Ok(HotSeat {
+            players,
+            scores: Vec::new(),
+            hide_names: false,
+        })
+    }
+
+    // Whoever is playing, or plays next between turns.
+    pub fn current(&self) -> &str {
+        &self.players[self.scores.len() % self.players.len()]
+    }
+
+    // The current player as shown on screen.
+    pub fn current_label(&self) -> String {
+        self.label(self.scores.len() % self.players.len())
+    }
+
+    fn label(&self, n: usize) -> String {
+        if self.hide_names {
+            streamer::player_label(n)
+        } else {
+            self.players[n].clone()
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.scores.len() == self.players.len()
+    }
+
+    // Ends the current player's turn with `score`.
+    pub fn record(&mut self, score: i32) {
+        if !self.finished() {
+            self.scores.push(score);
+        }
+    }
+
+    // Called as a run starts; after the ranking that's a new round.
+    pub fn next_turn(&mut self) {
+        if self.finished() {
+            self.scores.clear();
+        }
+    }
+
+    // Players and scores, best first. Tied players share a rank.
+    fn ranking(&self) -> Vec<(usize, String, i32)> {
+        let mut order: Vec<_> = self.scores.iter().enumerate().collect();
+        // Stable, so ties keep turn order.
+        order.sort_by_key(|&(_, &score)| -score);
+
+        let mut ranking: Vec<(usize, String, i32)> = Vec::with_capacity(order.len());
+        for (n, (player, &score)) in order.into_iter().enumerate() {
+            let rank = match ranking.last() {
+                Some(&(rank, _, last)) if last == score => rank,
+                _ => n + 1,
+            };
+            ranking.push((rank, self.label(player), score));
+        }
+        ranking
+    }
+
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, state: GameState) {
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        if state != GameState::GameOver || !self.finished() {
+            cmds.push(DrawCmd::text(
+                lang::fill(
+                    "turn",
+                    &[
+                        &self.current_label(),
+                        &(self.scores.len() % self.players.len() + 1),
+                        &self.players.len(),
+                    ],
+                ),
+                Vec2::new(10.0, 10.0),
+                22.0,
+                white,
+            ));
+            return;
+        }
+
+        let center_x = engine::screen_width() / 2.0;
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("ranking"),
+            Vec2::new(center_x, 90.0),
+            60.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+        for (n, (rank, name, score)) in self.ranking().into_iter().enumerate() {
+            let color = if rank == 1 {
+                Color::new(1.0, 0.9, 0.2, 1.0)
+            } else {
+                white
+            };
+            cmds.push(DrawCmd::centered_text(
+                format!("{}. {}  {}", rank, name, score),
+                Vec2::new(center_x, 170.0 + 40.0 * n as f32),
+                30.0,
+                color,
+            ));
+        }
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("another_round"),
+            Vec2::new(center_x, engine::SCREEN_HEIGHT - 40.0),
+            20.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::engine::{self, Vec2};
+use crate::lang;
+use crate::systems::particles;
+use crate::{
+    Camera, CollisionBox, Game, GameEvent, GameEvents, Lives, ParticleEffects, PlayerId, PlayerTag,
+    TimeScale, PULSE_ZOOM,
+};
+use serde::Deserialize;
+use specs::*;
+
+// Ticks the score takes to settle after popping out as it goes up.
+pub const POP_TICKS: u32 = 12;
+
+// Where the HUD's pieces go, from the `[hud]` section of the config. Each
+// piece is placed at an offset from a corner of the screen, and can be
+// hidden, e.g. to leave room for a stream overlay. The score is centered on
+// its position, at the middle of the top edge unless moved. A piece in the
+// file starts from the top left corner, not from where it's shown by
+// default:
+//
+//     [hud.score]
+//     anchor = "bottom-left"
+//     offset = [10, -40]
+//
+//     [hud.timer]
+//     visible = false
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct HudLayout {
+    pub score: HudItem,
+    pub best: HudItem,
+    // The practice speed, when it isn't 1.
+    pub speed: HudItem,
+    // The speedrun timer and its splits, listed below it.
+    pub timer: HudItem,
+    // Lives left, in lives mode.
+    pub lives: HudItem,
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        HudLayout {
+            score: HudItem::new(Anchor::Top, 0.0, 20.0),
+            best: HudItem::new(Anchor::TopRight, -224.0, 10.0),
+            speed: HudItem::new(Anchor::TopRight, -224.0, 40.0),
+            timer: HudItem::new(Anchor::TopLeft, 10.0, 10.0),
+            lives: HudItem::new(Anchor::TopRight, -224.0, 70.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    // The middle of the top edge.
+    Top,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+// The offset goes from the anchor corner to the top left of the text, so
+// pieces on the right need a negative x.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct HudItem {
+    pub anchor: Anchor,
+    pub offset: [f32; 2],
+    pub visible: bool,
+}
+
+impl Default for HudItem {
+    fn default() -> Self {
+        HudItem::new(Anchor::TopLeft, 0.0, 0.0)
+    }
+}
+
+impl HudItem {
+    fn new(anchor: Anchor, x: f32, y: f32) -> Self {
+        HudItem {
+            anchor,
+            offset: [x, y],
+            visible: true,
+        }
+    }
+
+    // Where to draw the piece, or None when it's hidden. `margin` moves it
+    // that much further in from both edges of its corner.
+    pub fn position(&self, margin: f32) -> Option<Vec2> {
+        if !self.visible {
+            return None;
+        }
+        let (right, bottom) = match self.anchor {
+            Anchor::Top => {
+                return Some(Vec2::new(
+                    engine::screen_width() / 2.0 + self.offset[0],
+                    margin + self.offset[1],
+                ))
+            }
+            Anchor::TopLeft => (false, false),
+            Anchor::TopRight => (true, false),
+            Anchor::BottomLeft => (false, true),
+            Anchor::BottomRight => (true, true),
+        };
+        let (x, inward_x) = if right {
+            (engine::screen_width(), -margin)
+        } else {
+            (0.0, margin)
+        };
+        let (y, inward_y) = if bottom {
+            (engine::SCREEN_HEIGHT, -margin)
+        } else {
+            (0.0, margin)
+        };
+        Some(Vec2::new(
+            x + inward_x + self.offset[0],
+            y + inward_y + self.offset[1],
+        ))
+    }
+}
+
+// The text shown while playing next to the score, which is drawn in
+// digits. `HudSystem` only formats it again when the numbers behind it
+// change.
+#[derive(Default)]
+pub struct Hud {
+    // Set by the game once the run counts as assisted; marked under the
+    // score.
+    pub assisted: bool,
+    // Ticks left of the score popping out, see `ScorePopSystem`.
+    pub pop: u32,
+    // Empty while there's no best score yet.
+    pub best: String,
+    // Empty outside lives mode.
+    pub lives: String,
+    shown_best: Option<i32>,
+    shown_lives: Option<u32>,
+    pub layout: HudLayout,
+    // Kept clear around the edges in streamer mode.
+    pub margin: f32,
+}
+
+impl Hud {
+    pub fn new(layout: HudLayout, margin: f32) -> Self {
+        Hud {
+            layout,
+            margin,
+            ..Hud::default()
+        }
+    }
+}
+
+// Brings the HUD text up to date, right before drawing.
+pub struct HudSystem;
+
+impl<'a> System<'a> for HudSystem {
+    type SystemData = (Read<'a, Game>, Option<Read<'a, Lives>>, Write<'a, Hud>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (game, lives, mut hud) = data;
+
+        if hud.shown_best != Some(game.best) {
+            hud.best = if game.best > 0 {
+                lang::fill("hud_best", &[&game.best])
+            } else {
+                String::new()
+            };
+            hud.shown_best = Some(game.best);
+        }
+
+        let left = lives.map(|lives| lives.0.saturating_sub(game.lives_lost));
+        if hud.shown_lives != left {
+            hud.lives = left.map_or(String::new(), |left| lang::fill("hud_lives", &[&left]));
+            hud.shown_lives = left;
+        }
+    }
+}
+
+// The game's reader of `GameEvents` for the score's pop.
+pub struct ScorePopReader(pub ReaderId<GameEvent>);
+
+// Pops the score out whenever a pipe is passed, with a slight pulse of the
+// camera's zoom and a "+1" floating up from the first player's bird, and
+// lets it settle while the game runs. Runs every tick after the rest. The
+// "+1" is a particle, so low power mode goes without it.
+pub struct ScorePopSystem;
+
+impl<'a> System<'a> for ScorePopSystem {
+    type SystemData = (
+        WriteExpect<'a, ScorePopReader>,
+        Read<'a, GameEvents>,
+        Read<'a, TimeScale>,
+        Write<'a, Hud>,
+        Option<Write<'a, Camera>>,
+        Option<Read<'a, ParticleEffects>>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, PlayerId>,
+        ReadStorage<'a, CollisionBox>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut reader,
+            events,
+            time,
+            mut hud,
+            camera,
+            effects,
+            entities,
+            updater,
+            players,
+            ids,
+            boxes,
+        ) = data;
+        let passed = events
+            .read(&mut reader.0)
+            .any(|event| matches!(event, GameEvent::Passed { .. }));
+        if passed {
+            hud.pop = POP_TICKS;
+            let bird = (&players, &boxes, ids.maybe())
+                .join()
+                .find(|(_, _, id)| !id.is_some_and(|id| id.0 > 0));
+            if let (Some((_, coll_box, _)), Some(_)) = (bird, &effects) {
+                let above = coll_box.origin + Vec2::new(coll_box.width / 2.0, -12.0);
+                particles::score_popup(above, &entities, &updater);
+            }
+            // A crash's zoom isn't cut short.
+            if let Some(mut camera) = camera.filter(|camera| camera.zoom < PULSE_ZOOM) {
+                let center = Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT / 2.0);
+                camera.punch(PULSE_ZOOM, center);
+            }
+        } else if !time.is_paused() {
+            hud.pop = hud.pop.saturating_sub(1);
+        }
+    }
+}
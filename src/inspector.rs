@@ -0,0 +1,208 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::{
+    BackgroundTag, CollisionBox, Debris, GhostBird, Image, ObstacleTag, Particle, PlayerTag,
+    Position, ZOrder,
+};
+use specs::*;
+
+// The panel sits on the right, leaving the bird in view.
+const PANEL_WIDTH: f32 = 420.0;
+const TEXT_INSET: f32 = 8.0;
+const ROW_HEIGHT: f32 = 16.0;
+const TEXT_SIZE: f32 = 14.0;
+// Rows left at the bottom of the panel for the selected entity.
+const DETAIL_ROWS: usize = 4;
+
+fn panel_x() -> f32 {
+    (engine::screen_width() - PANEL_WIDTH).max(0.0)
+}
+
+// Debug panel listing every live entity with its components, for chasing
+// spawn and cleanup bugs. Clicking a row, or an entity in the world,
+// selects it and outlines it on screen.
+#[derive(Default)]
+pub struct Inspector {
+    pub open: bool,
+    selected: Option<Entity>,
+}
+
+// What kind of thing an entity is, from its tags.
+fn layer(world: &World, entity: Entity) -> String {
+    let players = world.read_storage::<PlayerTag>();
+    let obstacles = world.read_storage::<ObstacleTag>();
+    let backgrounds = world.read_storage::<BackgroundTag>();
+
+    if players.contains(entity) {
+        "bird".to_string()
+    } else if world.read_storage::<GhostBird>().contains(entity) {
+        "ghost bird".to_string()
+    } else if world.read_storage::<Debris>().contains(entity) {
+        "debris".to_string()
+    } else if world.read_storage::<Particle>().contains(entity) {
+        "particle".to_string()
+    } else if let Some(obs) = obstacles.get(entity) {
+        match (obs.hazard, obs.cave, obs.top) {
+            (Some(hazard), _, _) => format!("{:?}", hazard).to_lowercase(),
+            (None, true, true) => "cave slot".to_string(),
+            (None, true, false) => "cave".to_string(),
+            (None, false, true) => "pipe top".to_string(),
+            (None, false, false) => "pipe bottom".to_string(),
+        }
+    } else if let Some(bg) = backgrounds.get(entity) {
+        let z = world.read_storage::<ZOrder>().get(entity).copied();
+        format!("scenery v{} z{}", bg.velocity, z.unwrap_or_default().0)
+    } else {
+        "-".to_string()
+    }
+}
+
+// The area an entity covers on screen: its collider, or else its image.
+fn bounds(world: &World, entity: Entity) -> Option<Rect> {
+    if let Some(coll_box) = world.read_storage::<CollisionBox>().get(entity) {
+        return Some(Rect::new(
+            coll_box.origin.x,
+            coll_box.origin.y,
+            coll_box.width,
+            coll_box.height,
+        ));
+    }
+    let positions = world.read_storage::<Position>();
+    let images = world.read_storage::<Image>();
+    match (positions.get(entity), images.get(entity)) {
+        (Some(pos), Some(image)) => Some(Rect::new(
+            pos.position.x,
+            pos.position.y,
+            image.image.width(),
+            image.image.height(),
+        )),
+        _ => None,
+    }
+}
+
+fn summary(world: &World, entity: Entity) -> String {
+    let mut line = format!(
+        "#{}.{} {}",
+        entity.id(),
+        entity.gen().id(),
+        layer(world, entity)
+    );
+    if let Some(pos) = world.read_storage::<Position>().get(entity) {
+        line.push_str(&format!("  ({:.0}, {:.0})", pos.position.x, pos.position.y));
+    }
+    line
+}
+
+fn details(world: &World, entity: Entity) -> Vec<String> {
+    if !world.entities().is_alive(entity) {
+        return vec![format!("#{}.{} deleted", entity.id(), entity.gen().id())];
+    }
+
+    let mut lines = vec![summary(world, entity)];
+    if let Some(pos) = world.read_storage::<Position>().get(entity) {
+        lines.push(format!("speed ({:.2}, {:.2})", pos.speed.x, pos.speed.y));
+    }
+    if let Some(coll_box) = world.read_storage::<CollisionBox>().get(entity) {
+        lines.push(format!(
+            "collider ({:.0}, {:.0}) {:.0}x{:.0}",
+            coll_box.origin.x, coll_box.origin.y, coll_box.width, coll_box.height
+        ));
+    }
+    if let Some(obs) = world.read_storage::<ObstacleTag>().get(entity) {
+        lines.push(format!("passed {}  scored {}", obs.passed, obs.scored));
+    }
+    lines
+}
+
+impl Inspector {
+    fn rows(&self) -> usize {
+        ((engine::SCREEN_HEIGHT / ROW_HEIGHT) as usize).saturating_sub(DETAIL_ROWS + 2)
+    }
+
+    // Selects the entity under `point`, a row of the panel or something in
+    // the world.
+    pub fn click(&mut self, world: &World, point: Vec2) {
+        if !self.open {
+            return;
+        }
+
+        let entities = world.entities();
+        if point.x >= panel_x() {
+            let row = ((point.y - ROW_HEIGHT) / ROW_HEIGHT).floor();
+            if row >= 0.0 && (row as usize) < self.rows() {
+                if let Some(entity) = entities.join().nth(row as usize) {
+                    self.selected = Some(entity);
+                }
+            }
+            return;
+        }
+
+        // The smallest thing under the cursor, so pipes win over scenery.
+        self.selected = entities
+            .join()
+            .filter_map(|entity| bounds(world, entity).map(|rect| (entity, rect)))
+            .filter(|(_, rect)| rect.contains(point))
+            .min_by(|(_, a), (_, b)| (a.w * a.h).partial_cmp(&(b.w * b.h)).unwrap())
+            .map(|(entity, _)| entity);
+    }
+
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, world: &World) {
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let highlight = Color::new(1.0, 0.9, 0.2, 1.0);
+        let text_x = panel_x() + TEXT_INSET;
+
+        if let Some(rect) = self
+            .selected
+            .filter(|&entity| world.entities().is_alive(entity))
+            .and_then(|entity| bounds(world, entity))
+        {
+            cmds.extend_from_slice(&DrawCmd::outline(rect, 2.0, highlight));
+        }
+
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(panel_x(), 0.0, PANEL_WIDTH, engine::SCREEN_HEIGHT),
+            color: Color::new(0.0, 0.0, 0.0, 0.7),
+        });
+
+        let entities = world.entities();
+        let count = entities.join().count();
+        cmds.push(DrawCmd::text(
+            format!("{} entities", count),
+            Vec2::new(text_x, 0.0),
+            TEXT_SIZE,
+            white,
+        ));
+        for (n, entity) in entities.join().take(self.rows()).enumerate() {
+            let color = if Some(entity) == self.selected {
+                highlight
+            } else {
+                white
+            };
+            cmds.push(DrawCmd::text(
+                summary(world, entity),
+                Vec2::new(text_x, ROW_HEIGHT * (n + 1) as f32),
+                TEXT_SIZE,
+                color,
+            ));
+        }
+        if count > self.rows() {
+            cmds.push(DrawCmd::text(
+                format!("+{} more", count - self.rows()),
+                Vec2::new(text_x, ROW_HEIGHT * (self.rows() + 1) as f32),
+                TEXT_SIZE,
+                white,
+            ));
+        }
+
+        if let Some(entity) = self.selected {
+            let top = engine::SCREEN_HEIGHT - ROW_HEIGHT * DETAIL_ROWS as f32;
+            for (n, line) in details(world, entity).into_iter().enumerate() {
+                cmds.push(DrawCmd::text(
+                    line,
+                    Vec2::new(text_x, top + ROW_HEIGHT * n as f32),
+                    TEXT_SIZE,
+                    highlight,
+                ));
+            }
+        }
+    }
+}
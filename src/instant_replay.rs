@@ -0,0 +1,311 @@
+use crate::share;
+use ggez::Context;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SECONDS: usize = 5;
+const FPS: usize = 20;
+// GIF frame delays are in hundredths of a second.
+const DELAY: u16 = (100 / FPS) as u16;
+// Frames are scaled down to this width, keeping the window's shape.
+const WIDTH: u32 = 320;
+const GIF_FILE: &str = "instant_replay.gif";
+// Levels of red, green and blue in the palette, 252 colors in all.
+const LEVELS: [u8; 3] = [6, 7, 6];
+
+// A frame scaled down, as palette indices.
+struct Frame {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+// Keeps the last few seconds on screen, to save them as an animated GIF
+// when the run ends.
+pub struct InstantReplay {
+    frames: VecDeque<Frame>,
+    last: Option<Instant>,
+}
+
+impl InstantReplay {
+    pub fn new() -> Self {
+        InstantReplay {
+            frames: VecDeque::new(),
+            last: None,
+        }
+    }
+
+    // Takes in the frame on screen, if it's time for the next one.
+    pub fn capture(&mut self, ctx: &Context) {
+        let interval = Duration::from_secs(1) / FPS as u32;
+        if self.last.is_some_and(|last| last.elapsed() < interval) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        match share::frame_pixels(ctx) {
+            Ok((width, height, rgba)) => {
+                if self.frames.len() == SECONDS * FPS {
+                    self.frames.pop_front();
+                }
+                self.frames.push_back(shrink(width, height, &rgba));
+            }
+            Err(e) => println!("can't capture the instant replay: {}", e),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.last = None;
+    }
+
+    // Encodes what was captured to `instant_replay.gif` in the user data
+    // directory on another thread, replacing the last run's, and starts
+    // over. Returns where it goes.
+    pub fn save(&mut self, ctx: &Context) -> PathBuf {
+        let path = ctx.fs.user_data_dir().join(GIF_FILE);
+        let frames = std::mem::take(&mut self.frames);
+        self.last = None;
+        let target = path.clone();
+        thread::spawn(move || {
+            if let Err(e) = write_gif(&target, frames) {
+                println!("can't save the instant replay: {}", e);
+            }
+        });
+        path
+    }
+}
+
+fn write_gif(path: &Path, frames: VecDeque<Frame>) -> io::Result<()> {
+    // Frames from before the window was resized are left out.
+    let last = match frames.back() {
+        Some(last) => (last.width, last.height),
+        None => return Ok(()),
+    };
+    let frames: Vec<Vec<u8>> = frames
+        .into_iter()
+        .filter(|frame| (frame.width, frame.height) == last)
+        .map(|frame| frame.pixels)
+        .collect();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, encode(last.0 as u16, last.1 as u16, &frames, DELAY))
+}
+
+// `rgba` scaled down to `WIDTH` wide by nearest neighbour, in the palette.
+fn shrink(width: u32, height: u32, rgba: &[u8]) -> Frame {
+    let scale = width as f32 / WIDTH as f32;
+    let small = ((height as f32 / scale) as u32).max(1);
+    let mut pixels = Vec::with_capacity((WIDTH * small) as usize);
+    for y in 0..small {
+        let sy = ((y as f32 * scale) as u32).min(height - 1);
+        for x in 0..WIDTH {
+            let sx = ((x as f32 * scale) as u32).min(width - 1);
+            let i = ((sy * width + sx) * 4) as usize;
+            pixels.push(index(&rgba[i..i + 3]));
+        }
+    }
+    Frame {
+        width: WIDTH,
+        height: small,
+        pixels,
+    }
+}
+
+// The palette entry nearest `rgb`.
+fn index(rgb: &[u8]) -> u8 {
+    let level = |c: u8, levels: u8| (c as u32 * (levels as u32 - 1) + 127) / 255;
+    let (r, g, b) = (
+        level(rgb[0], LEVELS[0]),
+        level(rgb[1], LEVELS[1]),
+        level(rgb[2], LEVELS[2]),
+    );
+    ((r * LEVELS[1] as u32 + g) * LEVELS[2] as u32 + b) as u8
+}
+
+// The palette, as RGB triples padded to 256 colors.
+fn palette() -> Vec<u8> {
+    let value = |level: usize, levels: u8| (level * 255 / (levels as usize - 1)) as u8;
+    let mut rgb = Vec::with_capacity(256 * 3);
+    for r in 0..LEVELS[0] as usize {
+        for g in 0..LEVELS[1] as usize {
+            for b in 0..LEVELS[2] as usize {
+                rgb.extend_from_slice(&[
+                    value(r, LEVELS[0]),
+                    value(g, LEVELS[1]),
+                    value(b, LEVELS[2]),
+                ]);
+            }
+        }
+    }
+    rgb.resize(256 * 3, 0);
+    rgb
+}
+
+// An animated GIF of `frames` of palette indices, looping forever, each
+// shown for `delay` hundredths of a second.
+fn encode(width: u16, height: u16, frames: &[Vec<u8>], delay: u16) -> Vec<u8> {
+    let mut gif = b"GIF89a".to_vec();
+    gif.extend_from_slice(&width.to_le_bytes());
+    gif.extend_from_slice(&height.to_le_bytes());
+    // A global palette of 256 colors.
+    gif.extend_from_slice(&[0xf7, 0, 0]);
+    gif.extend_from_slice(&palette());
+    gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+    for pixels in frames {
+        gif.extend_from_slice(&[0x21, 0xf9, 4, 0]);
+        gif.extend_from_slice(&delay.to_le_bytes());
+        gif.extend_from_slice(&[0, 0, 0x2c, 0, 0, 0, 0]);
+        gif.extend_from_slice(&width.to_le_bytes());
+        gif.extend_from_slice(&height.to_le_bytes());
+        gif.extend_from_slice(&[0, 8]);
+        for block in lzw(pixels).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3b);
+    gif
+}
+
+const CLEAR: u16 = 256;
+const END: u16 = 257;
+const MAX_CODES: u16 = 4096;
+
+// Packs codes least significant bit first, as GIFs do.
+struct Bits {
+    bytes: Vec<u8>,
+    pending: u32,
+    count: u32,
+}
+
+impl Bits {
+    fn write(&mut self, code: u16, width: u32) {
+        self.pending |= (code as u32) << self.count;
+        self.count += width;
+        while self.count >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.count -= 8;
+        }
+    }
+}
+
+// `indices` LZW compressed with 8 bit symbols, as GIF image data.
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let mut bits = Bits {
+        bytes: Vec::new(),
+        pending: 0,
+        count: 0,
+    };
+    let mut width = 9;
+    let mut next = END + 1;
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    bits.write(CLEAR, width);
+    let mut prefix: Option<u16> = None;
+    for &symbol in indices {
+        let current = match prefix {
+            None => {
+                prefix = Some(symbol as u16);
+                continue;
+            }
+            Some(current) => current,
+        };
+        if let Some(&code) = codes.get(&(current, symbol)) {
+            prefix = Some(code);
+            continue;
+        }
+        bits.write(current, width);
+        if next == 1 << width && width < 12 {
+            width += 1;
+        }
+        if next < MAX_CODES {
+            codes.insert((current, symbol), next);
+            next += 1;
+        } else {
+            bits.write(CLEAR, width);
+            codes.clear();
+            next = END + 1;
+            width = 9;
+        }
+        prefix = Some(symbol as u16);
+    }
+    if let Some(current) = prefix {
+        bits.write(current, width);
+        if next == 1 << width && width < 12 {
+            width += 1;
+        }
+    }
+    bits.write(END, width);
+    if bits.count > 0 {
+        bits.bytes.push(bits.pending as u8);
+    }
+    bits.bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Undoes `lzw` the way GIF decoders do.
+    fn unlzw(bytes: &[u8]) -> Vec<u8> {
+        let (mut pos, mut width) = (0, 9);
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        loop {
+            let mut code = 0;
+            for bit in 0..width {
+                let set = bytes[(pos + bit) / 8] >> ((pos + bit) % 8) & 1;
+                code |= (set as usize) << bit;
+            }
+            pos += width;
+            if code == CLEAR as usize {
+                table = (0..=END).map(|c| vec![c as u8]).collect();
+                width = 9;
+                prev = None;
+                continue;
+            }
+            if code == END as usize {
+                return out;
+            }
+            let entry = match (table.get(code), &prev) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => [prev.clone(), vec![prev[0]]].concat(),
+                (None, None) => panic!("code {} before any other", code),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(prev) = prev {
+                table.push([prev, vec![entry[0]]].concat());
+            }
+            if table.len() == 1 << width && width < 12 {
+                width += 1;
+            }
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn frames_compress_and_come_back_the_same() {
+        // Long enough to fill the code table and start it over.
+        let noisy: Vec<u8> = (0..40_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8 % 252)
+            .collect();
+        let flat = vec![7; 5000];
+        for pixels in [noisy, flat, vec![1]] {
+            assert_eq!(unlzw(&lzw(&pixels)), pixels);
+        }
+
+        let gif = encode(2, 1, &[vec![0, 251], vec![251, 0]], DELAY);
+        assert!(gif.starts_with(b"GIF89a"));
+        assert_eq!(gif.last(), Some(&0x3b));
+        assert_eq!(&palette()[251 * 3..252 * 3], &[255, 255, 255]);
+        assert_eq!(index(&[255, 255, 255]), 251);
+        assert_eq!(index(&[0, 0, 0]), 0);
+    }
+}
@@ -0,0 +1,41 @@
+use crate::engine::Vec2;
+use crate::Position;
+use specs::*;
+use std::collections::HashMap;
+
+// Anything that moved further than this in one tick jumped, like scenery
+// wrapping around, and is drawn where it is now.
+const MAX_STEP: f32 = 64.0;
+
+// Physics runs at a fixed rate while frames are drawn whenever the display
+// is ready, usually somewhere between two ticks. Keeping every position
+// from the tick before lets a frame show entities part of the way from
+// there to where they are now, so motion stays smooth at any frame rate.
+#[derive(Default)]
+pub struct Interpolation {
+    previous: HashMap<Entity, Vec2>,
+    // How far into the next tick the frame is drawn, from 0.0 to 1.0.
+    pub alpha: f32,
+}
+
+impl Interpolation {
+    // Called right before every tick.
+    pub fn remember(&mut self, world: &World) {
+        let entities = world.entities();
+        let positions = world.read_storage::<Position>();
+        self.previous.clear();
+        for (entity, pos) in (&entities, &positions).join() {
+            self.previous.insert(entity, pos.position);
+        }
+    }
+
+    // Where to draw `entity`, which is at `now` as of the last tick.
+    pub fn at(&self, entity: Entity, now: Vec2) -> Vec2 {
+        match self.previous.get(&entity) {
+            Some(&before) if (now.x - before.x).abs().max((now.y - before.y).abs()) <= MAX_STEP => {
+                before + (now - before) * self.alpha
+            }
+            _ => now,
+        }
+    }
+}
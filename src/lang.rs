@@ -0,0 +1,155 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// The on-screen text, by key, in the player's language. Tables live in
+// `lang/<code>.toml` in the assets directory:
+//
+//     name = "Español"
+//     font = "/fonts/NotoSans-Regular.ttf"
+//
+//     [strings]
+//     game_over = "FIN"
+//
+// English is built in, and fills in any key a table leaves out. The font,
+// if given, replaces the built-in one, which only has English letters. The
+// debug overlays and the changelog stay in English.
+const ENGLISH: &str = include_str!("../assets/lang/en.toml");
+
+#[derive(Debug, Deserialize)]
+pub struct Language {
+    pub name: String,
+    #[serde(default)]
+    pub font: Option<String>,
+    #[serde(default)]
+    strings: HashMap<String, String>,
+}
+
+static CURRENT: OnceLock<Language> = OnceLock::new();
+
+fn english() -> &'static Language {
+    static BUILT_IN: OnceLock<Language> = OnceLock::new();
+    BUILT_IN.get_or_init(|| toml::from_str(ENGLISH).expect("invalid built-in English"))
+}
+
+fn lang_dir(assets: &Path) -> std::path::PathBuf {
+    assets.join("lang")
+}
+
+// The table for `code`, like "es". A missing or unreadable one is reported
+// and English used instead.
+pub fn load(assets: &Path, code: &str) -> Option<Language> {
+    let path = lang_dir(assets).join(format!("{}.toml", code));
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            println!("can't read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    toml::from_str(&contents)
+        .map_err(|e| println!("ignoring invalid {}: {}", path.display(), e))
+        .ok()
+}
+
+// Picks the language for the rest of the game. Only the first call counts.
+pub fn set(language: Language) {
+    let _ = CURRENT.set(language);
+}
+
+// The codes and names of the languages there are tables for, English
+// always among them.
+pub fn available(assets: &Path) -> Vec<(String, String)> {
+    let mut languages = vec![("en".to_string(), english().name.clone())];
+    let entries = fs::read_dir(lang_dir(assets)).into_iter().flatten();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let code = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(code) if path.extension().is_some_and(|ext| ext == "toml") => code,
+            _ => continue,
+        };
+        if code == "en" {
+            continue;
+        }
+        if let Some(language) = load(assets, code) {
+            languages.push((code.to_string(), language.name));
+        }
+    }
+    languages.sort();
+    languages
+}
+
+// The font the language needs in place of the built-in one.
+pub fn font() -> Option<&'static str> {
+    CURRENT.get()?.font.as_deref()
+}
+
+// The text for `key`, in English if the language lacks it and the key
+// itself if English does too.
+pub fn tr(key: &'static str) -> &'static str {
+    CURRENT
+        .get()
+        .and_then(|language| language.strings.get(key))
+        .or_else(|| english().strings.get(key))
+        .map_or(key, String::as_str)
+}
+
+// The language's own text for a key made up as the game runs, like an
+// achievement's, which has its English in another file.
+pub fn lookup(key: &str) -> Option<&'static str> {
+    CURRENT
+        .get()
+        .and_then(|language| language.strings.get(key))
+        .map(String::as_str)
+}
+
+// The text for `key` with each `{}` in it replaced by the next of `args`.
+pub fn fill(key: &'static str, args: &[&dyn Display]) -> String {
+    fill_in(tr(key), args)
+}
+
+fn fill_in(text: &str, args: &[&dyn Display]) -> String {
+    let mut filled = String::new();
+    let mut args = args.iter();
+    let mut pieces = text.split("{}");
+    filled.push_str(pieces.next().unwrap_or(""));
+    for piece in pieces {
+        if let Some(arg) = args.next() {
+            filled.push_str(&arg.to_string());
+        }
+        filled.push_str(piece);
+    }
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_language_has_english_keys_only() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let languages = available(&dir);
+        assert!(languages.iter().any(|(code, _)| code == "es"));
+        for (code, _) in languages {
+            let language = load(&dir, &code).unwrap();
+            // Achievements have their English in `achievements.toml`.
+            let keys = language.strings.keys();
+            for key in keys.filter(|key| !key.starts_with("achievement_")) {
+                assert!(english().strings.contains_key(key), "{}: {}", code, key);
+            }
+        }
+    }
+
+    #[test]
+    fn arguments_fill_in_their_places_in_order() {
+        assert_eq!(
+            fill_in("Seed {} saved to {}", &[&7, &"seeds.txt"]),
+            "Seed 7 saved to seeds.txt"
+        );
+        assert_eq!(fill_in("PAUSED", &[&1]), "PAUSED");
+        assert_eq!(tr("no such key"), "no such key");
+    }
+}
@@ -0,0 +1,191 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Presses kept for the averages.
+const SAMPLES: usize = 20;
+// How much each new frame moves the average frame time.
+const SMOOTHING: f64 = 0.1;
+
+// A hidden diagnostic screen, toggled with `F8`, for tuning vsync and
+// buffering: every key press flashes the screen white and is timed, from
+// when the window hands it to the game, until the next update sees it and
+// until the flashed frame has been presented. The game can't see the
+// display itself, so the last stretch from the swap chain to the panel
+// isn't included.
+#[derive(Default)]
+pub struct LatencyProbe {
+    pub open: bool,
+    press: Option<Press>,
+    samples: VecDeque<Sample>,
+    last_frame: Option<Instant>,
+    // Smoothed time between drawn frames, for counting latency in frames.
+    frame_secs: f64,
+}
+
+struct Press {
+    at: Instant,
+    handled: Option<Duration>,
+    // Set once a frame with the flash has been drawn.
+    drawn: bool,
+}
+
+#[derive(Clone, Copy)]
+struct Sample {
+    handled: Duration,
+    shown: Duration,
+}
+
+impl LatencyProbe {
+    // A key went down; ignored while the last press is still on its way.
+    pub fn press(&mut self, now: Instant) {
+        if self.press.is_none() {
+            self.press = Some(Press {
+                at: now,
+                handled: None,
+                drawn: false,
+            });
+        }
+    }
+
+    // Call at the start of every update. The first one after a press
+    // handles it, and the first one after the flash was drawn comes after
+    // that frame was presented.
+    pub fn update(&mut self, now: Instant) {
+        let press = match &mut self.press {
+            Some(press) => press,
+            None => return,
+        };
+        let elapsed = now - press.at;
+        match press.handled {
+            None => press.handled = Some(elapsed),
+            Some(handled) if press.drawn => {
+                self.samples.push_back(Sample {
+                    handled,
+                    shown: elapsed,
+                });
+                if self.samples.len() > SAMPLES {
+                    self.samples.pop_front();
+                }
+                self.press = None;
+            }
+            Some(_) => (),
+        }
+    }
+
+    // Call once per drawn frame, before drawing the probe.
+    pub fn frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame {
+            let secs = (now - last).as_secs_f64();
+            self.frame_secs = if self.frame_secs == 0.0 {
+                secs
+            } else {
+                self.frame_secs + (secs - self.frame_secs) * SMOOTHING
+            };
+        }
+        self.last_frame = Some(now);
+        if let Some(press) = &mut self.press {
+            press.drawn = press.handled.is_some();
+        }
+    }
+
+    fn frames(&self, time: Duration) -> f64 {
+        if self.frame_secs > 0.0 {
+            time.as_secs_f64() / self.frame_secs
+        } else {
+            0.0
+        }
+    }
+
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>) {
+        let flashing = self.press.as_ref().is_some_and(|press| press.drawn);
+        let (background, text) = if flashing {
+            (
+                Color::new(1.0, 1.0, 1.0, 1.0),
+                Color::new(0.0, 0.0, 0.0, 1.0),
+            )
+        } else {
+            (
+                Color::new(0.0, 0.0, 0.0, 0.85),
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            )
+        };
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(0.0, 0.0, engine::screen_width(), engine::SCREEN_HEIGHT),
+            color: background,
+        });
+
+        let hertz = if self.frame_secs > 0.0 {
+            1.0 / self.frame_secs
+        } else {
+            0.0
+        };
+        let mut lines = vec![
+            lang::tr("latency_title").to_string(),
+            lang::tr("latency_hint").to_string(),
+            lang::fill(
+                "latency_frame_time",
+                &[
+                    &format!("{:.2}", self.frame_secs * 1000.0),
+                    &format!("{:.0}", hertz),
+                ],
+            ),
+        ];
+        let ms = |time: Duration| format!("{:.1}", time.as_secs_f64() * 1000.0);
+        let frames = |time: Duration| format!("{:.1}", self.frames(time));
+        if let Some(last) = self.samples.back() {
+            let count = self.samples.len() as u32;
+            let handled: Duration = self.samples.iter().map(|s| s.handled).sum();
+            let shown: Duration = self.samples.iter().map(|s| s.shown).sum();
+            let worst = self.samples.iter().map(|s| s.shown).max().unwrap();
+            lines.push(lang::fill(
+                "latency_last",
+                &[&ms(last.handled), &ms(last.shown), &frames(last.shown)],
+            ));
+            lines.push(lang::fill(
+                "latency_average",
+                &[
+                    &count,
+                    &ms(handled / count),
+                    &ms(shown / count),
+                    &frames(shown / count),
+                ],
+            ));
+            lines.push(lang::fill("latency_worst", &[&ms(worst), &frames(worst)]));
+        }
+        for (n, line) in lines.into_iter().enumerate() {
+            let size = if n == 0 { 50.0 } else { 24.0 };
+            cmds.push(DrawCmd::centered_text(
+                line,
+                Vec2::new(engine::screen_width() / 2.0, 150.0 + 50.0 * n as f32),
+                size,
+                text,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_a_press_until_its_frame_is_presented() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut probe = LatencyProbe::default();
+        probe.frame(at(0));
+        probe.frame(at(16));
+
+        probe.press(at(20));
+        probe.update(at(25));
+        probe.frame(at(32));
+        probe.update(at(40));
+
+        let sample = probe.samples[0];
+        assert_eq!(sample.handled, Duration::from_millis(5));
+        assert_eq!(sample.shown, Duration::from_millis(20));
+        assert!(probe.press.is_none());
+    }
+}
@@ -0,0 +1,315 @@
+use crate::engine::{self, Color, DrawCmd, Vec2};
+use crate::lang;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+// Shown on the menu, however many the server sends back.
+const TOP: usize = 10;
+const MAX_NAME: usize = 16;
+// Gives up on a server that doesn't answer, leaving the board as it was.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// The `[leaderboard]` table of `config.toml`. Without an endpoint nothing
+// is ever sent or fetched.
+//
+//     [leaderboard]
+//     endpoint = "http://scores.example.com:8080/rusty_bird"
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LeaderboardConfig {
+    pub endpoint: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub score: i32,
+}
+
+#[derive(Serialize)]
+struct Submission {
+    name: String,
+    score: i32,
+    mode: String,
+}
+
+enum Request {
+    Fetch,
+    Submit(Submission),
+}
+
+// Where scores go: plain HTTP only, there's no TLS to speak it with.
+#[derive(Clone, Debug, PartialEq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Endpoint {
+    fn parse(url: &str) -> Result<Self, String> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("{} isn't an http:// address", url))?;
+        let (authority, path) = match rest.find('/') {
+            Some(n) => (&rest[..n], &rest[n..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.split_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("{} has an invalid port", url))?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("{} has no host", url));
+        }
+        Ok(Endpoint {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    // One request and its response body, on a connection of its own.
+    fn request(&self, method: &str, body: &str) -> Result<String, String> {
+        let fail = |e: std::io::Error| e.to_string();
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).map_err(fail)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(fail)?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(fail)?;
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            self.path,
+            self.host,
+            body.len(),
+            body
+        )
+        .map_err(fail)?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(fail)?;
+
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or("the server sent an incomplete response")?;
+        let status = head.lines().next().unwrap_or("");
+        match status.split_whitespace().nth(1) {
+            Some(code) if code.starts_with('2') => Ok(body.to_string()),
+            _ => Err(format!("the server answered {}", status)),
+        }
+    }
+
+    // Every request ends with the board as it is now.
+    fn handle(&self, request: Request) -> Result<Vec<Entry>, String> {
+        if let Request::Submit(submission) = request {
+            let body = serde_json::to_string(&submission).map_err(|e| e.to_string())?;
+            self.request("POST", &body)?;
+        }
+        let body = self.request("GET", "")?;
+        let mut top: Vec<Entry> = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+        top.truncate(TOP);
+        Ok(top)
+    }
+}
+
+// Client for an online leaderboard, only there when one is configured:
+// finished runs are POSTed as `{"name", "score", "mode"}` JSON to the
+// endpoint, and a GET of it is expected to answer with the best scores as
+// a `[{"name", "score"}]` array. Requests run on a background thread so a
+// slow server never holds up a frame.
+pub struct Leaderboard {
+    sender: mpsc::Sender<Request>,
+    receiver: mpsc::Receiver<Vec<Entry>>,
+    top: Vec<Entry>,
+    // The name typed on the game over screen, kept for the next run.
+    name: String,
+    // Typing a name on the game over screen.
+    pub entering: bool,
+    // The run on the game over screen was sent already.
+    pub submitted: bool,
+}
+
+impl Leaderboard {
+    pub fn new(config: &LeaderboardConfig) -> Option<Self> {
+        let endpoint = match Endpoint::parse(config.endpoint.as_deref()?) {
+            Ok(endpoint) => endpoint,
+            Err(e) => {
+                println!("leaderboard: {}", e);
+                return None;
+            }
+        };
+        let (sender, requests) = mpsc::channel::<Request>();
+        let (results, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for request in requests {
+                match endpoint.handle(request) {
+                    Ok(top) => {
+                        if results.send(top).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => println!("leaderboard: {}", e),
+                }
+            }
+        });
+
+        let _ = sender.send(Request::Fetch);
+        Some(Leaderboard {
+            sender,
+            receiver,
+            top: Vec::new(),
+            name: String::new(),
+            entering: false,
+            submitted: false,
+        })
+    }
+
+    // Takes the latest board from the background thread, if a new one came
+    // in.
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(top) = self.receiver.try_recv() {
+            self.top = top;
+            changed = true;
+        }
+        changed
+    }
+
+    // Sends `score` under the typed name. False if there's no name yet.
+    pub fn submit(&mut self, score: i32, mode: &str) -> bool {
+        if self.name.is_empty() {
+            return false;
+        }
+        let _ = self.sender.send(Request::Submit(Submission {
+            name: self.name.clone(),
+            score,
+            mode: mode.to_string(),
+        }));
+        self.entering = false;
+        self.submitted = true;
+        true
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        if (c.is_alphanumeric() || c == '_') && self.name.chars().count() < MAX_NAME {
+            self.name.push(c);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        self.name.pop();
+    }
+
+    // The name entry, over the game over screen.
+    pub fn draw_entry(&self, cmds: &mut Vec<DrawCmd>) {
+        let center_x = engine::screen_width() / 2.0;
+        cmds.push(DrawCmd::Rect {
+            rect: engine::panel(Vec2::new(center_x, 300.0), 520.0, 160.0),
+            color: Color::new(0.0, 0.0, 0.0, 0.85),
+        });
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("leaderboard_name"),
+            Vec2::new(center_x, 250.0),
+            24.0,
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        ));
+        cmds.push(DrawCmd::centered_text(
+            format!("{}_", self.name),
+            Vec2::new(center_x, 300.0),
+            36.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("leaderboard_entry_hint"),
+            Vec2::new(center_x, 350.0),
+            18.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+    }
+
+    // The best scores, down the right of the menu.
+    pub fn draw_top(&self, cmds: &mut Vec<DrawCmd>) {
+        if self.top.is_empty() {
+            return;
+        }
+        let x = engine::screen_width() - 200.0;
+        cmds.push(DrawCmd::text(
+            lang::tr("leaderboard"),
+            Vec2::new(x, 250.0),
+            20.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+        for (n, entry) in self.top.iter().enumerate() {
+            cmds.push(DrawCmd::text(
+                format!("{:>2}. {:<16} {}", n + 1, entry.name, entry.score),
+                Vec2::new(x, 276.0 + 18.0 * n as f32),
+                16.0,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+
+    #[test]
+    fn submits_and_fetches_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream.unwrap();
+                let mut reader = std::io::BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                requests.push(line.trim().to_string());
+                // The rest of the request, so closing doesn't reset it.
+                let mut length = 0;
+                while line != "\r\n" {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(n) = line.strip_prefix("Content-Length: ") {
+                        length = n.trim().parse().unwrap();
+                    }
+                }
+                reader.read_exact(&mut vec![0; length]).unwrap();
+                let body = r#"[{"name": "ana", "score": 40}, {"name": "bo", "score": 12}]"#;
+                write!(stream, "HTTP/1.0 200 OK\r\n\r\n{}", body).unwrap();
+            }
+            requests
+        });
+
+        let endpoint = Endpoint::parse(&format!("http://127.0.0.1:{}/scores", port)).unwrap();
+        let top = endpoint
+            .handle(Request::Submit(Submission {
+                name: "bo".to_string(),
+                score: 12,
+                mode: "endless".to_string(),
+            }))
+            .unwrap();
+        assert_eq!(top[0].name, "ana");
+        assert_eq!(top[1].score, 12);
+        assert_eq!(
+            server.join().unwrap(),
+            vec!["POST /scores HTTP/1.0", "GET /scores HTTP/1.0"]
+        );
+        assert!(Endpoint::parse("https://example.com").is_err());
+    }
+}
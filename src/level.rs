@@ -0,0 +1,134 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::savestate::SaveState;
+use crate::{Game, GameState, PlayerTag, Position, FLOOR_Y};
+use specs::*;
+
+// The fixed seed and length of the level played with `--level`.
+pub const LEVEL_SEED: u64 = 20_200_815;
+pub const LEVEL_LENGTH: u32 = 40;
+
+// A checkpoint flag is planted every quarter of the level, except at the
+// finish.
+const CHECKPOINTS: u32 = 4;
+
+const FLAG_TOP: f32 = FLOOR_Y - 80.0;
+
+// Finite level mode: pass LEVEL_LENGTH pipes to finish. Every checkpoint
+// snapshots the run, and dying respawns the bird at the last flag with the
+// same pipes ahead instead of restarting the level.
+pub struct Level {
+    length: u32,
+    checkpoint: Option<SaveState>,
+    // World x of every flag planted so far.
+    flags: Vec<f32>,
+    pub finished: bool,
+}
+
+impl Level {
+    pub fn new(length: u32) -> Self {
+        Level {
+            length,
+            checkpoint: None,
+            flags: Vec::new(),
+            finished: false,
+        }
+    }
+
+    // Starts the level over, without any flags.
+    pub fn reset(&mut self) {
+        *self = Level::new(self.length);
+    }
+
+    // Called after every tick: plants the next flag once its quarter of the
+    // level is passed and ends the run at the finish.
+    pub fn update(&mut self, world: &World) {
+        let mut state = world.write_resource::<GameState>();
+        if *state != GameState::Playing {
+            return;
+        }
+        let game = world.read_resource::<Game>();
+        if game.pipes_passed >= self.length {
+            *state = GameState::GameOver;
+            self.finished = true;
+            return;
+        }
+
+        let next_flag = (self.flags.len() as u32 + 1) * self.length / CHECKPOINTS;
+        if game.pipes_passed < next_flag {
+            return;
+        }
+        let positions = world.read_storage::<Position>();
+        let players = world.read_storage::<PlayerTag>();
+        let bird_x = (&positions, &players)
+            .join()
+            .next()
+            .map_or(0.0, |(pos, _)| pos.position.x);
+        self.flags.push(game.distance + bird_x);
+        drop(game);
+        drop(state);
+        self.checkpoint = Some(SaveState::capture(world));
+    }
+
+    pub fn can_respawn(&self) -> bool {
+        !self.finished && self.checkpoint.is_some()
+    }
+
+    // Puts the run back at the last flag. Returns false if there is none.
+    pub fn respawn(&self, world: &mut World) -> bool {
+        match &self.checkpoint {
+            Some(checkpoint) if !self.finished => {
+                checkpoint.restore_into(world);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, game: &Game, state: GameState) {
+        for &flag in &self.flags {
+            let x = flag - game.distance;
+            if !(-40.0..=engine::screen_width()).contains(&x) {
+                continue;
+            }
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x, FLAG_TOP, 4.0, FLOOR_Y - FLAG_TOP),
+                color: Color::new(0.85, 0.85, 0.85, 1.0),
+            });
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x + 4.0, FLAG_TOP, 32.0, 20.0),
+                color: Color::new(0.9, 0.2, 0.2, 1.0),
+            });
+        }
+
+        cmds.push(DrawCmd::text(
+            lang::fill(
+                "level_progress",
+                &[
+                    &game.pipes_passed.min(self.length),
+                    &self.length,
+                    &self.flags.len(),
+                    &(CHECKPOINTS - 1),
+                ],
+            ),
+            Vec2::new(10.0, 10.0),
+            22.0,
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        ));
+        if self.finished {
+            cmds.push(DrawCmd::centered_text(
+                lang::tr("level_complete"),
+                Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT / 2.0),
+                100.0,
+                Color::new(1.0, 0.9, 0.2, 1.0),
+            ));
+        } else if state == GameState::GameOver && self.can_respawn() {
+            cmds.push(DrawCmd::centered_text(
+                lang::tr("level_continue"),
+                Vec2::new(engine::screen_width() / 2.0, FLOOR_Y + 5.0),
+                26.0,
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ));
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Rusty Bird as a library: the components, resources and systems of the
+// game, and the setup that puts them together into a world, for tools and
+// tests that run the game without the window.
+
+// ggez opens a desktop window, and has nothing for browsers, so a browser
+// build is declined until it does.
+#[cfg(target_arch = "wasm32")]
+compile_error!("rusty_bird has no browser build: ggez 0.9 has no wasm32 backend");
+
+pub mod components;
+pub mod engine;
+pub mod resources;
+pub mod systems;
+pub mod tuning;
+pub mod world_setup;
+
+mod achievements;
+pub mod app;
+mod assets;
+mod audio;
+mod best_run;
+mod branding;
+mod calendar;
+mod caves;
+mod collectibles;
+mod config;
+mod controller;
+mod customize;
+mod daylight;
+mod dev;
+mod digits;
+#[cfg(test)]
+mod golden;
+mod hazards;
+#[cfg(feature = "hot-reload")]
+mod hot_reload;
+mod hotseat;
+mod hud;
+mod inspector;
+#[cfg(feature = "instant-replay")]
+mod instant_replay;
+mod interpolate;
+mod lang;
+mod latency;
+mod leaderboard;
+mod level;
+#[cfg(feature = "livesplit")]
+mod livesplit;
+mod markers;
+mod medals;
+#[cfg(feature = "model")]
+mod model;
+mod parallax;
+mod pipes;
+mod policy;
+mod profile;
+mod profiler;
+mod replay_file;
+mod rules;
+mod savestate;
+mod schedule;
+mod seeds;
+mod settings;
+mod shadow;
+mod share;
+mod simulate;
+mod skins;
+mod spacing;
+mod speedrun;
+mod stats;
+mod streamer;
+mod tas;
+mod tas_editor;
+mod telemetry;
+mod theme;
+mod timeline;
+mod tips;
+mod tournament;
+mod wellbeing;
+mod whats_new;
+
+pub use components::*;
+pub use resources::*;
+pub use simulate::Simulation;
+pub use systems::*;
+pub use world_setup::*;
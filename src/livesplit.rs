@@ -0,0 +1,58 @@
+use crate::speedrun::{self, SplitEvent};
+use std::io::Write;
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::thread;
+
+// Client for the LiveSplit Server component. Commands are handed to a
+// background thread so a slow or missing server never stalls the game loop.
+pub struct LiveSplit {
+    sender: mpsc::Sender<String>,
+}
+
+impl LiveSplit {
+    pub fn connect(address: String) -> Self {
+        let (sender, receiver) = mpsc::channel::<String>();
+
+        thread::spawn(move || {
+            let mut stream = match TcpStream::connect(&address) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    println!("livesplit: can't connect to {}: {}", address, e);
+                    return;
+                }
+            };
+            let _ = stream.set_nodelay(true);
+
+            for command in receiver {
+                if let Err(e) = stream.write_all(command.as_bytes()) {
+                    println!("livesplit: connection lost: {}", e);
+                    return;
+                }
+            }
+        });
+
+        LiveSplit { sender }
+    }
+
+    // LiveSplit follows the game's own tick clock through game time, so
+    // splits are frame accurate regardless of the real-time timer.
+    pub fn send(&self, event: SplitEvent) {
+        let commands = match event {
+            SplitEvent::Start => vec![
+                "starttimer".to_string(),
+                "initgametime".to_string(),
+                "pausegametime".to_string(),
+            ],
+            SplitEvent::Split(ticks) => vec![
+                format!("setgametime {}", speedrun::format_ticks(ticks)),
+                "split".to_string(),
+            ],
+            SplitEvent::Reset => vec!["reset".to_string()],
+        };
+
+        for command in commands {
+            let _ = self.sender.send(command + "\r\n");
+        }
+    }
+}
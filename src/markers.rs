@@ -0,0 +1,66 @@
+use crate::engine::{self, Color, DrawCmd, Sprite, Vec2};
+use crate::stats::Stats;
+use ggez::*;
+
+const SKULL: [&str; 8] = [
+    ".######.", "########", "#..##..#", "#..##..#", "########", ".##..##.", "..####..", "..#.#.#.",
+];
+const SKULL_SCALE: f32 = 2.0;
+
+// Skulls at the places earlier attempts of the same seeded challenge ended.
+// Positions are in world space and scroll with the pipes.
+pub struct DeathMarkers {
+    image: Sprite,
+    points: Vec<Vec2>,
+}
+
+impl DeathMarkers {
+    pub fn new(ctx: &Context, stats: &Stats, seed: u64) -> Self {
+        let points = stats
+            .deaths
+            .iter()
+            .filter(|death| death.seed == Some(seed))
+            .map(|death| Vec2::new(death.world_x, death.y))
+            .collect();
+
+        DeathMarkers {
+            image: skull_image(ctx),
+            points,
+        }
+    }
+
+    pub fn draw<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>, distance: f32) {
+        let half = SKULL.len() as f32 * SKULL_SCALE / 2.0;
+        for point in &self.points {
+            let x = point.x - distance;
+            if x < -half || x > engine::screen_width() + half {
+                continue;
+            }
+
+            cmds.push(DrawCmd::Sprite {
+                sprite: &self.image,
+                dest: Vec2::new(x - half, point.y - half),
+                scale: SKULL_SCALE,
+                flip: false,
+                tint: Color::WHITE,
+                rotation: 0.0,
+            });
+        }
+    }
+}
+
+fn skull_image(ctx: &Context) -> Sprite {
+    let mut rgba = Vec::with_capacity(SKULL.len() * SKULL.len() * 4);
+    for row in SKULL.iter() {
+        for pixel in row.chars() {
+            if pixel == '#' {
+                rgba.extend_from_slice(&[240, 240, 230, 220]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+    }
+
+    let size = SKULL.len() as u32;
+    Sprite::from_rgba8(ctx, size, size, &rgba)
+}
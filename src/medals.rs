@@ -0,0 +1,200 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Sprite, Vec2};
+use crate::lang;
+use ggez::Context;
+use serde::Deserialize;
+
+// '#' is the rim, '+' the face and '*' its shine; the sprite is tinted by
+// the medal's color.
+const MEDAL: [&str; 12] = [
+    "....####....",
+    "..##++++##..",
+    ".#+**+++++#.",
+    ".#+*++++++#.",
+    "#++++++++++#",
+    "#++++++++++#",
+    "#++++++++++#",
+    "#++++++++++#",
+    ".#++++++++#.",
+    ".#++++++++#.",
+    "..##++++##..",
+    "....####....",
+];
+const MEDAL_SCALE: f32 = 6.0;
+const PANEL_WIDTH: f32 = 440.0;
+const PANEL_HEIGHT: f32 = 160.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Medal {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+impl Medal {
+    pub fn name(self) -> &'static str {
+        match self {
+            Medal::Bronze => lang::tr("bronze"),
+            Medal::Silver => lang::tr("silver"),
+            Medal::Gold => lang::tr("gold"),
+            Medal::Platinum => lang::tr("platinum"),
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Medal::Bronze => Color::new(0.8, 0.5, 0.2, 1.0),
+            Medal::Silver => Color::new(0.75, 0.75, 0.8, 1.0),
+            Medal::Gold => Color::new(1.0, 0.8, 0.1, 1.0),
+            Medal::Platinum => Color::new(0.85, 0.95, 1.0, 1.0),
+        }
+    }
+}
+
+// The scores each medal takes, as in the original game unless changed in
+// the `[medals]` section of `config.toml`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct Medals {
+    pub bronze: i32,
+    pub silver: i32,
+    pub gold: i32,
+    pub platinum: i32,
+}
+
+impl Default for Medals {
+    fn default() -> Self {
+        Medals {
+            bronze: 10,
+            silver: 20,
+            gold: 30,
+            platinum: 40,
+        }
+    }
+}
+
+impl Medals {
+    // The best medal `score` earns, if any.
+    pub fn award(&self, score: i32) -> Option<Medal> {
+        vec![
+            (self.platinum, Medal::Platinum),
+            (self.gold, Medal::Gold),
+            (self.silver, Medal::Silver),
+            (self.bronze, Medal::Bronze),
+        ]
+        .into_iter()
+        .find(|&(min, _)| score >= min)
+        .map(|(_, medal)| medal)
+    }
+}
+
+// The game over screen's panel with the run's medal, score and best score.
+pub struct ResultsPanel {
+    medals: Medals,
+    image: Sprite,
+}
+
+impl ResultsPanel {
+    pub fn new(ctx: &Context, medals: Medals) -> Self {
+        ResultsPanel {
+            medals,
+            image: medal_image(ctx),
+        }
+    }
+
+    pub fn medal(&self, score: i32) -> Option<Medal> {
+        self.medals.award(score)
+    }
+
+    // Draws the panel centered on `center`, narrowed to fit the screen.
+    pub fn draw<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>, center: Vec2, score: i32, best: i32) {
+        let width = PANEL_WIDTH.min(engine::screen_width() - 20.0);
+        let left = center.x - width / 2.0;
+        let top = center.y - PANEL_HEIGHT / 2.0;
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(left - 4.0, top - 4.0, width + 8.0, PANEL_HEIGHT + 8.0),
+            color: Color::new(0.33, 0.22, 0.27, 1.0),
+        });
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(left, top, width, PANEL_HEIGHT),
+            color: Color::new(0.87, 0.85, 0.58, 1.0),
+        });
+
+        let label = Color::new(0.9, 0.45, 0.3, 1.0);
+        let medal_center = Vec2::new(left + 100.0, center.y + 10.0);
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("medal"),
+            Vec2::new(medal_center.x, top + 12.0),
+            20.0,
+            label,
+        ));
+        let half = MEDAL.len() as f32 * MEDAL_SCALE / 2.0;
+        match self.medal(score) {
+            Some(medal) => cmds.push(DrawCmd::Sprite {
+                sprite: &self.image,
+                dest: medal_center - Vec2::new(half, half),
+                scale: MEDAL_SCALE,
+                flip: false,
+                tint: medal.color(),
+                rotation: 0.0,
+            }),
+            None => cmds.push(DrawCmd::Circle {
+                center: medal_center,
+                radius: half,
+                color: Color::new(0.78, 0.74, 0.5, 1.0),
+            }),
+        }
+
+        let right = left + width - 30.0;
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        for (n, (name, value)) in vec![(lang::tr("score"), score), (lang::tr("best"), best)]
+            .into_iter()
+            .enumerate()
+        {
+            let y = top + 12.0 + 74.0 * n as f32;
+            cmds.push(DrawCmd::text(
+                name,
+                Vec2::new(right - 120.0, y),
+                20.0,
+                label,
+            ));
+            cmds.push(DrawCmd::text(
+                value.to_string(),
+                Vec2::new(right - 120.0, y + 24.0),
+                36.0,
+                white,
+            ));
+        }
+    }
+}
+
+fn medal_image(ctx: &Context) -> Sprite {
+    let mut rgba = Vec::with_capacity(MEDAL.len() * MEDAL.len() * 4);
+    for row in MEDAL.iter() {
+        for pixel in row.chars() {
+            match pixel {
+                '#' => rgba.extend_from_slice(&[150, 150, 150, 255]),
+                '+' => rgba.extend_from_slice(&[225, 225, 225, 255]),
+                '*' => rgba.extend_from_slice(&[255, 255, 255, 255]),
+                _ => rgba.extend_from_slice(&[0, 0, 0, 0]),
+            }
+        }
+    }
+
+    let size = MEDAL.len() as u32;
+    Sprite::from_rgba8(ctx, size, size, &rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn awards_the_best_medal_reached() {
+        let medals = Medals::default();
+        assert_eq!(medals.award(9), None);
+        assert_eq!(medals.award(10), Some(Medal::Bronze));
+        assert_eq!(medals.award(29), Some(Medal::Silver));
+        assert_eq!(medals.award(100), Some(Medal::Platinum));
+    }
+}
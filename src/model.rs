@@ -0,0 +1,128 @@
+use crate::engine::{LANDSCAPE_WIDTH, SCREEN_HEIGHT};
+use crate::policy::{Action, BirdPolicy, Observation};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+const INPUTS: usize = 6;
+
+// The observation as a flat vector, roughly scaled to 0..1: positions by the
+// landscape screen's size and the speed by 10. Models are trained on this
+// order.
+fn inputs(obs: &Observation) -> [f32; INPUTS] {
+    [
+        obs.bird_y / SCREEN_HEIGHT,
+        obs.bird_height / SCREEN_HEIGHT,
+        obs.speed_y / 10.0,
+        obs.gap_dx / LANDSCAPE_WIDTH,
+        obs.ceiling / SCREEN_HEIGHT,
+        obs.floor / SCREEN_HEIGHT,
+    ]
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Activation {
+    #[default]
+    Linear,
+    Relu,
+    Tanh,
+    Sigmoid,
+}
+
+impl Activation {
+    fn apply(self, x: f32) -> f32 {
+        match self {
+            Activation::Linear => x,
+            Activation::Relu => x.max(0.0),
+            Activation::Tanh => x.tanh(),
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+        }
+    }
+}
+
+// A fully connected layer: one row of `weights` and one `bias` per output.
+#[derive(Deserialize)]
+struct Layer {
+    weights: Vec<Vec<f32>>,
+    bias: Vec<f32>,
+    #[serde(default)]
+    activation: Activation,
+}
+
+// A trained feed-forward network playing as a policy. Models are exported
+// as JSON, `{"layers": [{"weights": [[..]], "bias": [..], "activation":
+// "relu"}, ..]}`, take `inputs` and end in a single output; the bird flaps
+// when it's above 0.5. This stands in for ONNX models run with tract, which
+// isn't a dependency, until that substitution is agreed on or tract added.
+#[derive(Deserialize)]
+pub struct ModelPolicy {
+    layers: Vec<Layer>,
+}
+
+impl ModelPolicy {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("onnx"))
+        {
+            return Err(format!(
+                "can't load model {}: ONNX isn't supported, export the weights as JSON",
+                path.display()
+            ));
+        }
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("can't read model {}: {}", path.display(), e))?;
+        let model: ModelPolicy = serde_json::from_str(&contents)
+            .map_err(|e| format!("invalid model {}: {}", path.display(), e))?;
+
+        let mut width = INPUTS;
+        for (n, layer) in model.layers.iter().enumerate() {
+            if layer.bias.len() != layer.weights.len()
+                || layer.weights.iter().any(|row| row.len() != width)
+            {
+                return Err(format!(
+                    "invalid model {}: layer {} doesn't take {} inputs",
+                    path.display(),
+                    n,
+                    width
+                ));
+            }
+            width = layer.bias.len();
+        }
+        if model.layers.is_empty() || width != 1 {
+            return Err(format!(
+                "invalid model {}: needs a single output",
+                path.display()
+            ));
+        }
+
+        Ok(model)
+    }
+
+    fn forward(&self, inputs: &[f32]) -> f32 {
+        let mut values = inputs.to_vec();
+        for layer in &self.layers {
+            values = layer
+                .weights
+                .iter()
+                .zip(&layer.bias)
+                .map(|(row, bias)| {
+                    let sum: f32 = row.iter().zip(&values).map(|(w, v)| w * v).sum();
+                    layer.activation.apply(sum + bias)
+                })
+                .collect();
+        }
+        values[0]
+    }
+}
+
+impl BirdPolicy for ModelPolicy {
+    fn act(&mut self, obs: Observation) -> Action {
+        if self.forward(&inputs(&obs)) > 0.5 {
+            Action::Flap
+        } else {
+            Action::Idle
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::assets::Assets;
+use crate::engine;
+use crate::theme::THEMES;
+use crate::tuning::Tuning;
+use crate::world_setup::FLOOR_Y;
+use crate::{Backdrop, BackgroundTag, Image, Position, ZOrder};
+use serde::Deserialize;
+use specs::world::EntitiesRes;
+use specs::*;
+
+// One strip of scrolling scenery, tiled across the screen. Layers with a
+// higher `z` are drawn over lower ones; pipes and birds go over them all.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ParallaxLayer {
+    pub image: String,
+    // Pixels a second; without one the layer keeps pace with the pipes at
+    // the tuning's scroll speed, like the floor.
+    pub speed: Option<f32>,
+    // How far apart the tiles are: the image's own width unless given, e.g.
+    // to overlap tiles whose edges don't quite match.
+    pub width: Option<f32>,
+    pub y: f32,
+    pub z: i32,
+}
+
+impl Default for ParallaxLayer {
+    fn default() -> Self {
+        ParallaxLayer {
+            image: String::new(),
+            speed: None,
+            width: None,
+            y: 0.0,
+            z: 0,
+        }
+    }
+}
+
+// Enough tiles `width` apart to cover the screen however the width
+// divides it, with one more scrolling in from the right.
+fn copies(width: f32) -> u32 {
+    (engine::screen_width() / width).ceil() as u32 + 1
+}
+
+impl ParallaxLayer {
+    // Which of the theme's backgrounds this is, so the theme can swap it.
+    fn backdrop(&self) -> Option<usize> {
+        THEMES[0]
+            .backgrounds
+            .iter()
+            .position(|&path| path == self.image)
+    }
+}
+
+// The `[[parallax]]` tables of `config.toml`, back to front unless `z` says
+// otherwise. Any tables replace all of the default layers: the two sky
+// backgrounds and the floor.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Parallax(pub Vec<ParallaxLayer>);
+
+impl Default for Parallax {
+    fn default() -> Self {
+        Parallax(vec![
+            ParallaxLayer {
+                image: THEMES[0].backgrounds[0].to_string(),
+                speed: Some(120.0),
+                ..ParallaxLayer::default()
+            },
+            ParallaxLayer {
+                image: THEMES[0].backgrounds[1].to_string(),
+                speed: Some(180.0),
+                z: 10,
+                ..ParallaxLayer::default()
+            },
+            ParallaxLayer {
+                image: "/floor.png".to_string(),
+                y: FLOOR_Y,
+                z: 20,
+                ..ParallaxLayer::default()
+            },
+        ])
+    }
+}
+
+impl Parallax {
+    // The images to load with the rest of the assets.
+    pub fn images(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|layer| layer.image.as_str())
+    }
+
+    // Every layer's image, in the same order as the layers.
+    pub fn load(&self, assets: &Assets) -> Vec<(ParallaxLayer, Image)> {
+        self.0
+            .iter()
+            .map(|layer| (layer.clone(), assets.image(&layer.image).clone()))
+            .collect()
+    }
+}
+
+// Tiles every layer across the screen.
+pub fn spawn_layers(world: &mut World, layers: &[(ParallaxLayer, Image)]) {
+    let scroll_speed = world.read_resource::<Tuning>().scroll_speed;
+    for (layer, image) in layers {
+        let width = layer.width.unwrap_or_else(|| image.image.width()).max(1.0);
+        let copies = copies(width);
+        for n in 0..copies {
+            let mut builder = world
+                .create_entity()
+                .with(Position {
+                    position: engine::Vec2::new(width * n as f32, layer.y),
+                    speed: engine::Vec2::ZERO,
+                })
+                .with(BackgroundTag {
+                    velocity: layer.speed.unwrap_or(scroll_speed),
+                    width,
+                    num_copies: copies,
+                })
+                .with(ZOrder(layer.z))
+                .with(image.clone());
+            if let Some(backdrop) = layer.backdrop() {
+                builder = builder.with(Backdrop(backdrop));
+            }
+            builder.build();
+        }
+    }
+}
+
+// The scenery's images and where they are, sorted back to front. Ties,
+// like the tiles of one layer, keep storage order.
+pub fn in_draw_order<'a>(
+    entities: &Read<'a, EntitiesRes>,
+    positions: &ReadStorage<'a, Position>,
+    images: &'a ReadStorage<'a, Image>,
+    zorders: &ReadStorage<'a, ZOrder>,
+) -> Vec<(Entity, engine::Vec2, &'a Image)> {
+    let mut scenery: Vec<_> = (&**entities, positions, images, zorders.maybe())
+        .join()
+        .map(|(e, p, i, z)| (z.copied().unwrap_or_default(), e, p.position, i))
+        .collect();
+    scenery.sort_by_key(|&(z, ..)| z.0);
+    scenery
+        .into_iter()
+        .map(|(_, e, at, image)| (e, at, image))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::PHYSICS_FPS;
+
+    #[test]
+    fn layers_from_config_replace_the_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            [[parallax]]
+            image = "/hills.png"
+            speed = 90.0
+            width = 700.0
+            z = -5
+
+            [[parallax]]
+            image = "/floor.png"
+            "#,
+        )
+        .unwrap();
+        let parallax = config.parallax;
+
+        assert_eq!(parallax.0.len(), 2);
+        assert_eq!(parallax.0[0].speed, Some(90.0));
+        assert_eq!(parallax.0[0].width, Some(700.0));
+        assert_eq!(parallax.0[1].speed, None);
+        assert_eq!(parallax.0[1].width, None);
+        assert_eq!(Parallax::default().0[1].backdrop(), Some(1));
+    }
+
+    #[test]
+    fn tiles_stay_seamless_at_any_width() {
+        let mut world = crate::empty_world(Tuning::default());
+        // Doesn't divide the screen, and scrolls more than a tile a tick.
+        let width = 300.0;
+        let copies = copies(width);
+        for n in 0..copies {
+            world
+                .create_entity()
+                .with(Position {
+                    position: engine::Vec2::new(width * n as f32, 0.0),
+                    speed: engine::Vec2::ZERO,
+                })
+                .with(BackgroundTag {
+                    velocity: 317.0 * PHYSICS_FPS as f32,
+                    width,
+                    num_copies: copies,
+                })
+                .build();
+        }
+
+        for _ in 0..50 {
+            crate::ScrollSystem.run_now(&world);
+            let positions = world.read_storage::<Position>();
+            let mut xs: Vec<f32> = positions.join().map(|p| p.position.x).collect();
+            xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!(xs[0] >= -width && xs[0] <= 0.0);
+            assert!(xs.windows(2).all(|w| (w[1] - w[0] - width).abs() < 0.01));
+            assert!(xs[xs.len() - 1] + width >= engine::screen_width());
+        }
+    }
+}
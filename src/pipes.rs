@@ -0,0 +1,144 @@
+use crate::assets::Assets;
+use crate::engine::{self, Color, DrawCmd, Rect, Sprite, Vec2};
+use crate::systems::debris::{BODY_CHUNK_HEIGHT, CAP_HEIGHT, CHUNK_WIDTH};
+use crate::{CollisionBox, Debris, PipeColor, Position};
+
+// Pipe art: a cap for the lip and a body segment tiled from the cap to the
+// edge of the screen, so a pipe can end at any height. Top pipes are drawn
+// upside down. Not present when running headless.
+pub struct PipeSprites {
+    cap: Sprite,
+    body: Sprite,
+    // The parts of the art debris chunks show, indexed by `Debris::piece`.
+    pieces: Vec<Sprite>,
+}
+
+impl PipeSprites {
+    pub fn new(assets: &Assets) -> Self {
+        let cap = assets.sprite("pipe_cap").image.clone();
+        let body = assets.sprite("pipe_body").image.clone();
+        PipeSprites::from_art(cap, body)
+    }
+
+    // Pipes in other art, e.g. a skin's, the same size as the usual.
+    pub fn from_art(cap: Sprite, body: Sprite) -> Self {
+        let mut pieces = Vec::with_capacity(6);
+        for column in 0..2 {
+            let x = CHUNK_WIDTH * column as f32;
+            pieces.push(cap.region(Rect::new(x, 0.0, CHUNK_WIDTH, CAP_HEIGHT)));
+        }
+        for row in 0..2 {
+            for column in 0..2 {
+                let (x, y) = (CHUNK_WIDTH * column as f32, BODY_CHUNK_HEIGHT * row as f32);
+                pieces.push(body.region(Rect::new(x, y, CHUNK_WIDTH, BODY_CHUNK_HEIGHT)));
+            }
+        }
+        PipeSprites { cap, body, pieces }
+    }
+
+    pub fn cap(&self) -> &Sprite {
+        &self.cap
+    }
+
+    pub fn body(&self) -> &Sprite {
+        &self.body
+    }
+
+    // Cap chunks come first so the body chunks batch together.
+    pub(crate) fn draw_debris<'a>(
+        &'a self,
+        cmds: &mut Vec<DrawCmd<'a>>,
+        chunks: &[(Position, Debris)],
+    ) {
+        let mut body = Vec::with_capacity(chunks.len());
+        for (pos, debris) in chunks {
+            let cmd =
+                DrawCmd::rotated_sprite(&self.pieces[debris.piece], pos.position, debris.rotation);
+            if debris.piece < 2 {
+                cmds.push(cmd);
+            } else {
+                body.push(cmd);
+            }
+        }
+        cmds.append(&mut body);
+    }
+
+    // Draws a pipe for each box, its lip on the gap side of the box; the
+    // flag marks top pipes. Every body segment goes before every cap, so
+    // each sprite is drawn in one batch. The theme's `tint` goes over each
+    // pipe's own paint.
+    pub(crate) fn draw<'a>(
+        &'a self,
+        cmds: &mut Vec<DrawCmd<'a>>,
+        pipes: &[(CollisionBox, bool, PipeColor)],
+        tint: Color,
+    ) {
+        let cap_height = self.cap.height();
+        let body_height = self.body.height();
+        let mut caps = Vec::with_capacity(pipes.len());
+        let piece = |sprite: &'a Sprite, dest: Vec2, flip: bool, paint: Color| DrawCmd::Sprite {
+            sprite,
+            dest,
+            scale: 1.0,
+            flip,
+            tint: Color::new(tint.r * paint.r, tint.g * paint.g, tint.b * paint.b, tint.a),
+            rotation: 0.0,
+        };
+
+        for (coll_box, top, color) in pipes {
+            let paint = color.tint();
+            let x = coll_box.origin.x;
+            if *top {
+                let lip = coll_box.origin.y + coll_box.height;
+                let mut y = lip - cap_height;
+                caps.push(piece(&self.cap, Vec2::new(x, y), true, paint));
+                while y > 0.0 {
+                    y -= body_height;
+                    cmds.push(piece(&self.body, Vec2::new(x, y), true, paint));
+                }
+            } else {
+                let mut y = coll_box.origin.y;
+                caps.push(piece(&self.cap, Vec2::new(x, y), false, paint));
+                y += cap_height;
+                while y < engine::SCREEN_HEIGHT {
+                    cmds.push(piece(&self.body, Vec2::new(x, y), false, paint));
+                    y += body_height;
+                }
+            }
+        }
+        cmds.append(&mut caps);
+    }
+
+    // The high contrast pipes: the same shapes as `draw`, filled with one
+    // flat `color`.
+    pub(crate) fn draw_flat(
+        &self,
+        cmds: &mut Vec<DrawCmd>,
+        pipes: &[(CollisionBox, bool, PipeColor)],
+        color: Color,
+    ) {
+        let (cap_width, cap_height) = (self.cap.width(), self.cap.height());
+        let body_width = self.body.width();
+        for (coll_box, top, _) in pipes {
+            let x = coll_box.origin.x;
+            let (cap_y, body) = if *top {
+                let lip = coll_box.origin.y + coll_box.height;
+                (
+                    lip - cap_height,
+                    Rect::new(x, 0.0, body_width, lip - cap_height),
+                )
+            } else {
+                let y = coll_box.origin.y + cap_height;
+                (
+                    coll_box.origin.y,
+                    Rect::new(x, y, body_width, engine::SCREEN_HEIGHT - y),
+                )
+            };
+            cmds.push(DrawCmd::Rect { rect: body, color });
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x, cap_y, cap_width, cap_height),
+                color,
+            });
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::engine::{Color, DrawCmd, Vec2};
+use crate::{
+    CollisionBox, Direction, ObstacleTag, PlayerTag, Position, BIRD_FLOOR_Y, PHYSICS_FPS,
+    PIPE_WIDTH,
+};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use specs::*;
+
+// Policies that can be picked by name on the command line.
+#[cfg(not(feature = "model"))]
+pub const POLICY_NAMES: &str = "rule|random|noop";
+#[cfg(feature = "model")]
+pub const POLICY_NAMES: &str = "rule|random|noop|model:FILE";
+
+// What a policy sees of the world every tick, in screen pixels. "Lips" are
+// the edges of the next gap: `ceiling` is the bottom of the next top pipe or
+// cave ceiling and `floor` the top of the next bottom pipe or cave floor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observation {
+    // X of the bird's front edge.
+    pub bird_front: f32,
+    pub bird_y: f32,
+    pub bird_height: f32,
+    // In pixels a tick, what the model was trained on.
+    pub speed_y: f32,
+    // Horizontal distance from the bird's front to the next obstacle.
+    pub gap_dx: f32,
+    pub ceiling: f32,
+    // The highest floor lip just ahead, so rising cave floors are seen
+    // coming, or else the next pipe's.
+    pub floor: f32,
+}
+
+impl Observation {
+    // Returns None once there's no bird to observe.
+    pub(crate) fn from_world(world: &World) -> Option<Self> {
+        Observation::observe(
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+            &world.read_storage(),
+        )
+    }
+
+    fn observe(
+        positions: &ReadStorage<Position>,
+        boxes: &ReadStorage<CollisionBox>,
+        players: &ReadStorage<PlayerTag>,
+        obstacles: &ReadStorage<ObstacleTag>,
+    ) -> Option<Self> {
+        let (bird_pos, bird_box) = (positions, boxes, players)
+            .join()
+            .next()
+            .map(|(pos, coll_box, _)| (pos, coll_box))?;
+        let bird_front = bird_box.origin.x + bird_box.width;
+
+        // Obstacles the bird hasn't fully passed yet, split by whether they
+        // hang from the top of the screen.
+        let mut ceilings = Vec::new();
+        let mut floors = Vec::new();
+        // Hazards just ahead can only raise the floor.
+        let mut hazards = Vec::new();
+        for (coll_box, obs) in (boxes, obstacles).join() {
+            if coll_box.origin.x + coll_box.width <= bird_box.origin.x {
+                continue;
+            }
+            if obs.hazard.is_some() {
+                hazards.push(coll_box);
+            } else if obs.top || coll_box.origin.y <= 0.0 {
+                ceilings.push(coll_box);
+            } else {
+                floors.push(coll_box);
+            }
+        }
+
+        let ahead = bird_front + 64.0;
+        let floor = floors
+            .iter()
+            .filter(|coll_box| coll_box.origin.x < ahead)
+            .map(|coll_box| coll_box.origin.y)
+            .fold(None, |lip: Option<f32>, y| {
+                Some(lip.map_or(y, |lip| lip.min(y)))
+            })
+            .or_else(|| nearest(&floors).map(|coll_box| coll_box.origin.y))
+            .unwrap_or(BIRD_FLOOR_Y);
+        let floor = hazards
+            .iter()
+            .filter(|coll_box| coll_box.origin.x < ahead)
+            .fold(floor, |floor, coll_box| floor.min(coll_box.origin.y));
+        let next_ceiling = nearest(&ceilings);
+
+        Some(Observation {
+            bird_front,
+            bird_y: bird_box.origin.y,
+            bird_height: bird_box.height,
+            speed_y: bird_pos.speed.y / PHYSICS_FPS as f32,
+            gap_dx: nearest(&floors).map_or(0.0, |coll_box| coll_box.origin.x - bird_front),
+            ceiling: next_ceiling.map_or(0.0, |coll_box| coll_box.origin.y + coll_box.height),
+            floor,
+        })
+    }
+}
+
+fn nearest<'a>(boxes: &[&'a CollisionBox]) -> Option<&'a CollisionBox> {
+    boxes
+        .iter()
+        .min_by(|a, b| a.origin.x.partial_cmp(&b.origin.x).unwrap())
+        .copied()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Idle,
+    Flap,
+}
+
+// Something that plays the game: the demo bot and the balance simulator
+// ask it what to do once per tick.
+pub trait BirdPolicy {
+    fn act(&mut self, obs: Observation) -> Action;
+}
+
+// Flaps whenever the bird is falling towards the floor lip.
+pub struct RulePolicy;
+
+impl BirdPolicy for RulePolicy {
+    fn act(&mut self, obs: Observation) -> Action {
+        if obs.speed_y >= 0.0 && obs.bird_y + obs.bird_height > obs.floor - 20.0 {
+            Action::Flap
+        } else {
+            Action::Idle
+        }
+    }
+}
+
+// Flaps on one tick in twenty, on average.
+#[derive(Default)]
+pub struct RandomPolicy {
+    rng: ThreadRng,
+}
+
+impl BirdPolicy for RandomPolicy {
+    fn act(&mut self, _obs: Observation) -> Action {
+        if self.rng.gen_bool(0.05) {
+            Action::Flap
+        } else {
+            Action::Idle
+        }
+    }
+}
+
+pub struct NoopPolicy;
+
+impl BirdPolicy for NoopPolicy {
+    fn act(&mut self, _obs: Observation) -> Action {
+        Action::Idle
+    }
+}
+
+// Plays the game with a policy, flapping for it through the `Direction`
+// resource like a player would. Drives `--demo` and the menu's attract mode.
+pub struct AutopilotSystem {
+    bot: Box<dyn BirdPolicy>,
+}
+
+impl AutopilotSystem {
+    pub fn new(bot: Box<dyn BirdPolicy>) -> Self {
+        AutopilotSystem { bot }
+    }
+}
+
+impl<'a> System<'a> for AutopilotSystem {
+    type SystemData = (
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, CollisionBox>,
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, ObstacleTag>,
+        Write<'a, Direction>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (positions, boxes, players, obstacles, mut dir) = data;
+        let obs = Observation::observe(&positions, &boxes, &players, &obstacles);
+        if obs.map(|obs| self.bot.act(obs)) == Some(Action::Flap) {
+            *dir = Direction {
+                jump: true,
+                ..Direction::new()
+            };
+        }
+    }
+}
+
+pub fn from_name(name: &str) -> Result<Box<dyn BirdPolicy>, String> {
+    match name {
+        "rule" => Ok(Box::new(RulePolicy)),
+        "random" => Ok(Box::new(RandomPolicy::default())),
+        "noop" => Ok(Box::new(NoopPolicy)),
+        #[cfg(feature = "model")]
+        _ if name.starts_with("model:") => {
+            let path = std::path::Path::new(&name["model:".len()..]);
+            Ok(Box::new(crate::model::ModelPolicy::load(path)?))
+        }
+        _ => Err(format!(
+            "unknown policy {} (expected {})",
+            name, POLICY_NAMES
+        )),
+    }
+}
+
+// Debug overlay showing what a policy sees: the distance to the next
+// obstacle, the two lips relative to the bird and its vertical speed.
+pub fn draw_observation(cmds: &mut Vec<DrawCmd>, obs: &Observation) {
+    let front = obs.bird_front;
+    let center_y = obs.bird_y + obs.bird_height / 2.0;
+    let gap_x = front + obs.gap_dx;
+    let mut line = |from: Vec2, to: Vec2, color: Color, label: String| {
+        cmds.push(DrawCmd::Line {
+            from,
+            to,
+            width: 2.0,
+            color,
+        });
+        cmds.push(DrawCmd::text(label, to + Vec2::new(6.0, -8.0), 16.0, color));
+    };
+
+    line(
+        Vec2::new(front, center_y),
+        Vec2::new(gap_x, center_y),
+        Color::new(1.0, 1.0, 0.3, 1.0),
+        format!("dx {:.0}", obs.gap_dx),
+    );
+    line(
+        Vec2::new(front, obs.bird_y),
+        Vec2::new(gap_x, obs.ceiling),
+        Color::new(0.3, 1.0, 1.0, 1.0),
+        format!("ceiling {:+.0}", obs.ceiling - obs.bird_y),
+    );
+    line(
+        Vec2::new(front, obs.bird_y + obs.bird_height),
+        Vec2::new(gap_x, obs.floor),
+        Color::new(1.0, 0.4, 1.0, 1.0),
+        format!("floor {:+.0}", obs.floor - (obs.bird_y + obs.bird_height)),
+    );
+    line(
+        Vec2::new(front, center_y),
+        Vec2::new(front, center_y + obs.speed_y * 10.0),
+        Color::new(1.0, 0.6, 0.2, 1.0),
+        format!("vy {:.1}", obs.speed_y),
+    );
+}
+
+// The line through the middle of the next gap, from the bird's front to
+// the far side of the gap, to aim along in practice.
+pub fn draw_centerline(cmds: &mut Vec<DrawCmd>, obs: &Observation) {
+    let middle = (obs.ceiling + obs.floor) / 2.0;
+    cmds.push(DrawCmd::Line {
+        from: Vec2::new(obs.bird_front, middle),
+        to: Vec2::new(obs.bird_front + obs.gap_dx + PIPE_WIDTH, middle),
+        width: 2.0,
+        color: Color::new(1.0, 1.0, 0.3, 0.8),
+    });
+}
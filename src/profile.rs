@@ -0,0 +1,97 @@
+use crate::customize::Looks;
+use crate::lang;
+use crate::stats::Stats;
+use ggez::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// First line of a profile file, followed by the format version and the
+// checksum of the JSON on the lines after it.
+const MAGIC: &str = "rusty-bird-profile";
+const VERSION: u32 = 1;
+
+// Everything the game remembers about a player, to carry it over to
+// another machine in one file: scores and stats, and the bird looks.
+#[derive(Deserialize)]
+pub struct Profile {
+    pub stats: Stats,
+    pub looks: Looks,
+}
+
+#[derive(Serialize)]
+struct ProfileRef<'a> {
+    stats: &'a Stats,
+    looks: &'a Looks,
+}
+
+pub fn profile_path(ctx: &Context) -> PathBuf {
+    ctx.fs.user_data_dir().join("profile.rbp")
+}
+
+pub fn export(path: &Path, stats: &Stats, looks: &Looks) -> Result<(), String> {
+    let text = to_text(stats, looks).map_err(|e| e.to_string())?;
+    path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, text))
+        .map_err(|e| lang::fill("profile_cant_write", &[&path.display(), &e]))
+}
+
+// Reads a profile written by `export`, refusing files that were cut short
+// or edited since.
+pub fn import(path: &Path) -> Result<Profile, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| lang::fill("profile_cant_read", &[&path.display(), &e]))?;
+    parse(&text)
+}
+
+fn to_text(stats: &Stats, looks: &Looks) -> serde_json::Result<String> {
+    let json = serde_json::to_string(&ProfileRef { stats, looks })?;
+    Ok(format!(
+        "{} {} {:016x}\n{}",
+        MAGIC,
+        VERSION,
+        checksum(&json),
+        json
+    ))
+}
+
+fn parse(text: &str) -> Result<Profile, String> {
+    let (header, json) = text.split_once('\n').unwrap_or((text, ""));
+    let fields: Vec<&str> = header.split_whitespace().collect();
+    let (version, sum) = match fields[..] {
+        [MAGIC, version, sum] => (version, sum),
+        _ => return Err(lang::tr("profile_not_a_profile").to_string()),
+    };
+    if version.parse() != Ok(VERSION) {
+        return Err(lang::fill("profile_unsupported", &[&version]));
+    }
+    if u64::from_str_radix(sum, 16) != Ok(checksum(json)) {
+        return Err(lang::tr("profile_damaged").to_string());
+    }
+    serde_json::from_str(json).map_err(|e| lang::fill("profile_invalid", &[&e]))
+}
+
+// 64-bit FNV-1a, enough to catch damaged or hand edited files.
+fn checksum(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_damaged_profiles() {
+        let mut stats = Stats::default();
+        stats.record_run(12, 600);
+        let text = to_text(&stats, &Looks::default()).unwrap();
+        assert_eq!(parse(&text).unwrap().stats.scores, vec![12]);
+
+        let edited = text.replace("[12]", "[99]");
+        assert!(parse(&edited).is_err());
+        assert!(parse(text.lines().nth(1).unwrap()).is_err());
+    }
+}
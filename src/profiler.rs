@@ -0,0 +1,151 @@
+use crate::engine::{Color, DrawCmd, Rect, Vec2};
+use specs::System;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Where a frame's time goes, as shown by the profiler bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    Movement,
+    Collision,
+    // Spawning and removing obstacles, and applying entity changes on
+    // `maintain`.
+    Spawn,
+    // Every other system.
+    Systems,
+    Draw,
+    // Laying out and queuing text, part of the draw pass.
+    Text,
+}
+
+const SCOPES: [(Scope, &str, (f32, f32, f32)); 6] = [
+    (Scope::Movement, "movement", (0.3, 0.7, 1.0)),
+    (Scope::Collision, "collision", (1.0, 0.4, 0.3)),
+    (Scope::Spawn, "spawn", (1.0, 0.8, 0.2)),
+    (Scope::Systems, "systems", (0.6, 0.6, 0.6)),
+    (Scope::Draw, "draw", (0.4, 0.9, 0.4)),
+    (Scope::Text, "text", (0.9, 0.5, 1.0)),
+];
+
+// How much each new frame moves the averages, so the bar stays readable.
+const SMOOTHING: f32 = 0.05;
+// The bar's full width stands for one frame at 60 FPS.
+const BUDGET_MS: f32 = 1000.0 / 60.0;
+
+// Times scopes within each frame and keeps a smoothed average per scope.
+#[derive(Default)]
+pub struct Profiler {
+    frame: [Duration; SCOPES.len()],
+    average_ms: [f32; SCOPES.len()],
+    systems: Arc<SystemTimes>,
+}
+
+// Nanoseconds per scope spent in systems a dispatcher ran, maybe several at
+// once, until the profiler folds them into its frame.
+#[derive(Default)]
+pub struct SystemTimes([AtomicU64; SCOPES.len()]);
+
+// Runs a system and charges its time to `scope`.
+pub struct Timed<S> {
+    system: S,
+    scope: Scope,
+    times: Arc<SystemTimes>,
+}
+
+impl<S> Timed<S> {
+    pub fn new(system: S, scope: Scope, times: Arc<SystemTimes>) -> Self {
+        Timed {
+            system,
+            scope,
+            times,
+        }
+    }
+}
+
+impl<'a, S: System<'a>> System<'a> for Timed<S> {
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        let start = Instant::now();
+        self.system.run(data);
+        let nanos = start.elapsed().as_nanos() as u64;
+        self.times.0[index(self.scope)].fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+fn index(scope: Scope) -> usize {
+    SCOPES.iter().position(|&(s, _, _)| s == scope).unwrap()
+}
+
+impl Profiler {
+    // Charges the time since `since` to `scope` and restarts it, for timing
+    // a sequence of steps one after the other.
+    pub fn lap(&mut self, scope: Scope, since: &mut Instant) {
+        let now = Instant::now();
+        self.add(scope, now - *since);
+        *since = now;
+    }
+
+    pub fn add(&mut self, scope: Scope, elapsed: Duration) {
+        self.frame[index(scope)] += elapsed;
+    }
+
+    // Where systems wrapped in `Timed` charge their time.
+    pub fn system_times(&self) -> Arc<SystemTimes> {
+        self.systems.clone()
+    }
+
+    // Folds the frame's timings into the averages and starts a new frame.
+    // Call once per drawn frame.
+    pub fn end_frame(&mut self) {
+        for (frame, nanos) in self.frame.iter_mut().zip(self.systems.0.iter()) {
+            *frame += Duration::from_nanos(nanos.swap(0, Ordering::Relaxed));
+        }
+        for (average, frame) in self.average_ms.iter_mut().zip(self.frame.iter_mut()) {
+            let ms = frame.as_secs_f32() * 1000.0;
+            *average += (ms - *average) * SMOOTHING;
+            *frame = Duration::ZERO;
+        }
+    }
+
+    // A stacked bar of the average frame against the 60 FPS budget, with a
+    // legend below it.
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, area: Rect) {
+        cmds.push(DrawCmd::Rect {
+            rect: area,
+            color: Color::new(0.0, 0.0, 0.0, 0.6),
+        });
+
+        let total: f32 = self.average_ms.iter().sum();
+        cmds.push(DrawCmd::text(
+            format!("frame {:.2} ms of {:.2}", total, BUDGET_MS),
+            Vec2::new(area.x, area.y - 16.0),
+            14.0,
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        ));
+
+        let mut x = area.x;
+        for (&(_, _, (r, g, b)), &ms) in SCOPES.iter().zip(self.average_ms.iter()) {
+            let w = (area.w * ms / BUDGET_MS).min(area.x + area.w - x);
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x, area.y, w, area.h),
+                color: Color::new(r, g, b, 1.0),
+            });
+            x += w;
+        }
+
+        let mut label_x = area.x;
+        for (&(_, name, (r, g, b)), &ms) in SCOPES.iter().zip(self.average_ms.iter()) {
+            let label = format!("{} {:.2}", name, ms);
+            let width = 8.0 * label.len() as f32 + 12.0;
+            cmds.push(DrawCmd::text(
+                label,
+                Vec2::new(label_x, area.y + area.h + 2.0),
+                14.0,
+                Color::new(r, g, b, 1.0),
+            ));
+            label_x += width;
+        }
+    }
+}
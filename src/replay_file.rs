@@ -0,0 +1,322 @@
+use crate::{Direction, Game, TimeScale};
+use specs::*;
+use std::convert::{TryFrom, TryInto};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"RBR1";
+
+// The input on one tick, kept when it differs from the tick before.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InputEvent {
+    // Counted like `Game::frames`.
+    pub frame: i32,
+    pub input: Direction,
+    // The time scale, which the practice speed and slow motion change.
+    pub time: f32,
+}
+
+// A run's seed and inputs, from `--record` and for `--replay`. The
+// `.rbr` file is binary and small: the magic `RBR1`, the seed as a little
+// endian u64 and then every event as the frames since the last one in a
+// LEB128 varint, a byte of input flags and the time scale as an f32.
+// Replays are only faithful to the tuning and options they were recorded
+// with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayFile {
+    pub seed: u64,
+    pub events: Vec<InputEvent>,
+}
+
+fn flags(input: &Direction) -> u8 {
+    input.jump as u8
+        | (input.release as u8) << 1
+        | (input.left as u8) << 2
+        | (input.right as u8) << 3
+}
+
+fn from_flags(flags: u8) -> Direction {
+    Direction {
+        jump: flags & 1 != 0,
+        release: flags & 2 != 0,
+        left: flags & 4 != 0,
+        right: flags & 8 != 0,
+    }
+}
+
+impl ReplayFile {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        let mut last = 0;
+        for event in &self.events {
+            let mut delta = (event.frame - last) as u32;
+            last = event.frame;
+            loop {
+                let byte = (delta & 0x7f) as u8;
+                delta >>= 7;
+                if delta == 0 {
+                    bytes.push(byte);
+                    break;
+                }
+                bytes.push(byte | 0x80);
+            }
+            bytes.push(flags(&event.input));
+            bytes.extend_from_slice(&event.time.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.get(..4) != Some(&MAGIC[..]) {
+            return Err("not a replay file".to_string());
+        }
+        let truncated = || "the replay file is cut short".to_string();
+        let seed = bytes
+            .get(4..12)
+            .and_then(|b| b.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or_else(truncated)?;
+
+        let mut file = ReplayFile {
+            seed,
+            events: Vec::new(),
+        };
+        let bad_frame = || "the replay file has a bad frame count".to_string();
+        let mut rest = &bytes[12..];
+        let mut frame = 0i32;
+        while !rest.is_empty() {
+            let mut delta = 0u32;
+            let mut shift = 0;
+            loop {
+                let (&byte, tail) = rest.split_first().ok_or_else(truncated)?;
+                rest = tail;
+                if shift > 28 {
+                    return Err(bad_frame());
+                }
+                delta |= ((byte & 0x7f) as u32) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            if rest.len() < 5 {
+                return Err(truncated());
+            }
+            frame = i32::try_from(delta)
+                .ok()
+                .and_then(|delta| frame.checked_add(delta))
+                .ok_or_else(bad_frame)?;
+            file.events.push(InputEvent {
+                frame,
+                input: from_flags(rest[0]),
+                time: f32::from_le_bytes(rest[1..5].try_into().unwrap()),
+            });
+            rest = &rest[5..];
+        }
+        Ok(file)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        ReplayFile::decode(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.encode()).map_err(|e| format!("can't write {}: {}", path.display(), e))
+    }
+}
+
+// The inputs of the run in progress, for `--record`. Saved over `path`
+// when the run ends.
+pub struct InputLog {
+    pub path: PathBuf,
+    pub file: ReplayFile,
+}
+
+impl InputLog {
+    pub fn new(path: PathBuf, seed: u64) -> Self {
+        InputLog {
+            path,
+            file: ReplayFile {
+                seed,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    // Starts over for a run on `seed`.
+    pub fn begin(&mut self, seed: u64) {
+        self.file = ReplayFile {
+            seed,
+            events: Vec::new(),
+        };
+    }
+}
+
+// Runs right before `MovementSystem`, like `ReplaySystem`, catching the
+// input it's about to act on.
+pub struct InputLogSystem;
+
+impl<'a> System<'a> for InputLogSystem {
+    type SystemData = (
+        Option<Write<'a, InputLog>>,
+        Read<'a, Direction>,
+        Read<'a, Game>,
+        Read<'a, TimeScale>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (log, dir, game, time) = data;
+        let mut log = match log {
+            Some(log) if !time.is_paused() => log,
+            _ => return,
+        };
+        let changed = log
+            .file
+            .events
+            .last()
+            .is_none_or(|last| last.input != *dir || last.time != time.0);
+        if changed {
+            log.file.events.push(InputEvent {
+                frame: game.frames,
+                input: *dir,
+                time: time.0,
+            });
+        }
+    }
+}
+
+// Feeds a replay file back in, replacing player input and the time scale.
+pub struct InputReplay {
+    file: ReplayFile,
+    next: usize,
+    current: Option<InputEvent>,
+}
+
+impl InputReplay {
+    pub fn new(file: ReplayFile) -> Self {
+        InputReplay {
+            file,
+            next: 0,
+            current: None,
+        }
+    }
+
+    // Starts the replay over for a new run.
+    pub fn rewind(&mut self) {
+        self.next = 0;
+        self.current = None;
+    }
+}
+
+// Runs where `TasSystem` does, right before the rest of the tick.
+pub struct InputReplaySystem;
+
+impl<'a> System<'a> for InputReplaySystem {
+    type SystemData = (
+        Option<Write<'a, InputReplay>>,
+        Write<'a, Direction>,
+        Write<'a, TimeScale>,
+        Read<'a, Game>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (replay, mut dir, mut time, game) = data;
+        let mut replay = match replay {
+            Some(replay) if !time.is_paused() => replay,
+            _ => return,
+        };
+        while let Some(&event) = replay.file.events.get(replay.next) {
+            if event.frame > game.frames {
+                break;
+            }
+            replay.current = Some(event);
+            replay.next += 1;
+        }
+        if let Some(event) = replay.current {
+            *dir = event.input;
+            *time = TimeScale(event.time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulate::tick_headless;
+    use crate::tuning::Tuning;
+    use crate::{build_world, Challenge, GameState};
+
+    fn world(seed: u64) -> World {
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(Challenge::new(seed));
+        *world.write_resource::<GameState>() = GameState::Playing;
+        world
+    }
+
+    #[test]
+    fn a_recorded_run_replays_the_same() {
+        let mut played = world(11);
+        played.insert(InputLog::new(PathBuf::new(), 11));
+        for frame in 0..900 {
+            if frame % 23 == 0 {
+                *played.write_resource::<Direction>() = Direction {
+                    jump: true,
+                    release: true,
+                    ..Direction::new()
+                };
+            }
+            InputLogSystem.run_now(&played);
+            if !tick_headless(&mut played) {
+                break;
+            }
+        }
+        let file = played.read_resource::<InputLog>().file.clone();
+        let decoded = ReplayFile::decode(&file.encode()).unwrap();
+        assert_eq!(decoded, file);
+
+        let mut replayed = world(decoded.seed);
+        replayed.insert(InputReplay::new(decoded));
+        loop {
+            InputReplaySystem.run_now(&replayed);
+            if !tick_headless(&mut replayed) {
+                break;
+            }
+        }
+        let (played, replayed) = (
+            played.read_resource::<Game>(),
+            replayed.read_resource::<Game>(),
+        );
+        assert_eq!(replayed.frames, played.frames);
+        assert_eq!(replayed.score, played.score);
+    }
+
+    #[test]
+    fn frames_past_the_last_one_are_turned_away() {
+        let event = |frame| InputEvent {
+            frame,
+            input: Direction::new(),
+            time: 0.0,
+        };
+        let file = ReplayFile {
+            seed: 1,
+            events: vec![event(i32::MAX)],
+        };
+        assert_eq!(ReplayFile::decode(&file.encode()), Ok(file));
+
+        let mut bytes = ReplayFile {
+            seed: 1,
+            events: vec![event(i32::MAX), event(i32::MAX)],
+        }
+        .encode();
+        // The second event's delta, before its flags and time, made one
+        // frame on from the last frame there can be.
+        let delta = bytes.len() - 6;
+        bytes[delta] = 1;
+        assert_eq!(
+            ReplayFile::decode(&bytes),
+            Err("the replay file has a bad frame count".to_string())
+        );
+    }
+}
@@ -0,0 +1,507 @@
+// Resources shared by the systems: the game's progress and what the player
+// is doing, plus the optional ones that turn on modes.
+
+use crate::engine::{self, DrawCmd, Vec2};
+use crate::lang;
+use crate::tuning::Tuning;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use specs::shrev::{EventChannel, ReaderId};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeathCause {
+    TopPipe,
+    BottomPipe,
+    Cave,
+    Hazard,
+    Floor,
+}
+
+// Where the bird died: its center in screen space, plus its offset from the
+// lip of the pipe it hit (horizontally from the pipe's center line).
+// `world_x` is the center measured from the start of the run, and `seed` is
+// set when the run was a seeded challenge.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Death {
+    pub cause: DeathCause,
+    pub x: f32,
+    pub y: f32,
+    pub lip_dx: f32,
+    pub lip_dy: f32,
+    #[serde(default)]
+    pub world_x: f32,
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+// What happened during a tick, published by the systems that notice it so
+// the ones acting on it don't have to know who did: see `GameEvents`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    // A bird flapped.
+    Flapped,
+    // A pipe pair passed; `pan` is where it is relative to the bird, from -1
+    // (left) to 1 (right).
+    Passed { pan: f32 },
+    // A bird picked up a coin or power-up.
+    PickedUp,
+    // A bird hit an obstacle, whether or not that ended the run.
+    Crashed,
+    // The run is over, see `GameOverSystem`.
+    Died(Death),
+}
+
+// Every world has one. Readers register with `register_reader` and see the
+// events written after that; `GameOverReader` is the one the world starts
+// with.
+pub type GameEvents = EventChannel<GameEvent>;
+
+// Where `GameOverSystem` is up to in `GameEvents`. Kept in the world so the
+// system itself stays a unit struct, like the rest.
+pub struct GameOverReader(pub ReaderId<GameEvent>);
+
+// Where the game is at. Worlds start out `Playing`; the game itself
+// switches to `Menu` before the first run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameState {
+    Menu,
+    // Before a run, waiting out the countdown and then the first flap.
+    GetReady,
+    #[default]
+    Playing,
+    Paused,
+    // After a crash, the bird dropping to the floor before the results come
+    // up. The run is already over and counted by then.
+    Dying,
+    GameOver,
+}
+
+#[derive(Clone, Default)]
+pub struct Game {
+    // One point per pipe pair or cave slot flown through.
+    pub score: i32,
+    // Ticks played, not counting pauses.
+    pub frames: i32,
+    // Best score on record for the mode being played, raised as soon as
+    // the run beats it. Saved with the stats at game over.
+    pub best: i32,
+    pub pipes_passed: u32,
+    pub distance: f32,
+    pub death: Option<Death>,
+    // Lives mode: hits taken so far, and ticks left of blinking through
+    // everything after the last one. See `Lives`.
+    pub lives_lost: u32,
+    pub invulnerable: u32,
+    // Set when the run ended on its game mode's goal rather than a crash.
+    pub cleared: bool,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game {
+            score: 0,
+            frames: 0,
+            best: 0,
+            pipes_passed: 0,
+            distance: 0.0,
+            death: None,
+            lives_lost: 0,
+            invulnerable: 0,
+            cleared: false,
+        }
+    }
+}
+
+// Physics ticks a second. Speeds and accelerations are given per second and
+// every tick moves things by its share of one, at any refresh rate.
+pub const PHYSICS_FPS: u32 = 60;
+
+// How fast the game runs relative to normal. Multiplied into every velocity
+// and acceleration, so 0.0 pauses, values below 1.0 slow the game down and
+// values above 1.0 fast-forward it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        TimeScale(1.0)
+    }
+}
+
+impl TimeScale {
+    pub fn is_paused(&self) -> bool {
+        self.0 <= 0.0
+    }
+
+    // The seconds of game time a tick moves things by.
+    pub fn delta(&self) -> f32 {
+        self.0 / PHYSICS_FPS as f32
+    }
+}
+
+// How fast the world scrolls past the bird relative to the difficulty's
+// speed: the scenery, the floor, the pipes and everything between them.
+// Multiplied in on top of the time scale, so the world can stand still
+// while the bird and the particles go on moving.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollSpeed(pub f32);
+
+impl Default for ScrollSpeed {
+    fn default() -> Self {
+        ScrollSpeed(1.0)
+    }
+}
+
+// A seeded run: obstacles come from this generator instead of the thread
+// RNG, so every attempt at the same seed sees the same pipes.
+#[derive(Clone)]
+pub struct Challenge {
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl Challenge {
+    pub fn new(seed: u64) -> Self {
+        Challenge {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Direction {
+    pub jump: bool,
+    pub release: bool,
+    // Held drift keys; only used in drift mode.
+    pub left: bool,
+    pub right: bool,
+}
+
+impl Direction {
+    pub fn new() -> Self {
+        Direction {
+            jump: false,
+            release: true,
+            left: false,
+            right: false,
+        }
+    }
+}
+
+// Drift mode: left and right accelerate the bird backward and forward
+// within `min_x..max_x`, and it coasts to a stop when neither is held.
+#[derive(Clone, Copy, Debug)]
+pub struct Drift {
+    pub accel: f32,
+    pub friction: f32,
+    pub max_speed: f32,
+    pub min_x: f32,
+    pub max_x: f32,
+}
+
+impl Default for Drift {
+    fn default() -> Self {
+        Drift {
+            accel: 540.0,
+            friction: 180.0,
+            max_speed: 180.0,
+            min_x: 20.0,
+            // Clear of the right edge on a narrow screen.
+            max_x: (engine::screen_width() - 80.0).min(460.0),
+        }
+    }
+}
+
+// Race mode: two birds fly the same pipes, the first on Space and the
+// second on Up or a controller. A bird that crashes drops out with the
+// score it had, and the run goes on until the last one crashes too.
+#[derive(Clone, Copy, Default)]
+pub struct Race {
+    // The second player's controls; the first player's are `Direction`.
+    pub second: Direction,
+    // The score each bird crashed on, indexed by `PlayerId`, while the other
+    // flies on.
+    pub crashed: [Option<i32>; RACERS],
+    // The last bird flying, once the other crashed.
+    pub winner: Option<usize>,
+}
+
+pub const RACERS: usize = 2;
+
+impl Race {
+    pub fn flying(&self, id: usize) -> bool {
+        self.crashed[id].is_none()
+    }
+
+    // Bird `id`'s score with the run at `score`.
+    pub fn score(&self, id: usize, score: i32) -> i32 {
+        self.crashed[id].unwrap_or(score)
+    }
+
+    // Takes bird `id` out of the race at `score`. Returns whether it was
+    // the last one flying.
+    pub fn crash(&mut self, id: usize, score: i32) -> bool {
+        self.crashed[id] = Some(score);
+        let flying: Vec<usize> = (0..RACERS).filter(|&n| self.flying(n)).collect();
+        if let [last] = flying[..] {
+            self.winner = Some(last);
+        }
+        flying.is_empty()
+    }
+
+    // How the race ended, for the game over screen.
+    pub fn result(&self, score: i32) -> String {
+        let (first, second) = (self.score(0, score), self.score(1, score));
+        match self.winner {
+            Some(winner) => lang::fill("player_wins", &[&(winner + 1), &first, &second]),
+            None => lang::fill("both_crashed", &[&first, &second]),
+        }
+    }
+}
+
+// Wind, a custom game rule: gusts blow the bird up and down, turning
+// around every `wavelength / 2` pixels of the run so they're the same on
+// every attempt at a seed.
+#[derive(Clone, Copy, Debug)]
+pub struct Wind {
+    // Pixels a second at the strongest.
+    pub strength: f32,
+    pub wavelength: f32,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Wind {
+            strength: 48.0,
+            wavelength: 1800.0,
+        }
+    }
+}
+
+impl Wind {
+    // How fast the bird is blown down at `distance`, up if negative.
+    pub fn push(&self, distance: f32) -> f32 {
+        self.strength * (std::f32::consts::TAU * distance / self.wavelength).sin()
+    }
+}
+
+// The get ready screen before a run: a countdown, after which the first
+// flap starts the run. The bird hovers meanwhile, see `IdleBobSystem`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GetReady {
+    pub ticks: u32,
+}
+
+pub const COUNTDOWN_TICKS: u32 = 180;
+
+impl GetReady {
+    // What the countdown shows, 3, 2 and then 1, or None once it's over.
+    pub fn countdown(&self) -> Option<u32> {
+        COUNTDOWN_TICKS
+            .checked_sub(self.ticks)
+            .filter(|&left| left > 0)
+            .map(|left| (left * 3).div_ceil(COUNTDOWN_TICKS))
+    }
+}
+
+// The bird's fall after a crash, see `DyingSystem`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeathFall {
+    // In radians, turning nose down as it drops.
+    pub rotation: f32,
+}
+
+// Ghost mode, an accessibility option: hitting an obstacle flashes the
+// screen, plays the crash and freezes the score for a second, but the run
+// goes on, and a pipe that was hit breaks apart. Obstacles can't be hit
+// again while the score is frozen.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ghost {
+    // Ticks left before the score counts again.
+    pub freeze: u32,
+    pub hits: u32,
+}
+
+pub const GHOST_FREEZE_TICKS: u32 = PHYSICS_FPS;
+
+// Practice mode, for learning the physics: nothing the bird touches ends
+// the run or does anything else, it's only counted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Practice {
+    // Times the bird ran into something.
+    pub hits: u32,
+    // Whether it's touching something this tick.
+    pub touching: bool,
+}
+
+// What a run is played for, picked on the title screen. Besides a crash,
+// time attack runs end when the clock runs out and target runs once the
+// score reaches the target, see `RulesSystem`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Endless,
+    // As many pipes as possible in `TIME_ATTACK_TICKS`.
+    TimeAttack,
+    // `TARGET_SCORE` points, with a crash losing.
+    Target,
+}
+
+pub const TIME_ATTACK_TICKS: i32 = 60 * PHYSICS_FPS as i32;
+pub const TARGET_SCORE: i32 = 30;
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [GameMode::Endless, GameMode::TimeAttack, GameMode::Target];
+
+    // The one after this on the title screen, wrapping around.
+    pub fn next(self) -> Self {
+        let index = GameMode::ALL.iter().position(|&mode| mode == self);
+        GameMode::ALL[index.map_or(0, |i| (i + 1) % GameMode::ALL.len())]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Endless => lang::tr("mode_endless"),
+            GameMode::TimeAttack => lang::tr("mode_time_attack"),
+            GameMode::Target => lang::tr("mode_target"),
+        }
+    }
+
+    // Added to the mode the best scores are kept under, so each game mode
+    // keeps its own. None for endless, which keeps the plain ones.
+    pub fn suffix(self) -> Option<&'static str> {
+        match self {
+            GameMode::Endless => None,
+            GameMode::TimeAttack => Some("time-attack"),
+            GameMode::Target => Some("target"),
+        }
+    }
+
+    // What's left to go, for the HUD.
+    pub fn goal(self, game: &Game) -> Option<String> {
+        match self {
+            GameMode::Endless => None,
+            GameMode::TimeAttack => {
+                let seconds = (TIME_ATTACK_TICKS - game.frames).max(0) as u32 / PHYSICS_FPS;
+                let clock = format!("{}:{:02}", seconds / 60, seconds % 60);
+                Some(lang::fill("time_left", &[&clock]))
+            }
+            GameMode::Target => Some(lang::fill("to_go", &[&(TARGET_SCORE - game.score).max(0)])),
+        }
+    }
+
+    // How the run went, for the game over screen.
+    pub fn result(self, game: &Game) -> Option<String> {
+        match (self, game.cleared) {
+            (GameMode::Endless, _) => None,
+            (GameMode::TimeAttack, true) => Some(lang::fill("times_up", &[&game.score])),
+            (GameMode::TimeAttack, false) => Some(lang::tr("crashed_in_time").to_string()),
+            (GameMode::Target, true) => {
+                let seconds = format!("{:.1}", game.frames as f32 / PHYSICS_FPS as f32);
+                Some(lang::fill("target_reached", &[&seconds]))
+            }
+            (GameMode::Target, false) => {
+                Some(lang::fill("target_short", &[&(TARGET_SCORE - game.score)]))
+            }
+        }
+    }
+}
+
+// Lives mode: a run starts with this many lives and a hit only ends it on
+// the last one. Any other hit costs a life, puts the bird back in the
+// middle of the gap it hit and leaves it untouchable, blinking, for
+// `INVULNERABLE_TICKS`.
+#[derive(Clone, Copy, Debug)]
+pub struct Lives(pub u32);
+
+pub const DEFAULT_LIVES: u32 = 3;
+pub const INVULNERABLE_TICKS: u32 = 2 * PHYSICS_FPS;
+
+// The gap telegraph assist: the middle of the next pipe pair's gap, worked
+// out before it comes on screen so an arrow at the right edge can point at
+// it. None when a cave comes next or the pipes aren't seeded.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Telegraph {
+    pub next: Option<f32>,
+}
+
+// How the world is looked at, see `CameraSystem`: shifted by `offset` as a
+// hit knocks the camera off its rest, and drawn `zoom` times bigger about
+// `focus`. The UI stays put. Only interactive runs outside low power mode
+// have a camera.
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub offset: Vec2,
+    // Ticks of shaking left.
+    pub shake: u32,
+    // Never below 1, so the world always fills the screen.
+    pub zoom: f32,
+    pub focus: Vec2,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            offset: Vec2::ZERO,
+            shake: 0,
+            zoom: 1.0,
+            focus: Vec2::ZERO,
+        }
+    }
+}
+
+impl Camera {
+    // Moves a world draw command to where the camera shows it.
+    pub fn view(&self, cmd: &mut DrawCmd) {
+        if self.zoom != 1.0 {
+            cmd.zoom(self.focus, self.zoom);
+        }
+        cmd.shift(self.offset);
+    }
+
+    // Zooms in by `zoom` about `focus` at once, to ease back out.
+    pub fn punch(&mut self, zoom: f32, focus: Vec2) {
+        self.zoom = zoom.max(1.0);
+        self.focus = focus;
+    }
+}
+
+pub const SHAKE_TICKS: u32 = 24;
+// How far a crash zooms in on the bird, and a point the whole screen.
+pub const CRASH_ZOOM: f32 = 1.12;
+pub const PULSE_ZOOM: f32 = 1.03;
+
+// Turns on feathers and dust, see `ParticleSystem`: present in interactive
+// runs outside low power mode, so headless runs make no particles.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParticleEffects;
+
+// How hard the run is right now, following the tuning's curves as the score
+// rises. Updated every tick by `DifficultySystem`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Difficulty {
+    // Scroll speed of the obstacles and the floor.
+    pub speed: f32,
+    // Horizontal distance between two pipe slots.
+    pub spacing: f32,
+    // Height of the opening between a pipe pair.
+    pub gap: f32,
+}
+
+impl Difficulty {
+    pub fn at(tuning: &Tuning, score: i32) -> Self {
+        Difficulty {
+            speed: tuning.scroll_speed * tuning.speed_curve.at(score),
+            spacing: tuning.spacing_curve.at(score),
+            gap: tuning.gap_curve.at(score),
+        }
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::at(&Tuning::default(), 0)
+    }
+}
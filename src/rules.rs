@@ -0,0 +1,417 @@
+use crate::engine::{self, Color, DrawCmd, Vec2};
+use crate::lang;
+use crate::tuning::Tuning;
+use crate::{Lives, Wind, PHYSICS_FPS};
+use ggez::Context;
+use serde::{Deserialize, Serialize};
+use specs::*;
+use std::fs;
+use std::path::PathBuf;
+
+// How far each rule goes, and how far a press of Left or Right moves it.
+const GRAVITY: (f32, f32, f32) = (540.0, 2160.0, 180.0);
+// Codes and older custom games files give gravity in pixels a tick squared,
+// from before speeds were per second.
+const TICKS_SQUARED: f32 = (PHYSICS_FPS * PHYSICS_FPS) as f32;
+const GAP: (f32, f32, f32) = (160.0, 320.0, 10.0);
+const SPEED_RAMP: (f32, f32, f32) = (1.0, 2.0, 0.1);
+const MAX_LIVES: u32 = 9;
+// Gaps narrow by this much over the run, like the default tuning's.
+const GAP_NARROWING: f32 = 30.0;
+const MAX_NAME: usize = 16;
+
+// A custom game: a few rules mixed and matched over the tuning, under a
+// name of the player's choosing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rules {
+    pub name: String,
+    pub gravity: f32,
+    // The gap a run starts with.
+    pub gap: f32,
+    // How much faster the pipes get by the end of the speed curve.
+    pub speed_ramp: f32,
+    // 1 for the usual single life.
+    pub lives: u32,
+    pub wind: bool,
+    pub moving_pipes: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        let tuning = Tuning::default();
+        Rules {
+            name: "custom".to_string(),
+            gravity: tuning.gravity,
+            gap: tuning.gap_curve.start,
+            speed_ramp: tuning.speed_curve.end,
+            lives: 1,
+            wind: false,
+            moving_pipes: true,
+        }
+    }
+}
+
+// Names go into the mode after a dash, so they can't have one of their own.
+fn name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn step(value: f32, (min, max, by): (f32, f32, f32), steps: i32) -> f32 {
+    // Rounded to the step, so repeated presses don't drift.
+    let stepped = ((value - min) / by).round() + steps as f32;
+    (min + stepped * by).clamp(min, max)
+}
+
+impl Rules {
+    // The rules as a short code to pass on, e.g. `30-240-13-3-1-windy`:
+    // gravity in hundredths of a pixel a tick squared, the gap, the speed
+    // ramp in tenths, the lives, wind plus two for moving pipes, and the
+    // name.
+    pub fn code(&self) -> String {
+        format!(
+            "{}-{}-{}-{}-{}-{}",
+            (self.gravity / TICKS_SQUARED * 100.0).round(),
+            self.gap.round(),
+            (self.speed_ramp * 10.0).round(),
+            self.lives,
+            self.wind as u32 + 2 * self.moving_pipes as u32,
+            self.name
+        )
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, String> {
+        let invalid = || format!("{} isn't a custom game code", code);
+        let fields: Vec<&str> = code.trim().splitn(6, '-').collect();
+        let number = |n: usize| fields[n].parse::<f32>().map_err(|_| invalid());
+        if fields.len() != 6 || !fields[5].chars().any(name_char) {
+            return Err(invalid());
+        }
+        let flags = fields[4].parse::<u32>().map_err(|_| invalid())?;
+        Ok(Rules {
+            name: fields[5]
+                .chars()
+                .filter(|&c| name_char(c))
+                .take(MAX_NAME)
+                .collect(),
+            gravity: step(number(0)? / 100.0 * TICKS_SQUARED, GRAVITY, 0),
+            gap: step(number(1)?, GAP, 0),
+            speed_ramp: step(number(2)? / 10.0, SPEED_RAMP, 0),
+            lives: fields[3]
+                .parse::<u32>()
+                .map_err(|_| invalid())?
+                .clamp(1, MAX_LIVES),
+            wind: flags & 1 != 0,
+            moving_pipes: flags & 2 != 0,
+        })
+    }
+
+    // Sets the rules' fields of `tuning`, leaving the rest.
+    pub fn apply(&self, tuning: &mut Tuning) {
+        tuning.gravity = self.gravity;
+        tuning.gap_curve.start = self.gap;
+        tuning.gap_curve.end = self.gap - GAP_NARROWING;
+        tuning.speed_curve.end = self.speed_ramp;
+        tuning.moving_pipes.from_score = if self.moving_pipes {
+            Tuning::default().moving_pipes.from_score
+        } else {
+            i32::MAX
+        };
+    }
+
+    // Puts the rules' lives and wind into `world`, or takes them out.
+    pub fn insert(&self, world: &mut World) {
+        if self.lives > 1 {
+            world.insert(Lives(self.lives));
+        } else {
+            world.remove::<Lives>();
+        }
+        if self.wind {
+            world.insert(Wind::default());
+        } else {
+            world.remove::<Wind>();
+        }
+    }
+
+    // The key best scores for these rules are kept under.
+    pub fn mode(&self) -> String {
+        format!("custom-{}", self.name)
+    }
+}
+
+// Every saved custom game, persisted as JSON in the user data directory.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleBook {
+    pub presets: Vec<Rules>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+pub fn rule_book_path(ctx: &Context) -> PathBuf {
+    ctx.fs.user_data_dir().join("custom_games.json")
+}
+
+impl RuleBook {
+    // Missing or unreadable files mean no custom games yet.
+    pub fn load(path: PathBuf) -> Self {
+        let mut book = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                println!(
+                    "ignoring corrupt custom games file {}: {}",
+                    path.display(),
+                    e
+                );
+                RuleBook::default()
+            }),
+            Err(_) => RuleBook::default(),
+        };
+        for rules in &mut book.presets {
+            if rules.gravity < 1.0 {
+                rules.gravity *= TICKS_SQUARED;
+            }
+        }
+        book.path = Some(path);
+        book
+    }
+
+    pub fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string(self)?;
+                fs::write(path, json)
+            });
+        if let Err(e) = result {
+            println!("can't save custom games to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rules> {
+        self.presets.iter().find(|rules| rules.name == name)
+    }
+
+    // Saves `rules`, replacing any preset of the same name.
+    pub fn keep(&mut self, rules: Rules) {
+        match self.presets.iter_mut().find(|p| p.name == rules.name) {
+            Some(preset) => *preset = rules,
+            None => self.presets.push(rules),
+        }
+    }
+
+    // A saved preset by name, or the rules in a code, which are saved too.
+    pub fn find_or_import(&mut self, name_or_code: &str) -> Result<Rules, String> {
+        if let Some(rules) = self.get(name_or_code) {
+            return Ok(rules.clone());
+        }
+        let rules = Rules::from_code(name_or_code)
+            .map_err(|_| format!("no custom game or code {}", name_or_code))?;
+        self.keep(rules.clone());
+        self.save();
+        Ok(rules)
+    }
+}
+
+const ROWS: [&str; 7] = [
+    "rule_name",
+    "rule_gravity",
+    "rule_gap",
+    "rule_speed_ramp",
+    "rule_lives",
+    "rule_wind",
+    "rule_moving_pipes",
+];
+
+// What a key does in the custom game screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Backspace,
+    Delete,
+    Play,
+}
+
+// The custom game screen on the title screen: Up and Down pick a rule,
+// Left and Right change it. On the name row they go through the saved
+// presets instead, and typing renames the one shown.
+#[derive(Default)]
+pub struct RulesBuilder {
+    pub open: bool,
+    rules: Rules,
+    row: usize,
+}
+
+impl RulesBuilder {
+    pub fn show(&mut self, book: &RuleBook) {
+        self.open = true;
+        self.row = 0;
+        self.rules = book.presets.first().cloned().unwrap_or_default();
+    }
+
+    // Applies a key, saving to `book` as the rules change. Returns the
+    // rules to play if they were picked.
+    pub fn press(&mut self, book: &mut RuleBook, key: Key) -> Option<Rules> {
+        let steps = match key {
+            Key::Up => {
+                self.row = (self.row + ROWS.len() - 1) % ROWS.len();
+                return None;
+            }
+            Key::Down => {
+                self.row = (self.row + 1) % ROWS.len();
+                return None;
+            }
+            Key::Play => {
+                book.keep(self.rules.clone());
+                book.save();
+                return Some(self.rules.clone());
+            }
+            Key::Backspace => {
+                if self.row == 0 {
+                    self.rules.name.pop();
+                }
+                return None;
+            }
+            Key::Delete => {
+                book.presets.retain(|p| p.name != self.rules.name);
+                book.save();
+                self.rules = book.presets.first().cloned().unwrap_or_default();
+                return None;
+            }
+            Key::Left => -1,
+            Key::Right => 1,
+        };
+        let rules = &mut self.rules;
+        match self.row {
+            0 => self.cycle(book, steps),
+            1 => rules.gravity = step(rules.gravity, GRAVITY, steps),
+            2 => rules.gap = step(rules.gap, GAP, steps),
+            3 => rules.speed_ramp = step(rules.speed_ramp, SPEED_RAMP, steps),
+            4 => rules.lives = (rules.lives as i32 + steps).clamp(1, MAX_LIVES as i32) as u32,
+            5 => rules.wind = !rules.wind,
+            _ => rules.moving_pipes = !rules.moving_pipes,
+        }
+        None
+    }
+
+    // Goes through the saved presets and then a new one.
+    fn cycle(&mut self, book: &RuleBook, steps: i32) {
+        let count = book.presets.len() as i32 + 1;
+        let current = book
+            .presets
+            .iter()
+            .position(|p| p.name == self.rules.name)
+            .map_or(count - 1, |n| n as i32);
+        let next = (current + steps).rem_euclid(count) as usize;
+        self.rules = match book.presets.get(next) {
+            Some(preset) => preset.clone(),
+            None => Rules {
+                name: format!("custom{}", count),
+                ..Rules::default()
+            },
+        };
+    }
+
+    // Typed characters go into the name while it's picked.
+    pub fn type_char(&mut self, c: char) {
+        let name = &mut self.rules.name;
+        if self.row == 0 && name_char(c) && name.chars().count() < MAX_NAME {
+            name.push(c);
+        }
+    }
+
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>) {
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let yellow = Color::new(1.0, 0.9, 0.2, 1.0);
+        let center_x = engine::screen_width() / 2.0;
+        cmds.push(DrawCmd::Rect {
+            rect: engine::Rect::new(0.0, 0.0, engine::screen_width(), engine::SCREEN_HEIGHT),
+            color: Color::new(0.0, 0.0, 0.0, 0.75),
+        });
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("custom_game"),
+            Vec2::new(center_x, 40.0),
+            50.0,
+            yellow,
+        ));
+
+        let on_off = |on: bool| lang::tr(if on { "on" } else { "off" }).to_string();
+        let rules = &self.rules;
+        let values = [
+            rules.name.clone(),
+            format!("{:.0}", rules.gravity),
+            format!("{}", rules.gap),
+            format!("x{:.1}", rules.speed_ramp),
+            rules.lives.to_string(),
+            on_off(rules.wind),
+            on_off(rules.moving_pipes),
+        ];
+        for (n, (row, value)) in ROWS.iter().zip(values.iter()).enumerate() {
+            let color = if n == self.row { yellow } else { white };
+            cmds.push(DrawCmd::centered_text(
+                format!("{}   < {} >", lang::tr(row), value),
+                Vec2::new(center_x, 120.0 + 44.0 * n as f32),
+                28.0,
+                color,
+            ));
+        }
+
+        cmds.push(DrawCmd::centered_text(
+            lang::fill("rules_code", &[&rules.code()]),
+            Vec2::new(center_x, 450.0),
+            22.0,
+            white,
+        ));
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("rules_hint"),
+            Vec2::new(center_x, 525.0),
+            18.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("rules_keys_hint"),
+            Vec2::new(center_x, 550.0),
+            18.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_carry_the_rules() {
+        let rules = Rules {
+            name: "windy_day".to_string(),
+            gravity: 1620.0,
+            gap: 200.0,
+            speed_ramp: 1.6,
+            lives: 3,
+            wind: true,
+            moving_pipes: false,
+        };
+        assert_eq!(rules.code(), "45-200-16-3-1-windy_day");
+        assert_eq!(
+            Rules::from_code(&rules.code()).unwrap().code(),
+            rules.code()
+        );
+        assert_eq!(Rules::from_code("45-200-16-3-1-a-b").unwrap().name, "ab");
+        assert!(Rules::from_code("45-200-16").is_err());
+        assert_eq!(Rules::from_code("999-0-16-0-2-x").unwrap().gap, GAP.0);
+
+        let mut tuning = Tuning::default();
+        rules.apply(&mut tuning);
+        assert_eq!(tuning.gap_curve.end, 170.0);
+        assert_eq!(tuning.moving_pipes.from_score, i32::MAX);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::caves::Caves;
+use crate::collectibles::Collectibles;
+use crate::hazards::Hazards;
+use crate::spacing::Spacing;
+use crate::tuning::Tuning;
+use crate::{
+    empty_world, BackgroundTag, Challenge, Collectible, CollisionBox, Difficulty, Direction, Game,
+    GameState, Hitbox, ObstacleTag, PlayerTag, Position, VerticalOscillator,
+};
+use specs::*;
+
+// Everything needed to resume a headless run from an exact tick: the game
+// resources, the generator state and every obstacle, pickup and the bird.
+#[derive(Clone)]
+pub struct SaveState {
+    game: Game,
+    state: GameState,
+    direction: Direction,
+    // The speed obstacles were moving at, so they keep up with later
+    // changes to it.
+    difficulty: Difficulty,
+    challenge: Option<Challenge>,
+    bird: Vec<(Position, CollisionBox)>,
+    caves: Caves,
+    spacing: Spacing,
+    hazards: Hazards,
+    obstacles: Vec<SavedObstacle>,
+    collectibles: Option<Collectibles>,
+    pickups: Vec<SavedPickup>,
+}
+
+type SavedObstacle = (
+    Position,
+    BackgroundTag,
+    ObstacleTag,
+    Option<CollisionBox>,
+    Option<VerticalOscillator>,
+);
+
+type SavedPickup = (
+    Position,
+    BackgroundTag,
+    Collectible,
+    CollisionBox,
+    Option<VerticalOscillator>,
+);
+
+impl SaveState {
+    pub fn capture(world: &World) -> Self {
+        let positions = world.read_storage::<Position>();
+        let boxes = world.read_storage::<CollisionBox>();
+        let backgrounds = world.read_storage::<BackgroundTag>();
+        let obstacles = world.read_storage::<ObstacleTag>();
+        let players = world.read_storage::<PlayerTag>();
+        let oscillators = world.read_storage::<VerticalOscillator>();
+        let items = world.read_storage::<Collectible>();
+
+        SaveState {
+            game: (*world.read_resource::<Game>()).clone(),
+            state: *world.read_resource::<GameState>(),
+            direction: *world.read_resource::<Direction>(),
+            difficulty: *world.read_resource::<Difficulty>(),
+            challenge: world.try_fetch::<Challenge>().map(|c| (*c).clone()),
+            bird: (&positions, &boxes, &players)
+                .join()
+                .map(|(pos, coll_box, _)| (pos.clone(), *coll_box))
+                .collect(),
+            caves: (*world.read_resource::<Caves>()).clone(),
+            spacing: (*world.read_resource::<Spacing>()).clone(),
+            hazards: (*world.read_resource::<Hazards>()).clone(),
+            obstacles: (
+                &positions,
+                &backgrounds,
+                &obstacles,
+                boxes.maybe(),
+                oscillators.maybe(),
+            )
+                .join()
+                .map(|(pos, bg, obs, coll_box, osc)| {
+                    (
+                        pos.clone(),
+                        bg.clone(),
+                        obs.clone(),
+                        coll_box.copied(),
+                        osc.copied(),
+                    )
+                })
+                .collect(),
+            collectibles: world.try_fetch::<Collectibles>().map(|c| (*c).clone()),
+            pickups: (
+                &positions,
+                &backgrounds,
+                &items,
+                &boxes,
+                oscillators.maybe(),
+            )
+                .join()
+                .map(|(pos, bg, item, coll_box, osc)| {
+                    (pos.clone(), bg.clone(), *item, *coll_box, osc.copied())
+                })
+                .collect(),
+        }
+    }
+
+    // Builds a new world without sprites in the captured state.
+    pub fn restore(&self, tuning: &Tuning) -> World {
+        let mut world = empty_world(tuning.clone());
+        for (pos, coll_box) in &self.bird {
+            let mut builder = world
+                .create_entity()
+                .with(pos.clone())
+                .with(PlayerTag)
+                .with(*coll_box);
+            if let Some(hitbox) = Hitbox::bird(tuning.hitbox) {
+                builder = builder.with(hitbox);
+            }
+            builder.build();
+        }
+        self.restore_into(&mut world);
+
+        world
+    }
+
+    // Rewinds an existing world to the captured state. Obstacles and
+    // pickups are replaced, while the bird and the scenery keep their
+    // entities and sprites.
+    pub fn restore_into(&self, world: &mut World) {
+        {
+            let entities = world.entities();
+            let obstacles = world.read_storage::<ObstacleTag>();
+            let items = world.read_storage::<Collectible>();
+            for (entity, _) in (&entities, obstacles.mask() | items.mask()).join() {
+                entities.delete(entity).unwrap();
+            }
+        }
+        world.maintain();
+
+        for (pos, bg, obs, coll_box, osc) in &self.obstacles {
+            let mut builder = world
+                .create_entity()
+                .with(pos.clone())
+                .with(bg.clone())
+                .with(obs.clone());
+            if let Some(coll_box) = coll_box {
+                builder = builder.with(*coll_box);
+            }
+            if let Some(osc) = osc {
+                builder = builder.with(*osc);
+            }
+            builder.build();
+        }
+        for (pos, bg, item, coll_box, osc) in &self.pickups {
+            let mut builder = world
+                .create_entity()
+                .with(pos.clone())
+                .with(bg.clone())
+                .with(*item)
+                .with(*coll_box);
+            if let Some(osc) = osc {
+                builder = builder.with(*osc);
+            }
+            builder.build();
+        }
+        {
+            let mut positions = world.write_storage::<Position>();
+            let mut boxes = world.write_storage::<CollisionBox>();
+            let players = world.read_storage::<PlayerTag>();
+            for ((pos, coll_box, _), (saved_pos, saved_box)) in
+                (&mut positions, &mut boxes, &players)
+                    .join()
+                    .zip(&self.bird)
+            {
+                *pos = saved_pos.clone();
+                *coll_box = *saved_box;
+            }
+        }
+
+        world.insert(self.game.clone());
+        world.insert(self.state);
+        world.insert(self.direction);
+        world.insert(self.difficulty);
+        world.insert(self.caves.clone());
+        world.insert(self.spacing.clone());
+        world.insert(self.hazards.clone());
+        if let Some(challenge) = &self.challenge {
+            world.insert(challenge.clone());
+        }
+        if let Some(collectibles) = &self.collectibles {
+            world.insert(collectibles.clone());
+        }
+    }
+}
@@ -0,0 +1,210 @@
+// The order systems run in each tick, built into specs dispatchers.
+//
+// Systems added with `then` run one after the other in the order they're
+// added, each needing what the last one did. Those added with `alongside`
+// only wait for the systems they name, and otherwise run in parallel with
+// whatever doesn't touch the same resources and storages. A new system only
+// needs adding here.
+
+use crate::best_run::{GhostPlaybackSystem, ReplayRecorderSystem};
+use crate::hud::ScorePopSystem;
+use crate::profiler::{Scope, SystemTimes, Timed};
+use crate::replay_file::{InputLogSystem, InputReplaySystem};
+use crate::speedrun::SpeedrunSystem;
+use crate::tas::TasSystem;
+use crate::telemetry::TelemetrySystem;
+use crate::tips::TrailSystem;
+use crate::tournament::ReplaySystem;
+use crate::{
+    AnimationSystem, CameraSystem, CleanupSystem, CollisionSystem, DebrisSystem, DifficultySystem,
+    GameOverSystem, HazardSystem, MovementSystem, ObstacleSpawnSystem, ParticleSystem,
+    PipeCounterSystem, RulesSystem, ScoringSystem, ScrollSystem,
+};
+use specs::rayon::{ThreadPool, ThreadPoolBuilder};
+use specs::{Dispatcher, DispatcherBuilder, System};
+use std::sync::{Arc, OnceLock};
+
+pub(crate) struct Schedule {
+    builder: DispatcherBuilder<'static, 'static>,
+    last: Option<&'static str>,
+    // Where the profiler gets each system's time, if it's watching.
+    times: Option<Arc<SystemTimes>>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Schedule {
+            builder: DispatcherBuilder::new(),
+            last: None,
+            times: None,
+        }
+    }
+
+    pub fn timed(times: Arc<SystemTimes>) -> Self {
+        Schedule {
+            times: Some(times),
+            ..Schedule::new()
+        }
+    }
+
+    pub fn then<S>(self, name: &'static str, scope: Scope, system: S) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        let last: Vec<&str> = self.last.into_iter().collect();
+        let mut schedule = self.alongside(name, scope, system, &last);
+        schedule.last = Some(name);
+        schedule
+    }
+
+    pub fn alongside<S>(mut self, name: &str, scope: Scope, system: S, after: &[&str]) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        match &self.times {
+            Some(times) => {
+                let timed = Timed::new(system, scope, times.clone());
+                self.builder.add(timed, name, after);
+            }
+            None => self.builder.add(system, name, after),
+        }
+        self
+    }
+
+    pub fn build(self) -> Dispatcher<'static, 'static> {
+        self.builder.build()
+    }
+
+    // For dispatchers only ever run one system at a time, sharing a single
+    // thread instead of starting a pool each.
+    pub fn build_sequential(self) -> Dispatcher<'static, 'static> {
+        static POOL: OnceLock<Arc<ThreadPool>> = OnceLock::new();
+        let pool = POOL.get_or_init(|| {
+            let pool = ThreadPoolBuilder::new().num_threads(1).build();
+            Arc::new(pool.expect("can't start a thread"))
+        });
+        self.builder.with_pool(pool.clone()).build()
+    }
+}
+
+// What every run goes through, in the game and headless: difficulty,
+// scrolling and movement, obstacle and hazard spawning and cleanup, then
+// collision and ending the run on a death, debris, scoring, the game mode's
+// goal and telemetry.
+pub(crate) fn gameplay(schedule: Schedule) -> Schedule {
+    schedule
+        .then("difficulty", Scope::Systems, DifficultySystem)
+        .then("scroll", Scope::Movement, ScrollSystem)
+        .then("movement", Scope::Movement, MovementSystem)
+        .then("obstacle_spawn", Scope::Spawn, ObstacleSpawnSystem)
+        .then("hazard", Scope::Spawn, HazardSystem)
+        .then("cleanup", Scope::Spawn, CleanupSystem)
+        .then("collision", Scope::Collision, CollisionSystem)
+        .then("game_over", Scope::Collision, GameOverSystem)
+        .then("debris", Scope::Systems, DebrisSystem)
+        .then("pipe_counter", Scope::Systems, PipeCounterSystem)
+        .then("scoring", Scope::Systems, ScoringSystem)
+        .then("rules", Scope::Systems, RulesSystem)
+        .then("telemetry", Scope::Systems, TelemetrySystem)
+}
+
+// A tick of a run in the game. The flap comes in first from a script or a
+// replay, and is recorded, before the gameplay. The animation, particles,
+// camera, tip trail and score pop only show it.
+pub(crate) fn playing(times: Arc<SystemTimes>) -> Dispatcher<'static, 'static> {
+    let input = Schedule::timed(times)
+        .then("tas", Scope::Systems, TasSystem)
+        .then("input_replay", Scope::Systems, InputReplaySystem)
+        .then("speedrun", Scope::Systems, SpeedrunSystem)
+        .then("replay", Scope::Systems, ReplaySystem)
+        .then("replay_recorder", Scope::Systems, ReplayRecorderSystem)
+        .then("input_log", Scope::Systems, InputLogSystem)
+        .then("ghost_playback", Scope::Systems, GhostPlaybackSystem);
+    gameplay(input)
+        // A replay can change the time scale.
+        .alongside(
+            "animation",
+            Scope::Systems,
+            AnimationSystem::default(),
+            &["input_replay"],
+        )
+        .alongside("particles", Scope::Systems, ParticleSystem, &["debris"])
+        .alongside("camera", Scope::Systems, CameraSystem, &["score_pop"])
+        .alongside("trail", Scope::Systems, TrailSystem, &["collision"])
+        .alongside(
+            "score_pop",
+            Scope::Systems,
+            ScorePopSystem,
+            &["pipe_counter"],
+        )
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Action, BirdPolicy, Observation, RulePolicy};
+    use crate::simulate::tick_headless;
+    use crate::tuning::Tuning;
+    use crate::{build_world, Challenge, Direction, Game, GameState, Position};
+    use specs::{Join, RunNow, World, WorldExt};
+
+    fn seeded() -> World {
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(Challenge::new(7));
+        world
+    }
+
+    // As the rule bot would.
+    fn flap(world: &World) {
+        let action = Observation::from_world(world).map(|obs| RulePolicy.act(obs));
+        if action == Some(Action::Flap) {
+            *world.write_resource::<Direction>() = Direction {
+                jump: true,
+                ..Direction::new()
+            };
+        }
+    }
+
+    #[test]
+    fn dispatching_plays_out_like_running_each_system_in_turn() {
+        let (mut dispatched, mut by_hand) = (seeded(), seeded());
+        for _ in 0..1200 {
+            flap(&dispatched);
+            flap(&by_hand);
+            tick_headless(&mut dispatched);
+            if *by_hand.read_resource::<GameState>() == GameState::Playing {
+                by_hand.write_resource::<Game>().frames += 1;
+                DifficultySystem.run_now(&by_hand);
+                ScrollSystem.run_now(&by_hand);
+                MovementSystem.run_now(&by_hand);
+                ObstacleSpawnSystem.run_now(&by_hand);
+                HazardSystem.run_now(&by_hand);
+                CleanupSystem.run_now(&by_hand);
+                CollisionSystem.run_now(&by_hand);
+                GameOverSystem.run_now(&by_hand);
+                DebrisSystem.run_now(&by_hand);
+                PipeCounterSystem.run_now(&by_hand);
+                ScoringSystem.run_now(&by_hand);
+                RulesSystem.run_now(&by_hand);
+                TelemetrySystem.run_now(&by_hand);
+                by_hand.maintain();
+            }
+        }
+
+        let positions = |world: &World| -> Vec<(f32, f32)> {
+            let positions = world.read_storage::<Position>();
+            positions
+                .join()
+                .map(|p| (p.position.x, p.position.y))
+                .collect()
+        };
+        let game = |world: &World| {
+            let game = world.read_resource::<Game>();
+            (game.frames, game.score, game.death.is_some())
+        };
+        assert!(game(&dispatched).1 > 0);
+        assert_eq!(game(&dispatched), game(&by_hand));
+        assert_eq!(positions(&dispatched), positions(&by_hand));
+    }
+}
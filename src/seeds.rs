@@ -0,0 +1,287 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::stats::Stats;
+use crate::tuning::Tuning;
+use crate::{
+    build_world, Challenge, CleanupSystem, CollisionBox, DifficultySystem, Game, HazardSystem,
+    MovementSystem, ObstacleSpawnSystem, ObstacleTag, PipeCounterSystem, Position, ScoringSystem,
+    ScrollSystem,
+};
+use specs::*;
+use std::collections::HashMap;
+
+// Slots shown in a seed's preview.
+const PREVIEW_SLOTS: usize = 10;
+// Gives up on a preview that takes longer than this, e.g. with a tuning
+// that barely scrolls.
+const PREVIEW_TICKS: u32 = 20_000;
+// Rows of seeds on screen at once.
+const ROWS: usize = 7;
+const ROW_HEIGHT: f32 = 64.0;
+// Previews sit right of the seeds, from here to near the screen's edge.
+const PREVIEW_X: f32 = 330.0;
+const PREVIEW_HEIGHT: f32 = 56.0;
+// Below this a preview is too squeezed to read and is left out.
+const PREVIEW_MIN_WIDTH: f32 = 120.0;
+
+// The obstacles of a seed's first slots, in world coordinates from where
+// the run starts.
+pub struct Preview {
+    boxes: Vec<(Rect, bool)>,
+    width: f32,
+}
+
+// Flies the first `PREVIEW_SLOTS` slots of `seed` with a bird that can't
+// crash, so the gaps tighten with the score like they would in a run, and
+// keeps every obstacle as it comes in. Nothing is drawn or played.
+pub fn preview(tuning: &Tuning, seed: u64) -> Preview {
+    let mut world = build_world(tuning.clone(), None);
+    world.insert(Challenge::new(seed));
+
+    // Where each entity was, as pipes come back as new ones further right.
+    let mut seen = HashMap::new();
+    let mut boxes = Vec::new();
+    let mut slots = Vec::new();
+    for _ in 0..PREVIEW_TICKS {
+        let distance = world.read_resource::<Game>().distance;
+        {
+            let entities = world.entities();
+            let obstacles = world.read_storage::<ObstacleTag>();
+            let positions = world.read_storage::<Position>();
+            let collision = world.read_storage::<CollisionBox>();
+            for (entity, obstacle, pos) in (&entities, &obstacles, &positions).join() {
+                let x = pos.position.x;
+                if seen.insert(entity, x).is_some_and(|last| x <= last) {
+                    continue;
+                }
+                if obstacle.top {
+                    slots.push(pos.position.x + distance);
+                }
+                // Cave slots are only markers, the cave itself is in
+                // segments of its own.
+                if let Some(b) = collision.get(entity) {
+                    boxes.push((
+                        Rect::new(b.origin.x + distance, b.origin.y, b.width, b.height),
+                        obstacle.cave,
+                    ));
+                }
+            }
+        }
+        if slots.len() > PREVIEW_SLOTS {
+            break;
+        }
+
+        world.write_resource::<Game>().frames += 1;
+        DifficultySystem.run_now(&world);
+        ScrollSystem.run_now(&world);
+        MovementSystem.run_now(&world);
+        ObstacleSpawnSystem.run_now(&world);
+        HazardSystem.run_now(&world);
+        CleanupSystem.run_now(&world);
+        PipeCounterSystem.run_now(&world);
+        ScoringSystem.run_now(&world);
+        world.maintain();
+    }
+
+    slots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let start = slots.first().copied().unwrap_or(0.0);
+    let end = slots
+        .get(PREVIEW_SLOTS)
+        .copied()
+        .unwrap_or_else(|| boxes.iter().map(|(b, _)| b.x + b.w).fold(start, f32::max));
+    boxes.retain(|(b, _)| b.x < end);
+    for (b, _) in &mut boxes {
+        b.x -= start;
+    }
+    Preview {
+        boxes,
+        width: end - start,
+    }
+}
+
+impl Preview {
+    // Draws the preview shrunk to fit `area`, keeping its proportions.
+    fn draw(&self, cmds: &mut Vec<DrawCmd>, area: Rect) {
+        cmds.push(DrawCmd::Rect {
+            rect: area,
+            color: Color::new(0.45, 0.75, 0.95, 1.0),
+        });
+        let scale = (area.h / engine::SCREEN_HEIGHT).min(area.w / self.width.max(1.0));
+        for (b, cave) in &self.boxes {
+            let color = if *cave {
+                Color::new(0.45, 0.35, 0.3, 1.0)
+            } else {
+                Color::new(0.3, 0.6, 0.3, 1.0)
+            };
+            let top = b.y.max(0.0);
+            let bottom = (b.y + b.h).min(engine::SCREEN_HEIGHT);
+            if bottom <= top {
+                continue;
+            }
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(
+                    area.x + b.x * scale,
+                    area.y + top * scale,
+                    (b.w * scale).max(1.0),
+                    (bottom - top) * scale,
+                ),
+                color,
+            });
+        }
+    }
+}
+
+// What a key does in the seed browser.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Play,
+    Bookmark,
+    Share,
+}
+
+// What the game should do with the selected seed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Choice {
+    Play(u64),
+    Share(u64),
+}
+
+// A menu screen, opened with `S`, listing bookmarked seeds and then the
+// seeds of the latest runs, each with a preview of its first pipes.
+#[derive(Default)]
+pub struct SeedBrowser {
+    pub open: bool,
+    // Shown under the list, e.g. where a shared seed was saved.
+    pub message: Option<String>,
+    // Seeds and whether they're bookmarked, in the order shown.
+    entries: Vec<(u64, bool)>,
+    selected: usize,
+    previews: HashMap<u64, Preview>,
+}
+
+impl SeedBrowser {
+    pub fn show(&mut self, stats: &Stats, tuning: &Tuning) {
+        self.open = true;
+        self.message = None;
+        self.selected = 0;
+        self.refresh(stats, tuning);
+    }
+
+    fn refresh(&mut self, stats: &Stats, tuning: &Tuning) {
+        let bookmarked = stats.bookmarked_seeds.iter().map(|&seed| (seed, true));
+        let recent = stats
+            .recent_seeds
+            .iter()
+            .filter(|seed| !stats.bookmarked_seeds.contains(seed))
+            .map(|&seed| (seed, false));
+        self.entries = bookmarked.chain(recent).collect();
+        for &(seed, _) in &self.entries {
+            self.previews
+                .entry(seed)
+                .or_insert_with(|| preview(tuning, seed));
+        }
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    // Handles a key; bookmarking saves to `stats` right away. Returns what
+    // to do with a seed that was picked.
+    pub fn press(&mut self, stats: &mut Stats, tuning: &Tuning, key: Key) -> Option<Choice> {
+        let &(seed, bookmarked) = self.entries.get(self.selected)?;
+        match key {
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => self.selected = (self.selected + 1).min(self.entries.len() - 1),
+            Key::Play => return Some(Choice::Play(seed)),
+            Key::Share => return Some(Choice::Share(seed)),
+            Key::Bookmark => {
+                stats.bookmark_seed(seed, !bookmarked);
+                stats.save();
+                self.refresh(stats, tuning);
+                if let Some(n) = self.entries.iter().position(|&(s, _)| s == seed) {
+                    self.selected = n;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>) {
+        let center_x = engine::screen_width() / 2.0;
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(0.0, 0.0, engine::screen_width(), engine::SCREEN_HEIGHT),
+            color: Color::new(0.0, 0.0, 0.0, 0.75),
+        });
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("seeds"),
+            Vec2::new(center_x, 40.0),
+            50.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+
+        if self.entries.is_empty() {
+            cmds.push(DrawCmd::centered_text(
+                lang::tr("no_seeds"),
+                Vec2::new(center_x, 250.0),
+                24.0,
+                white,
+            ));
+        }
+        let first = (self.selected + 1).saturating_sub(ROWS);
+        for (row, &(seed, bookmarked)) in self.entries.iter().enumerate().skip(first).take(ROWS) {
+            let y = 90.0 + ROW_HEIGHT * (row - first) as f32;
+            if row == self.selected {
+                cmds.push(DrawCmd::Rect {
+                    rect: Rect::new(20.0, y - 4.0, engine::screen_width() - 40.0, ROW_HEIGHT),
+                    color: Color::new(1.0, 1.0, 1.0, 0.2),
+                });
+            }
+            let mark = if bookmarked { "*" } else { " " };
+            cmds.push(DrawCmd::text(
+                format!("{} {}", mark, seed),
+                Vec2::new(40.0, y + 14.0),
+                24.0,
+                white,
+            ));
+            let width = engine::screen_width() - PREVIEW_X - 34.0;
+            match self.previews.get(&seed) {
+                Some(preview) if width >= PREVIEW_MIN_WIDTH => {
+                    preview.draw(cmds, Rect::new(PREVIEW_X, y, width, PREVIEW_HEIGHT));
+                }
+                _ => (),
+            }
+        }
+
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("seeds_hint"),
+            Vec2::new(center_x, 550.0),
+            18.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+        if let Some(message) = &self.message {
+            cmds.push(DrawCmd::centered_text(
+                message.as_str(),
+                Vec2::new(center_x, 575.0),
+                18.0,
+                Color::new(1.0, 0.9, 0.2, 1.0),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews_are_the_seeds_pipes() {
+        let tuning = Tuning::default();
+        let first = preview(&tuning, 7);
+        assert!(first.boxes.iter().filter(|(_, cave)| !cave).count() >= 6);
+        assert!(first.boxes.iter().all(|(b, _)| b.x < first.width));
+
+        assert_eq!(first.boxes, preview(&tuning, 7).boxes);
+        assert_ne!(first.boxes, preview(&tuning, 8).boxes);
+    }
+}
@@ -0,0 +1,538 @@
+use crate::engine::{self, Color, DrawCmd, Vec2};
+use crate::lang;
+use crate::tuning::Preset;
+use ggez::event::Button;
+use ggez::input::keyboard::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// Keys the controls can be bound to, by the name saved for them. The
+// function keys and Escape stay as they are.
+const KEYS: [(KeyCode, &str); 36] = [
+    (KeyCode::Space, "space"),
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Return, "return"),
+    (KeyCode::LShift, "lshift"),
+    (KeyCode::RShift, "rshift"),
+    (KeyCode::LControl, "lcontrol"),
+    (KeyCode::RControl, "rcontrol"),
+    (KeyCode::Backslash, "backslash"),
+    (KeyCode::Slash, "slash"),
+    (KeyCode::A, "a"),
+    (KeyCode::B, "b"),
+    (KeyCode::C, "c"),
+    (KeyCode::D, "d"),
+    (KeyCode::E, "e"),
+    (KeyCode::F, "f"),
+    (KeyCode::G, "g"),
+    (KeyCode::H, "h"),
+    (KeyCode::I, "i"),
+    (KeyCode::J, "j"),
+    (KeyCode::K, "k"),
+    (KeyCode::L, "l"),
+    (KeyCode::M, "m"),
+    (KeyCode::N, "n"),
+    (KeyCode::O, "o"),
+    (KeyCode::P, "p"),
+    (KeyCode::Q, "q"),
+    (KeyCode::R, "r"),
+    (KeyCode::S, "s"),
+    (KeyCode::T, "t"),
+    (KeyCode::U, "u"),
+    (KeyCode::V, "v"),
+    (KeyCode::W, "w"),
+    (KeyCode::X, "x"),
+    (KeyCode::Y, "y"),
+    (KeyCode::Z, "z"),
+];
+
+fn key_named(name: &str) -> Option<KeyCode> {
+    KEYS.iter().find(|&&(_, n)| n == name).map(|&(key, _)| key)
+}
+
+fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEYS.iter().find(|&&(k, _)| k == key).map(|&(_, name)| name)
+}
+
+// Controller buttons the actions can be bound to. The D-pad stays as it is
+// to get around the menus.
+const BUTTONS: [(Button, &str); 12] = [
+    (Button::South, "a"),
+    (Button::East, "b"),
+    (Button::West, "x"),
+    (Button::North, "y"),
+    (Button::LeftTrigger, "lb"),
+    (Button::RightTrigger, "rb"),
+    (Button::LeftTrigger2, "lt"),
+    (Button::RightTrigger2, "rt"),
+    (Button::LeftThumb, "ls"),
+    (Button::RightThumb, "rs"),
+    (Button::Start, "start"),
+    (Button::Select, "select"),
+];
+
+fn button_named(name: &str) -> Option<Button> {
+    BUTTONS
+        .iter()
+        .find(|&&(_, n)| n == name)
+        .map(|&(btn, _)| btn)
+}
+
+fn button_name(btn: Button) -> Option<&'static str> {
+    BUTTONS
+        .iter()
+        .find(|&&(b, _)| b == btn)
+        .map(|&(_, name)| name)
+}
+
+// What the player can rebind.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Flap,
+    Pause,
+    Restart,
+    Quit,
+}
+
+const ACTIONS: [Action; 4] = [Action::Flap, Action::Pause, Action::Restart, Action::Quit];
+
+impl Action {
+    // The key that does it, which keeps working whatever else is bound.
+    fn key(self) -> KeyCode {
+        match self {
+            Action::Flap => KeyCode::Space,
+            Action::Pause => KeyCode::P,
+            Action::Restart => KeyCode::R,
+            Action::Quit => KeyCode::Escape,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Action::Flap => lang::tr("flap"),
+            Action::Pause => lang::tr("pause"),
+            Action::Restart => lang::tr("restart"),
+            Action::Quit => lang::tr("quit"),
+        }
+    }
+}
+
+// A name for each action, of a key or of a button.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Slots {
+    pub flap: Option<String>,
+    pub pause: Option<String>,
+    pub restart: Option<String>,
+    pub quit: Option<String>,
+}
+
+impl Slots {
+    fn get(&self, action: Action) -> &Option<String> {
+        match action {
+            Action::Flap => &self.flap,
+            Action::Pause => &self.pause,
+            Action::Restart => &self.restart,
+            Action::Quit => &self.quit,
+        }
+    }
+
+    fn get_mut(&mut self, action: Action) -> &mut Option<String> {
+        match action {
+            Action::Flap => &mut self.flap,
+            Action::Pause => &mut self.pause,
+            Action::Restart => &mut self.restart,
+            Action::Quit => &mut self.quit,
+        }
+    }
+
+    // The action bound to `name`.
+    fn find(&self, name: &str) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .copied()
+            .find(|&action| self.get(action).as_deref() == Some(name))
+    }
+
+    // Binds `name` to `action` alone, since a key does one thing at a time.
+    fn bind(&mut self, action: Action, name: &str) {
+        if let Some(other) = self.find(name) {
+            *self.get_mut(other) = None;
+        }
+        *self.get_mut(action) = Some(name.to_string());
+    }
+}
+
+// An extra key and button for each action, on top of the usual controls.
+// The keys sit at the top of the table so older settings files, which only
+// had keys, still read:
+//
+//     "bindings": {"flap": "w", "quit": "q", "buttons": {"pause": "y"}}
+//
+// A bound key or button stops doing whatever it did before.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputMap {
+    #[serde(flatten)]
+    pub keys: Slots,
+    pub buttons: Slots,
+}
+
+impl InputMap {
+    // The key `key` stands in for.
+    pub fn resolve(&self, key: Option<KeyCode>) -> Option<KeyCode> {
+        let action = key.and_then(key_name).and_then(|name| self.keys.find(name));
+        action.map(Action::key).or(key)
+    }
+
+    // The key a bound button stands in for, if it's bound.
+    pub fn button(&self, btn: Button) -> Option<KeyCode> {
+        let action = self.buttons.find(button_name(btn)?)?;
+        Some(action.key())
+    }
+
+    // What does `action`, for the settings screen: "SPACE and W, button Y".
+    fn describe(&self, action: Action) -> String {
+        let mut text = match action.key() {
+            KeyCode::Escape => "ESC".to_string(),
+            key => key_name(key).unwrap_or("?").to_uppercase(),
+        };
+        if let Some(key) = self
+            .keys
+            .get(action)
+            .as_deref()
+            .filter(|k| key_named(k).is_some())
+        {
+            text = format!("{} and {}", text, key.to_uppercase());
+        }
+        if let Some(btn) = self
+            .buttons
+            .get(action)
+            .as_deref()
+            .filter(|b| button_named(b).is_some())
+        {
+            text = format!("{}, button {}", text, btn.to_uppercase());
+        }
+        text
+    }
+}
+
+// The preferences on the settings screen, saved as JSON in the user data
+// directory whenever it closes. Kept in the world as a resource for
+// whatever reads them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Of the music and every sound, from 0 to 1.
+    pub volume: f32,
+    // The difficulty runs are played at without `--difficulty`. Like vsync
+    // it's only read as the game starts.
+    pub difficulty: Preset,
+    pub show_fps: bool,
+    pub vsync: bool,
+    pub bindings: InputMap,
+    // The code of the language the text is in, without `--lang`. English
+    // if none.
+    pub lang: Option<String>,
+    // Flat bright pipes and birds over dimmed scenery.
+    pub high_contrast: bool,
+    // How much bigger the score and the rest of the HUD are drawn.
+    pub hud_scale: f32,
+    // Far backgrounds hold still and crashes don't shake or zoom.
+    pub reduced_motion: bool,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            volume: 1.0,
+            difficulty: Preset::Normal,
+            show_fps: false,
+            vsync: true,
+            bindings: InputMap::default(),
+            lang: None,
+            high_contrast: false,
+            hud_scale: 1.0,
+            reduced_motion: false,
+            path: None,
+        }
+    }
+}
+
+impl Settings {
+    // Missing or unreadable files mean the defaults.
+    pub fn load(path: PathBuf) -> Self {
+        let mut settings = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                println!("ignoring corrupt settings file {}: {}", path.display(), e);
+                Settings::default()
+            }),
+            Err(_) => Settings::default(),
+        };
+        settings.volume = settings.volume.clamp(0.0, 1.0);
+        settings.hud_scale = settings.hud_scale.clamp(HUD_SCALES[0], HUD_SCALES[2]);
+        settings.path = Some(path);
+        settings
+    }
+
+    pub fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string(self)?;
+                fs::write(path, json)
+            });
+        if let Err(e) = result {
+            println!("can't save the settings to {}: {}", path.display(), e);
+        }
+    }
+}
+
+// The keys of their names in the string tables, followed by a row for
+// each action.
+const ROWS: [&str; 8] = [
+    "volume",
+    "difficulty",
+    "show_fps",
+    "vsync",
+    "language",
+    "high_contrast",
+    "hud_text",
+    "reduce_motion",
+];
+const PRESETS: [Preset; 3] = [Preset::Easy, Preset::Normal, Preset::Hard];
+const HUD_SCALES: [f32; 3] = [1.0, 1.25, 1.5];
+
+// The settings screen, opened with O on the title screen: Up and Down pick
+// a setting, Left and Right change it, and Return on an action waits for a
+// key or controller button to bind to it, or Back clears them.
+#[derive(Default)]
+pub struct SettingsScreen {
+    pub open: bool,
+    row: usize,
+    // Waiting for the key or button to bind on the current row.
+    pub capturing: bool,
+    // The codes and names of the languages to pick from.
+    languages: Vec<(String, String)>,
+}
+
+const ROW_COUNT: usize = ROWS.len() + ACTIONS.len();
+
+impl SettingsScreen {
+    pub fn new(languages: Vec<(String, String)>) -> Self {
+        SettingsScreen {
+            languages,
+            ..SettingsScreen::default()
+        }
+    }
+
+    // The action on the current row, if it's one.
+    fn action(&self) -> Option<Action> {
+        self.row.checked_sub(ROWS.len()).map(|n| ACTIONS[n])
+    }
+
+    // Applies a key to `settings`. Returns whether they changed.
+    pub fn press(&mut self, settings: &mut Settings, key: KeyCode) -> bool {
+        if self.capturing {
+            self.capturing = false;
+            return match (self.action(), key_name(key)) {
+                (Some(action), Some(name)) => {
+                    settings.bindings.keys.bind(action, name);
+                    true
+                }
+                _ => false,
+            };
+        }
+
+        let step = match key {
+            KeyCode::Up => {
+                self.row = (self.row + ROW_COUNT - 1) % ROW_COUNT;
+                return false;
+            }
+            KeyCode::Down => {
+                self.row = (self.row + 1) % ROW_COUNT;
+                return false;
+            }
+            KeyCode::Left => -1,
+            KeyCode::Right => 1,
+            KeyCode::Return | KeyCode::Space => 0,
+            KeyCode::Back | KeyCode::Delete => {
+                let action = match self.action() {
+                    Some(action) => action,
+                    None => return false,
+                };
+                let key = settings.bindings.keys.get_mut(action).take();
+                let button = settings.bindings.buttons.get_mut(action).take();
+                return key.is_some() || button.is_some();
+            }
+            _ => return false,
+        };
+        match self.row {
+            0 if step != 0 => {
+                let tenths = (settings.volume * 10.0).round() as i32 + step;
+                settings.volume = tenths.clamp(0, 10) as f32 / 10.0;
+            }
+            1 if step != 0 => {
+                let n = PRESETS.iter().position(|&p| p == settings.difficulty);
+                let n = (n.unwrap_or(1) as i32 + step).rem_euclid(PRESETS.len() as i32);
+                settings.difficulty = PRESETS[n as usize];
+            }
+            2 => settings.show_fps = !settings.show_fps,
+            3 => settings.vsync = !settings.vsync,
+            4 if step != 0 && !self.languages.is_empty() => {
+                let current = settings.lang.as_deref().unwrap_or("en");
+                let n = self.languages.iter().position(|(code, _)| code == current);
+                let n = (n.unwrap_or(0) as i32 + step).rem_euclid(self.languages.len() as i32);
+                settings.lang = Some(self.languages[n as usize].0.clone());
+            }
+            5 => settings.high_contrast = !settings.high_contrast,
+            6 if step != 0 => {
+                let n = HUD_SCALES.iter().position(|&s| s == settings.hud_scale);
+                let n = (n.unwrap_or(0) as i32 + step).clamp(0, HUD_SCALES.len() as i32 - 1);
+                settings.hud_scale = HUD_SCALES[n as usize];
+            }
+            7 => settings.reduced_motion = !settings.reduced_motion,
+            _ if step == 0 && self.action().is_some() => {
+                self.capturing = true;
+                return false;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // Binds a controller button while waiting for one. Returns whether the
+    // settings changed.
+    pub fn press_button(&mut self, settings: &mut Settings, btn: Button) -> bool {
+        let action = match self.action() {
+            Some(action) if self.capturing => action,
+            _ => return false,
+        };
+        let name = match button_name(btn) {
+            Some(name) => name,
+            None => return false,
+        };
+        self.capturing = false;
+        settings.bindings.buttons.bind(action, name);
+        true
+    }
+
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, settings: &Settings) {
+        let center_x = engine::screen_width() / 2.0;
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("settings"),
+            Vec2::new(center_x, 110.0),
+            50.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+
+        let on_off = |on: bool| lang::tr(if on { "on" } else { "off" }).to_string();
+        let code = settings.lang.as_deref().unwrap_or("en");
+        let language = self.languages.iter().find(|(c, _)| c == code);
+        let values = [
+            format!("{:.0}%", settings.volume * 100.0),
+            settings.difficulty.label().to_string(),
+            on_off(settings.show_fps),
+            on_off(settings.vsync),
+            language.map_or(code, |(_, name)| name).to_string(),
+            on_off(settings.high_contrast),
+            format!("{:.0}%", settings.hud_scale * 100.0),
+            on_off(settings.reduced_motion),
+        ];
+        let mut rows: Vec<(&str, String)> =
+            ROWS.iter().map(|&row| lang::tr(row)).zip(values).collect();
+        for action in ACTIONS {
+            rows.push((action.label(), settings.bindings.describe(action)));
+        }
+        for (n, (row, value)) in rows.iter().enumerate() {
+            let value = if n == self.row && self.capturing {
+                lang::tr("press_to_bind").to_string()
+            } else {
+                format!("< {} >", value)
+            };
+            let color = if n == self.row {
+                Color::new(1.0, 0.9, 0.2, 1.0)
+            } else {
+                Color::new(1.0, 1.0, 1.0, 1.0)
+            };
+            cmds.push(DrawCmd::centered_text(
+                format!("{}   {}", row, value),
+                Vec2::new(center_x, 160.0 + 26.0 * n as f32),
+                22.0,
+                color,
+            ));
+        }
+        let hints = [
+            lang::tr("settings_hint"),
+            lang::tr("settings_bind_hint"),
+            lang::tr("settings_restart_hint"),
+        ];
+        for (n, hint) in hints.iter().enumerate() {
+            cmds.push(DrawCmd::centered_text(
+                *hint,
+                Vec2::new(center_x, 490.0 + 22.0 * n as f32),
+                18.0,
+                Color::new(0.8, 0.8, 0.8, 1.0),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bound_keys_stand_in_for_the_usual_ones() {
+        let mut settings = Settings::default();
+        let mut screen = SettingsScreen::default();
+        for _ in 0..ROWS.len() {
+            screen.press(&mut settings, KeyCode::Down);
+        }
+        screen.press(&mut settings, KeyCode::Return);
+        assert!(screen.capturing);
+        assert!(screen.press(&mut settings, KeyCode::W));
+
+        let bindings = &settings.bindings;
+        assert_eq!(bindings.resolve(Some(KeyCode::W)), Some(KeyCode::Space));
+        assert_eq!(bindings.resolve(Some(KeyCode::Space)), Some(KeyCode::Space));
+        assert_eq!(bindings.resolve(Some(KeyCode::Q)), Some(KeyCode::Q));
+        assert_eq!(bindings.resolve(None), None);
+
+        // Binding it to pause takes it off flapping.
+        screen.press(&mut settings, KeyCode::Down);
+        screen.press(&mut settings, KeyCode::Return);
+        screen.press(&mut settings, KeyCode::W);
+        assert_eq!(settings.bindings.keys.flap, None);
+        assert_eq!(
+            settings.bindings.resolve(Some(KeyCode::W)),
+            Some(KeyCode::P)
+        );
+
+        screen.press(&mut settings, KeyCode::Return);
+        assert!(screen.press_button(&mut settings, Button::North));
+        assert_eq!(settings.bindings.button(Button::North), Some(KeyCode::P));
+        assert_eq!(settings.bindings.button(Button::South), None);
+    }
+
+    #[test]
+    fn bindings_read_from_older_settings_files() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"bindings": {"flap": "w", "pause": "q"}}"#).unwrap();
+        assert_eq!(settings.bindings.keys.flap.as_deref(), Some("w"));
+        assert_eq!(settings.bindings.buttons, Slots::default());
+
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+    }
+}
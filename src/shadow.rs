@@ -0,0 +1,156 @@
+use crate::best_run;
+use crate::engine::{self, Color, DrawCmd, Sprite, Vec2};
+use crate::lang;
+use crate::policy::{Action, BirdPolicy, Observation, RulePolicy};
+use crate::simulate::Simulation;
+use crate::tuning::Tuning;
+use crate::{Challenge, PlayerTag, Position, TimeScale};
+use rand::rngs::ThreadRng;
+use rand::Rng;
+use specs::*;
+
+pub const MAX_SKILL: u32 = 10;
+pub const DEFAULT_SKILL: u32 = 5;
+// Chance of fumbling a flap the rule bot would make, per missing point of
+// skill.
+const FUMBLE_PER_SKILL: f64 = 0.04;
+
+// The rule bot with shaky hands: it sometimes doesn't flap when it should.
+struct Fumbling {
+    fumble: f64,
+    rng: ThreadRng,
+}
+
+impl BirdPolicy for Fumbling {
+    fn act(&mut self, obs: Observation) -> Action {
+        match RulePolicy.act(obs) {
+            Action::Flap if self.rng.gen_bool(self.fumble) => Action::Idle,
+            action => action,
+        }
+    }
+}
+
+// A shadow race: a bot flies the run's seed in a world of its own, a tick
+// for every tick of the player's, and is drawn as a dark bird beside them.
+// The HUD shows who's ahead. Its pipes are only the same while its score
+// is, since the gaps and spacing follow the score.
+pub struct ShadowRace {
+    sim: Simulation,
+    bot: Fumbling,
+}
+
+impl ShadowRace {
+    // `skill` goes from 0, fumbling two flaps in five, to `MAX_SKILL`, the
+    // rule bot itself.
+    pub fn new(skill: u32, world: &World) -> Self {
+        let fumble = MAX_SKILL.saturating_sub(skill) as f64 * FUMBLE_PER_SKILL;
+        ShadowRace {
+            sim: shadow_world(world),
+            bot: Fumbling {
+                fumble,
+                rng: rand::thread_rng(),
+            },
+        }
+    }
+
+    // Lines the shadow up again for the run `world` is about to start.
+    pub fn restart(&mut self, world: &World) {
+        self.sim = shadow_world(world);
+    }
+
+    // One tick alongside the player's, at their time scale.
+    pub fn step(&mut self, time: TimeScale) {
+        if self.sim.is_over() {
+            return;
+        }
+        self.sim.world_mut().insert(time);
+        let action = Observation::from_world(self.sim.world()).map(|obs| self.bot.act(obs));
+        self.sim.step(action == Some(Action::Flap));
+    }
+
+    pub fn score(&self) -> i32 {
+        self.sim.score()
+    }
+
+    fn bird(&self) -> Option<Vec2> {
+        let positions = self.sim.world().read_storage::<Position>();
+        let players = self.sim.world().read_storage::<PlayerTag>();
+        (&positions, &players)
+            .join()
+            .next()
+            .map(|(pos, _)| pos.position)
+    }
+
+    // The shadow bird until it crashes, in the player's bird `frame`.
+    pub fn draw_bird<'a>(&self, cmds: &mut Vec<DrawCmd<'a>>, frame: &'a Sprite) {
+        if let (false, Some(at)) = (self.sim.is_over(), self.bird()) {
+            best_run::draw_shadow(cmds, frame, at);
+        }
+    }
+
+    // The HUD's race standing against the player's `score`.
+    pub fn draw_standing(&self, cmds: &mut Vec<DrawCmd>, score: i32) {
+        let lead = score - self.score();
+        let standing = if lead > 0 {
+            lang::fill("you_lead", &[&lead])
+        } else if lead < 0 {
+            lang::fill("shadow_leads", &[&-lead])
+        } else {
+            lang::tr("level_with_shadow").to_string()
+        };
+        let key = if self.sim.is_over() {
+            "shadow_crashed"
+        } else {
+            "shadow"
+        };
+        cmds.push(DrawCmd::centered_text(
+            lang::fill(key, &[&self.score(), &standing]),
+            // Under the score.
+            Vec2::new(engine::screen_width() / 2.0, 100.0),
+            20.0,
+            Color::new(0.8, 0.8, 1.0, 1.0),
+        ));
+    }
+
+    // How the race ended, for the game over screen.
+    pub fn result(&self, score: i32) -> String {
+        let shadow = self.score();
+        if score > shadow {
+            lang::fill("beat_shadow", &[&score, &shadow])
+        } else if score < shadow {
+            lang::fill("shadow_won", &[&shadow, &score])
+        } else {
+            lang::fill("shadow_tie", &[&score])
+        }
+    }
+}
+
+// A headless world with the same tuning and seed as the player's.
+fn shadow_world(world: &World) -> Simulation {
+    let tuning = (*world.read_resource::<Tuning>()).clone();
+    match world.try_fetch::<Challenge>() {
+        Some(challenge) => Simulation::seeded(tuning, challenge.seed),
+        None => Simulation::new(tuning),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_perfect_shadow_flies_like_the_rule_bot() {
+        let mut world = crate::build_world(Tuning::default(), None);
+        world.insert(Challenge::new(7));
+        let mut shadow = ShadowRace::new(MAX_SKILL, &world);
+
+        let mut sim = Simulation::seeded(Tuning::default(), 7);
+        for _ in 0..1200 {
+            shadow.step(TimeScale::default());
+            let action = Observation::from_world(sim.world()).map(|obs| RulePolicy.act(obs));
+            sim.step(action == Some(Action::Flap));
+        }
+        assert_eq!(shadow.score(), sim.score());
+        assert_eq!(shadow.sim.is_over(), sim.is_over());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::calendar::civil_from_days;
+use crate::customize::BirdLook;
+use crate::engine::{self, Color, DrawCmd, Rect, Sprite, Vec2};
+use crate::lang;
+use crate::medals::Medal;
+use ggez::graphics::{Image, ImageEncodingFormat, ImageFormat};
+use ggez::{Context, GameError, GameResult};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Sized for link previews on most social networks.
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 630;
+const CARD_FILE: &str = "/results.png";
+const SEED_FILE: &str = "seed.txt";
+// ggez only encodes into the user config directory, so screenshots go
+// there first.
+const SCREENSHOT_FILE: &str = "/screenshot.png";
+const SCREENSHOT_DIR: &str = "screenshots";
+const BIRD_SCALE: f32 = 3.0;
+
+// What the results card of a finished run shows.
+pub struct Card {
+    pub title: String,
+    pub score: i32,
+    pub best: i32,
+    pub medal: Option<Medal>,
+    pub mode: String,
+    pub seed: Option<u64>,
+    pub look: BirdLook,
+}
+
+// Where `compose` saves the card: ggez writes to the user config directory.
+pub fn card_path(ctx: &Context) -> PathBuf {
+    ctx.fs.user_config_dir().join(&CARD_FILE[1..])
+}
+
+impl Card {
+    // Renders the card with the bird in `frame` and saves it, replacing the
+    // previous run's.
+    pub fn compose(&self, ctx: &mut Context, frame: &Sprite) -> GameResult {
+        let (width, height) = (WIDTH as f32, HEIGHT as f32);
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let mut cmds = vec![DrawCmd::Rect {
+            rect: Rect::new(0.0, height - 90.0, width, 90.0),
+            color: Color::new(0.87, 0.85, 0.58, 1.0),
+        }];
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(0.0, height - 90.0, width, 12.0),
+            color: Color::new(0.45, 0.75, 0.2, 1.0),
+        });
+        cmds.push(DrawCmd::text(
+            self.title.as_str(),
+            Vec2::new(60.0, 40.0),
+            56.0,
+            white,
+        ));
+
+        let bird = Vec2::new(140.0, 220.0);
+        let mut sprite = DrawCmd::sprite(frame, bird);
+        if let DrawCmd::Sprite { scale, .. } = &mut sprite {
+            *scale = BIRD_SCALE;
+        }
+        cmds.push(sprite);
+        self.look.draw_hat(&mut cmds, bird, BIRD_SCALE);
+
+        cmds.push(DrawCmd::text(
+            lang::tr("score"),
+            Vec2::new(520.0, 150.0),
+            36.0,
+            white,
+        ));
+        cmds.push(DrawCmd::text(
+            self.score.to_string(),
+            Vec2::new(520.0, 190.0),
+            140.0,
+            white,
+        ));
+        cmds.push(DrawCmd::text(
+            lang::fill("card_best", &[&self.best]),
+            Vec2::new(520.0, 350.0),
+            36.0,
+            white,
+        ));
+
+        if let Some(medal) = self.medal {
+            let Color { r, g, b, .. } = medal.color();
+            let center = Vec2::new(1000.0, 250.0);
+            cmds.push(DrawCmd::Circle {
+                center,
+                radius: 84.0,
+                color: Color::new(r * 0.7, g * 0.7, b * 0.7, 1.0),
+            });
+            cmds.push(DrawCmd::Circle {
+                center,
+                radius: 72.0,
+                color: Color::new(r, g, b, 1.0),
+            });
+            cmds.push(DrawCmd::centered_text(
+                medal.name(),
+                center + Vec2::new(0.0, 120.0),
+                36.0,
+                white,
+            ));
+        }
+
+        let mut footer = vec![date_today(), self.mode.clone()];
+        if let Some(seed) = self.seed {
+            footer.push(lang::fill("card_seed", &[&seed]));
+        }
+        cmds.push(DrawCmd::text(
+            footer.join("   "),
+            Vec2::new(60.0, height - 60.0),
+            30.0,
+            Color::new(0.35, 0.3, 0.2, 1.0),
+        ));
+
+        engine::render_png(
+            ctx,
+            WIDTH,
+            HEIGHT,
+            Color::new(0.31, 0.75, 0.93, 1.0),
+            &cmds,
+            CARD_FILE,
+        )
+    }
+}
+
+// Saves a line inviting someone to fly `seed`, ready to paste, to
+// `seed.txt` in the user config directory. Returns where it went.
+pub fn share_seed(ctx: &Context, seed: u64) -> io::Result<PathBuf> {
+    let dir = ctx.fs.user_config_dir();
+    fs::create_dir_all(dir)?;
+    let path = dir.join(SEED_FILE);
+    let text = format!("Fly my pipes in rusty_bird: rusty_bird --seed {}\n", seed);
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+// The frame on screen as RGBA rows, with its width and height.
+pub fn frame_pixels(ctx: &Context) -> GameResult<(u32, u32, Vec<u8>)> {
+    let frame = ctx.gfx.frame();
+    let mut pixels = frame.to_pixels(ctx)?;
+    match frame.format() {
+        ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => (),
+        // What most windows are, and ggez won't encode.
+        ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        other => {
+            return Err(GameError::RenderError(format!(
+                "can't save a {:?} frame",
+                other
+            )))
+        }
+    }
+    Ok((frame.width(), frame.height(), pixels))
+}
+
+// Saves the frame on screen as a PNG named after the time, in
+// `screenshots` in the user data directory. Returns where it went.
+pub fn screenshot(ctx: &Context) -> GameResult<PathBuf> {
+    let (width, height, pixels) = frame_pixels(ctx)?;
+    let image = Image::from_pixels(ctx, &pixels, ImageFormat::Rgba8UnormSrgb, width, height);
+    image.encode(ctx, ImageEncodingFormat::Png, SCREENSHOT_FILE)?;
+
+    let encoded = ctx.fs.user_config_dir().join(&SCREENSHOT_FILE[1..]);
+    let dir = ctx.fs.user_data_dir().join(SCREENSHOT_DIR);
+    let path = dir.join(format!("rusty_bird-{}.png", time_stamp(now())));
+    fs::create_dir_all(&dir)?;
+    fs::copy(&encoded, &path)?;
+    fs::remove_file(&encoded)?;
+    Ok(path)
+}
+
+// Seconds since 1970-01-01.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+// Today's date in UTC as YYYY-MM-DD.
+fn date_today() -> String {
+    let (year, month, day) = civil_from_days((now() / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// The UTC date and time `secs` after 1970-01-01 as YYYY-MM-DD_HH-MM-SS,
+// which sorts in order and is a valid file name everywhere.
+fn time_stamp(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stamps_screenshots_with_the_time() {
+        assert_eq!(time_stamp(0), "1970-01-01_00-00-00");
+        assert_eq!(time_stamp(1_792_069_407), "2026-10-15_13-03-27");
+    }
+}
@@ -0,0 +1,268 @@
+use crate::policy::{self, Action, BirdPolicy, Observation};
+use crate::schedule::{self, Schedule};
+use crate::telemetry::Telemetry;
+use crate::tuning::Tuning;
+use crate::{build_world, Challenge, DeathCause, Direction, Game, GameState};
+use specs::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "usage: rusty_bird simulate [--runs N] [--policy rule|random|noop] \
+                     [--tuning FILE] [--max-frames N] [--telemetry DIR]";
+
+struct RunResult {
+    score: i32,
+    death_cause: Option<DeathCause>,
+}
+
+thread_local! {
+    static GAMEPLAY: RefCell<Dispatcher<'static, 'static>> =
+        RefCell::new(schedule::gameplay(Schedule::new()).build_sequential());
+}
+
+// One gameplay tick of a world built without sprites, run the same way
+// `State::update` does. Returns false once the run is over.
+pub(crate) fn tick_headless(world: &mut World) -> bool {
+    if *world.read_resource::<GameState>() != GameState::Playing {
+        return false;
+    }
+    world.write_resource::<Game>().frames += 1;
+
+    GAMEPLAY.with(|gameplay| gameplay.borrow_mut().dispatch_seq(world));
+    world.maintain();
+    true
+}
+
+// Ticks until the run ends or `max_frames`, calling `input` before every
+// tick.
+pub(crate) fn run_headless(world: &mut World, max_frames: i32, mut input: impl FnMut(&World)) {
+    loop {
+        input(world);
+        if world.read_resource::<Game>().frames >= max_frames || !tick_headless(world) {
+            return;
+        }
+    }
+}
+
+// A run of the game stepped by hand instead of by a window: movement,
+// collision and spawning on collision boxes, with no `Context` and no
+// sprites. For integration tests and bots:
+//
+//     let mut sim = Simulation::seeded(Tuning::default(), 7);
+//     while sim.step(sim.frames() % 30 == 0) {}
+//     println!("scored {}", sim.score());
+pub struct Simulation {
+    world: World,
+}
+
+impl Simulation {
+    // Pipes come from an unseeded generator, so every run is different.
+    pub fn new(tuning: Tuning) -> Self {
+        Simulation {
+            world: build_world(tuning, None),
+        }
+    }
+
+    // The same seed and flaps always play out the same.
+    pub fn seeded(tuning: Tuning, seed: u64) -> Self {
+        let mut sim = Simulation::new(tuning);
+        sim.world.insert(Challenge::new(seed));
+        sim
+    }
+
+    // Advances one tick, flapping first if `flap`. Returns false once the
+    // run is over.
+    pub fn step(&mut self, flap: bool) -> bool {
+        if flap {
+            *self.world.write_resource::<Direction>() = Direction {
+                jump: true,
+                ..Direction::new()
+            };
+        }
+        tick_headless(&mut self.world)
+    }
+
+    pub fn frames(&self) -> i32 {
+        self.world.read_resource::<Game>().frames
+    }
+
+    pub fn score(&self) -> i32 {
+        self.world.read_resource::<Game>().score
+    }
+
+    pub fn is_over(&self) -> bool {
+        *self.world.read_resource::<GameState>() == GameState::GameOver
+    }
+
+    // For anything else, e.g. reading obstacle positions or inserting a
+    // `Telemetry` resource.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+}
+
+fn play(
+    tuning: &Tuning,
+    policy: &mut dyn BirdPolicy,
+    max_frames: i32,
+    telemetry: Telemetry,
+) -> RunResult {
+    let mut sim = Simulation::new(tuning.clone());
+    sim.world_mut().insert(telemetry);
+
+    while sim.frames() < max_frames {
+        let action = Observation::from_world(sim.world()).map(|obs| policy.act(obs));
+        if !sim.step(action == Some(Action::Flap)) {
+            break;
+        }
+    }
+
+    let game = sim.world().read_resource::<Game>();
+    RunResult {
+        score: game.score,
+        death_cause: game.death.map(|d| d.cause),
+    }
+}
+
+fn percentile(sorted: &[i32], p: f64) -> i32 {
+    let rank = ((p * sorted.len() as f64).ceil() as usize).max(1);
+    sorted[rank - 1]
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> T {
+    match value.and_then(|v| v.parse().ok()) {
+        Some(v) => v,
+        None => {
+            eprintln!("invalid or missing value for {}\n{}", flag, USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+pub fn run(mut args: impl Iterator<Item = String>) {
+    let mut runs: usize = 100;
+    let mut policy = "rule".to_string();
+    let mut tuning_path: Option<PathBuf> = None;
+    let mut max_frames: i32 = 10_000;
+    let mut telemetry_dir: Option<PathBuf> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => runs = parse_value(&arg, args.next()),
+            "--max-frames" => max_frames = parse_value(&arg, args.next()),
+            "--tuning" => tuning_path = Some(parse_value(&arg, args.next())),
+            "--telemetry" => telemetry_dir = Some(parse_value(&arg, args.next())),
+            "--policy" => policy = parse_value(&arg, args.next()),
+            _ => {
+                eprintln!("unknown argument {}\n{}", arg, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    if let Err(e) = policy::from_name(&policy) {
+        eprintln!("{}\n{}", e, USAGE);
+        process::exit(2);
+    }
+    if runs == 0 {
+        eprintln!("--runs must be at least 1");
+        process::exit(2);
+    }
+
+    let tuning = match &tuning_path {
+        Some(path) => Tuning::from_file(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => Tuning::default(),
+    };
+
+    let mut scores = Vec::with_capacity(runs);
+    let mut causes: HashMap<Option<DeathCause>, usize> = HashMap::new();
+    for run in 0..runs {
+        let telemetry = match &telemetry_dir {
+            Some(dir) => {
+                let mut telemetry = Telemetry::new(dir.clone());
+                telemetry.begin_run(&run.to_string());
+                telemetry
+            }
+            None => Telemetry::default(),
+        };
+        // A fresh policy every run, so stateful ones start over too.
+        let mut bot = policy::from_name(&policy).unwrap();
+        let result = play(&tuning, bot.as_mut(), max_frames, telemetry);
+        scores.push(result.score);
+        *causes.entry(result.death_cause).or_insert(0) += 1;
+    }
+    scores.sort_unstable();
+
+    let mean = scores.iter().map(|&s| s as f64).sum::<f64>() / runs as f64;
+    println!(
+        "{} runs, policy {}, tuning {}",
+        runs,
+        policy,
+        tuning_path
+            .as_ref()
+            .map_or("default".to_string(), |p| p.display().to_string())
+    );
+    println!(
+        "score: mean {:.1}, median {}, p95 {}, min {}, max {}",
+        mean,
+        percentile(&scores, 0.5),
+        percentile(&scores, 0.95),
+        scores[0],
+        scores[runs - 1]
+    );
+    println!("death causes:");
+    for (cause, label) in [
+        (Some(DeathCause::TopPipe), "top pipe"),
+        (Some(DeathCause::BottomPipe), "bottom pipe"),
+        (Some(DeathCause::Cave), "cave"),
+        (Some(DeathCause::Hazard), "hazard"),
+        (Some(DeathCause::Floor), "floor"),
+        (None, "survived"),
+    ]
+    .iter()
+    {
+        let count = causes.get(cause).copied().unwrap_or(0);
+        println!(
+            "  {:<12} {:>6} ({:.1}%)",
+            label,
+            count,
+            100.0 * count as f64 / runs as f64
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeathCause;
+
+    #[test]
+    fn seeded_simulations_repeat() {
+        let fly = || {
+            let mut sim = Simulation::seeded(Tuning::default(), 42);
+            while sim.frames() < 2000 && sim.step(sim.frames() % 25 == 0) {}
+            (sim.frames(), sim.score(), sim.is_over())
+        };
+        assert_eq!(fly(), fly());
+    }
+
+    #[test]
+    fn a_bird_that_never_flaps_crashes() {
+        // It drops to the floor, long before the first pipe.
+        let mut sim = Simulation::seeded(Tuning::default(), 1);
+        while sim.frames() < 600 && sim.step(false) {}
+        assert!(sim.is_over());
+        assert_eq!(sim.score(), 0);
+        let death = sim.world().read_resource::<Game>().death.unwrap();
+        assert_eq!(death.cause, DeathCause::Floor);
+    }
+}
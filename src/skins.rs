@@ -0,0 +1,179 @@
+use crate::engine::Sprite;
+use crate::pipes::PipeSprites;
+use crate::SpriteSheet;
+use ggez::Context;
+use std::path::Path;
+
+// In the assets directory, one folder per skin.
+const SKINS_DIR: &str = "/skins";
+
+// A look for the bird and the pipes. Skins are folders with any of the
+// usual files: `player1.png`, `player2.png` and so on, maybe with a
+// `player_hit.png`, or a `player.json` atlas for the bird, and
+// `pipe_cap.png` and `pipe_body.png` for the pipes, each the same size as
+// the one it replaces. What a skin leaves out stays as usual.
+#[derive(Clone)]
+pub struct Skin {
+    pub name: String,
+    pub bird: SpriteSheet,
+    pub cap: Sprite,
+    pub body: Sprite,
+}
+
+// Whether a skin folder may have `file`, in either density.
+fn expected(file: &str) -> bool {
+    let file = file.replace("@2x", "");
+    let frame = file
+        .strip_prefix("player")
+        .and_then(|rest| rest.strip_suffix(".png"))
+        .is_some_and(|n| n.parse::<u32>().is_ok_and(|n| n > 0));
+    frame
+        || [
+            "player_hit.png",
+            "player.json",
+            "pipe_cap.png",
+            "pipe_body.png",
+        ]
+        .contains(&file.as_str())
+}
+
+fn check_size(path: &str, sprite: &Sprite, usual: &Sprite) -> Result<(), String> {
+    let (width, height) = (sprite.width(), sprite.height());
+    if (width, height) != (usual.width(), usual.height()) {
+        return Err(format!(
+            "{} is {}x{}, it has to be {}x{}",
+            path,
+            width,
+            height,
+            usual.width(),
+            usual.height()
+        ));
+    }
+    Ok(())
+}
+
+impl Skin {
+    // The skin in `dir`, over the `usual` one. Fails on files of the wrong
+    // size, or a folder with none of a skin's files.
+    fn load(ctx: &Context, dir: &Path, usual: &Skin) -> Result<Self, String> {
+        let folder = dir.to_string_lossy().replace('\\', "/");
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let files: Vec<String> = ctx
+            .fs
+            .read_dir(dir)
+            .map_err(|e| e.to_string())?
+            .filter_map(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .collect();
+        for file in files.iter().filter(|file| !expected(file)) {
+            println!("skin {}: ignoring {}", name, file);
+        }
+        let has = |file: &str| files.iter().any(|f| f == file);
+
+        let mut skin = Skin {
+            name: name.into_owned(),
+            ..usual.clone()
+        };
+        let mut found = false;
+        if has("player1.png") || has("player.json") {
+            let base = format!("{}/player", folder);
+            let bird = SpriteSheet::load(ctx, &base).map_err(|e| format!("{}: {}", base, e))?;
+            let usual_frame = &usual.bird.frames()[0];
+            for frame in bird.frames().iter().chain([bird.hit_frame()]) {
+                check_size(&base, frame, usual_frame)?;
+            }
+            skin.bird = bird;
+            found = true;
+        }
+        for (file, part, usual_part) in [
+            ("pipe_cap.png", &mut skin.cap, &usual.cap),
+            ("pipe_body.png", &mut skin.body, &usual.body),
+        ] {
+            if !has(file) {
+                continue;
+            }
+            let path = format!("{}/{}", folder, file);
+            let sprite = Sprite::load(ctx, &path).map_err(|e| format!("{}: {}", path, e))?;
+            check_size(&path, &sprite, usual_part)?;
+            *part = sprite;
+            found = true;
+        }
+        if !found {
+            return Err(format!("{} has none of a skin's files", folder));
+        }
+        Ok(skin)
+    }
+}
+
+// The skins to pick from on the menu, K cycling through them: the usual
+// look, called "Classic", then every valid skin by folder name. Invalid
+// ones are reported when the game starts and left out.
+pub struct Skins {
+    skins: Vec<Skin>,
+    current: usize,
+}
+
+impl Skins {
+    pub fn scan(ctx: &Context, bird: &SpriteSheet, pipes: &PipeSprites) -> Self {
+        let usual = Skin {
+            name: "Classic".to_string(),
+            bird: bird.clone(),
+            cap: pipes.cap().clone(),
+            body: pipes.body().clone(),
+        };
+        let mut dirs: Vec<_> = match ctx.fs.read_dir(SKINS_DIR) {
+            Ok(paths) => paths.filter(|path| ctx.fs.is_dir(path)).collect(),
+            Err(_) => Vec::new(),
+        };
+        dirs.sort();
+
+        let mut skins = vec![usual];
+        for dir in dirs {
+            match Skin::load(ctx, &dir, &skins[0]) {
+                Ok(skin) => skins.push(skin),
+                Err(e) => println!("ignoring skin: {}", e),
+            }
+        }
+        Skins { skins, current: 0 }
+    }
+
+    // Only the usual look, so there's nothing to pick.
+    pub fn is_empty(&self) -> bool {
+        self.skins.len() < 2
+    }
+
+    pub fn current(&self) -> &Skin {
+        &self.skins[self.current]
+    }
+
+    // The skin picked, to save with the looks. None for the usual look.
+    pub fn name(&self) -> Option<&str> {
+        (self.current > 0).then(|| self.current().name.as_str())
+    }
+
+    // Picks the skin called `name`, or the usual look if it's gone.
+    pub fn select(&mut self, name: Option<&str>) {
+        self.current = name
+            .and_then(|name| self.skins.iter().skip(1).position(|s| s.name == name))
+            .map_or(0, |n| n + 1);
+    }
+
+    pub fn next(&mut self) {
+        self.current = (self.current + 1) % self.skins.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skins_only_take_the_usual_file_names() {
+        assert!(expected("player1.png"));
+        assert!(expected("player12@2x.png"));
+        assert!(expected("player.json"));
+        assert!(expected("pipe_cap.png"));
+        assert!(!expected("player0.png"));
+        assert!(!expected("playerx.png"));
+        assert!(!expected("pipe.png"));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::tuning::Tuning;
+use rand::{Rng, RngCore};
+
+// Tight gaps keep the next pipe pair's opening within this distance of
+// the last one, so a cluster can be flown without a full climb or dive in
+// between.
+pub const CLUSTER_SHIFT: f32 = 100.0;
+
+// Only this much of what the bird can climb or drop between two slots is
+// asked of it, leaving room for the time it takes to line up.
+const REACH_MARGIN: f32 = 0.8;
+
+// Spawn state for spacing patterns: the gaps left in the pattern being
+// played, how far off it the next one is jittered, and the last pipe
+// pair's opening.
+#[derive(Clone, Default)]
+pub struct Spacing {
+    // Last gap first, so the next one pops off the end.
+    gaps: Vec<f32>,
+    // A fraction of the gap, within the tuning's `spacing_jitter`.
+    jitter: f32,
+    pub last_center: Option<f32>,
+}
+
+impl Spacing {
+    // The distance to the next slot, as a multiple of the difficulty's
+    // spacing.
+    pub fn factor(&self) -> f32 {
+        self.gaps.last().copied().unwrap_or(1.0) * (1.0 + self.jitter)
+    }
+
+    // Moves on to the gap after the next slot, picking a new pattern from
+    // the tuning's when the current one is through, and jittering it.
+    pub fn advance(&mut self, tuning: &Tuning, rng: &mut dyn RngCore) {
+        self.gaps.pop();
+        if self.gaps.is_empty() {
+            self.pick_pattern(tuning, rng);
+        }
+        let bound = tuning.spacing_jitter;
+        self.jitter = if bound > 0.0 {
+            rng.gen_range(-bound, bound)
+        } else {
+            0.0
+        };
+    }
+
+    fn pick_pattern(&mut self, tuning: &Tuning, rng: &mut dyn RngCore) {
+        let patterns = &tuning.spacing_patterns;
+        let total: u32 = patterns.iter().map(|p| p.weight).sum();
+        if total == 0 {
+            return;
+        }
+        let mut roll = rng.gen_range(0, total);
+        for pattern in patterns {
+            if roll < pattern.weight {
+                self.gaps = pattern.gaps.iter().rev().copied().collect();
+                return;
+            }
+            roll -= pattern.weight;
+        }
+    }
+}
+
+// How far the bird can surely climb and drop in `seconds`: climbing one
+// flap at a time, each flap rising until gravity has taken its impulse
+// back, and dropping from a standstill up to the max fall speed.
+pub fn reach(tuning: &Tuning, seconds: f32) -> (f32, f32) {
+    let seconds = seconds.max(0.0);
+    let climb = tuning.flap_impulse / 2.0 * seconds;
+    let top_speed_at = tuning.max_fall_speed / tuning.gravity;
+    let drop = if seconds < top_speed_at {
+        tuning.gravity * seconds * seconds / 2.0
+    } else {
+        tuning.max_fall_speed * (seconds - top_speed_at / 2.0)
+    };
+    (climb * REACH_MARGIN, drop * REACH_MARGIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_bird_reaches_further_given_longer() {
+        let tuning = Tuning::default();
+        let (climb, drop) = reach(&tuning, 1.0);
+        assert!(climb > 0.0 && drop > 0.0);
+        let (longer_climb, longer_drop) = reach(&tuning, 2.0);
+        assert_eq!(longer_climb, climb * 2.0);
+        // Falling at the max fall speed by then.
+        assert!((longer_drop - drop - tuning.max_fall_speed * REACH_MARGIN).abs() < 0.01);
+        assert_eq!(reach(&tuning, 0.0), (0.0, 0.0));
+    }
+}
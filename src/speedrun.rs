@@ -0,0 +1,186 @@
+use crate::engine::{self, Color, DrawCmd, Vec2};
+use crate::lang;
+use crate::{Direction, Game, GameState, TimeScale, PHYSICS_FPS};
+use specs::*;
+
+// The published seed every marathon attempt is played on.
+pub const MARATHON_SEED: u64 = 20_200_601;
+pub const MARATHON_TARGET: u32 = 100;
+
+// A split is taken every this many pipes.
+pub const SPLIT_INTERVAL: u32 = 25;
+
+// Things an external timer needs to hear about, in the order they happened.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitEvent {
+    Start,
+    Split(u64),
+    Reset,
+}
+
+// Speedrun clock. It counts physics ticks, starts on the first flap, takes a
+// split every SPLIT_INTERVAL pipes and, when the category has a target,
+// freezes and ends the run once the target is reached.
+pub struct Speedrun {
+    pub category: &'static str,
+    pub target: Option<u32>,
+    pub started: bool,
+    pub finished: bool,
+    pub ticks: u64,
+    pub splits: Vec<u64>,
+    // Best time for each split before this run, for comparison.
+    pub best_splits: Vec<u64>,
+    // Drained every update; see `State::forward_split_events`.
+    pub events: Vec<SplitEvent>,
+}
+
+impl Speedrun {
+    // Reach MARATHON_TARGET pipes on MARATHON_SEED as fast as possible.
+    pub fn marathon(best_splits: Vec<u64>) -> Self {
+        Speedrun::new("marathon", Some(MARATHON_TARGET), best_splits)
+    }
+
+    // Plain endless runs with the timer overlay turned on.
+    pub fn endless(best_splits: Vec<u64>) -> Self {
+        Speedrun::new("endless", None, best_splits)
+    }
+
+    // A fresh clock in the same category, for the next attempt.
+    pub fn reset(&mut self, best_splits: Vec<u64>) {
+        *self = Speedrun::new(self.category, self.target, best_splits);
+    }
+
+    fn new(category: &'static str, target: Option<u32>, best_splits: Vec<u64>) -> Self {
+        Speedrun {
+            category,
+            target,
+            started: false,
+            finished: false,
+            ticks: 0,
+            splits: Vec::new(),
+            best_splits,
+            events: Vec::new(),
+        }
+    }
+}
+
+pub(crate) struct SpeedrunSystem;
+
+impl<'a> System<'a> for SpeedrunSystem {
+    type SystemData = (
+        Option<Write<'a, Speedrun>>,
+        Read<'a, Direction>,
+        Read<'a, TimeScale>,
+        Read<'a, Game>,
+        Write<'a, GameState>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (speedrun, dir, time, game, mut state) = data;
+        let mut speedrun = match speedrun {
+            Some(speedrun) => speedrun,
+            None => return,
+        };
+
+        if speedrun.finished || time.is_paused() {
+            return;
+        }
+        if !speedrun.started && dir.jump && dir.release {
+            speedrun.started = true;
+            speedrun.events.push(SplitEvent::Start);
+        }
+        if speedrun.started {
+            speedrun.ticks += 1;
+        }
+
+        let next_split = (speedrun.splits.len() as u32 + 1) * SPLIT_INTERVAL;
+        if game.pipes_passed >= next_split {
+            let ticks = speedrun.ticks;
+            speedrun.splits.push(ticks);
+            speedrun.events.push(SplitEvent::Split(ticks));
+        }
+        if speedrun.target.is_some_and(|t| game.pipes_passed >= t) {
+            speedrun.finished = true;
+            *state = GameState::GameOver;
+        }
+    }
+}
+
+// Formats a tick count as m:ss.cc.
+pub fn format_ticks(ticks: u64) -> String {
+    let centis = ticks * 100 / u64::from(PHYSICS_FPS);
+    format!(
+        "{}:{:02}.{:02}",
+        centis / 6000,
+        (centis / 100) % 60,
+        centis % 100
+    )
+}
+
+fn format_delta(ticks: u64, best: u64) -> String {
+    if ticks < best {
+        format!("-{}", format_ticks(best - ticks))
+    } else {
+        format!("+{}", format_ticks(ticks - best))
+    }
+}
+
+// Elapsed time in the top left corner, with one line per split taken so far
+// showing how it compares to the best time for that split.
+pub fn draw_timer(cmds: &mut Vec<DrawCmd>, speedrun: &Speedrun, pipes: u32, at: Vec2) {
+    let white = Color::new(1.0, 1.0, 1.0, 1.0);
+    let header = match speedrun.target {
+        Some(target) => format!(
+            "{}  {}/{}",
+            format_ticks(speedrun.ticks),
+            pipes.min(target),
+            target
+        ),
+        None => format_ticks(speedrun.ticks),
+    };
+    cmds.push(DrawCmd::text(header, at, 30.0, white));
+
+    for (n, ticks) in speedrun.splits.iter().enumerate() {
+        let mut line = format!(
+            "{:>3}  {}",
+            (n as u32 + 1) * SPLIT_INTERVAL,
+            format_ticks(*ticks)
+        );
+        let mut color = white;
+        if let Some(best) = speedrun.best_splits.get(n) {
+            line.push_str(&format!("  {}", format_delta(*ticks, *best)));
+            color = if ticks <= best {
+                Color::new(0.3, 1.0, 0.3, 1.0)
+            } else {
+                Color::new(1.0, 0.3, 0.3, 1.0)
+            };
+        }
+        let y = 35.0 + 25.0 * n as f32;
+        cmds.push(DrawCmd::text(line, at + Vec2::new(0.0, y), 22.0, color));
+    }
+}
+
+// Marathon end screen: the run's time (if it finished) and the local
+// leaderboard of best times.
+pub fn draw_results(cmds: &mut Vec<DrawCmd>, speedrun: &Speedrun, leaderboard: &[u64]) {
+    let white = Color::new(1.0, 1.0, 1.0, 1.0);
+    let title = if speedrun.finished {
+        lang::fill("finished", &[&format_ticks(speedrun.ticks)])
+    } else {
+        lang::tr("game_over").to_string()
+    };
+    let center_x = engine::screen_width() / 2.0;
+    cmds.push(DrawCmd::centered_text(
+        title,
+        Vec2::new(center_x, 110.0),
+        60.0,
+        white,
+    ));
+
+    let mut board = format!("{}\n", lang::tr("marathon_best"));
+    for (rank, ticks) in leaderboard.iter().enumerate() {
+        board.push_str(&format!("{:>2}. {}\n", rank + 1, format_ticks(*ticks)));
+    }
+    let left = (center_x - 172.0).max(20.0);
+    cmds.push(DrawCmd::text(board, Vec2::new(left, 170.0), 30.0, white));
+}
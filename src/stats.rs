@@ -0,0 +1,483 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::{Death, DeathCause, PHYSICS_FPS};
+use ggez::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Oldest deaths are dropped past this so the file stays small.
+const MAX_DEATHS: usize = 1000;
+
+const LEADERBOARD_SIZE: usize = 10;
+
+// Seeds of this many of the latest runs are kept for the seed browser.
+const RECENT_SEEDS: usize = 10;
+
+// Same for the score of every finished run.
+const MAX_RUNS: usize = 1000;
+// And for play sessions.
+const MAX_SESSIONS: usize = 200;
+// How many of the latest runs the history graph shows.
+const GRAPH_RUNS: usize = 50;
+
+// Gap height of the default pipe layout, used to lay out the heatmap.
+const HEATMAP_GAP: f32 = 240.0;
+
+// Lifetime statistics, persisted as JSON in the user data directory.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub deaths: Vec<Death>,
+    // Final score of every run, oldest first. Scores used to count frames
+    // survived; those are left behind under the old keys.
+    #[serde(rename = "pipe_scores")]
+    pub scores: Vec<i32>,
+    // Best score for each mode, keyed by `State::mode`.
+    #[serde(rename = "best_pipe_scores")]
+    pub best_scores: HashMap<String, i32>,
+    // Best marathon times in ticks, fastest first.
+    pub marathon_times: Vec<u64>,
+    // Fastest time for each speedrun split, per category.
+    pub best_splits: HashMap<String, Vec<u64>>,
+    // Seeds of the latest runs and the bookmarked ones, newest first.
+    pub recent_seeds: Vec<u64>,
+    pub bookmarked_seeds: Vec<u64>,
+    // The game's version at the last launch, for the "What's new" panel.
+    pub last_seen_version: Option<String>,
+    // Every sitting with at least one run, oldest first.
+    pub sessions: Vec<Session>,
+    pub lifetime: Lifetime,
+    // Ids of the achievements unlocked, in the order they were.
+    pub achievements: Vec<String>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Stats {
+    // Missing or unreadable files start a fresh history instead of failing.
+    pub fn load(path: PathBuf) -> Self {
+        let mut stats = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                println!("ignoring corrupt stats file {}: {}", path.display(), e);
+                Stats::default()
+            }),
+            Err(_) => Stats::default(),
+        };
+        // Files from before the totals were kept start them from the runs
+        // they have.
+        if stats.lifetime.runs == 0 {
+            stats.lifetime.runs = stats.scores.len() as u64;
+            stats.lifetime.points = stats.scores.iter().map(|&s| i64::from(s)).sum();
+        }
+        stats.path = Some(path);
+        stats
+    }
+
+    pub fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                let json = serde_json::to_string(self)?;
+                fs::write(path, json)
+            });
+        if let Err(e) = result {
+            println!("can't save stats to {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn record_death(&mut self, death: Death) {
+        self.deaths.push(death);
+        keep_latest(&mut self.deaths, MAX_DEATHS);
+    }
+
+    // A finished run that lasted `ticks`.
+    pub fn record_run(&mut self, score: i32, ticks: u64) {
+        self.scores.push(score);
+        keep_latest(&mut self.scores, MAX_RUNS);
+        self.lifetime.runs += 1;
+        self.lifetime.points += i64::from(score);
+        self.lifetime.longest = self.lifetime.longest.max(ticks);
+    }
+
+    pub fn record_session(&mut self, session: Session) {
+        self.sessions.push(session);
+        keep_latest(&mut self.sessions, MAX_SESSIONS);
+    }
+
+    // Adds the history of another profile to this one. Its runs and deaths
+    // count as older than the ones here, and bests are the better of both.
+    pub fn merge(&mut self, other: Stats) {
+        self.deaths.splice(..0, other.deaths);
+        keep_latest(&mut self.deaths, MAX_DEATHS);
+        self.scores.splice(..0, other.scores);
+        keep_latest(&mut self.scores, MAX_RUNS);
+        self.sessions.splice(..0, other.sessions);
+        keep_latest(&mut self.sessions, MAX_SESSIONS);
+        self.lifetime.add(&other.lifetime);
+        for id in other.achievements {
+            if !self.achievements.contains(&id) {
+                self.achievements.push(id);
+            }
+        }
+        for (mode, score) in other.best_scores {
+            self.record_best_score(&mode, score);
+        }
+        for ticks in other.marathon_times {
+            self.record_marathon(ticks);
+        }
+        for (category, splits) in other.best_splits {
+            self.record_splits(&category, &splits);
+        }
+        for seed in other.bookmarked_seeds {
+            if !self.bookmarked_seeds.contains(&seed) {
+                self.bookmarked_seeds.push(seed);
+            }
+        }
+    }
+
+    // Takes the history of another profile instead, still saving here.
+    pub fn replace(&mut self, other: Stats) {
+        let path = self.path.take();
+        *self = other;
+        self.path = path;
+    }
+
+    // Puts the seed of a finished run first in the recent seeds.
+    pub fn record_seed(&mut self, seed: u64) {
+        self.recent_seeds.retain(|&s| s != seed);
+        self.recent_seeds.insert(0, seed);
+        self.recent_seeds.truncate(RECENT_SEEDS);
+    }
+
+    pub fn bookmark_seed(&mut self, seed: u64, bookmarked: bool) {
+        self.bookmarked_seeds.retain(|&s| s != seed);
+        if bookmarked {
+            self.bookmarked_seeds.insert(0, seed);
+        }
+    }
+
+    pub fn best_score(&self, mode: &str) -> Option<i32> {
+        self.best_scores.get(mode).copied()
+    }
+
+    pub fn record_best_score(&mut self, mode: &str, score: i32) {
+        let best = self.best_scores.entry(mode.to_string()).or_insert(score);
+        *best = (*best).max(score);
+    }
+
+    pub fn record_marathon(&mut self, ticks: u64) {
+        self.marathon_times.push(ticks);
+        self.marathon_times.sort_unstable();
+        self.marathon_times.truncate(LEADERBOARD_SIZE);
+    }
+
+    pub fn best_splits(&self, category: &str) -> Vec<u64> {
+        self.best_splits.get(category).cloned().unwrap_or_default()
+    }
+
+    pub fn record_splits(&mut self, category: &str, splits: &[u64]) {
+        let best = self.best_splits.entry(category.to_string()).or_default();
+        for (n, ticks) in splits.iter().enumerate() {
+            match best.get_mut(n) {
+                Some(b) if *b <= *ticks => {}
+                Some(b) => *b = *ticks,
+                None => best.push(*ticks),
+            }
+        }
+    }
+
+    fn count(&self, cause: DeathCause) -> usize {
+        self.deaths.iter().filter(|d| d.cause == cause).count()
+    }
+}
+
+// Drops the oldest entries past `max`.
+fn keep_latest<T>(items: &mut Vec<T>, max: usize) {
+    if items.len() > max {
+        let excess = items.len() - max;
+        items.drain(..excess);
+    }
+}
+
+// The seconds past the minute, as the two digits of a clock.
+fn minutes_past(seconds: u64) -> String {
+    format!("{:02}", seconds % 60)
+}
+
+// Totals over every run ever played, which unlike the score history are
+// never cut short.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Lifetime {
+    pub runs: u64,
+    pub flaps: u64,
+    pub pipes: u64,
+    // Every run's score summed, for the average.
+    pub points: i64,
+    // Ticks of the longest run, pauses left out.
+    pub longest: u64,
+}
+
+impl Lifetime {
+    pub fn average(&self) -> f32 {
+        self.points as f32 / self.runs.max(1) as f32
+    }
+
+    fn add(&mut self, other: &Lifetime) {
+        self.runs += other.runs;
+        self.flaps += other.flaps;
+        self.pipes += other.pipes;
+        self.points += other.points;
+        self.longest = self.longest.max(other.longest);
+    }
+
+    // The totals in a panel around `center`.
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, center: Vec2) {
+        cmds.push(DrawCmd::Rect {
+            rect: engine::panel(center, 520.0, 260.0),
+            color: Color::new(0.1, 0.15, 0.25, 0.92),
+        });
+        let seconds = self.longest / u64::from(PHYSICS_FPS);
+        let average = format!("{:.1}", self.average());
+        let lines = [
+            (lang::tr("lifetime_stats").to_string(), 32.0),
+            (lang::fill("runs_played", &[&self.runs]), 20.0),
+            (lang::fill("flaps", &[&self.flaps]), 20.0),
+            (lang::fill("pipes_passed", &[&self.pipes]), 20.0),
+            (lang::fill("average_score", &[&average]), 20.0),
+            (
+                lang::fill("longest_run", &[&(seconds / 60), &minutes_past(seconds)]),
+                20.0,
+            ),
+            (lang::tr("tab_back").to_string(), 16.0),
+        ];
+        for (n, (line, size)) in lines.iter().enumerate() {
+            cmds.push(DrawCmd::centered_text(
+                line.clone(),
+                Vec2::new(center.x, center.y - 104.0 + 34.0 * n as f32),
+                *size,
+                Color::new(0.9, 0.95, 1.0, 1.0),
+            ));
+        }
+    }
+}
+
+// One sitting at the game, from launch to quit.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    // Seconds since the Unix epoch when the game was launched.
+    pub started: u64,
+    pub runs: u32,
+    pub best: i32,
+    // Pipes passed over every run.
+    pub pipes: i64,
+    // Ticks spent in runs, pauses left out.
+    pub ticks: u64,
+}
+
+impl Session {
+    pub fn start() -> Self {
+        Session {
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            ..Session::default()
+        }
+    }
+
+    pub fn record_run(&mut self, score: i32) {
+        self.best = if self.runs == 0 {
+            score
+        } else {
+            self.best.max(score)
+        };
+        self.runs += 1;
+        self.pipes += i64::from(score.max(0));
+    }
+
+    // The summary shown on the way out, over whatever screen was up.
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>) {
+        let center = Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT / 2.0);
+        cmds.push(DrawCmd::Rect {
+            rect: engine::panel(center, 520.0, 220.0),
+            color: Color::new(0.1, 0.15, 0.25, 0.92),
+        });
+        let seconds = self.ticks / u64::from(PHYSICS_FPS);
+        let runs = if self.runs == 1 {
+            lang::fill("one_run_played", &[&self.runs])
+        } else {
+            lang::fill("runs_played", &[&self.runs])
+        };
+        let lines = [
+            (lang::tr("session_title").to_string(), 32.0),
+            (runs, 20.0),
+            (lang::fill("session_best", &[&self.best]), 20.0),
+            (lang::fill("pipes_passed", &[&self.pipes]), 20.0),
+            (
+                lang::fill("in_the_air", &[&(seconds / 60), &minutes_past(seconds)]),
+                20.0,
+            ),
+            (lang::tr("session_hint").to_string(), 16.0),
+        ];
+        for (n, (line, size)) in lines.iter().enumerate() {
+            cmds.push(DrawCmd::centered_text(
+                line.clone(),
+                Vec2::new(center.x, center.y - 84.0 + 34.0 * n as f32),
+                *size,
+                Color::new(0.9, 0.95, 1.0, 1.0),
+            ));
+        }
+    }
+}
+
+pub fn stats_path(ctx: &Context) -> PathBuf {
+    ctx.fs.user_data_dir().join("stats.json")
+}
+
+// Draws every recorded death relative to the lip of the pipe that was hit,
+// on a schematic pipe pair centered at `center`. Dots are translucent so
+// the spots where deaths pile up read as hotter.
+pub fn draw_death_heatmap(cmds: &mut Vec<DrawCmd>, stats: &Stats, center: Vec2) {
+    let pipe_width = 64.0;
+    let pipe_height = 200.0;
+    let top_lip = center.y - HEATMAP_GAP / 2.0;
+    let bottom_lip = center.y + HEATMAP_GAP / 2.0;
+    let pipe_color = Color::new(0.3, 0.6, 0.3, 1.0);
+
+    cmds.push(DrawCmd::Rect {
+        rect: Rect::new(
+            center.x - pipe_width / 2.0,
+            top_lip - pipe_height,
+            pipe_width,
+            pipe_height,
+        ),
+        color: pipe_color,
+    });
+    cmds.push(DrawCmd::Rect {
+        rect: Rect::new(
+            center.x - pipe_width / 2.0,
+            bottom_lip,
+            pipe_width,
+            pipe_height,
+        ),
+        color: pipe_color,
+    });
+
+    for death in &stats.deaths {
+        let lip = match death.cause {
+            DeathCause::TopPipe => top_lip,
+            DeathCause::BottomPipe => bottom_lip,
+            // Caves, hazards and the floor have no pipe lip to line up with.
+            DeathCause::Cave | DeathCause::Hazard | DeathCause::Floor => continue,
+        };
+        cmds.push(DrawCmd::Circle {
+            center: Vec2::new(center.x + death.lip_dx, lip + death.lip_dy),
+            radius: 6.0,
+            color: Color::new(1.0, 0.2, 0.1, 0.25),
+        });
+    }
+
+    let total = stats.deaths.len().max(1) as f32;
+    let percent = |cause| format!("{:.0}", 100.0 * stats.count(cause) as f32 / total);
+    // Beside the pipes, nearer and smaller on a narrow screen.
+    let (dx, size) = if engine::narrow() {
+        (60.0, 18.0)
+    } else {
+        (150.0, 30.0)
+    };
+    let summary = lang::fill(
+        "death_summary",
+        &[
+            &stats.deaths.len(),
+            &percent(DeathCause::TopPipe),
+            &percent(DeathCause::BottomPipe),
+            &percent(DeathCause::Cave),
+            &percent(DeathCause::Hazard),
+            &percent(DeathCause::Floor),
+        ],
+    );
+    cmds.push(DrawCmd::text(
+        summary,
+        Vec2::new(center.x + dx, center.y - 60.0),
+        size,
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    ));
+}
+
+// Draws the scores of the last GRAPH_RUNS runs as a bar chart inside
+// `area`, scaled to the best of them, with the latest run highlighted.
+pub fn draw_score_history(cmds: &mut Vec<DrawCmd>, stats: &Stats, area: Rect) {
+    let runs = &stats.scores[stats.scores.len().saturating_sub(GRAPH_RUNS)..];
+
+    cmds.push(DrawCmd::Rect {
+        rect: area,
+        color: Color::new(0.0, 0.0, 0.0, 0.5),
+    });
+    cmds.push(DrawCmd::text(
+        lang::fill("last_runs", &[&runs.len()]),
+        Vec2::new(area.x, area.y - 26.0),
+        20.0,
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    ));
+
+    let best = match runs.iter().max() {
+        Some(&best) if best > 0 => best,
+        _ => return,
+    };
+    let slot = area.w / GRAPH_RUNS as f32;
+    for (n, &score) in runs.iter().enumerate() {
+        let height = area.h * score as f32 / best as f32;
+        let color = if n + 1 == runs.len() {
+            Color::new(1.0, 0.9, 0.2, 1.0)
+        } else {
+            Color::new(0.3, 0.6, 0.9, 1.0)
+        };
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(
+                area.x + slot * n as f32 + 1.0,
+                area.y + area.h - height,
+                slot - 2.0,
+                height,
+            ),
+            color,
+        });
+    }
+    cmds.push(DrawCmd::text(
+        lang::fill("hud_best", &[&best]),
+        Vec2::new(area.x + area.w - 120.0, area.y - 26.0),
+        20.0,
+        Color::new(1.0, 1.0, 1.0, 1.0),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifetime_totals_outlast_the_score_history() {
+        let mut stats = Stats::default();
+        for score in 0..MAX_RUNS as i32 + 10 {
+            stats.record_run(score % 4, score as u64);
+        }
+        stats.lifetime.flaps = 7;
+
+        assert_eq!(stats.scores.len(), MAX_RUNS);
+        assert_eq!(stats.lifetime.runs, MAX_RUNS as u64 + 10);
+        assert_eq!(stats.lifetime.longest, MAX_RUNS as u64 + 9);
+        assert!((stats.lifetime.average() - 1.5).abs() < 0.01);
+
+        let mut other = Stats::default();
+        other.merge(stats);
+        assert_eq!((other.lifetime.runs, other.lifetime.flaps), (1010, 7));
+    }
+}
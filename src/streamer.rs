@@ -0,0 +1,84 @@
+use crate::lang;
+use crate::Game;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Streamer mode, from the `[streamer]` section of the config or
+// `--streamer`: player names and the directories of saved files stay off
+// the screen, the HUD keeps `margin` pixels clear of the edges for webcam
+// overlays, and the score and best score are kept in a text file that OBS
+// can show.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct StreamerConfig {
+    pub enabled: bool,
+    pub margin: f32,
+    pub file: PathBuf,
+}
+
+impl Default for StreamerConfig {
+    fn default() -> Self {
+        StreamerConfig {
+            enabled: false,
+            margin: 48.0,
+            file: PathBuf::from("./stream.txt"),
+        }
+    }
+}
+
+// Writes the score file, only when the numbers change.
+pub struct StreamOutput {
+    path: PathBuf,
+    shown: Option<(i32, i32)>,
+}
+
+impl StreamOutput {
+    pub fn new(path: PathBuf) -> Self {
+        StreamOutput { path, shown: None }
+    }
+
+    pub fn update(&mut self, game: &Game) {
+        let numbers = (game.score, game.best);
+        if self.shown == Some(numbers) {
+            return;
+        }
+        // Not retried until the numbers change, so a bad path isn't
+        // reported every frame.
+        self.shown = Some(numbers);
+        let text = format!("Score: {}\nBest: {}\n", game.score, game.best);
+        if let Err(e) = fs::write(&self.path, text) {
+            println!("can't write {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+// How a player is called on screen: by number, in turn order.
+pub fn player_label(n: usize) -> String {
+    lang::fill("player", &[&(n + 1)])
+}
+
+// `text` with `path`'s directory taken out, leaving the file name, since
+// it usually gives away the user's name.
+pub fn hide_dir(text: &str, path: &Path) -> String {
+    match path.parent().map(|dir| dir.display().to_string()) {
+        Some(dir) if !dir.is_empty() => text
+            .replace(&format!("{}{}", dir, std::path::MAIN_SEPARATOR), "")
+            .replace(&dir, ""),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hides_the_directory_of_a_path() {
+        let path = Path::new("/home/ana/.local/share/rusty_bird/profile.rbp");
+        assert_eq!(
+            hide_dir(&format!("Profile exported to {}", path.display()), path),
+            "Profile exported to profile.rbp"
+        );
+    }
+}
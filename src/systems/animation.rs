@@ -0,0 +1,33 @@
+use crate::{SpriteSheet, TimeScale};
+use specs::*;
+
+// The bird flaps its wings at 15 frames a second.
+pub const ANIMATION_TICKS: u32 = 4;
+
+// Steps every sprite sheet to its next frame once every `ANIMATION_TICKS`
+// ticks it runs, not counting paused ones.
+#[derive(Default)]
+pub struct AnimationSystem {
+    ticks: u32,
+}
+
+impl<'a> System<'a> for AnimationSystem {
+    type SystemData = (Read<'a, TimeScale>, WriteStorage<'a, SpriteSheet>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (time, mut sheets) = data;
+        if time.is_paused() {
+            return;
+        }
+        self.ticks += 1;
+        if !self.ticks.is_multiple_of(ANIMATION_TICKS) {
+            return;
+        }
+        for sheet in (&mut sheets).join() {
+            sheet.current_frame += 1;
+            if sheet.current_frame as usize >= sheet.frames().len() {
+                sheet.current_frame = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::engine::Vec2;
+use crate::settings::Settings;
+use crate::{Camera, TimeScale, SHAKE_TICKS};
+use rand::Rng;
+use specs::*;
+
+// How far the camera jumps right after a hit, in pixels.
+const SHAKE_AMPLITUDE: f32 = 12.0;
+// The part of a zoom left after each tick as it eases back out.
+const ZOOM_EASE: f32 = 0.96;
+// Closer than this and the zoom is back to none.
+const ZOOM_REST: f32 = 0.001;
+
+// Jitters the camera while a hit's shake lasts, less and less as it dies
+// down, and eases a zoom from a crash or a point back out. The jitter
+// doesn't use the challenge's generator, so seeded runs still get the same
+// pipes. It holds still while paused, and with reduced motion in the
+// settings the camera never moves, though the shake still counts down.
+pub struct CameraSystem;
+impl<'a> System<'a> for CameraSystem {
+    type SystemData = (
+        Read<'a, TimeScale>,
+        Option<Read<'a, Settings>>,
+        Option<Write<'a, Camera>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (settings, mut camera) = match data {
+            (time, settings, Some(camera)) if !time.is_paused() => (settings, camera),
+            _ => return,
+        };
+        let still = settings.is_some_and(|settings| settings.reduced_motion);
+        camera.zoom = 1.0 + (camera.zoom - 1.0) * ZOOM_EASE;
+        if camera.zoom - 1.0 < ZOOM_REST || still {
+            camera.zoom = 1.0;
+        }
+        if camera.shake == 0 || still {
+            camera.offset = Vec2::ZERO;
+            camera.shake = camera.shake.saturating_sub(1);
+            return;
+        }
+
+        let reach = SHAKE_AMPLITUDE * camera.shake as f32 / SHAKE_TICKS as f32;
+        let mut rng = rand::thread_rng();
+        camera.offset = Vec2::new(rng.gen_range(-reach, reach), rng.gen_range(-reach, reach));
+        camera.shake -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{screen_width, DrawCmd, Rect, SCREEN_HEIGHT};
+    use crate::tuning::Tuning;
+    use crate::world_setup::empty_world;
+    use crate::CRASH_ZOOM;
+
+    #[test]
+    fn a_crash_zooms_in_and_eases_back_out() {
+        let mut world = empty_world(Tuning::default());
+        let mut camera = Camera::default();
+        camera.punch(CRASH_ZOOM, Vec2::new(200.0, 300.0));
+        world.insert(camera);
+
+        // Zoomed in, the screen is still covered edge to edge.
+        let mut cmd = DrawCmd::Rect {
+            rect: Rect::new(0.0, 0.0, screen_width(), SCREEN_HEIGHT),
+            color: crate::engine::Color::WHITE,
+        };
+        camera.view(&mut cmd);
+        match cmd {
+            DrawCmd::Rect { rect, .. } => {
+                assert!(rect.x < 0.0 && rect.y < 0.0);
+                assert!(rect.x + rect.w > screen_width() && rect.y + rect.h > SCREEN_HEIGHT);
+            }
+            _ => unreachable!(),
+        }
+
+        let mut last = CRASH_ZOOM;
+        for _ in 0..200 {
+            CameraSystem.run_now(&world);
+            let zoom = world.read_resource::<Camera>().zoom;
+            assert!(zoom <= last && zoom >= 1.0);
+            last = zoom;
+        }
+        assert_eq!(last, 1.0);
+    }
+}
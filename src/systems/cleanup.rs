@@ -0,0 +1,75 @@
+use crate::engine::{self, SCREEN_HEIGHT};
+use crate::{PlayerTag, Position};
+use specs::*;
+
+// Nothing belongs further off screen than this many screen widths, or one
+// screen height. Scenery wraps within a few screen widths and obstacles are
+// removed as soon as they leave on the left.
+const MAX_OFFSET_SCREENS: f32 = 2.0;
+const MAX_OFFSET_Y: f32 = SCREEN_HEIGHT;
+
+// A world holds the scenery, the bird, a few pipe slots and at most a
+// couple of screens of cave segments, well under this. Any more means the
+// spawn and delete logic is leaking.
+pub const MAX_ENTITIES: usize = 512;
+
+// Despawns stray entities that ended up far outside the screen, and in
+// debug builds checks that the number of live entities stays bounded.
+// Runs after `ObstacleSpawnSystem`.
+pub struct CleanupSystem;
+
+impl<'a> System<'a> for CleanupSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PlayerTag>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, pos, player) = data;
+
+        let width = engine::screen_width();
+        let max_offset_x = MAX_OFFSET_SCREENS * width;
+        let sane_x = -max_offset_x..=width + max_offset_x;
+        let sane_y = -MAX_OFFSET_Y..=SCREEN_HEIGHT + MAX_OFFSET_Y;
+        for (ent, pos, _) in (&*entities, &pos, !&player).join() {
+            if !sane_x.contains(&pos.position.x) || !sane_y.contains(&pos.position.y) {
+                let _ = entities.delete(ent);
+            }
+        }
+
+        debug_assert!(
+            (&*entities).join().count() <= MAX_ENTITIES,
+            "more than {} live entities, something is leaking",
+            MAX_ENTITIES
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulate::tick_headless;
+    use crate::tuning::Tuning;
+    use crate::{build_world, Challenge, Ghost};
+
+    // Ten minutes at 60 ticks a second, enough for the difficulty to top
+    // out and for dozens of caves.
+    const TICKS: usize = 10 * 60 * 60;
+
+    #[test]
+    fn entity_count_stays_bounded() {
+        // Ghost runs never end, so the bird sinks through every pipe and
+        // keeps the spawner busy the whole time.
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(Challenge::new(1));
+        world.insert(Ghost::default());
+
+        let mut peak = 0;
+        for _ in 0..TICKS {
+            assert!(tick_headless(&mut world));
+            peak = peak.max(world.entities().join().count());
+        }
+        assert!(peak <= MAX_ENTITIES, "peaked at {} entities", peak);
+    }
+}
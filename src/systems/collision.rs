@@ -0,0 +1,615 @@
+use super::{debris, particles};
+use crate::collectibles::Collectibles;
+use crate::engine::Vec2;
+use crate::{
+    Camera, Challenge, Collectible, CollisionBox, Death, DeathCause, Difficulty, Game, GameEvent,
+    GameEvents, Ghost, Hitbox, Lives, ObstacleTag, ParticleEffects, PlayerId, PlayerTag, Position,
+    Practice, Race, BIRD_FLOOR_Y, BIRD_START_Y, CRASH_ZOOM, FLOOR_Y, GHOST_FREEZE_TICKS,
+    INVULNERABLE_TICKS, SHAKE_TICKS,
+};
+use specs::*;
+
+// Checks the birds against everything else with a collision box. Pickups
+// are collected; anything else ends the run, unless a shield, a spare life
+// or ghost mode takes the hit, or it's practice. In a race it only takes
+// the bird that hit out, until the last one. Ending the run is left to
+// `GameOverSystem`, which hears of it as `GameEvent::Died`.
+pub struct CollisionSystem;
+
+impl<'a> System<'a> for CollisionSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, CollisionBox>,
+        ReadStorage<'a, Hitbox>,
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, PlayerId>,
+        ReadStorage<'a, ObstacleTag>,
+        ReadStorage<'a, Collectible>,
+        Write<'a, Game>,
+        Option<Read<'a, Challenge>>,
+        Option<Write<'a, Ghost>>,
+        Option<Write<'a, Practice>>,
+        Option<Write<'a, Collectibles>>,
+        Option<Read<'a, Lives>>,
+        Option<Write<'a, Race>>,
+        Write<'a, GameEvents>,
+        Option<Write<'a, Camera>>,
+        Option<Read<'a, ParticleEffects>>,
+        Read<'a, Difficulty>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut pos,
+            mut coll_box,
+            hitbox,
+            player,
+            ids,
+            obs,
+            items,
+            mut game,
+            challenge,
+            ghost,
+            mut practice,
+            mut collectibles,
+            lives,
+            mut race,
+            mut events,
+            mut camera,
+            effects,
+            difficulty,
+            updater,
+        ) = data;
+
+        game.invulnerable = game.invulnerable.saturating_sub(1);
+        let mut collided = None;
+        // The pipe that was hit, if it was one, to break in ghost mode.
+        let mut hit_pipe = None;
+        // The bird that hit something, and the middle of the gap it hit if
+        // it was a pipe's, to put it back in on a spare life.
+        let mut hit_bird = None;
+        let mut picked = Vec::new();
+        // Find the player collision box
+        for (bird, player_pos, player_box, hitbox, id, _) in (
+            &entities,
+            &pos,
+            &coll_box,
+            hitbox.maybe(),
+            ids.maybe(),
+            &player,
+        )
+            .join()
+        {
+            if let (Some(race), Some(id)) = (&race, id) {
+                if !race.flying(id.0) {
+                    continue;
+                }
+            }
+            // Now check all entities with a collision box that aren't player
+            // controlled
+            for (ent, _, coll_box, obs, item, _) in (
+                &entities,
+                &pos,
+                &coll_box,
+                obs.maybe(),
+                items.maybe(),
+                !&player,
+            )
+                .join()
+            {
+                let hit = match hitbox {
+                    Some(hitbox) => hitbox.hits(player_pos.position, coll_box),
+                    None => {
+                        player_box.origin.x < coll_box.origin.x + coll_box.width
+                            && player_box.origin.x + player_box.width > coll_box.origin.x
+                            && player_box.origin.y < coll_box.origin.y + coll_box.height
+                            && player_box.origin.y + player_box.height > coll_box.origin.y
+                    }
+                };
+                if let (true, Some(item)) = (hit, item) {
+                    // More than one bird of a flock can reach it at once.
+                    if !picked.contains(&(ent, item.kind)) {
+                        picked.push((ent, item.kind));
+                    }
+                } else if hit {
+                    let top = obs.is_some_and(|obs| obs.top);
+                    let cave = obs.is_some_and(|obs| obs.cave);
+                    let hazard = obs.is_some_and(|obs| obs.hazard.is_some());
+                    if obs.is_some() && !cave && !hazard {
+                        hit_pipe = Some((ent, *coll_box, top));
+                    }
+                    // Cave ceilings start at the top of the screen.
+                    let hanging = top || (cave && coll_box.origin.y <= 0.0);
+                    let x = player_box.origin.x + player_box.width / 2.0;
+                    let y = player_box.origin.y + player_box.height / 2.0;
+                    let lip_y = if hanging {
+                        coll_box.origin.y + coll_box.height
+                    } else {
+                        coll_box.origin.y
+                    };
+                    let gap_y = match (cave || hazard, top) {
+                        (true, _) => None,
+                        (false, true) => Some(lip_y + difficulty.gap / 2.0),
+                        (false, false) => Some(lip_y - difficulty.gap / 2.0),
+                    };
+                    hit_bird = Some((bird, gap_y));
+                    collided = Some(Death {
+                        cause: if hazard {
+                            DeathCause::Hazard
+                        } else if cave {
+                            DeathCause::Cave
+                        } else if top {
+                            DeathCause::TopPipe
+                        } else {
+                            DeathCause::BottomPipe
+                        },
+                        x,
+                        y,
+                        lip_dx: x - (coll_box.origin.x + coll_box.width / 2.0),
+                        lip_dy: y - lip_y,
+                        world_x: game.distance + x,
+                        seed: challenge.as_ref().map(|c| c.seed),
+                    });
+                }
+            }
+
+            // Coming down on the floor is as deadly as a pipe.
+            let hit_pipe_too = matches!(hit_bird, Some((hit, _)) if hit == bird);
+            if player_pos.position.y >= BIRD_FLOOR_Y && !hit_pipe_too {
+                let x = player_box.origin.x + player_box.width / 2.0;
+                let y = player_box.origin.y + player_box.height / 2.0;
+                hit_bird = Some((bird, None));
+                collided = Some(Death {
+                    cause: DeathCause::Floor,
+                    x,
+                    y,
+                    lip_dx: 0.0,
+                    lip_dy: y - FLOOR_Y,
+                    world_x: game.distance + x,
+                    seed: challenge.as_ref().map(|c| c.seed),
+                });
+            }
+        }
+
+        for (ent, kind) in picked {
+            let _ = entities.delete(ent);
+            if let Some(collectibles) = &mut collectibles {
+                collectibles.pick_up(kind, &mut game);
+            }
+            events.single_write(GameEvent::PickedUp);
+        }
+
+        if let Some(practice) = &mut practice {
+            if collided.is_some() && !practice.touching {
+                practice.hits += 1;
+            }
+            practice.touching = collided.is_some();
+            return;
+        }
+        let death = match collided {
+            Some(death) => death,
+            None => return,
+        };
+        if ghost.as_ref().is_some_and(|ghost| ghost.freeze > 0) {
+            return;
+        }
+        if game.invulnerable > 0 {
+            return;
+        }
+        let shielded = match &mut collectibles {
+            Some(collectibles) if collectibles.grace > 0 => return,
+            Some(collectibles) => collectibles.break_shield(),
+            None => false,
+        };
+        if effects.is_some() {
+            particles::burst(Vec2::new(death.x, death.y), &entities, &updater);
+        }
+        let spare_life = !shielded
+            && ghost.is_none()
+            && lives.is_some_and(|lives| game.lives_lost + 1 < lives.0);
+        let survived = spare_life || shielded || ghost.is_some();
+        if spare_life {
+            game.lives_lost += 1;
+            game.invulnerable = INVULNERABLE_TICKS;
+            if let Some((bird, gap_y)) = hit_bird {
+                let height = coll_box.get(bird).map_or(0.0, |b| b.height);
+                if let Some(pos) = pos.get_mut(bird) {
+                    pos.position.y = gap_y.map_or(BIRD_START_Y, |y| y - height / 2.0);
+                    pos.speed.y = 0.0;
+                    if let Some(bird_box) = coll_box.get_mut(bird) {
+                        bird_box.origin = pos.position;
+                    }
+                }
+            }
+        } else if ghost.is_none() && !shielded {
+            let racer = hit_bird.and_then(|(bird, _)| ids.get(bird));
+            let last = match (&mut race, racer) {
+                (Some(race), Some(id)) => race.crash(id.0, game.score),
+                _ => true,
+            };
+            if last {
+                events.single_write(GameEvent::Died(death));
+                // Zoomed in on the crash, and slowly back out as the bird
+                // falls.
+                if let Some(camera) = &mut camera {
+                    camera.punch(CRASH_ZOOM, Vec2::new(death.x, death.y));
+                }
+            }
+        } else {
+            // A shield takes the hit before ghost mode does.
+            if let (Some(mut ghost), false) = (ghost, shielded) {
+                ghost.freeze = GHOST_FREEZE_TICKS;
+                ghost.hits += 1;
+            }
+        }
+        // A pipe hit that a shield, a spare life or ghost mode took breaks.
+        if let (true, Some((ent, pipe_box, top))) = (survived, hit_pipe) {
+            // The pipe stays as an invisible slot marker, so spawning and
+            // scoring carry on as usual.
+            coll_box.remove(ent);
+            debris::shatter(&pipe_box, top, difficulty.speed, &entities, &updater);
+        }
+        events.single_write(GameEvent::Crashed);
+        if let Some(mut camera) = camera {
+            camera.shake = SHAKE_TICKS;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Vec2, SCREEN_HEIGHT};
+    use crate::tuning::Tuning;
+    use crate::world_setup::{
+        bird_start, build_world, empty_world, spawn_flock, spawn_race, FLOCK_SIZE,
+    };
+    use crate::{CollectibleKind, Debris, DebrisSystem, GameOverSystem, GameState, RACERS};
+
+    // A tick's collisions and what they lead to.
+    fn collide(world: &World) {
+        CollisionSystem.run_now(world);
+        GameOverSystem.run_now(world);
+    }
+
+    fn spawn_box(world: &mut World, x: f32, y: f32) -> EntityBuilder<'_> {
+        world
+            .create_entity()
+            .with(Position {
+                position: Vec2::new(x, y),
+                speed: Vec2::ZERO,
+            })
+            .with(CollisionBox {
+                origin: Vec2::new(x, y),
+                height: 60.0,
+                width: 60.0,
+            })
+    }
+
+    #[test]
+    fn hitting_a_pipe_ends_the_run() {
+        let mut world = empty_world(Tuning::default());
+        world.insert(Camera::default());
+        spawn_box(&mut world, 100.0, 200.0).with(PlayerTag).build();
+        spawn_box(&mut world, 130.0, 240.0)
+            .with(ObstacleTag::default())
+            .build();
+        let mut reader = world.write_resource::<GameEvents>().register_reader();
+
+        collide(&world);
+
+        let events: Vec<_> = world
+            .read_resource::<GameEvents>()
+            .read(&mut reader)
+            .copied()
+            .collect();
+        assert!(matches!(
+            events[..],
+            [GameEvent::Died(_), GameEvent::Crashed]
+        ));
+        assert_eq!(*world.read_resource::<GameState>(), GameState::GameOver);
+        let death = world.read_resource::<Game>().death.unwrap();
+        assert_eq!(death.cause, DeathCause::BottomPipe);
+        assert_eq!(world.read_resource::<Camera>().shake, SHAKE_TICKS);
+    }
+
+    #[test]
+    fn boxes_only_collide_when_they_overlap() {
+        // Obstacles against a bird at (100, 200), both 60 wide and tall.
+        let cases = [
+            (
+                "touching its right edge",
+                Vec2::new(160.0, 200.0),
+                60.0,
+                false,
+            ),
+            (
+                "touching its top edge",
+                Vec2::new(100.0, 140.0),
+                60.0,
+                false,
+            ),
+            (
+                "just past its right edge",
+                Vec2::new(160.5, 200.0),
+                60.0,
+                false,
+            ),
+            (
+                "just past its bottom edge",
+                Vec2::new(100.0, 260.5),
+                60.0,
+                false,
+            ),
+            (
+                "overlapping by a pixel",
+                Vec2::new(159.0, 259.0),
+                60.0,
+                true,
+            ),
+            ("inside it", Vec2::new(120.0, 220.0), 10.0, true),
+            ("around it", Vec2::new(90.0, 190.0), 80.0, true),
+        ];
+        for (case, origin, size, hit) in cases {
+            let mut world = empty_world(Tuning::default());
+            spawn_box(&mut world, 100.0, 200.0).with(PlayerTag).build();
+            spawn_box(&mut world, origin.x, origin.y)
+                .with(CollisionBox {
+                    origin,
+                    width: size,
+                    height: size,
+                })
+                .with(ObstacleTag::default())
+                .build();
+
+            collide(&world);
+
+            let over = *world.read_resource::<GameState>() == GameState::GameOver;
+            assert_eq!(over, hit, "an obstacle {}", case);
+        }
+    }
+
+    #[test]
+    fn landing_on_the_floor_ends_the_run() {
+        let mut world = empty_world(Tuning::default());
+        spawn_box(&mut world, 100.0, BIRD_FLOOR_Y - 1.0)
+            .with(PlayerTag)
+            .build();
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+
+        world.insert(Lives(2));
+        let bird = spawn_box(&mut world, 100.0, BIRD_FLOOR_Y)
+            .with(PlayerTag)
+            .build();
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        let y = world
+            .read_storage::<Position>()
+            .get(bird)
+            .unwrap()
+            .position
+            .y;
+        assert_eq!(y, BIRD_START_Y);
+
+        world
+            .write_storage::<Position>()
+            .get_mut(bird)
+            .unwrap()
+            .position
+            .y = BIRD_FLOOR_Y;
+        world.write_resource::<Game>().invulnerable = 0;
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::GameOver);
+    }
+
+    #[test]
+    fn a_race_goes_on_until_the_last_bird_crashes() {
+        let mut world = build_world(Tuning::default(), None);
+        spawn_race(&mut world);
+        world.insert(Race::default());
+        world.write_resource::<Game>().score = 4;
+        // Only reaches the second bird.
+        let second = bird_start(1, RACERS);
+        let pipe = spawn_box(&mut world, second.x, second.y + 50.0)
+            .with(ObstacleTag::default())
+            .build();
+
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        assert_eq!(world.read_resource::<Race>().winner, Some(0));
+
+        // Out of the race, the second bird can't crash again.
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+
+        world.delete_entity(pipe).unwrap();
+        let first = bird_start(0, RACERS);
+        spawn_box(&mut world, first.x, first.y - 50.0)
+            .with(ObstacleTag {
+                top: true,
+                ..ObstacleTag::default()
+            })
+            .build();
+        world.write_resource::<Game>().score = 9;
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::GameOver);
+        assert_eq!(
+            world.read_resource::<Race>().result(9),
+            "Player 1 wins, 9 to 4"
+        );
+    }
+
+    #[test]
+    fn any_bird_of_a_flock_ends_the_run() {
+        let mut world = build_world(Tuning::default(), None);
+        spawn_flock(&mut world, FLOCK_SIZE);
+        // Only reaches the bottom bird of the stack.
+        let bottom = bird_start(FLOCK_SIZE - 1, FLOCK_SIZE);
+        spawn_box(&mut world, bottom.x, bottom.y + 50.0)
+            .with(ObstacleTag::default())
+            .build();
+
+        collide(&world);
+
+        assert_eq!(world.read_storage::<PlayerTag>().join().count(), FLOCK_SIZE);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::GameOver);
+    }
+
+    #[test]
+    fn rounded_hitboxes_forgive_the_corners() {
+        let mut world = empty_world(Tuning::default());
+        let hitbox = Hitbox::bird(crate::tuning::HitboxShape::Capsule).unwrap();
+        spawn_box(&mut world, 100.0, 200.0)
+            .with(PlayerTag)
+            .with(hitbox)
+            .build();
+        // Overlaps the bird's box by its bottom right corner only.
+        spawn_box(&mut world, 150.0, 250.0)
+            .with(ObstacleTag::default())
+            .build();
+
+        collide(&world);
+
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+    }
+
+    #[test]
+    fn ghost_hits_break_pipes() {
+        let mut world = empty_world(Tuning::default());
+        world.insert(Ghost::default());
+        spawn_box(&mut world, 100.0, 200.0).with(PlayerTag).build();
+        let pipe = spawn_box(&mut world, 130.0, 240.0)
+            .with(ObstacleTag::default())
+            .build();
+
+        collide(&world);
+        world.maintain();
+
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        assert!(!world.read_storage::<CollisionBox>().contains(pipe));
+        assert!(world.read_storage::<Debris>().join().count() > 0);
+
+        // A tick on, the chunks are still tumbling on screen.
+        DebrisSystem.run_now(&world);
+        world.maintain();
+        let positions = world.read_storage::<Position>();
+        let debris = world.read_storage::<Debris>();
+        let chunks: Vec<_> = (&positions, &debris).join().collect();
+        assert!(!chunks.is_empty());
+        assert!(chunks
+            .iter()
+            .all(|(pos, _)| pos.position.x > 0.0 && pos.position.y < SCREEN_HEIGHT));
+    }
+
+    #[test]
+    fn practice_runs_go_on_through_pipes() {
+        let mut world = empty_world(Tuning::default());
+        world.insert(Practice::default());
+        spawn_box(&mut world, 100.0, 200.0).with(PlayerTag).build();
+        spawn_box(&mut world, 130.0, 240.0)
+            .with(ObstacleTag::default())
+            .build();
+
+        // Staying in the pipe is one hit.
+        collide(&world);
+        collide(&world);
+
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        assert_eq!(world.read_storage::<CollisionBox>().join().count(), 2);
+        assert_eq!(world.read_resource::<Practice>().hits, 1);
+    }
+
+    #[test]
+    fn spare_lives_put_the_bird_back_in_the_gap() {
+        let mut world = empty_world(Tuning::default());
+        world.insert(Lives(2));
+        let bird = spawn_box(&mut world, 100.0, 200.0).with(PlayerTag).build();
+        // A top pipe whose lip is at 220.
+        let pipe = spawn_box(&mut world, 130.0, 160.0)
+            .with(ObstacleTag {
+                top: true,
+                ..ObstacleTag::default()
+            })
+            .build();
+
+        collide(&world);
+        world.maintain();
+
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        // The life took the hit, so the pipe breaks.
+        assert!(!world.read_storage::<CollisionBox>().contains(pipe));
+        assert!(world.read_storage::<Debris>().join().count() > 0);
+        let game = (*world.read_resource::<Game>()).clone();
+        assert_eq!(
+            (game.lives_lost, game.invulnerable),
+            (1, INVULNERABLE_TICKS)
+        );
+        let gap = world.read_resource::<Difficulty>().gap;
+        let y = world
+            .read_storage::<Position>()
+            .get(bird)
+            .unwrap()
+            .position
+            .y;
+        assert_eq!(y, 220.0 + gap / 2.0 - 30.0);
+
+        // Untouchable for a while, then the last life goes.
+        spawn_box(&mut world, 100.0, y)
+            .with(ObstacleTag::default())
+            .build();
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        world.write_resource::<Game>().invulnerable = 0;
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::GameOver);
+    }
+
+    #[test]
+    fn pickups_are_collected_and_shields_take_a_hit() {
+        let mut world = empty_world(Tuning::default());
+        world.insert(Collectibles::new(1));
+        spawn_box(&mut world, 100.0, 200.0).with(PlayerTag).build();
+        let coin = spawn_box(&mut world, 130.0, 240.0)
+            .with(Collectible {
+                kind: CollectibleKind::Coin,
+            })
+            .build();
+        spawn_box(&mut world, 90.0, 190.0)
+            .with(Collectible {
+                kind: CollectibleKind::Shield,
+            })
+            .build();
+
+        collide(&world);
+        world.maintain();
+
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        assert!(!world.entities().is_alive(coin));
+        assert!(world.read_resource::<Game>().score > 0);
+        assert!(world.read_resource::<Collectibles>().shield);
+
+        let pipe = spawn_box(&mut world, 130.0, 240.0)
+            .with(ObstacleTag::default())
+            .build();
+        collide(&world);
+        world.maintain();
+
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        assert!(!world.read_storage::<CollisionBox>().contains(pipe));
+        assert!(!world.read_resource::<Collectibles>().shield);
+
+        // Only the shield's grace saves the bird from the next hit.
+        spawn_box(&mut world, 110.0, 210.0)
+            .with(ObstacleTag::default())
+            .build();
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::Playing);
+        world.write_resource::<Collectibles>().grace = 0;
+        collide(&world);
+        assert_eq!(*world.read_resource::<GameState>(), GameState::GameOver);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::engine::{self, Vec2};
+use crate::{CollisionBox, Debris, Position, TimeScale};
+use specs::world::EntitiesRes;
+use specs::*;
+
+// Chunks are this wide; cap chunks are as tall as the cap and body chunks
+// as a quarter of a body segment.
+pub const CHUNK_WIDTH: f32 = 32.0;
+pub const CAP_HEIGHT: f32 = 32.0;
+pub const BODY_CHUNK_HEIGHT: f32 = 24.0;
+// Pixels a second squared.
+const GRAVITY: f32 = 1260.0;
+
+// Breaks a pipe into chunks covering what's on screen of it, from the lip
+// out to the edge. The chunks burst away from the lip and drift with the
+// scroll as they fall.
+pub(crate) fn shatter(
+    coll_box: &CollisionBox,
+    top: bool,
+    speed: f32,
+    entities: &EntitiesRes,
+    updater: &LazyUpdate,
+) {
+    let lip = if top {
+        coll_box.origin.y + coll_box.height
+    } else {
+        coll_box.origin.y
+    };
+    let length = if top {
+        lip
+    } else {
+        engine::SCREEN_HEIGHT - lip
+    };
+
+    let mut row = 0;
+    let mut along = 0.0;
+    while along < length {
+        let (height, first_piece) = if row == 0 {
+            (CAP_HEIGHT, 0)
+        } else {
+            (BODY_CHUNK_HEIGHT, 2 + 2 * (row % 2))
+        };
+        let y = if top {
+            lip - along - height
+        } else {
+            lip + along
+        };
+        for column in 0..2 {
+            let side = if column == 0 { -1.0 } else { 1.0 };
+            let chunk = entities.create();
+            updater.insert(
+                chunk,
+                Position {
+                    position: Vec2::new(coll_box.origin.x + CHUNK_WIDTH * column as f32, y),
+                    speed: Vec2::new(
+                        -speed + side * (60.0 + 30.0 * (row % 3) as f32),
+                        -120.0 - 60.0 * (row % 2) as f32,
+                    ),
+                },
+            );
+            updater.insert(
+                chunk,
+                Debris {
+                    piece: first_piece + column,
+                    rotation: 0.0,
+                    spin: side * (3.0 + 1.2 * (row % 4) as f32),
+                },
+            );
+        }
+        along += height;
+        row += 1;
+    }
+}
+
+// Tumbles pipe chunks under gravity and removes them once they've left
+// the screen.
+pub struct DebrisSystem;
+
+impl<'a> System<'a> for DebrisSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, TimeScale>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Debris>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, time, mut pos, mut debris) = data;
+        let dt = time.delta();
+
+        for (ent, pos, debris) in (&*entities, &mut pos, &mut debris).join() {
+            pos.speed.y += GRAVITY * dt;
+            pos.position += pos.speed * dt;
+            debris.rotation += debris.spin * dt;
+            if pos.position.y > engine::SCREEN_HEIGHT || pos.position.x < -2.0 * CHUNK_WIDTH {
+                let _ = entities.delete(ent);
+            }
+        }
+    }
+}
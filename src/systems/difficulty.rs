@@ -0,0 +1,31 @@
+use crate::tuning::Tuning;
+use crate::{BackgroundTag, Difficulty, Game};
+use specs::*;
+
+// Ramps the difficulty up with the score. When the scroll speed changes,
+// everything moving at the old one, obstacles and floor alike, picks up
+// the new one; pipes spawned from then on use the new spacing and gap.
+pub struct DifficultySystem;
+
+impl<'a> System<'a> for DifficultySystem {
+    type SystemData = (
+        Read<'a, Game>,
+        Read<'a, Tuning>,
+        Write<'a, Difficulty>,
+        WriteStorage<'a, BackgroundTag>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (game, tuning, mut difficulty, mut bg) = data;
+
+        let next = Difficulty::at(&tuning, game.score);
+        if next.speed != difficulty.speed {
+            for bg in (&mut bg).join() {
+                if bg.velocity == difficulty.speed {
+                    bg.velocity = next.speed;
+                }
+            }
+        }
+        *difficulty = next;
+    }
+}
@@ -0,0 +1,78 @@
+use crate::tuning::Tuning;
+use crate::{CollisionBox, DeathFall, GameState, PlayerTag, Position, TimeScale, BIRD_FLOOR_Y};
+use specs::*;
+use std::f32::consts::FRAC_PI_2;
+
+// How fast the bird flips over as it drops, in radians per second.
+const SPIN: f32 = 2.0 * std::f32::consts::PI;
+
+// Drops the birds to the floor after a crash, turning them nose down, and
+// brings up the results once they lie there. Nothing else moves meanwhile.
+pub struct DyingSystem;
+impl<'a> System<'a> for DyingSystem {
+    type SystemData = (
+        WriteExpect<'a, DeathFall>,
+        Write<'a, GameState>,
+        Read<'a, Tuning>,
+        Read<'a, TimeScale>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, CollisionBox>,
+        ReadStorage<'a, PlayerTag>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut fall, mut state, tuning, time, mut positions, mut boxes, players) = data;
+        let dt = time.delta();
+        fall.rotation = (fall.rotation + SPIN * dt).min(FRAC_PI_2);
+
+        let mut landed = true;
+        for (pos, coll_box, _) in (&mut positions, (&mut boxes).maybe(), &players).join() {
+            pos.speed.y = (pos.speed.y + tuning.gravity * dt).min(tuning.max_fall_speed);
+            pos.position.y = (pos.position.y + pos.speed.y * dt).min(BIRD_FLOOR_Y);
+            if let Some(coll_box) = coll_box {
+                coll_box.origin = pos.position;
+            }
+            landed &= pos.position.y >= BIRD_FLOOR_Y;
+        }
+        if landed && fall.rotation >= FRAC_PI_2 {
+            *state = GameState::GameOver;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::empty_world;
+    use crate::engine::Vec2;
+
+    #[test]
+    fn the_bird_drops_to_the_floor_before_the_results() {
+        let mut world = empty_world(Tuning::default());
+        world.insert(GameState::Dying);
+        world.insert(DeathFall::default());
+        let bird = world
+            .create_entity()
+            .with(Position {
+                position: Vec2::new(100.0, 200.0),
+                speed: Vec2::new(0.0, -300.0),
+            })
+            .with(PlayerTag)
+            .build();
+
+        let mut ticks = 0;
+        while *world.read_resource::<GameState>() == GameState::Dying {
+            DyingSystem.run_now(&world);
+            ticks += 1;
+            assert!(ticks < 300, "still falling");
+        }
+        let y = world
+            .read_storage::<Position>()
+            .get(bird)
+            .unwrap()
+            .position
+            .y;
+        assert_eq!(y, BIRD_FLOOR_Y);
+        assert_eq!(world.read_resource::<DeathFall>().rotation, FRAC_PI_2);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::{Game, GameEvent, GameEvents, GameOverReader, GameState};
+use specs::*;
+
+// Ends the run when `GameEvent::Died` comes through, right after
+// `CollisionSystem`, keeping the death for the game over screen.
+pub struct GameOverSystem;
+
+impl<'a> System<'a> for GameOverSystem {
+    type SystemData = (
+        Read<'a, GameEvents>,
+        WriteExpect<'a, GameOverReader>,
+        Write<'a, Game>,
+        Write<'a, GameState>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (events, mut reader, mut game, mut state) = data;
+        for event in events.read(&mut reader.0) {
+            if let GameEvent::Died(death) = event {
+                *state = GameState::GameOver;
+                game.death = Some(*death);
+            }
+        }
+    }
+}
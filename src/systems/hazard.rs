@@ -0,0 +1,42 @@
+use crate::engine;
+use crate::hazards::Hazards;
+use crate::{Challenge, Difficulty, Game};
+use rand::RngCore;
+use specs::*;
+
+// Brings in the next floor hazard as its spot reaches the right edge of the
+// screen. Hazards scroll along with the floor as obstacles, so they're
+// removed by `ObstacleSpawnSystem` like pipes. Runs after it.
+pub struct HazardSystem;
+
+impl<'a> System<'a> for HazardSystem {
+    type SystemData = (
+        Read<'a, Game>,
+        Read<'a, Difficulty>,
+        Option<Write<'a, Challenge>>,
+        Write<'a, Hazards>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (game, difficulty, mut challenge, mut hazards, entities, updater) = data;
+
+        if !hazards.due(game.distance, engine::screen_width()) {
+            return;
+        }
+
+        let mut thread_rng = rand::thread_rng();
+        let rng: &mut dyn RngCore = match &mut challenge {
+            Some(challenge) => &mut challenge.rng,
+            None => &mut thread_rng,
+        };
+        hazards.spawn(
+            engine::screen_width(),
+            &difficulty,
+            rng,
+            &entities,
+            &updater,
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use crate::world_setup::bird_start;
+use crate::{CollisionBox, GetReady, PlayerTag, Position};
+use specs::*;
+
+// How far above and below its start height the bird bobs, and how fast, in
+// radians per tick.
+const BOB_HEIGHT: f32 = 8.0;
+const BOB_SPEED: f32 = 0.1;
+
+// Bobs the birds gently up and down around their start heights on the get
+// ready screen, and counts down the countdown.
+pub struct IdleBobSystem;
+impl<'a> System<'a> for IdleBobSystem {
+    type SystemData = (
+        WriteExpect<'a, GetReady>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, CollisionBox>,
+        ReadStorage<'a, PlayerTag>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut ready, mut positions, mut boxes, players) = data;
+        ready.ticks += 1;
+
+        let bob = BOB_HEIGHT * (ready.ticks as f32 * BOB_SPEED).sin();
+        let birds = players.join().count();
+        for (n, (pos, coll_box, _)) in (&mut positions, &mut boxes, &players).join().enumerate() {
+            pos.position.y = bird_start(n, birds).y + bob;
+            pos.speed.y = 0.0;
+            coll_box.origin = pos.position;
+        }
+    }
+}
@@ -0,0 +1,39 @@
+// The core systems, run in order each tick by `schedule.rs`: difficulty,
+// movement and scrolling, obstacle and hazard spawning and cleanup, then
+// animation, collision and ending the run on a death, breaking pipes hit
+// in ghost mode into debris, and finally counting and scoring passed
+// obstacles, checking the game mode's goal, and moving particles. Before a
+// run only the idle bob and animation run, while the bird drops after a
+// crash the fall, and after one only the camera shake and the particles.
+
+mod animation;
+mod camera;
+mod cleanup;
+mod collision;
+pub(crate) mod debris;
+mod difficulty;
+mod dying;
+mod game_over;
+mod hazard;
+mod idle_bob;
+mod movement;
+pub(crate) mod particles;
+mod rules;
+mod scoring;
+mod spawn;
+
+pub use animation::{AnimationSystem, ANIMATION_TICKS};
+pub use camera::CameraSystem;
+pub use cleanup::{CleanupSystem, MAX_ENTITIES};
+pub use collision::CollisionSystem;
+pub use debris::DebrisSystem;
+pub use difficulty::DifficultySystem;
+pub use dying::DyingSystem;
+pub use game_over::GameOverSystem;
+pub use hazard::HazardSystem;
+pub use idle_bob::IdleBobSystem;
+pub use movement::{MovementSystem, ScrollSystem};
+pub use particles::ParticleSystem;
+pub use rules::RulesSystem;
+pub use scoring::{PipeCounterSystem, ScoringSystem};
+pub use spawn::{ObstacleSpawnSystem, PipePool, PIPE_WIDTH};
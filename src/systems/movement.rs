@@ -0,0 +1,363 @@
+use super::particles;
+use crate::settings::Settings;
+use crate::tuning::Tuning;
+use crate::{
+    Backdrop, BackgroundTag, Collectible, CollisionBox, Difficulty, Direction, Drift, Game,
+    GameEvent, GameEvents, ObstacleTag, ParticleEffects, PlayerId, PlayerTag, Position, Race,
+    ScrollSpeed, TimeScale, VerticalOscillator, Wind, BIRD_FLOOR_Y,
+};
+use specs::*;
+use std::f32::consts::TAU;
+
+// Moves the bird and scrolls the obstacles, keeping collision boxes on top
+// of their entities. Obstacles come and go in `ObstacleSpawnSystem`.
+pub struct MovementSystem;
+impl<'a> System<'a> for MovementSystem {
+    type SystemData = (
+        Write<'a, Direction>,
+        Write<'a, Game>,
+        Read<'a, Tuning>,
+        Read<'a, Difficulty>,
+        Read<'a, TimeScale>,
+        Read<'a, ScrollSpeed>,
+        Option<Read<'a, Drift>>,
+        Option<Read<'a, Wind>>,
+        Option<Write<'a, Race>>,
+        Write<'a, GameEvents>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, PlayerTag>,
+        ReadStorage<'a, PlayerId>,
+        ReadStorage<'a, BackgroundTag>,
+        ReadStorage<'a, ObstacleTag>,
+        ReadStorage<'a, Collectible>,
+        WriteStorage<'a, CollisionBox>,
+        WriteStorage<'a, VerticalOscillator>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+        Option<Read<'a, ParticleEffects>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut dir,
+            mut game,
+            tuning,
+            difficulty,
+            time,
+            scroll_speed,
+            drift,
+            wind,
+            mut race,
+            mut events,
+            mut pos,
+            player,
+            ids,
+            bg,
+            obs,
+            items,
+            mut coll,
+            mut oscillators,
+            entities,
+            updater,
+            effects,
+        ) = data;
+        let dt = time.delta();
+        let scroll = dt * scroll_speed.0;
+        game.distance += difficulty.speed * scroll;
+
+        // One flap moves every bird, in flock mode too. In a race the second
+        // bird has controls of its own.
+        let mut take_flap = |dir: &mut Direction| {
+            let flap = dir.jump && dir.release && !time.is_paused();
+            if flap {
+                dir.jump = false;
+                events.single_write(GameEvent::Flapped);
+            }
+            flap
+        };
+        let flaps = [
+            take_flap(&mut dir),
+            race.as_mut()
+                .is_some_and(|race| take_flap(&mut race.second)),
+        ];
+        // A press is held from the flap until the key goes up.
+        let held = [
+            !dir.release,
+            race.as_ref().is_some_and(|race| !race.second.release),
+        ];
+        for (pos, id, _) in (&mut pos, ids.maybe(), &player).join() {
+            let id = id.map_or(0, |id| id.0);
+            // Crashed racers stay where they fell, out of sight.
+            if race.as_ref().is_some_and(|race| !race.flying(id)) {
+                continue;
+            }
+            if flaps[id] {
+                pos.speed.y = (pos.speed.y - tuning.flap_impulse).max(-tuning.max_rise_speed);
+                if effects.is_some() {
+                    particles::puff(pos.position, &entities, &updater);
+                }
+            } else {
+                let gravity = if held[id] && pos.speed.y < 0.0 {
+                    tuning.gravity * tuning.hold_gravity
+                } else {
+                    tuning.gravity
+                };
+                pos.speed.y = (pos.speed.y + gravity * dt).min(tuning.max_fall_speed);
+            }
+
+            pos.position.y += pos.speed.y * dt;
+            if let Some(wind) = &wind {
+                pos.position.y += wind.push(game.distance) * dt;
+            }
+
+            if let Some(drift) = &drift {
+                if dir.right != dir.left {
+                    let push = if dir.right { drift.accel } else { -drift.accel };
+                    pos.speed.x =
+                        (pos.speed.x + push * dt).clamp(-drift.max_speed, drift.max_speed);
+                } else if pos.speed.x.abs() <= drift.friction * dt {
+                    pos.speed.x = 0.0;
+                } else {
+                    pos.speed.x -= drift.friction * dt * pos.speed.x.signum();
+                }
+
+                pos.position.x += pos.speed.x * dt;
+                if pos.position.x < drift.min_x || pos.position.x > drift.max_x {
+                    pos.position.x = pos.position.x.clamp(drift.min_x, drift.max_x);
+                    pos.speed.x = 0.0;
+                }
+            }
+
+            if pos.position.y < 0.0 {
+                pos.position.y = 0.0;
+                pos.speed.y = 0.0;
+            } else if pos.position.y > BIRD_FLOOR_Y {
+                pos.position.y = BIRD_FLOOR_Y;
+                pos.speed.y = 0.0;
+            }
+        }
+
+        // Pickups scroll along with the pipes they're between.
+        for (pos, bg, _) in (&mut pos, &bg, obs.mask() | items.mask()).join() {
+            pos.position.x -= bg.velocity * scroll;
+        }
+        for (pos, osc) in (&mut pos, &mut oscillators).join() {
+            osc.phase = (osc.phase + TAU / osc.period * dt) % TAU;
+            pos.position.y = osc.base_y + osc.amplitude * osc.phase.sin();
+        }
+
+        for (pos, coll_box) in (&mut pos, &mut coll).join() {
+            // if an entity has an updated position, we also need to update
+            // it's collision box
+            coll_box.origin.x = pos.position.x;
+            coll_box.origin.y = pos.position.y;
+        }
+    }
+}
+
+// Scrolls the scenery (background layers and floor), wrapping every copy
+// around once it leaves the screen. Runs in the menu too, where nothing
+// else moves. With reduced motion in the settings the far backgrounds hold
+// still, and only the floor keeps pace with the pipes.
+pub struct ScrollSystem;
+impl<'a> System<'a> for ScrollSystem {
+    type SystemData = (
+        Read<'a, TimeScale>,
+        Read<'a, ScrollSpeed>,
+        Option<Read<'a, Settings>>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, BackgroundTag>,
+        ReadStorage<'a, Backdrop>,
+        ReadStorage<'a, ObstacleTag>,
+        ReadStorage<'a, Collectible>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (time, scroll_speed, settings, mut pos, bg, backdrops, obs, items) = data;
+        let still = settings.is_some_and(|settings| settings.reduced_motion);
+
+        for (pos, bg, backdrop, _, _) in (&mut pos, &bg, backdrops.maybe(), !&obs, !&items).join() {
+            if still && backdrop.is_some() {
+                continue;
+            }
+            pos.position.x -= bg.velocity * time.delta() * scroll_speed.0;
+
+            // A tile that's gone off the left edge moves behind the last
+            // one, however far past the edge it got in one tick.
+            let span = bg.width * bg.num_copies as f32;
+            pos.position.x = (pos.position.x + bg.width).rem_euclid(span) - bg.width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::empty_world;
+    use crate::engine::Vec2;
+
+    fn bird(world: &mut World, y: f32) -> Entity {
+        world
+            .create_entity()
+            .with(Position {
+                position: Vec2::new(100.0, y),
+                speed: Vec2::ZERO,
+            })
+            .with(PlayerTag)
+            .build()
+    }
+
+    fn speed(world: &World, bird: Entity) -> Vec2 {
+        world.read_storage::<Position>().get(bird).unwrap().speed
+    }
+
+    #[test]
+    fn gravity_stops_at_the_max_fall_speed() {
+        let mut world = empty_world(Tuning::default());
+        let bird = bird(&mut world, 0.0);
+        let tuning = Tuning::default();
+        let dt = TimeScale::default().delta();
+
+        MovementSystem.run_now(&world);
+        assert!((speed(&world, bird).y - tuning.gravity * dt).abs() < 0.01);
+        for _ in 0..60 {
+            MovementSystem.run_now(&world);
+            assert!(speed(&world, bird).y <= tuning.max_fall_speed);
+        }
+        assert_eq!(speed(&world, bird).y, tuning.max_fall_speed);
+    }
+
+    #[test]
+    fn a_flap_kicks_once_a_press() {
+        let mut world = empty_world(Tuning::default());
+        let bird = bird(&mut world, 300.0);
+        let impulse = Tuning::default().flap_impulse;
+        let press = Direction {
+            jump: true,
+            ..Direction::new()
+        };
+
+        *world.write_resource::<Direction>() = press;
+        MovementSystem.run_now(&world);
+        assert_eq!(speed(&world, bird).y, -impulse);
+        // Held down, the key doesn't flap again until it's let go.
+        world.write_resource::<Direction>().release = false;
+        MovementSystem.run_now(&world);
+        assert!(speed(&world, bird).y > -impulse);
+
+        // Another flap adds up, but only to the max rise speed.
+        let rising = speed(&world, bird).y;
+        *world.write_resource::<Direction>() = press;
+        MovementSystem.run_now(&world);
+        assert_eq!(speed(&world, bird).y, rising - impulse);
+        *world.write_resource::<Direction>() = press;
+        MovementSystem.run_now(&world);
+        assert_eq!(speed(&world, bird).y, -Tuning::default().max_rise_speed);
+
+        // The top of the screen stops it dead.
+        for _ in 0..60 {
+            *world.write_resource::<Direction>() = press;
+            MovementSystem.run_now(&world);
+        }
+        let pos = world.read_storage::<Position>().get(bird).unwrap().clone();
+        assert_eq!(pos.position.y, 0.0);
+    }
+
+    #[test]
+    fn holding_the_flap_flies_higher() {
+        let peak = |hold_gravity: f32| {
+            let mut world = empty_world(Tuning {
+                hold_gravity,
+                ..Tuning::default()
+            });
+            let bird = bird(&mut world, 400.0);
+            *world.write_resource::<Direction>() = Direction {
+                jump: true,
+                ..Direction::new()
+            };
+            MovementSystem.run_now(&world);
+            // Held down until it starts falling again.
+            world.write_resource::<Direction>().release = false;
+            while speed(&world, bird).y < 0.0 {
+                MovementSystem.run_now(&world);
+            }
+            let y = world
+                .read_storage::<Position>()
+                .get(bird)
+                .unwrap()
+                .position
+                .y;
+            y
+        };
+        assert!(peak(0.5) < peak(1.0) - 50.0);
+    }
+
+    #[test]
+    fn a_stopped_world_leaves_the_pipes_where_they_are() {
+        let mut world = empty_world(Tuning::default());
+        let bird = bird(&mut world, 300.0);
+        let pipe = world
+            .create_entity()
+            .with(Position {
+                position: Vec2::new(500.0, 300.0),
+                speed: Vec2::ZERO,
+            })
+            .with(BackgroundTag {
+                velocity: 240.0,
+                width: 100.0,
+                num_copies: 1,
+            })
+            .with(ObstacleTag::default())
+            .build();
+        let x = |world: &World| {
+            world
+                .read_storage::<Position>()
+                .get(pipe)
+                .unwrap()
+                .position
+                .x
+        };
+
+        MovementSystem.run_now(&world);
+        assert!(x(&world) < 500.0);
+
+        world.insert(ScrollSpeed(0.0));
+        let stopped_at = x(&world);
+        MovementSystem.run_now(&world);
+        assert_eq!(x(&world), stopped_at);
+        // The bird still falls.
+        assert!(speed(&world, bird).y > 0.0);
+    }
+
+    #[test]
+    fn reduced_motion_holds_the_far_backgrounds_still() {
+        let mut world = empty_world(Tuning::default());
+        let mut settings = Settings::default();
+        settings.reduced_motion = true;
+        world.insert(settings);
+        let layer = |world: &mut World, backdrop: Option<Backdrop>| {
+            let mut builder = world
+                .create_entity()
+                .with(Position {
+                    position: Vec2::new(0.0, 0.0),
+                    speed: Vec2::ZERO,
+                })
+                .with(BackgroundTag {
+                    velocity: 120.0,
+                    width: 1024.0,
+                    num_copies: 2,
+                });
+            if let Some(backdrop) = backdrop {
+                builder = builder.with(backdrop);
+            }
+            builder.build()
+        };
+        let sky = layer(&mut world, Some(Backdrop(0)));
+        let floor = layer(&mut world, None);
+
+        ScrollSystem.run_now(&world);
+        let positions = world.read_storage::<Position>();
+        assert_eq!(positions.get(sky).unwrap().position.x, 0.0);
+        assert!(positions.get(floor).unwrap().position.x < 0.0);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::engine::{self, Color, Vec2};
+use crate::{FloatingText, Particle, Position, TimeScale, PHYSICS_FPS};
+use rand::Rng;
+use specs::world::EntitiesRes;
+use specs::*;
+
+const FEATHERS_PER_FLAP: usize = 5;
+const PARTICLES_PER_CRASH: usize = 24;
+
+// A few feathers falling away behind a bird at `at`, its top left corner,
+// as it flaps.
+pub(crate) fn puff(at: Vec2, entities: &EntitiesRes, updater: &LazyUpdate) {
+    let mut rng = rand::thread_rng();
+    for _ in 0..FEATHERS_PER_FLAP {
+        let shade = rng.gen_range(0.85, 1.0);
+        spawn(
+            entities,
+            updater,
+            at + Vec2::new(rng.gen_range(4.0, 20.0), rng.gen_range(28.0, 48.0)),
+            Vec2::new(rng.gen_range(-150.0, -30.0), rng.gen_range(30.0, 120.0)),
+            Particle {
+                age: 0.0,
+                lifetime: rng.gen_range(20.0, 35.0),
+                gravity: 180.0,
+                size: 4.0,
+                color: Color::new(shade, shade, shade * 0.8, 1.0),
+            },
+        );
+    }
+}
+
+// Feathers and dust bursting out of a crash at `at`.
+pub(crate) fn burst(at: Vec2, entities: &EntitiesRes, updater: &LazyUpdate) {
+    let mut rng = rand::thread_rng();
+    for n in 0..PARTICLES_PER_CRASH {
+        let angle = rng.gen_range(0.0, std::f32::consts::TAU);
+        let speed = rng.gen_range(90.0, 300.0);
+        let color = if n % 3 == 0 {
+            Color::new(1.0, 0.95, 0.8, 1.0)
+        } else {
+            Color::new(0.6, 0.5, 0.35, 1.0)
+        };
+        spawn(
+            entities,
+            updater,
+            at,
+            Vec2::new(angle.cos(), angle.sin()) * speed,
+            Particle {
+                age: 0.0,
+                lifetime: rng.gen_range(30.0, 50.0),
+                gravity: 540.0,
+                size: rng.gen_range(3.0, 6.0),
+                color,
+            },
+        );
+    }
+}
+
+// A "+1" rising from just above `at` and fading out over half a second.
+pub(crate) fn score_popup(at: Vec2, entities: &EntitiesRes, updater: &LazyUpdate) {
+    let entity = entities.create();
+    updater.insert(
+        entity,
+        Position {
+            position: at,
+            speed: Vec2::new(0.0, -120.0),
+        },
+    );
+    updater.insert(
+        entity,
+        Particle {
+            age: 0.0,
+            lifetime: PHYSICS_FPS as f32 / 2.0,
+            gravity: 180.0,
+            size: 28.0,
+            color: Color::new(1.0, 0.95, 0.6, 1.0),
+        },
+    );
+    updater.insert(entity, FloatingText("+1"));
+}
+
+fn spawn(
+    entities: &EntitiesRes,
+    updater: &LazyUpdate,
+    position: Vec2,
+    speed: Vec2,
+    particle: Particle,
+) {
+    let entity = entities.create();
+    updater.insert(entity, Position { position, speed });
+    updater.insert(entity, particle);
+}
+
+// Moves particles under their gravity, fading them out over their lifetime,
+// and removes them once it's up or they've left the screen.
+pub struct ParticleSystem;
+
+impl<'a> System<'a> for ParticleSystem {
+    type SystemData = (
+        Entities<'a>,
+        Read<'a, TimeScale>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Particle>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, time, mut pos, mut particles) = data;
+        let dt = time.delta();
+
+        for (ent, pos, particle) in (&*entities, &mut pos, &mut particles).join() {
+            particle.age += time.0;
+            pos.speed.y += particle.gravity * dt;
+            pos.position += pos.speed * dt;
+            particle.color.a = (1.0 - particle.age / particle.lifetime).max(0.0);
+            if particle.age >= particle.lifetime
+                || pos.position.y > engine::SCREEN_HEIGHT
+                || pos.position.x < -particle.size
+            {
+                let _ = entities.delete(ent);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::Tuning;
+    use crate::world_setup::empty_world;
+
+    #[test]
+    fn particles_fade_out_and_go() {
+        let mut world = empty_world(Tuning::default());
+        burst(
+            Vec2::new(100.0, 100.0),
+            &world.entities(),
+            &world.read_resource(),
+        );
+        world.maintain();
+
+        ParticleSystem.run_now(&world);
+        let fading = world
+            .read_storage::<Particle>()
+            .join()
+            .next()
+            .map(|p| p.color.a);
+        assert!(fading.is_some_and(|alpha| alpha > 0.0 && alpha < 1.0));
+
+        for _ in 0..60 {
+            ParticleSystem.run_now(&world);
+            world.maintain();
+        }
+        assert_eq!(world.read_storage::<Particle>().join().count(), 0);
+    }
+
+    #[test]
+    fn score_popups_rise_and_are_gone_in_half_a_second() {
+        let mut world = empty_world(Tuning::default());
+        let at = Vec2::new(100.0, 100.0);
+        score_popup(at, &world.entities(), &world.read_resource());
+        world.maintain();
+
+        ParticleSystem.run_now(&world);
+        {
+            let positions = world.read_storage::<Position>();
+            let texts = world.read_storage::<FloatingText>();
+            let (pos, text) = (&positions, &texts).join().next().unwrap();
+            assert_eq!(text.0, "+1");
+            assert!(pos.position.y < at.y);
+        }
+
+        for _ in 1..PHYSICS_FPS / 2 {
+            ParticleSystem.run_now(&world);
+            world.maintain();
+        }
+        assert_eq!(world.read_storage::<FloatingText>().join().count(), 0);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{Game, GameMode, GameState, TARGET_SCORE, TIME_ATTACK_TICKS};
+use specs::*;
+
+// Ends the run once its game mode's goal is met, after scoring: when the
+// time attack clock runs out, or when the score reaches the target.
+// Crashes are left to `GameOverSystem` in every mode.
+pub struct RulesSystem;
+
+impl<'a> System<'a> for RulesSystem {
+    type SystemData = (Read<'a, GameMode>, Write<'a, Game>, Write<'a, GameState>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mode, mut game, mut state) = data;
+        if *state != GameState::Playing {
+            return;
+        }
+        let cleared = match *mode {
+            GameMode::Endless => false,
+            GameMode::TimeAttack => game.frames >= TIME_ATTACK_TICKS,
+            GameMode::Target => game.score >= TARGET_SCORE,
+        };
+        if cleared {
+            game.cleared = true;
+            *state = GameState::GameOver;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::Tuning;
+    use crate::world_setup::empty_world;
+
+    #[test]
+    fn each_mode_ends_on_its_own_goal() {
+        let cases = [
+            (GameMode::Endless, TIME_ATTACK_TICKS, TARGET_SCORE, false),
+            (GameMode::TimeAttack, TIME_ATTACK_TICKS - 1, 99, false),
+            (GameMode::TimeAttack, TIME_ATTACK_TICKS, 0, true),
+            (GameMode::Target, TIME_ATTACK_TICKS, TARGET_SCORE - 1, false),
+            (GameMode::Target, 1, TARGET_SCORE, true),
+        ];
+        for (mode, frames, score, over) in cases {
+            let mut world = empty_world(Tuning::default());
+            world.insert(mode);
+            {
+                let mut game = world.write_resource::<Game>();
+                game.frames = frames;
+                game.score = score;
+            }
+
+            RulesSystem.run_now(&world);
+
+            let state = *world.read_resource::<GameState>();
+            assert_eq!(state == GameState::GameOver, over, "{:?}", mode);
+            assert_eq!(world.read_resource::<Game>().cleared, over, "{:?}", mode);
+        }
+    }
+}
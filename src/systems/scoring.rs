@@ -0,0 +1,75 @@
+use crate::engine;
+use crate::{
+    BackgroundTag, CollisionBox, Game, GameEvent, GameEvents, Ghost, ObstacleTag, PlayerTag,
+    Position,
+};
+use specs::*;
+
+// Counts a pipe pair, or a cave slot, once the bird is fully past its top
+// obstacle.
+pub struct PipeCounterSystem;
+
+impl<'a> System<'a> for PipeCounterSystem {
+    type SystemData = (
+        WriteStorage<'a, ObstacleTag>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, BackgroundTag>,
+        ReadStorage<'a, CollisionBox>,
+        ReadStorage<'a, PlayerTag>,
+        Write<'a, Game>,
+        Write<'a, GameEvents>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut obs, pos, bg, coll_box, player, mut game, mut events) = data;
+
+        for (player_box, _) in (&coll_box, &player).join() {
+            for (obs, pos, bg) in (&mut obs, &pos, &bg).join() {
+                if obs.top && !obs.passed && pos.position.x + bg.width < player_box.origin.x {
+                    obs.passed = true;
+                    game.pipes_passed += 1;
+
+                    let pipe_x = pos.position.x + bg.width / 2.0;
+                    let bird_x = player_box.origin.x + player_box.width / 2.0;
+                    let pan = (pipe_x - bird_x) / (engine::screen_width() / 2.0);
+                    events.single_write(GameEvent::Passed { pan });
+                }
+            }
+        }
+    }
+}
+
+// Scores a point when the bird's center passes the middle of a pipe pair,
+// or of a cave slot, like the original game. Slots passed while a ghost hit
+// has the score frozen don't count.
+pub struct ScoringSystem;
+
+impl<'a> System<'a> for ScoringSystem {
+    type SystemData = (
+        WriteStorage<'a, ObstacleTag>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, BackgroundTag>,
+        ReadStorage<'a, CollisionBox>,
+        ReadStorage<'a, PlayerTag>,
+        Write<'a, Game>,
+        Option<Read<'a, Ghost>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut obs, pos, bg, coll_box, player, mut game, ghost) = data;
+        let frozen = ghost.is_some_and(|ghost| ghost.freeze > 0);
+
+        for (player_box, _) in (&coll_box, &player).join() {
+            let bird_x = player_box.origin.x + player_box.width / 2.0;
+            for (obs, pos, bg) in (&mut obs, &pos, &bg).join() {
+                if obs.top && !obs.scored && pos.position.x + bg.width / 2.0 < bird_x {
+                    obs.scored = true;
+                    if !frozen {
+                        game.score += 1;
+                        game.best = game.best.max(game.score);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,540 @@
+use crate::caves::Caves;
+use crate::collectibles::{self, Collectibles};
+use crate::engine::{self, Vec2};
+use crate::spacing::{self, Spacing, CLUSTER_SHIFT};
+use crate::theme::PipePaint;
+use crate::tuning::{Preset, Tuning};
+use crate::{
+    BackgroundTag, Challenge, Collectible, CollisionBox, Difficulty, Game, ObstacleTag, PipeColor,
+    Position, Telegraph, VerticalOscillator,
+};
+use rand::{Rng, RngCore};
+use specs::world::EntitiesRes;
+use specs::*;
+use std::f32::consts::TAU;
+
+// Removes obstacles once they've scrolled off the left edge and brings in
+// new slots, pipe pairs or caves, at the difficulty's spacing stretched or
+// squeezed by the tuning's spacing patterns. In collectibles mode some
+// pairs get a pickup in their gap. Pipes are painted for the `Preset`
+// being played, if any, or as the theme's `PipePaint` says. Runs after
+// `MovementSystem`. Pipes go back to the `PipePool` and come out of it as
+// soon as the next pair does, while caves and pickups show up on the next
+// `maintain`.
+pub struct ObstacleSpawnSystem;
+
+// Pipe entities that scrolled off, stripped of their components until
+// they're brought back as the next pair, so pipes aren't deleted and
+// created every slot.
+#[derive(Default)]
+pub struct PipePool(Vec<Entity>);
+
+// The storages a pipe is made of, and the pool of parked ones.
+struct Pipes<'a> {
+    pool: Write<'a, PipePool>,
+    positions: WriteStorage<'a, Position>,
+    scenery: WriteStorage<'a, BackgroundTag>,
+    obstacles: WriteStorage<'a, ObstacleTag>,
+    boxes: WriteStorage<'a, CollisionBox>,
+    swings: WriteStorage<'a, VerticalOscillator>,
+}
+
+impl Pipes<'_> {
+    fn park(&mut self, pipe: Entity) {
+        self.positions.remove(pipe);
+        self.scenery.remove(pipe);
+        self.obstacles.remove(pipe);
+        self.boxes.remove(pipe);
+        self.swings.remove(pipe);
+        self.pool.0.push(pipe);
+    }
+
+    // A pipe from the pool, or a new one if it's empty. Anything else
+    // deleted a pooled pipe is left out.
+    fn take(&mut self, entities: &EntitiesRes) -> Entity {
+        while let Some(pipe) = self.pool.0.pop() {
+            if entities.is_alive(pipe) {
+                return pipe;
+            }
+        }
+        entities.create()
+    }
+}
+
+impl<'a> System<'a> for ObstacleSpawnSystem {
+    type SystemData = (
+        Read<'a, Game>,
+        Read<'a, Difficulty>,
+        Read<'a, Tuning>,
+        Option<Write<'a, Challenge>>,
+        Write<'a, Caves>,
+        Write<'a, Spacing>,
+        Option<Write<'a, Telegraph>>,
+        Option<Write<'a, Collectibles>>,
+        Option<Read<'a, Preset>>,
+        Option<Read<'a, PipePaint>>,
+        Write<'a, PipePool>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, BackgroundTag>,
+        WriteStorage<'a, ObstacleTag>,
+        WriteStorage<'a, CollisionBox>,
+        WriteStorage<'a, VerticalOscillator>,
+        ReadStorage<'a, Collectible>,
+        Entities<'a>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            game,
+            difficulty,
+            tuning,
+            mut challenge,
+            mut caves,
+            mut spacing,
+            telegraph,
+            mut pickups,
+            preset,
+            paint,
+            pool,
+            positions,
+            scenery,
+            obstacles,
+            boxes,
+            swings,
+            items,
+            entities,
+            updater,
+        ) = data;
+        let mut pipes = Pipes {
+            pool,
+            positions,
+            scenery,
+            obstacles,
+            boxes,
+            swings,
+        };
+
+        // The top obstacle of the rightmost slot decides when the next slot
+        // comes in.
+        let mut rightmost: Option<f32> = None;
+        let mut gone = Vec::new();
+        for (ent, pos, bg, obs) in (
+            &*entities,
+            &pipes.positions,
+            &pipes.scenery,
+            &pipes.obstacles,
+        )
+            .join()
+        {
+            if pos.position.x < -bg.width {
+                if obs.cave || obs.hazard.is_some() {
+                    let _ = entities.delete(ent);
+                } else {
+                    gone.push(ent);
+                }
+            } else if obs.top {
+                rightmost = Some(rightmost.map_or(pos.position.x, |x| x.max(pos.position.x)));
+            }
+        }
+        for pipe in gone {
+            pipes.park(pipe);
+        }
+        for (ent, pos, bg, _) in (&*entities, &pipes.positions, &pipes.scenery, &items).join() {
+            if pos.position.x < -bg.width {
+                let _ = entities.delete(ent);
+            }
+        }
+
+        // A new slot comes in behind the last one as soon as its spot
+        // reaches the right edge, so it's never seen popping up.
+        let spawn_x = rightmost.map_or(engine::screen_width(), |x| {
+            x + difficulty.spacing * spacing.factor()
+        });
+        if spawn_x > engine::screen_width() {
+            if let (Some(mut telegraph), Some(challenge)) = (telegraph, &challenge) {
+                telegraph.next = next_gap(challenge, &tuning, &difficulty, &caves, &spacing);
+            }
+            return;
+        }
+
+        // Draws from the generator only happen here, once per slot, so
+        // seeded layouts don't depend on entity order.
+        let mut thread_rng = rand::thread_rng();
+        let rng: &mut dyn RngCore = match &mut challenge {
+            Some(challenge) => &mut challenge.rng,
+            None => &mut thread_rng,
+        };
+        match roll_slot(rng, &tuning, &difficulty, &mut caves, &mut spacing) {
+            Some(center) => {
+                let moving = &tuning.moving_pipes;
+                let swing = (game.score >= moving.from_score
+                    && moving.amplitude > 0.0
+                    && moving.period > 0.0)
+                    .then(|| VerticalOscillator {
+                        base_y: 0.0,
+                        amplitude: moving.amplitude,
+                        period: moving.period,
+                        // From where the pair is in the world, so seeded
+                        // runs don't draw anything more for it.
+                        phase: ((game.distance + spawn_x) * SWING_PHASE) % TAU,
+                    });
+                let paint = paint.map_or(PipePaint::Difficulty, |paint| *paint);
+                let color = paint.pick(preset.map(|preset| *preset));
+                spawn_pipes(
+                    spawn_x,
+                    center,
+                    &difficulty,
+                    swing,
+                    color,
+                    &mut pipes,
+                    &entities,
+                );
+                if let Some(kind) = pickups.as_mut().and_then(|pickups| pickups.roll()) {
+                    let at = Vec2::new(spawn_x + PIPE_WIDTH / 2.0, center);
+                    collectibles::spawn(kind, at, &difficulty, swing, &entities, &updater);
+                }
+            }
+            None => {
+                // Caves reach as far as the next slot.
+                let difficulty = Difficulty {
+                    spacing: difficulty.spacing * spacing.factor(),
+                    ..*difficulty
+                };
+                caves.spawn_slot(
+                    spawn_x,
+                    game.distance,
+                    &difficulty,
+                    rng,
+                    &entities,
+                    &updater,
+                );
+            }
+        }
+    }
+}
+
+// Draws the layout of the next slot: the middle of a pipe pair's gap, or
+// None for a cave, whose segments come from the generator after this. The
+// gap is never further above or below the last one than the bird can
+// climb or drop on the way there.
+fn roll_slot(
+    rng: &mut dyn RngCore,
+    tuning: &Tuning,
+    difficulty: &Difficulty,
+    caves: &mut Caves,
+    spacing: &mut Spacing,
+) -> Option<f32> {
+    let factor = spacing.factor();
+    spacing.advance(tuning, rng);
+    if caves.roll(rng) {
+        spacing.last_center = None;
+        return None;
+    }
+    let mut center = rng.gen_range(GAP_CENTER_RANGE.0, GAP_CENTER_RANGE.1);
+    if let Some(last) = spacing.last_center {
+        // From leaving the last pair to entering this one.
+        let open = difficulty.spacing * factor - PIPE_WIDTH;
+        let (climb, drop) = spacing::reach(tuning, open / difficulty.speed);
+        center = center.clamp(last - climb, last + drop);
+        if factor < 1.0 {
+            center = center.clamp(last - CLUSTER_SHIFT, last + CLUSTER_SHIFT);
+        }
+    }
+    spacing.last_center = Some(center);
+    Some(center)
+}
+
+// Where the gap of the slot after the rightmost one will be, by rolling
+// it on copies of the spawn state. Only a seeded generator can be copied.
+fn next_gap(
+    challenge: &Challenge,
+    tuning: &Tuning,
+    difficulty: &Difficulty,
+    caves: &Caves,
+    spacing: &Spacing,
+) -> Option<f32> {
+    let mut rng = challenge.rng.clone();
+    roll_slot(
+        &mut rng,
+        tuning,
+        difficulty,
+        &mut caves.clone(),
+        &mut spacing.clone(),
+    )
+}
+
+// Radians of a moving pair's swing per pixel into the world.
+const SWING_PHASE: f32 = 0.01;
+
+// Where the middle of a pipe pair's gap can be, anywhere in between.
+const GAP_CENTER_RANGE: (f32, f32) = (120.0, 360.0);
+pub const PIPE_WIDTH: f32 = 64.0;
+
+// A pipe pair at screen x `x`, its gap `difficulty.gap` tall around
+// `center`. The pipes reach up to the top of the screen and down to the
+// bottom, however high the gap, so it can't be flown around. With a
+// `swing` the pair moves together, and both pipes are made longer by its
+// amplitude so they still reach as far at either end of it.
+fn spawn_pipes(
+    x: f32,
+    center: f32,
+    difficulty: &Difficulty,
+    swing: Option<VerticalOscillator>,
+    color: PipeColor,
+    pipes: &mut Pipes,
+    entities: &EntitiesRes,
+) {
+    let extra = swing.map_or(0.0, |swing| swing.amplitude);
+    let offset = swing.map_or(0.0, |swing| swing.amplitude * swing.phase.sin());
+    let top_lip = center - difficulty.gap / 2.0;
+    let bottom_y = center + difficulty.gap / 2.0;
+
+    for &(top, y, height) in [
+        (true, -extra, top_lip + extra),
+        (false, bottom_y, engine::SCREEN_HEIGHT - bottom_y + extra),
+    ]
+    .iter()
+    {
+        let pipe = pipes.take(entities);
+        let _ = pipes.positions.insert(
+            pipe,
+            Position {
+                position: Vec2::new(x, y + offset),
+                speed: Vec2::new(0.0, 0.0),
+            },
+        );
+        let _ = pipes.scenery.insert(
+            pipe,
+            BackgroundTag {
+                velocity: difficulty.speed,
+                width: PIPE_WIDTH,
+                num_copies: 1,
+            },
+        );
+        let _ = pipes.obstacles.insert(
+            pipe,
+            ObstacleTag {
+                top,
+                passed: false,
+                scored: false,
+                cave: false,
+                hazard: None,
+                color,
+            },
+        );
+        let _ = pipes.boxes.insert(
+            pipe,
+            CollisionBox {
+                origin: Vec2::new(x, y + offset),
+                height,
+                width: PIPE_WIDTH,
+            },
+        );
+        if let Some(swing) = swing {
+            let _ = pipes
+                .swings
+                .insert(pipe, VerticalOscillator { base_y: y, ..swing });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulate::tick_headless;
+    use crate::{build_world, Ghost, TimeScale};
+    use std::collections::HashMap;
+
+    // Whether `entity` came in as a new obstacle since it was last seen,
+    // at x `x`. Pooled pipes come back as new ones further right.
+    fn arrived(seen: &mut HashMap<Entity, f32>, entity: Entity, x: f32) -> bool {
+        seen.insert(entity, x).is_none_or(|last| x > last)
+    }
+
+    #[test]
+    fn the_telegraph_points_at_the_next_gap() {
+        // A ghost run never ends, so pipes keep coming.
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(Challenge::new(3));
+        world.insert(Ghost::default());
+        world.insert(Telegraph::default());
+
+        let mut seen = HashMap::new();
+        let mut foretold: Option<f32> = None;
+        let mut checked = 0;
+        for _ in 0..3000 {
+            assert!(tick_headless(&mut world));
+            let entities = world.entities();
+            let obstacles = world.read_storage::<ObstacleTag>();
+            let boxes = world.read_storage::<CollisionBox>();
+            let mut lips = Vec::new();
+            for (entity, obs, coll_box) in (&entities, &obstacles, &boxes).join() {
+                if arrived(&mut seen, entity, coll_box.origin.x)
+                    && !obs.cave
+                    && obs.hazard.is_none()
+                {
+                    lips.push(if obs.top {
+                        coll_box.origin.y + coll_box.height
+                    } else {
+                        coll_box.origin.y
+                    });
+                }
+            }
+            if let (Some(center), &[a, b]) = (foretold, lips.as_slice()) {
+                assert!(((a + b) / 2.0 - center).abs() < 0.01);
+                checked += 1;
+            }
+            foretold = world.read_resource::<Telegraph>().next;
+        }
+        assert!(checked > 5, "only {} pipe pairs were checked", checked);
+    }
+
+    #[test]
+    fn pipes_come_in_on_the_right_and_go_on_the_left() {
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(Challenge::new(7));
+        world.insert(Ghost::default());
+        // Leaving out the pipes a run starts with, further along.
+        assert!(tick_headless(&mut world));
+        let mut seen: HashMap<Entity, f32> = (
+            &world.entities(),
+            &world.read_storage::<ObstacleTag>(),
+            &world.read_storage::<Position>(),
+        )
+            .join()
+            .map(|(entity, _, pos)| (entity, pos.position.x))
+            .collect();
+        let mut arrivals = 0;
+
+        for _ in 0..3000 {
+            assert!(tick_headless(&mut world));
+            let entities = world.entities();
+            let obstacles = world.read_storage::<ObstacleTag>();
+            let positions = world.read_storage::<Position>();
+            let scenery = world.read_storage::<BackgroundTag>();
+            for (entity, obs, pos, bg) in (&entities, &obstacles, &positions, &scenery).join() {
+                if obs.cave || obs.hazard.is_some() {
+                    continue;
+                }
+                // Pipes come in at the right edge, however a pattern spaces
+                // them, and are gone once past the left one.
+                let x = pos.position.x;
+                if arrived(&mut seen, entity, x) {
+                    assert!(
+                        x <= engine::screen_width() && x > engine::screen_width() - bg.velocity
+                    );
+                    arrivals += 1;
+                }
+                assert!(pos.position.x >= -bg.width);
+            }
+        }
+        assert!(arrivals > 30, "only {} pipes came by", arrivals);
+        // Pipes are reused, not created for every pair.
+        assert!(seen.len() < 20, "{} pipe entities were made", seen.len());
+    }
+
+    #[test]
+    fn pairs_close_off_the_screen_but_for_their_gap() {
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(Challenge::new(5));
+        world.insert(Ghost::default());
+        let mut pairs = 0;
+        for _ in 0..600 {
+            assert!(tick_headless(&mut world));
+            let gap = world.read_resource::<Difficulty>().gap;
+            let obstacles = world.read_storage::<ObstacleTag>();
+            let boxes = world.read_storage::<CollisionBox>();
+            let pipes: Vec<_> = (&obstacles, &boxes)
+                .join()
+                .filter(|(obs, _)| !obs.cave && obs.hazard.is_none())
+                .collect();
+            for (_, top) in pipes.iter().filter(|(obs, _)| obs.top) {
+                let (_, bottom) = pipes
+                    .iter()
+                    .find(|(obs, b)| !obs.top && b.origin.x == top.origin.x)
+                    .unwrap();
+                assert!(top.origin.y <= 0.0);
+                assert!(bottom.origin.y + bottom.height >= engine::SCREEN_HEIGHT);
+                let lips = bottom.origin.y - (top.origin.y + top.height);
+                assert!((lips - gap).abs() < 0.01);
+                pairs += 1;
+            }
+        }
+        assert!(pairs > 100);
+    }
+
+    #[test]
+    fn gaps_stay_within_reach_of_the_last() {
+        // Flaps this weak can't climb the whole gap range between pairs.
+        let tuning = Tuning {
+            flap_impulse: 200.0,
+            ..Tuning::default()
+        };
+        let difficulty = Difficulty::at(&tuning, 0);
+        let mut rng = Challenge::new(9).rng;
+        let (mut caves, mut spacing) = (Caves::default(), Spacing::default());
+        let mut climbs = 0;
+        for _ in 0..500 {
+            let (last, factor) = (spacing.last_center, spacing.factor());
+            let center = roll_slot(&mut rng, &tuning, &difficulty, &mut caves, &mut spacing);
+            if center.is_none() {
+                // Straight out of the cave, which only spawning walks through.
+                caves = Caves::default();
+            }
+            if let (Some(last), Some(center)) = (last, center) {
+                let open = difficulty.spacing * factor - PIPE_WIDTH;
+                let (climb, drop) = spacing::reach(&tuning, open / difficulty.speed);
+                assert!(center >= last - climb - 0.01 && center <= last + drop + 0.01);
+                if center < last - climb + 1.0 {
+                    climbs += 1;
+                }
+            }
+        }
+        assert!(climbs > 0, "no gap was held back");
+    }
+
+    // The top and bottom lips of every moving pair, by x.
+    fn moving_gaps(world: &World) -> Vec<(f32, f32, f32)> {
+        let obstacles = world.read_storage::<ObstacleTag>();
+        let boxes = world.read_storage::<CollisionBox>();
+        let oscillators = world.read_storage::<VerticalOscillator>();
+        let pipes: Vec<_> = (&obstacles, &boxes, &oscillators).join().collect();
+        let mut gaps = Vec::new();
+        for &(top, top_box, _) in &pipes {
+            for &(bottom, bottom_box, _) in &pipes {
+                if top.top && !bottom.top && top_box.origin.x == bottom_box.origin.x {
+                    let lip = top_box.origin.y + top_box.height;
+                    gaps.push((top_box.origin.x, lip, bottom_box.origin.y));
+                }
+            }
+        }
+        gaps.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        gaps
+    }
+
+    #[test]
+    fn moving_pairs_swing_together() {
+        let mut tuning = Tuning::default();
+        tuning.moving_pipes.from_score = 0;
+        let mut world = build_world(tuning, None);
+        world.insert(Challenge::new(4));
+        world.insert(Ghost::default());
+        for _ in 0..300 {
+            tick_headless(&mut world);
+        }
+        let before = *moving_gaps(&world).last().unwrap();
+        for _ in 0..20 {
+            tick_headless(&mut world);
+        }
+        let moved = before.0
+            - 20.0 * world.read_resource::<Difficulty>().speed * TimeScale::default().delta();
+        let after = moving_gaps(&world)
+            .into_iter()
+            .find(|gap| (gap.0 - moved).abs() < 0.5)
+            .unwrap();
+
+        assert!((before.1 - after.1).abs() > 1.0, "the pair didn't move");
+        assert!(((before.2 - before.1) - (after.2 - after.1)).abs() < 0.01);
+    }
+}
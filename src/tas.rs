@@ -0,0 +1,219 @@
+use crate::simulate::run_headless;
+use crate::telemetry::Telemetry;
+use crate::tuning::Tuning;
+use crate::{build_world, Challenge, Direction, Game, TimeScale};
+use specs::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "usage: rusty_bird tas SCRIPT [--dump FILE] [--max-frames N]";
+
+// A tool-assisted input script. Unlike a replay it is written by hand: one
+// frame number per line, meaning "flap on this frame", counting from 1 for
+// the first physics tick. A `seed N` line picks the pipe layout and `#`
+// starts a comment.
+//
+//     seed 42
+//     # clear the first pipe
+//     12
+//     40
+#[derive(Clone, Debug, Default)]
+pub struct TasScript {
+    pub seed: u64,
+    flaps: Vec<u64>,
+}
+
+impl TasScript {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut script = TasScript::default();
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut words = line.split_whitespace();
+            let result = match (words.next(), words.next(), words.next()) {
+                (None, _, _) => Ok(()),
+                (Some("seed"), Some(seed), None) => seed.parse().map(|seed| script.seed = seed),
+                (Some(frame), None, None) => frame.parse().map(|frame| script.flaps.push(frame)),
+                _ => {
+                    return Err(format!(
+                        "line {}: expected a frame number or `seed N`",
+                        n + 1
+                    ))
+                }
+            };
+            result.map_err(|e| format!("line {}: {}", n + 1, e))?;
+        }
+
+        script.flaps.sort_unstable();
+        script.flaps.dedup();
+        Ok(script)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        TasScript::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // A script flapping on `flaps`, in any order.
+    pub fn from_flaps(seed: u64, mut flaps: Vec<u64>) -> Self {
+        flaps.sort_unstable();
+        flaps.dedup();
+        TasScript { seed, flaps }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut text = format!("seed {}\n", self.seed);
+        for frame in &self.flaps {
+            text.push_str(&format!("{}\n", frame));
+        }
+        fs::write(path, text).map_err(|e| format!("can't write {}: {}", path.display(), e))
+    }
+
+    pub fn flaps(&self) -> &[u64] {
+        &self.flaps
+    }
+
+    pub fn has_flap(&self, frame: u64) -> bool {
+        self.flaps.binary_search(&frame).is_ok()
+    }
+
+    // Adds a flap on `frame`, or removes the one already there.
+    pub fn toggle_flap(&mut self, frame: u64) {
+        match self.flaps.binary_search(&frame) {
+            Ok(n) => {
+                self.flaps.remove(n);
+            }
+            Err(n) => self.flaps.insert(n, frame),
+        }
+    }
+}
+
+// Feeds a script into the `Direction` resource, replacing player input.
+pub struct TasPlayback {
+    script: TasScript,
+    frame: u64,
+    next: usize,
+}
+
+impl TasPlayback {
+    pub fn new(script: TasScript) -> Self {
+        TasPlayback::at_frame(script, 0)
+    }
+
+    // Starts the script over for a new run.
+    pub fn rewind(&mut self) {
+        self.frame = 0;
+        self.next = 0;
+    }
+
+    pub fn script(&self) -> &TasScript {
+        &self.script
+    }
+
+    // Playback that resumes after `frame` has already been played.
+    pub fn at_frame(script: TasScript, frame: u64) -> Self {
+        let next = script.flaps.iter().take_while(|&&f| f <= frame).count();
+        TasPlayback {
+            script,
+            frame,
+            next,
+        }
+    }
+}
+
+// Runs right before `MovementSystem` on every tick.
+pub(crate) struct TasSystem;
+
+impl<'a> System<'a> for TasSystem {
+    type SystemData = (
+        Option<Write<'a, TasPlayback>>,
+        Write<'a, Direction>,
+        Read<'a, TimeScale>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (playback, mut dir, time) = data;
+        let mut playback = match playback {
+            Some(playback) => playback,
+            None => return,
+        };
+        // Frames only count while the game is running.
+        if time.is_paused() {
+            return;
+        }
+
+        playback.frame += 1;
+        *dir = Direction::new();
+        while let Some(&flap) = playback.script.flaps.get(playback.next) {
+            if flap > playback.frame {
+                break;
+            }
+            if flap == playback.frame {
+                dir.jump = true;
+            }
+            playback.next += 1;
+        }
+    }
+}
+
+// Plays a script headlessly with the default tuning until the run ends or
+// `max_frames`, optionally dumping every frame as telemetry CSV to `dump`.
+pub(crate) fn play(script: TasScript, dump: Option<PathBuf>, max_frames: i32) -> World {
+    let mut world = build_world(Tuning::default(), None);
+    world.insert(Challenge::new(script.seed));
+    if let Some(path) = dump {
+        world.insert(Telemetry::with_file(path));
+    }
+    world.insert(TasPlayback::new(script));
+
+    let mut tas_system = TasSystem;
+    run_headless(&mut world, max_frames, |world| tas_system.run_now(world));
+    world
+}
+
+// `rusty_bird tas SCRIPT`: plays a script headlessly and reports how the run
+// went, optionally dumping every frame as telemetry CSV.
+pub fn run(mut args: impl Iterator<Item = String>) {
+    let mut script_path: Option<PathBuf> = None;
+    let mut dump: Option<PathBuf> = None;
+    let mut max_frames: i32 = 100_000;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dump" => dump = args.next().map(PathBuf::from),
+            "--max-frames" => match args.next().and_then(|v| v.parse().ok()) {
+                Some(frames) => max_frames = frames,
+                None => exit_with_usage("invalid or missing value for --max-frames"),
+            },
+            _ if script_path.is_none() && !arg.starts_with("--") => {
+                script_path = Some(PathBuf::from(arg))
+            }
+            _ => exit_with_usage(&format!("unknown argument {}", arg)),
+        }
+    }
+
+    let script_path = script_path.unwrap_or_else(|| exit_with_usage("missing SCRIPT"));
+    let script = TasScript::load(&script_path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    let world = play(script, dump, max_frames);
+    let game = world.read_resource::<Game>();
+    println!("frames: {}", game.frames);
+    println!("pipes: {}", game.pipes_passed);
+    match game.death {
+        Some(death) => println!(
+            "died: {:?} at ({:.1}, {:.1})",
+            death.cause, death.x, death.y
+        ),
+        None => println!("survived"),
+    }
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    process::exit(2);
+}
@@ -0,0 +1,306 @@
+use crate::app::{load_branding, open_window, use_saved_language, ASSETS_DIR};
+use crate::assets;
+use crate::config;
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2, Viewport};
+use crate::lang;
+use crate::savestate::SaveState;
+use crate::simulate::tick_headless;
+use crate::tas::{TasPlayback, TasScript, TasSystem};
+use crate::tuning::Tuning;
+use crate::{
+    build_world, Challenge, CollisionBox, Game, GameState, ObstacleTag, PlayerTag, FLOOR_HEIGHT,
+    FLOOR_Y, PHYSICS_FPS,
+};
+use ggez::event;
+use ggez::input::keyboard::{KeyCode, KeyInput, KeyMods};
+use ggez::*;
+use specs::*;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// A save state is kept every second of the run, so stepping back or
+// editing an input only re-simulates from the closest one.
+const SNAPSHOT_INTERVAL: u64 = PHYSICS_FPS as u64;
+// How far ahead the predicted trajectory is drawn.
+const PREVIEW_FRAMES: u64 = 120;
+
+// Frame by frame editor for TAS scripts. The run is simulated headlessly and
+// drawn as collision boxes; every edit re-simulates from the last save state
+// before the edited frame.
+pub struct TasEditor {
+    path: PathBuf,
+    script: TasScript,
+    tuning: Tuning,
+    world: World,
+    frame: u64,
+    snapshots: Vec<SaveState>,
+    preview: Vec<(Vec2, bool)>,
+    message: String,
+    viewport: Viewport,
+}
+
+impl TasEditor {
+    // Opens `path`, or starts an empty script there if it doesn't exist.
+    pub fn new(path: PathBuf) -> Result<Self, String> {
+        let script = if path.exists() {
+            TasScript::load(&path)?
+        } else {
+            TasScript::default()
+        };
+        let tuning = Tuning::default();
+
+        let mut world = build_world(tuning.clone(), None);
+        world.insert(Challenge::new(script.seed));
+        world.insert(TasPlayback::new(script.clone()));
+        let snapshots = vec![SaveState::capture(&world)];
+
+        let mut editor = TasEditor {
+            path,
+            script,
+            tuning,
+            world,
+            frame: 0,
+            snapshots,
+            preview: Vec::new(),
+            message: String::new(),
+            viewport: Viewport::fit(engine::screen_width(), engine::SCREEN_HEIGHT),
+        };
+        editor.update_preview();
+        Ok(editor)
+    }
+
+    // Plays one frame of the script. Returns false if the run already ended.
+    fn step(&mut self) -> bool {
+        if *self.world.read_resource::<GameState>() != GameState::Playing {
+            return false;
+        }
+        TasSystem.run_now(&self.world);
+        tick_headless(&mut self.world);
+        self.frame += 1;
+
+        let slot = (self.frame / SNAPSHOT_INTERVAL) as usize;
+        if self.frame.is_multiple_of(SNAPSHOT_INTERVAL) && slot == self.snapshots.len() {
+            self.snapshots.push(SaveState::capture(&self.world));
+        }
+        true
+    }
+
+    fn forward(&mut self, frames: u64) {
+        for _ in 0..frames {
+            if !self.step() {
+                break;
+            }
+        }
+        self.update_preview();
+    }
+
+    fn goto(&mut self, target: u64) {
+        let slot = ((target / SNAPSHOT_INTERVAL) as usize).min(self.snapshots.len() - 1);
+        self.world = self.snapshots[slot].restore(&self.tuning);
+        self.frame = slot as u64 * SNAPSHOT_INTERVAL;
+        self.world
+            .insert(TasPlayback::at_frame(self.script.clone(), self.frame));
+
+        self.forward(target - self.frame);
+    }
+
+    fn toggle_next_flap(&mut self) {
+        let edited = self.frame + 1;
+        self.script.toggle_flap(edited);
+
+        // Save states from the edited frame on no longer match the script.
+        let valid = (self.frame / SNAPSHOT_INTERVAL) as usize + 1;
+        self.snapshots.truncate(valid);
+        self.world
+            .insert(TasPlayback::at_frame(self.script.clone(), self.frame));
+        self.update_preview();
+    }
+
+    // Simulates a copy of the run ahead of the current frame so the effect
+    // of an edit is visible straight away.
+    fn update_preview(&mut self) {
+        let mut world = SaveState::capture(&self.world).restore(&self.tuning);
+        world.insert(TasPlayback::at_frame(self.script.clone(), self.frame));
+
+        self.preview.clear();
+        for frame in self.frame + 1..=self.frame + PREVIEW_FRAMES {
+            if *world.read_resource::<GameState>() != GameState::Playing {
+                break;
+            }
+            TasSystem.run_now(&world);
+            tick_headless(&mut world);
+            if let Some(center) = bird_center(&world) {
+                self.preview.push((center, self.script.has_flap(frame)));
+            }
+        }
+    }
+
+    fn save(&mut self) {
+        self.message = match self.script.save(&self.path) {
+            Ok(()) => lang::fill("tas_saved", &[&self.path.display()]),
+            Err(e) => e,
+        };
+    }
+}
+
+fn bird_center(world: &World) -> Option<Vec2> {
+    let boxes = world.read_storage::<CollisionBox>();
+    let players = world.read_storage::<PlayerTag>();
+    (&boxes, &players).join().next().map(|(coll_box, _)| {
+        Vec2::new(
+            coll_box.origin.x + coll_box.width / 2.0,
+            coll_box.origin.y + coll_box.height / 2.0,
+        )
+    })
+}
+
+fn rect(coll_box: &CollisionBox) -> Rect {
+    Rect::new(
+        coll_box.origin.x,
+        coll_box.origin.y,
+        coll_box.width,
+        coll_box.height,
+    )
+}
+
+impl event::EventHandler for TasEditor {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let game = self.world.read_resource::<Game>();
+        let boxes = self.world.read_storage::<CollisionBox>();
+        let obstacles = self.world.read_storage::<ObstacleTag>();
+        let players = self.world.read_storage::<PlayerTag>();
+        let mut cmds = Vec::new();
+
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(0.0, FLOOR_Y, engine::screen_width(), FLOOR_HEIGHT),
+            color: Color::new(0.4, 0.3, 0.2, 1.0),
+        });
+        for (coll_box, _) in (&boxes, &obstacles).join() {
+            cmds.push(DrawCmd::Rect {
+                rect: rect(coll_box),
+                color: Color::new(0.3, 0.7, 0.3, 1.0),
+            });
+        }
+        let playing = *self.world.read_resource::<GameState>() == GameState::Playing;
+        let bird_color = if playing {
+            Color::new(1.0, 0.9, 0.2, 1.0)
+        } else {
+            Color::new(1.0, 0.2, 0.2, 1.0)
+        };
+        for (coll_box, _) in (&boxes, &players).join() {
+            cmds.push(DrawCmd::Rect {
+                rect: rect(coll_box),
+                color: bird_color,
+            });
+        }
+        for (center, flap) in &self.preview {
+            let (radius, color) = if *flap {
+                (4.0, Color::new(1.0, 0.3, 0.3, 1.0))
+            } else {
+                (2.0, Color::new(1.0, 1.0, 1.0, 0.7))
+            };
+            cmds.push(DrawCmd::Circle {
+                center: *center,
+                radius,
+                color,
+            });
+        }
+
+        let next = if self.script.has_flap(self.frame + 1) {
+            lang::tr("tas_flap")
+        } else {
+            "-"
+        };
+        let dead = if playing { "" } else { lang::tr("tas_dead") };
+        let counts = lang::fill(
+            "tas_status",
+            &[
+                &self.frame,
+                &game.pipes_passed,
+                &self.script.flaps().len(),
+                &next,
+                &dead,
+            ],
+        );
+        let status = format!("{}\n{}", counts, self.message);
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        cmds.push(DrawCmd::text(status, Vec2::new(10.0, 10.0), 24.0, white));
+        cmds.push(DrawCmd::text(
+            lang::tr("tas_help"),
+            Vec2::new(10.0, 570.0),
+            18.0,
+            white,
+        ));
+
+        engine::render(
+            ctx,
+            &self.viewport,
+            Color::new(0.1, 0.1, 0.15, 1.0),
+            &[],
+            &cmds,
+        )?;
+        timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _: bool) -> GameResult {
+        let frames = if input.mods.contains(KeyMods::SHIFT) {
+            10
+        } else {
+            1
+        };
+        self.message.clear();
+
+        match input.keycode {
+            Some(KeyCode::Right) => self.forward(frames),
+            Some(KeyCode::Left) => self.goto(self.frame.saturating_sub(frames)),
+            Some(KeyCode::Home) => self.goto(0),
+            Some(KeyCode::F) => self.toggle_next_flap(),
+            Some(KeyCode::S) => self.save(),
+            Some(KeyCode::Escape) => ctx.request_quit(),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.viewport = Viewport::fit(width, height);
+        Ok(())
+    }
+}
+
+// `rusty_bird tas-edit SCRIPT`: opens the editor on SCRIPT, creating it on
+// the first save if it doesn't exist yet.
+pub fn run(mut args: impl Iterator<Item = String>) {
+    let path = match args.next() {
+        Some(path) => PathBuf::from(path),
+        None => {
+            println!("usage: rusty_bird tas-edit SCRIPT");
+            process::exit(2);
+        }
+    };
+
+    let mut editor = match TasEditor::new(path) {
+        Ok(editor) => editor,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    use_saved_language(Path::new(ASSETS_DIR));
+    let (mut ctx, event_loop) = open_window(
+        &load_branding(Path::new(ASSETS_DIR)),
+        Path::new(ASSETS_DIR),
+        config::Window::default(),
+        Some("TAS editor"),
+    );
+    let (width, height) = ctx.gfx.drawable_size();
+    editor.viewport = Viewport::fit(width, height);
+    assets::load_or_exit(&mut ctx, Path::new(ASSETS_DIR), &[]);
+    event::run(ctx, event_loop, editor)
+}
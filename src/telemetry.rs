@@ -0,0 +1,121 @@
+use crate::{DeathCause, Game, GameState, PlayerTag, Position, TimeScale};
+use specs::*;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write as IoWrite};
+use std::path::{Path, PathBuf};
+
+// Per-run CSV log of the bird's trajectory. Every tick appends a "tick" row
+// and the run ends with a single "death" row carrying the cause.
+#[derive(Default)]
+pub struct Telemetry {
+    dir: Option<PathBuf>,
+    writer: Option<BufWriter<File>>,
+    frame: u64,
+}
+
+impl Telemetry {
+    pub fn new(dir: PathBuf) -> Self {
+        if let Err(e) = fs::create_dir_all(&dir) {
+            println!("telemetry disabled, can't create {}: {}", dir.display(), e);
+            return Telemetry::default();
+        }
+
+        Telemetry {
+            dir: Some(dir),
+            writer: None,
+            frame: 0,
+        }
+    }
+
+    // Logs a single run straight to `path`.
+    pub fn with_file(path: PathBuf) -> Self {
+        Telemetry {
+            dir: None,
+            writer: open(&path),
+            frame: 0,
+        }
+    }
+
+    // Opens `<dir>/run-<label>.csv` for the run about to start.
+    pub fn begin_run(&mut self, label: &str) {
+        if let Some(dir) = &self.dir {
+            self.frame = 0;
+            self.writer = open(&dir.join(format!("run-{}.csv", label)));
+        }
+    }
+
+    fn record(&mut self, event: &str, pos: &Position, cause: Option<DeathCause>) {
+        let frame = self.frame;
+        if let Some(writer) = &mut self.writer {
+            let cause = match cause {
+                Some(DeathCause::TopPipe) => "top_pipe",
+                Some(DeathCause::BottomPipe) => "bottom_pipe",
+                Some(DeathCause::Cave) => "cave",
+                Some(DeathCause::Hazard) => "hazard",
+                Some(DeathCause::Floor) => "floor",
+                None => "",
+            };
+            let result = writeln!(
+                writer,
+                "{},{},{:.2},{:.2},{:.2},{:.2},{}",
+                frame, event, pos.position.x, pos.position.y, pos.speed.x, pos.speed.y, cause
+            );
+            if let Err(e) = result {
+                println!("telemetry: write failed, stopping: {}", e);
+                self.writer = None;
+            }
+        }
+    }
+
+    fn end_run(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            let _ = writer.flush();
+        }
+    }
+}
+
+fn open(path: &Path) -> Option<BufWriter<File>> {
+    match File::create(path) {
+        Ok(file) => {
+            let mut writer = BufWriter::new(file);
+            let _ = writeln!(writer, "frame,event,x,y,speed_x,speed_y,cause");
+            Some(writer)
+        }
+        Err(e) => {
+            println!("telemetry: can't create {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub(crate) struct TelemetrySystem;
+
+impl<'a> System<'a> for TelemetrySystem {
+    type SystemData = (
+        Write<'a, Telemetry>,
+        Read<'a, Game>,
+        Read<'a, GameState>,
+        Read<'a, TimeScale>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PlayerTag>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut telemetry, game, state, time, pos, player) = data;
+        if telemetry.writer.is_none() || time.is_paused() {
+            return;
+        }
+
+        telemetry.frame += 1;
+        for (pos, _) in (&pos, &player).join() {
+            telemetry.record("tick", pos, None);
+            if *state == GameState::GameOver {
+                telemetry.record("death", pos, game.death.map(|d| d.cause));
+            }
+        }
+
+        if *state == GameState::GameOver {
+            telemetry.end_run();
+        }
+    }
+}
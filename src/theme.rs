@@ -0,0 +1,305 @@
+use crate::assets::Assets;
+use crate::daylight;
+use crate::engine::{Color, DrawCmd, Vec2};
+use crate::tuning::Preset;
+use crate::{Backdrop, Image, PipeColor};
+use rand::Rng;
+use serde::Deserialize;
+use specs::*;
+
+// How long a change of theme takes to blend in.
+const FADE_TICKS: u32 = 90;
+
+// A look for the scenery: the background layers, back to front, the tint
+// they and the floor are drawn with, and the pipes' tint. All the themes
+// share the one set of background art for now, but each can point at its
+// own.
+pub struct Theme {
+    pub backgrounds: [&'static str; 2],
+    pub scenery: Color,
+    pub pipes: Color,
+}
+
+// Every theme, the one runs start with first.
+pub const THEMES: [Theme; 3] = [
+    // Day
+    Theme {
+        backgrounds: ["/background1.png", "/background2.png"],
+        scenery: Color::new(1.0, 1.0, 1.0, 1.0),
+        pipes: Color::new(1.0, 1.0, 1.0, 1.0),
+    },
+    // Sunset
+    Theme {
+        backgrounds: ["/background1.png", "/background2.png"],
+        scenery: Color::new(1.0, 0.7, 0.55, 1.0),
+        pipes: Color::new(1.0, 0.75, 0.5, 1.0),
+    },
+    // Night
+    Theme {
+        backgrounds: ["/background1.png", "/background2.png"],
+        scenery: Color::new(0.3, 0.32, 0.5, 1.0),
+        pipes: Color::new(0.55, 0.6, 0.9, 1.0),
+    },
+];
+
+// The high contrast palette from the settings: the scenery dimmed behind
+// pipes and birds drawn flat in bright colors instead of their art.
+pub const CONTRAST_SCENERY: Color = Color::new(0.3, 0.3, 0.35, 1.0);
+pub const CONTRAST_PIPES: Color = Color::new(0.1, 0.95, 0.3, 1.0);
+const CONTRAST_BIRD: Color = Color::new(1.0, 0.9, 0.0, 1.0);
+const CONTRAST_OUTLINE: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+
+// A bird drawn flat for high contrast: a round body the size of its art
+// at `at`, outlined, with an eye on the side it flies to. `tint` goes over
+// the body like it would over the art.
+pub fn draw_flat_bird(cmds: &mut Vec<DrawCmd>, at: Vec2, size: Vec2, tint: Color) {
+    let center = at + size * 0.5;
+    let radius = size.x.min(size.y) / 2.0;
+    let body = Color::new(
+        CONTRAST_BIRD.r * tint.r,
+        CONTRAST_BIRD.g * tint.g,
+        CONTRAST_BIRD.b * tint.b,
+        CONTRAST_BIRD.a * tint.a,
+    );
+    cmds.push(DrawCmd::Circle {
+        center,
+        radius: radius + 2.0,
+        color: CONTRAST_OUTLINE,
+    });
+    cmds.push(DrawCmd::Circle {
+        center,
+        radius,
+        color: body,
+    });
+    cmds.push(DrawCmd::Circle {
+        center: center + Vec2::new(radius * 0.45, -radius * 0.3),
+        radius: radius * 0.2,
+        color: CONTRAST_OUTLINE,
+    });
+}
+
+// Every theme's background images, to load with the rest of the assets.
+pub fn background_images() -> impl Iterator<Item = &'static str> {
+    THEMES
+        .iter()
+        .flat_map(|theme| theme.backgrounds.iter().copied())
+}
+
+// Every theme's background layers, indexed by theme and then by layer.
+pub fn load_backgrounds(assets: &Assets) -> Vec<Vec<Image>> {
+    THEMES
+        .iter()
+        .map(|theme| {
+            theme
+                .backgrounds
+                .iter()
+                .map(|&path| assets.image(path).clone())
+                .collect()
+        })
+        .collect()
+}
+
+// The background layers of every theme, see `load_backgrounds`. Not
+// present when running headless.
+pub struct ThemeArt(pub Vec<Vec<Image>>);
+
+// Shows theme `index`'s art on every background layer.
+pub fn show(world: &World, index: usize) {
+    let art = match world.try_fetch::<ThemeArt>() {
+        Some(art) => art,
+        None => return,
+    };
+    let backdrops = world.read_storage::<Backdrop>();
+    let mut images = world.write_storage::<Image>();
+    for (backdrop, image) in (&backdrops, &mut images).join() {
+        if let Some(layer) = art.0[index].get(backdrop.0) {
+            *image = layer.clone();
+        }
+    }
+}
+
+// When the theme changes: never, leaving the scenery to the time of day,
+// every `every` points, or to a random one every run.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeChange {
+    Daylight,
+    Score,
+    Run,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub change: ThemeChange,
+    pub every: i32,
+    pub pipes: PipePaint,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            change: ThemeChange::Daylight,
+            every: 10,
+            pipes: PipePaint::Difficulty,
+        }
+    }
+}
+
+// How pipe pairs are painted: in the difficulty's color, or each pair in
+// one of `VARIETY` at random, which gives up telling the difficulty by the
+// pipes. Kept in the world for the spawner; without it pipes go by the
+// difficulty.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PipePaint {
+    #[default]
+    Difficulty,
+    Random,
+}
+
+const VARIETY: [PipeColor; 3] = [PipeColor::Green, PipeColor::Red, PipeColor::Blue];
+
+impl PipePaint {
+    // The color for a new pair. Only for looks, so it never draws from the
+    // challenge's generator and seeded runs get the same pipes either way.
+    pub fn pick(self, preset: Option<Preset>) -> PipeColor {
+        match self {
+            PipePaint::Difficulty => preset.map_or(PipeColor::Green, |p| p.pipe_color()),
+            PipePaint::Random => VARIETY[rand::thread_rng().gen_range(0, VARIETY.len())],
+        }
+    }
+}
+
+// The theme being shown, and the one it's fading in from.
+pub struct ThemeCycle {
+    config: ThemeConfig,
+    current: usize,
+    previous: usize,
+    fade: u32,
+}
+
+impl ThemeCycle {
+    pub fn new(config: ThemeConfig) -> Self {
+        ThemeCycle {
+            config: ThemeConfig {
+                every: config.every.max(1),
+                ..config
+            },
+            current: 0,
+            previous: 0,
+            fade: 0,
+        }
+    }
+
+    // Picks the theme a new run starts with, shown right away. Returns it
+    // if it changed.
+    pub fn start_run(&mut self) -> Option<usize> {
+        let next = match self.config.change {
+            ThemeChange::Daylight => return None,
+            ThemeChange::Score => 0,
+            ThemeChange::Run => rand::thread_rng().gen_range(0, THEMES.len()),
+        };
+        self.fade = 0;
+        self.previous = next;
+        (next != self.current).then(|| {
+            self.current = next;
+            next
+        })
+    }
+
+    // The theme shown, or the one fading in.
+    #[cfg(feature = "hot-reload")]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    // Call every tick of a run. Returns the theme to show if `score` just
+    // moved on to a new one.
+    pub fn update(&mut self, score: i32) -> Option<usize> {
+        self.fade = self.fade.saturating_sub(1);
+        if self.config.change != ThemeChange::Score {
+            return None;
+        }
+        let next = (score / self.config.every) as usize % THEMES.len();
+        (next != self.current).then(|| {
+            self.previous = self.current;
+            self.current = next;
+            self.fade = FADE_TICKS;
+            next
+        })
+    }
+
+    fn blend(&self, pick: impl Fn(&Theme) -> Color) -> Color {
+        let (from, to) = (pick(&THEMES[self.previous]), pick(&THEMES[self.current]));
+        let t = 1.0 - self.fade as f32 / FADE_TICKS as f32;
+        Color::new(
+            from.r + (to.r - from.r) * t,
+            from.g + (to.g - from.g) * t,
+            from.b + (to.b - from.b) * t,
+            1.0,
+        )
+    }
+
+    // The tint for the scenery at `distance` into the run.
+    pub fn scenery_tint(&self, distance: f32) -> Color {
+        match self.config.change {
+            ThemeChange::Daylight => daylight::tint(distance),
+            _ => self.blend(|theme| theme.scenery),
+        }
+    }
+
+    pub fn pipe_tint(&self) -> Color {
+        match self.config.change {
+            ThemeChange::Daylight => Color::WHITE,
+            _ => self.blend(|theme| theme.pipes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::Tuning;
+    use crate::world_setup::repaint_obstacles;
+    use crate::{build_world, ObstacleTag, Position};
+
+    #[test]
+    fn follows_the_score() {
+        let mut cycle = ThemeCycle::new(ThemeConfig {
+            change: ThemeChange::Score,
+            every: 10,
+            ..ThemeConfig::default()
+        });
+        assert_eq!(cycle.update(9), None);
+        assert_eq!(cycle.update(10), Some(1));
+        assert_eq!(cycle.update(11), None);
+        assert_eq!(cycle.update(25), Some(2));
+        assert_eq!(cycle.update(30), Some(0));
+        assert_eq!(cycle.start_run(), None);
+    }
+
+    #[test]
+    fn both_pipes_of_a_pair_get_the_same_random_paint() {
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(PipePaint::Random);
+        for _ in 0..20 {
+            repaint_obstacles(&mut world);
+            let positions = world.read_storage::<Position>();
+            let obstacles = world.read_storage::<ObstacleTag>();
+            let pipes: Vec<_> = (&positions, &obstacles).join().collect();
+            for (pos, obstacle) in &pipes {
+                let pair = pipes.iter().filter(|(p, _)| p.position.x == pos.position.x);
+                assert_eq!(pair.clone().count(), 2);
+                assert!(pair.clone().all(|(_, o)| o.color == obstacle.color));
+            }
+        }
+    }
+
+    #[test]
+    fn difficulty_paint_is_red_for_hard_and_green_otherwise() {
+        let difficulty = PipePaint::Difficulty;
+        assert_eq!(difficulty.pick(Some(Preset::Hard)), PipeColor::Red);
+        assert_eq!(difficulty.pick(None), PipeColor::Green);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::savestate::SaveState;
+use crate::simulate::tick_headless;
+use crate::tas::{TasPlayback, TasScript, TasSystem};
+use crate::tuning::Tuning;
+use crate::{Game, GameState, ScrollSpeed, TimeScale, PHYSICS_FPS};
+use specs::*;
+
+// A save state is kept every second of the run, so a seek only replays
+// the frames since the closest one.
+const SNAPSHOT_INTERVAL: u64 = PHYSICS_FPS as u64;
+// Scripts that never crash are only followed this far, ten minutes.
+const MAX_FRAMES: u64 = 10 * 60 * PHYSICS_FPS as u64;
+// The bar the run is drawn along, across the bottom of the screen.
+fn bar() -> Rect {
+    Rect::new(
+        20.0,
+        engine::SCREEN_HEIGHT - 16.0,
+        engine::screen_width() - 40.0,
+        8.0,
+    )
+}
+
+// The whole of a TAS script's run, for watching it in the game window: it's
+// played through headlessly up front to learn every flap and where it ends,
+// keeping save states along the way to seek with.
+pub struct Timeline {
+    script: TasScript,
+    snapshots: Vec<SaveState>,
+    frames: u64,
+    death: Option<u64>,
+}
+
+impl Timeline {
+    // `world` is at the start of a run with the script's playback in it.
+    pub fn new(world: &World) -> Self {
+        let script = world.read_resource::<TasPlayback>().script().clone();
+        let mut timeline = Timeline {
+            script,
+            snapshots: Vec::new(),
+            frames: 0,
+            death: None,
+        };
+        timeline.restart(world);
+        timeline
+    }
+
+    // Plays the script through again from the start of the run `world` is
+    // about to begin.
+    pub fn restart(&mut self, world: &World) {
+        let start = SaveState::capture(world);
+        let mut run = start.restore(&world.read_resource::<Tuning>());
+        run.insert(TasPlayback::new(self.script.clone()));
+        *run.write_resource::<GameState>() = GameState::Playing;
+
+        self.snapshots = vec![start];
+        let mut frame = 0;
+        while frame < MAX_FRAMES {
+            TasSystem.run_now(&run);
+            if !tick_headless(&mut run) {
+                break;
+            }
+            frame += 1;
+            if frame.is_multiple_of(SNAPSHOT_INTERVAL) {
+                self.snapshots.push(SaveState::capture(&run));
+            }
+        }
+        self.frames = frame;
+        self.death = run.read_resource::<Game>().death.map(|_| frame);
+    }
+
+    // Puts `world` at `frame` of the run, from the closest save state
+    // before it. Frames past the end stop short of the crash, so the game
+    // plays that out itself. A paused run stays paused.
+    pub fn seek(&self, world: &mut World, frame: i64) {
+        let last = match self.death {
+            Some(death) => death.saturating_sub(1),
+            None => self.frames,
+        };
+        let target = (frame.max(0) as u64).min(last);
+        let paused = *world.read_resource::<GameState>() == GameState::Paused;
+
+        let slot = ((target / SNAPSHOT_INTERVAL) as usize).min(self.snapshots.len() - 1);
+        self.snapshots[slot].restore_into(world);
+        let mut at = slot as u64 * SNAPSHOT_INTERVAL;
+        world.insert(TasPlayback::at_frame(self.script.clone(), at));
+        world.insert(TimeScale::default());
+        world.insert(ScrollSpeed::default());
+        *world.write_resource::<GameState>() = GameState::Playing;
+        while at < target {
+            TasSystem.run_now(world);
+            if !tick_headless(world) {
+                break;
+            }
+            at += 1;
+        }
+        if paused {
+            *world.write_resource::<GameState>() = GameState::Paused;
+        }
+    }
+
+    // The bar along the bottom of the screen: a tick for every flap, the
+    // crash in red and where the run is now.
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>, frame: u64) {
+        let length = self.frames.max(1) as f32;
+        let bar = bar();
+        let x = |frame: u64| bar.x + bar.w * (frame as f32 / length).min(1.0);
+        cmds.push(DrawCmd::Rect {
+            rect: bar,
+            color: Color::new(0.0, 0.0, 0.0, 0.6),
+        });
+        for &flap in self.script.flaps().iter().filter(|&&f| f <= self.frames) {
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x(flap), bar.y, 1.0, bar.h),
+                color: Color::new(1.0, 1.0, 1.0, 0.7),
+            });
+        }
+        if let Some(death) = self.death {
+            cmds.push(DrawCmd::Rect {
+                rect: Rect::new(x(death) - 2.0, bar.y - 4.0, 4.0, bar.h + 8.0),
+                color: Color::new(1.0, 0.2, 0.2, 1.0),
+            });
+        }
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(x(frame) - 1.0, bar.y - 6.0, 3.0, bar.h + 12.0),
+            color: Color::new(1.0, 0.9, 0.2, 1.0),
+        });
+        cmds.push(DrawCmd::text(
+            lang::fill("timeline", &[&frame, &self.frames]),
+            Vec2::new(bar.x, bar.y - 24.0),
+            16.0,
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_world, Challenge, PlayerTag, Position};
+
+    fn bird_y(world: &World) -> f32 {
+        let positions = world.read_storage::<Position>();
+        let players = world.read_storage::<PlayerTag>();
+        (&positions, &players).join().next().unwrap().0.position.y
+    }
+
+    #[test]
+    fn seeking_lands_where_playing_does() {
+        let flaps = (1..40).map(|n| n * 25).collect();
+        let mut world = build_world(Tuning::default(), None);
+        world.insert(Challenge::new(7));
+        world.insert(TasPlayback::new(TasScript::from_flaps(7, flaps)));
+        *world.write_resource::<GameState>() = GameState::Playing;
+        let timeline = Timeline::new(&world);
+
+        for _ in 0..150 {
+            TasSystem.run_now(&world);
+            tick_headless(&mut world);
+        }
+        let (y, game) = (bird_y(&world), (*world.read_resource::<Game>()).clone());
+
+        timeline.seek(&mut world, 30);
+        assert_eq!(world.read_resource::<Game>().frames, 30);
+        timeline.seek(&mut world, 150);
+        assert_eq!(world.read_resource::<Game>().frames, game.frames);
+        assert_eq!(world.read_resource::<Game>().score, game.score);
+        assert_eq!(bird_y(&world), y);
+    }
+}
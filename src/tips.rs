@@ -0,0 +1,111 @@
+use crate::engine::Vec2;
+use crate::lang;
+use crate::{Death, DeathCause, Game, PlayerTag, Position, TimeScale, PHYSICS_FPS};
+use specs::*;
+use std::collections::VecDeque;
+
+// How many ticks of the bird's path are kept for the end-of-run tip.
+const TRAIL_TICKS: usize = 90;
+
+// The bird's most recent positions and speeds, oldest first.
+#[derive(Default)]
+pub struct Trail {
+    samples: VecDeque<Position>,
+}
+
+impl Trail {
+    // The bird's positions, oldest first.
+    pub fn positions(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.samples.iter().map(|sample| sample.position)
+    }
+
+    // Ticks since each flap in the trail. A flap shows up as the vertical
+    // speed jumping upwards between two ticks, by over a pixel a tick.
+    fn flaps(&self) -> Vec<usize> {
+        let len = self.samples.len();
+        self.samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .enumerate()
+            .filter(|(_, (before, after))| after.speed.y < before.speed.y - PHYSICS_FPS as f32)
+            .map(|(n, _)| len - 2 - n)
+            .collect()
+    }
+}
+
+pub(crate) struct TrailSystem;
+
+impl<'a> System<'a> for TrailSystem {
+    type SystemData = (
+        Write<'a, Trail>,
+        Read<'a, TimeScale>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, PlayerTag>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut trail, time, pos, player) = data;
+        if time.is_paused() {
+            return;
+        }
+
+        // A flock flaps together, so the top bird's path stands for all.
+        if let Some((pos, _)) = (&pos, &player).join().next() {
+            trail.samples.push_back(pos.clone());
+            if trail.samples.len() > TRAIL_TICKS {
+                trail.samples.pop_front();
+            }
+        }
+    }
+}
+
+// `n` as "1st", "2nd" and so on, in the player's language.
+fn ordinal(n: u32) -> String {
+    let key = match (n % 10, n % 100) {
+        (_, 11..=13) => "ordinal",
+        (1, _) => "ordinal_1",
+        (2, _) => "ordinal_2",
+        (3, _) => "ordinal_3",
+        _ => "ordinal",
+    };
+    lang::fill(key, &[&n])
+}
+
+// A short tip on how to avoid the death that just ended the run, from
+// where the bird hit and how it flapped on the way there.
+pub fn tip(death: &Death, game: &Game, trail: &Trail) -> String {
+    let pipe = ordinal(game.pipes_passed + 1);
+    let flaps = trail.flaps();
+    let last_second = flaps.iter().filter(|&&t| t < PHYSICS_FPS as usize).count();
+    let falling = trail.samples.back().is_some_and(|pos| pos.speed.y > 0.0);
+
+    match death.cause {
+        DeathCause::BottomPipe if death.lip_dy > 0.0 && death.lip_dx < 0.0 => {
+            lang::fill("tip_too_low", &[&pipe])
+        }
+        DeathCause::BottomPipe if falling => lang::fill("tip_flapped_late", &[&pipe]),
+        DeathCause::BottomPipe => lang::fill("tip_weak_flap", &[&pipe]),
+        DeathCause::TopPipe if last_second > 1 => {
+            lang::fill("tip_flapped_often", &[&last_second, &pipe])
+        }
+        DeathCause::TopPipe => lang::fill("tip_flapped_early", &[&pipe]),
+        DeathCause::Cave => lang::tr("tip_cave").to_string(),
+        DeathCause::Hazard => lang::tr("tip_hazard").to_string(),
+        DeathCause::Floor => lang::fill("tip_floor", &[&pipe]),
+    }
+}
+
+// How this score compares with every recorded run, `scores` including it.
+pub fn rating(score: i32, scores: &[i32]) -> String {
+    if scores.len() < 2 {
+        return lang::tr("first_run").to_string();
+    }
+
+    let beaten = scores.iter().filter(|&&s| s < score).count();
+    let percent = 100 * beaten / (scores.len() - 1);
+    if scores.iter().all(|&s| s <= score) {
+        lang::tr("personal_best").to_string()
+    } else {
+        lang::fill("better_than", &[&percent])
+    }
+}
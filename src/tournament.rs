@@ -0,0 +1,412 @@
+use crate::calendar::days_from_civil;
+use crate::engine::{self, Color, DrawCmd, Vec2};
+use crate::lang;
+use crate::tas::{self, TasScript};
+use crate::{Direction, Game, GameState, TimeScale, FLOOR_Y};
+use serde::{Deserialize, Serialize};
+use specs::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: rusty_bird tournament FILE [RESULTS...]";
+
+// A seed-locked competition, set up by the host in a TOML file:
+//
+//     name = "Friday Cup"
+//     seed = 20261016
+//     attempts = 5
+//     opens = "2026-10-16 18:00"
+//     closes = "2026-10-16 22:00"
+//
+// Times are UTC, and either can be left out. Everyone plays the same pipes
+// with the default tuning, and every player's best run is kept with its
+// replay in `FILE.results.json` next to it, e.g. `cup.results.json`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Tournament {
+    pub name: String,
+    pub seed: u64,
+    pub attempts: u32,
+    #[serde(default)]
+    opens: Option<String>,
+    #[serde(default)]
+    closes: Option<String>,
+}
+
+impl Tournament {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let tournament: Tournament = toml::from_str(&contents)
+            .map_err(|e| format!("can't parse {}: {}", path.display(), e))?;
+        for time in tournament.opens.iter().chain(&tournament.closes) {
+            parse_utc(time).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        Ok(tournament)
+    }
+
+    // Why a new attempt can't start right now, if it can't.
+    fn check_open(&self) -> Result<(), String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        match (&self.opens, &self.closes) {
+            (Some(opens), _) if now < parse_utc(opens)? => {
+                Err(format!("{} opens at {} UTC", self.name, opens))
+            }
+            (_, Some(closes)) if now >= parse_utc(closes)? => {
+                Err(format!("{} closed at {} UTC", self.name, closes))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Seconds since the Unix epoch of a "YYYY-MM-DD HH:MM" UTC time.
+fn parse_utc(text: &str) -> Result<u64, String> {
+    let invalid = || {
+        format!(
+            "expected a time like \"2026-10-16 18:00\", got \"{}\"",
+            text
+        )
+    };
+    let numbers = |part: &str, separator| {
+        part.split(separator)
+            .map(|n| n.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())
+    };
+
+    let mut parts = text.split_whitespace();
+    let (date, time) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(date), Some(time), None) => (numbers(date, '-')?, numbers(time, ':')?),
+        _ => return Err(invalid()),
+    };
+    match (date.as_slice(), time.as_slice()) {
+        (&[year, month, day], &[hour, minute])
+            if year >= 1970
+                && (1..=12).contains(&month)
+                && (1..=31).contains(&day)
+                && (0..24).contains(&hour)
+                && (0..60).contains(&minute) =>
+        {
+            let days = days_from_civil(year, month, day);
+            Ok((days * 86_400 + hour * 3_600 + minute * 60) as u64)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+// A finished run, with the frames the bird flapped on so it can be
+// replayed like a `TasScript`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Run {
+    pub score: i32,
+    // The frame the bird died on.
+    pub frames: i32,
+    pub flaps: Vec<u64>,
+}
+
+impl Run {
+    // Whether replaying the flaps on `seed` ends the same way.
+    fn verify(&self, seed: u64) -> bool {
+        let script = TasScript::from_flaps(seed, self.flaps.clone());
+        let world = tas::play(script, None, self.frames);
+        let game = world.read_resource::<Game>();
+        let over = *world.read_resource::<GameState>() == GameState::GameOver;
+        over && game.score == self.score && game.frames == self.frames
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub player: String,
+    pub attempts: u32,
+    // None until the first attempt is finished.
+    pub best: Option<Run>,
+}
+
+impl Entry {
+    fn score(&self) -> i32 {
+        self.best.as_ref().map_or(0, |run| run.score)
+    }
+}
+
+// The results file: one entry per player, in the order they joined.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Results {
+    pub tournament: String,
+    pub seed: u64,
+    pub entries: Vec<Entry>,
+}
+
+impl Results {
+    pub fn path(tournament: &Path) -> PathBuf {
+        tournament.with_extension("results.json")
+    }
+
+    // A missing file means nobody has played yet.
+    fn load(path: &Path, tournament: &Tournament) -> Result<Self, String> {
+        let results: Results = match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| format!("can't parse {}: {}", path.display(), e))?,
+            Err(_) => Results {
+                tournament: tournament.name.clone(),
+                seed: tournament.seed,
+                entries: Vec::new(),
+            },
+        };
+        if results.seed != tournament.seed {
+            return Err(format!(
+                "{} is for seed {}, not {}",
+                path.display(),
+                results.seed,
+                tournament.seed
+            ));
+        }
+        Ok(results)
+    }
+
+    fn save(&self, path: &Path) {
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("can't save results to {}: {}", path.display(), e);
+        }
+    }
+
+    fn entry(&mut self, player: &str) -> &mut Entry {
+        let n = match self.entries.iter().position(|e| e.player == player) {
+            Some(n) => n,
+            None => {
+                self.entries.push(Entry {
+                    player: player.to_string(),
+                    attempts: 0,
+                    best: None,
+                });
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[n]
+    }
+
+    // Best score first, ties going to whoever needed fewer attempts.
+    fn standings(&self) -> Vec<&Entry> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by_key(|e| (-e.score(), e.attempts));
+        entries
+    }
+}
+
+// One player taking part in a tournament from this game. Attempts count as
+// soon as they start, so quitting a bad run doesn't give it back.
+pub struct TournamentPlay {
+    pub tournament: Tournament,
+    player: String,
+    results: Results,
+    path: PathBuf,
+    // Why the last attempt couldn't start, shown on the game over screen.
+    notice: Option<String>,
+    // Streamer mode leaves the player's name off the screen.
+    pub hide_name: bool,
+}
+
+impl TournamentPlay {
+    // Fails if the tournament can't be played right now, so the player
+    // finds out before the window opens.
+    pub fn open(tournament_path: &Path, player: String) -> Result<Self, String> {
+        let tournament = Tournament::load(tournament_path)?;
+        let path = Results::path(tournament_path);
+        let results = Results::load(&path, &tournament)?;
+        let play = TournamentPlay {
+            tournament,
+            player,
+            results,
+            path,
+            notice: None,
+            hide_name: false,
+        };
+        play.tournament.check_open()?;
+        if play.attempts_left() == 0 {
+            return Err(format!("{} has no attempts left", play.player));
+        }
+        Ok(play)
+    }
+
+    pub fn attempts_left(&self) -> u32 {
+        let used = self
+            .results
+            .entries
+            .iter()
+            .find(|e| e.player == self.player)
+            .map_or(0, |e| e.attempts);
+        self.tournament.attempts.saturating_sub(used)
+    }
+
+    // Uses up an attempt, or returns false if there's none to be had.
+    pub fn begin_attempt(&mut self) -> bool {
+        if let Err(e) = self.tournament.check_open() {
+            self.notice = Some(e);
+            return false;
+        }
+        if self.attempts_left() == 0 {
+            return false;
+        }
+        self.results.entry(&self.player).attempts += 1;
+        self.results.save(&self.path);
+        true
+    }
+
+    // Keeps `run` if it's the player's best so far.
+    pub fn finish(&mut self, run: Run) {
+        let entry = self.results.entry(&self.player);
+        if entry
+            .best
+            .as_ref()
+            .is_none_or(|best| run.score > best.score)
+        {
+            entry.best = Some(run);
+            self.results.save(&self.path);
+        }
+    }
+
+    pub fn draw<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>, state: GameState) {
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let used = self.tournament.attempts - self.attempts_left();
+        let mut title = self.tournament.name.clone();
+        if !self.hide_name {
+            title = format!("{}  {}", title, self.player);
+        }
+        cmds.push(DrawCmd::text(
+            lang::fill(
+                "attempt",
+                &[&title, &used.max(1), &self.tournament.attempts],
+            ),
+            Vec2::new(10.0, 10.0),
+            22.0,
+            white,
+        ));
+        if let (GameState::GameOver, Some(notice)) = (state, &self.notice) {
+            cmds.push(DrawCmd::centered_text(
+                notice.as_str(),
+                Vec2::new(engine::screen_width() / 2.0, FLOOR_Y + 5.0),
+                26.0,
+                white,
+            ));
+        }
+    }
+}
+
+// The flaps of the run in progress, for the tournament replay.
+#[derive(Default)]
+pub struct Replay {
+    pub flaps: Vec<u64>,
+}
+
+// Runs right before `MovementSystem`, catching the flaps it's about to do.
+pub struct ReplaySystem;
+
+impl<'a> System<'a> for ReplaySystem {
+    type SystemData = (
+        Option<Write<'a, Replay>>,
+        Read<'a, Direction>,
+        Read<'a, Game>,
+        Read<'a, TimeScale>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (replay, dir, game, time) = data;
+        if let Some(mut replay) = replay {
+            if dir.jump && dir.release && !time.is_paused() {
+                replay.flaps.push(game.frames as u64);
+            }
+        }
+    }
+}
+
+// `rusty_bird tournament FILE [RESULTS...]`: merges results files sent in
+// by players into the tournament's own and prints the standings. Every
+// run is replayed first, and runs that don't replay to their score are
+// left out.
+pub fn run(args: impl Iterator<Item = String>) {
+    let mut args = args.map(PathBuf::from);
+    let tournament_path = args.next().unwrap_or_else(|| {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    });
+    let tournament = Tournament::load(&tournament_path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+    let path = Results::path(&tournament_path);
+    let load = |path: &Path| {
+        Results::load(path, &tournament).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        })
+    };
+
+    let mut results = load(&path);
+    let mut merged = false;
+    for other in args {
+        for entry in load(&other).entries {
+            if !entry
+                .best
+                .as_ref()
+                .is_none_or(|run| run.verify(tournament.seed))
+            {
+                println!(
+                    "{}: the replay of {} doesn't match, skipping it",
+                    other.display(),
+                    entry.player
+                );
+                continue;
+            }
+            let ours = results.entry(&entry.player);
+            ours.attempts = ours.attempts.max(entry.attempts);
+            if entry.score() > ours.score() || ours.best.is_none() {
+                ours.best = entry.best;
+            }
+            merged = true;
+        }
+    }
+    if merged {
+        results.save(&path);
+    }
+
+    println!("{} (seed {})", tournament.name, tournament.seed);
+    for (rank, entry) in results.standings().iter().enumerate() {
+        let verified = entry
+            .best
+            .as_ref()
+            .is_none_or(|run| run.verify(tournament.seed));
+        println!(
+            "{:>3}. {:<20} {:>5}  {}/{} attempts{}",
+            rank + 1,
+            entry.player,
+            entry.score(),
+            entry.attempts,
+            tournament.attempts,
+            if verified {
+                ""
+            } else {
+                "  replay doesn't match"
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_utc_times() {
+        assert_eq!(parse_utc("1970-01-01 00:00"), Ok(0));
+        assert_eq!(parse_utc("2000-03-01 12:30"), Ok(951_913_800));
+        assert!(parse_utc("2026-13-01 00:00").is_err());
+        assert!(parse_utc("2026-10-16").is_err());
+    }
+}
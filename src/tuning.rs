@@ -0,0 +1,273 @@
+use crate::lang;
+use crate::PipeColor;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "hot-reload")]
+use std::{path::PathBuf, time::SystemTime};
+
+// Gameplay constants that balance work wants to change without a rebuild.
+// Any field missing from a tuning file keeps its default value.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Tuning {
+    // In pixels a second, and a second squared for gravity.
+    pub gravity: f32,
+    // Upward speed a flap adds, up to `max_rise_speed`.
+    pub flap_impulse: f32,
+    pub max_rise_speed: f32,
+    pub max_fall_speed: f32,
+    // Gravity is multiplied by this while the bird rises with the flap
+    // still held, so below 1.0 holding it flies a little higher. 1.0 turns
+    // holding off.
+    pub hold_gravity: f32,
+    pub scroll_speed: f32,
+    // How the game gets harder as the score rises, see `Difficulty`.
+    // Multiplier on `scroll_speed`.
+    pub speed_curve: Curve,
+    // Horizontal distance between two pipe slots.
+    pub spacing_curve: Curve,
+    // Rhythms the distance between slots follows, see `SpacingPattern`.
+    pub spacing_patterns: Vec<SpacingPattern>,
+    // Each distance is made up to this fraction longer or shorter, at
+    // random.
+    pub spacing_jitter: f32,
+    // Height of the opening between a pipe pair.
+    pub gap_curve: Curve,
+    // What of the bird counts when it touches a pipe, see `Hitbox`.
+    pub hitbox: HitboxShape,
+    pub moving_pipes: MovingPipes,
+}
+
+// Pipe pairs spawned once the score reaches `from_score` bob up and down,
+// `amplitude` pixels either way, once every `period` seconds. Any field
+// missing from a tuning file keeps its default.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MovingPipes {
+    pub from_score: i32,
+    pub amplitude: f32,
+    pub period: f32,
+}
+
+impl Default for MovingPipes {
+    fn default() -> Self {
+        MovingPipes {
+            from_score: 40,
+            amplitude: 40.0,
+            period: 3.0,
+        }
+    }
+}
+
+// The whole sprite box, transparent corners included, or a rounder shape
+// hugging the body.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HitboxShape {
+    Box,
+    Circle,
+    Capsule,
+}
+
+// A value ramping from `start` to `end` over the first `ramp` points of a
+// run, then staying at `end`. `shape` bends the ramp: 1.0 is linear, above
+// that it starts gently and steepens, below it starts steep. Curves in a
+// tuning file are given whole, except `shape` which defaults to linear.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Curve {
+    pub start: f32,
+    pub end: f32,
+    pub ramp: f32,
+    #[serde(default = "linear")]
+    pub shape: f32,
+}
+
+fn linear() -> f32 {
+    1.0
+}
+
+// A run of slots whose distances are `gaps` times the spacing curve's, one
+// after the other. Once a pattern is through, the next one is picked at
+// random, each `weight` times as likely as a pattern of weight 1.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct SpacingPattern {
+    pub weight: u32,
+    pub gaps: Vec<f32>,
+}
+
+impl Curve {
+    pub fn at(&self, score: i32) -> f32 {
+        let t = if self.ramp > 0.0 {
+            (score as f32 / self.ramp).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        self.start + (self.end - self.start) * t.powf(self.shape)
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning {
+            gravity: 1080.0,
+            flap_impulse: 600.0,
+            max_rise_speed: 1200.0,
+            max_fall_speed: 360.0,
+            hold_gravity: 1.0,
+            scroll_speed: 240.0,
+            speed_curve: Curve {
+                start: 1.0,
+                end: 1.3,
+                ramp: 80.0,
+                shape: 1.0,
+            },
+            spacing_curve: Curve {
+                start: 340.0,
+                end: 300.0,
+                ramp: 80.0,
+                shape: 1.0,
+            },
+            spacing_patterns: vec![
+                // Steady.
+                SpacingPattern {
+                    weight: 6,
+                    gaps: vec![1.0],
+                },
+                // A tight cluster.
+                SpacingPattern {
+                    weight: 2,
+                    gaps: vec![0.75, 0.75, 0.75],
+                },
+                // A long breather.
+                SpacingPattern {
+                    weight: 1,
+                    gaps: vec![1.6],
+                },
+                // Short, long, short, long.
+                SpacingPattern {
+                    weight: 2,
+                    gaps: vec![0.8, 1.25, 0.8, 1.25],
+                },
+            ],
+            spacing_jitter: 0.1,
+            // A full flap needs about 240 pixels of gap above the bird's
+            // feet, so narrower gaps ask for shorter flaps out of a fall.
+            gap_curve: Curve {
+                start: 240.0,
+                end: 210.0,
+                ramp: 80.0,
+                shape: 1.0,
+            },
+            hitbox: HitboxShape::Box,
+            moving_pipes: MovingPipes::default(),
+        }
+    }
+}
+
+// The `--difficulty` presets, each a change to whichever tuning is loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Preset {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "easy" => Ok(Preset::Easy),
+            "normal" => Ok(Preset::Normal),
+            "hard" => Ok(Preset::Hard),
+            _ => Err(format!(
+                "unknown difficulty {}, try easy, normal or hard",
+                name
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Easy => "easy",
+            Preset::Normal => "normal",
+            Preset::Hard => "hard",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::Easy => lang::tr("difficulty_easy"),
+            Preset::Normal => lang::tr("difficulty_normal"),
+            Preset::Hard => lang::tr("difficulty_hard"),
+        }
+    }
+
+    // Easy opens the gaps and slows the scrolling down, hard narrows them
+    // and speeds it up.
+    pub fn apply(self, tuning: &mut Tuning) {
+        let (gap, speed) = match self {
+            Preset::Easy => (40.0, 0.85),
+            Preset::Normal => return,
+            Preset::Hard => (-20.0, 1.15),
+        };
+        tuning.gap_curve.start += gap;
+        tuning.gap_curve.end += gap;
+        tuning.scroll_speed *= speed;
+    }
+
+    pub fn pipe_color(self) -> PipeColor {
+        match self {
+            Preset::Easy => PipeColor::Ice,
+            Preset::Normal => PipeColor::Green,
+            Preset::Hard => PipeColor::Red,
+        }
+    }
+}
+
+impl Tuning {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        toml::from_str(&contents).map_err(|e| format!("can't parse {}: {}", path.display(), e))
+    }
+}
+
+// Reloads a tuning file while the game runs whenever it's saved, so balance
+// changes can be tried without restarting.
+#[cfg(feature = "hot-reload")]
+pub struct TuningWatch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+#[cfg(feature = "hot-reload")]
+impl TuningWatch {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+        TuningWatch { path, modified }
+    }
+
+    // The new tuning if the file changed since the last poll. Files that
+    // fail to parse are reported and skipped until they're saved again.
+    pub fn poll(&mut self) -> Option<Tuning> {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        match Tuning::from_file(&self.path) {
+            Ok(tuning) => Some(tuning),
+            Err(e) => {
+                println!("keeping the current tuning: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "hot-reload")]
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
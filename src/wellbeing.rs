@@ -0,0 +1,121 @@
+use crate::engine::{self, Color, DrawCmd, Vec2};
+use crate::lang;
+use crate::{GameState, PHYSICS_FPS};
+use serde::Deserialize;
+
+const TICKS_PER_MINUTE: u32 = 60 * PHYSICS_FPS;
+// Time away from the runs, in the menu or paused, that counts as a break
+// and starts the count over.
+const BREAK_TICKS: u32 = 5 * TICKS_PER_MINUTE;
+
+// The break reminder, off unless turned on in the `[wellbeing]` section of
+// `config.toml`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct WellbeingConfig {
+    pub break_reminder: bool,
+    // Minutes of play before a break is suggested.
+    pub play_minutes: f32,
+}
+
+impl Default for WellbeingConfig {
+    fn default() -> Self {
+        WellbeingConfig {
+            break_reminder: false,
+            play_minutes: 45.0,
+        }
+    }
+}
+
+// How long the player has been at it without a break, in ticks. Present
+// in the world when the break reminder is on. Only runs count; a run is
+// never interrupted, the reminder waits for the game over screen.
+pub struct SessionTime {
+    played: u32,
+    idle: u32,
+    limit: u32,
+}
+
+impl SessionTime {
+    pub fn new(config: WellbeingConfig) -> Self {
+        SessionTime {
+            played: 0,
+            idle: 0,
+            limit: (config.play_minutes.max(1.0) * TICKS_PER_MINUTE as f32) as u32,
+        }
+    }
+
+    // Call every tick, `playing` when a run is going and not paused.
+    pub fn tick(&mut self, playing: bool) {
+        if playing {
+            self.played += 1;
+            self.idle = 0;
+        } else {
+            self.idle += 1;
+            if self.idle >= BREAK_TICKS {
+                self.played = 0;
+            }
+        }
+    }
+
+    // Whether to suggest a break now, on `state`'s screen.
+    pub fn reminding(&self, state: GameState) -> bool {
+        state == GameState::GameOver && self.played >= self.limit
+    }
+
+    // The player saw the suggestion; count a fresh stretch from here.
+    pub fn dismiss(&mut self) {
+        self.played = 0;
+    }
+
+    pub fn draw(&self, cmds: &mut Vec<DrawCmd>) {
+        let center = Vec2::new(engine::screen_width() / 2.0, engine::SCREEN_HEIGHT / 2.0);
+        cmds.push(DrawCmd::Rect {
+            rect: engine::panel(center, 520.0, 160.0),
+            color: Color::new(0.1, 0.15, 0.25, 0.92),
+        });
+        let minutes = self.played / TICKS_PER_MINUTE;
+        let lines = [
+            (lang::tr("break_title").to_string(), 32.0),
+            (lang::fill("break_played", &[&minutes]), 20.0),
+            (lang::tr("break_advice").to_string(), 20.0),
+            (lang::tr("break_carry_on").to_string(), 16.0),
+        ];
+        for (n, (line, size)) in lines.iter().enumerate() {
+            cmds.push(DrawCmd::centered_text(
+                line.clone(),
+                Vec2::new(center.x, center.y - 50.0 + 34.0 * n as f32),
+                *size,
+                Color::new(0.9, 0.95, 1.0, 1.0),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reminds_after_play_and_forgets_after_a_break() {
+        let mut session = SessionTime::new(WellbeingConfig {
+            break_reminder: true,
+            play_minutes: 1.0,
+        });
+        for _ in 0..TICKS_PER_MINUTE {
+            session.tick(true);
+        }
+        assert!(!session.reminding(GameState::Playing));
+        assert!(session.reminding(GameState::GameOver));
+        session.dismiss();
+        assert!(!session.reminding(GameState::GameOver));
+
+        for _ in 0..TICKS_PER_MINUTE {
+            session.tick(true);
+        }
+        for _ in 0..BREAK_TICKS {
+            session.tick(false);
+        }
+        assert!(!session.reminding(GameState::GameOver));
+    }
+}
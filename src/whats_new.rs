@@ -0,0 +1,161 @@
+use crate::engine::{self, Color, DrawCmd, Rect, Vec2};
+use crate::lang;
+use crate::stats::Stats;
+use serde::Deserialize;
+
+// The bundled changelog, see `changelog.toml`.
+const CHANGELOG: &str = include_str!("../changelog.toml");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+// Changes listed at most, the newest first.
+const MAX_LINES: usize = 14;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Release {
+    pub version: String,
+    pub changes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Changelog {
+    release: Vec<Release>,
+}
+
+fn parse(changelog: &str) -> Vec<Release> {
+    toml::from_str::<Changelog>(changelog)
+        .map(|changelog| changelog.release)
+        .unwrap_or_else(|e| {
+            println!("can't read the changelog: {}", e);
+            Vec::new()
+        })
+}
+
+// "1.10.2" as [1, 10, 2], so versions compare by number.
+fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|part| part.trim().parse().unwrap_or(0))
+        .collect()
+}
+
+// The releases after `last_seen`, newest first; all of them if nothing was
+// seen yet.
+fn since(releases: Vec<Release>, last_seen: Option<&str>) -> Vec<Release> {
+    let last_seen = last_seen.map(version_key);
+    releases
+        .into_iter()
+        .filter(|release| last_seen.as_ref() < Some(&version_key(&release.version)))
+        .collect()
+}
+
+// The "What's new" panel on the title screen: shown by itself on the first
+// launch after an update, and with `N` any time.
+#[derive(Default)]
+pub struct WhatsNew {
+    pub open: bool,
+    releases: Vec<Release>,
+}
+
+impl WhatsNew {
+    // Opens the panel if the game was updated since the last launch, then
+    // remembers this version in `stats`. A first launch ever has nothing
+    // to catch up on.
+    pub fn on_launch(stats: &mut Stats) -> Self {
+        if stats.last_seen_version.as_deref() == Some(VERSION) {
+            return WhatsNew::default();
+        }
+        let fresh = stats.last_seen_version.is_none() && stats.scores.is_empty();
+        let releases = since(parse(CHANGELOG), stats.last_seen_version.as_deref());
+        stats.last_seen_version = Some(VERSION.to_string());
+        stats.save();
+        WhatsNew {
+            open: !fresh && !releases.is_empty(),
+            releases,
+        }
+    }
+
+    // Opens the panel with the whole changelog.
+    pub fn show(&mut self) {
+        self.releases = parse(CHANGELOG);
+        self.open = true;
+    }
+
+    pub fn draw<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>) {
+        let center_x = engine::screen_width() / 2.0;
+        cmds.push(DrawCmd::Rect {
+            rect: Rect::new(0.0, 0.0, engine::screen_width(), engine::SCREEN_HEIGHT),
+            color: Color::new(0.0, 0.0, 0.0, 0.75),
+        });
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("whats_new"),
+            Vec2::new(center_x, 40.0),
+            50.0,
+            Color::new(1.0, 0.9, 0.2, 1.0),
+        ));
+
+        let left = if engine::narrow() { 20.0 } else { 60.0 };
+        let mut y = 100.0;
+        let mut lines = 0;
+        for release in &self.releases {
+            if lines >= MAX_LINES {
+                break;
+            }
+            cmds.push(DrawCmd::text(
+                lang::fill("version", &[&release.version]),
+                Vec2::new(left, y),
+                24.0,
+                Color::new(1.0, 0.9, 0.2, 1.0),
+            ));
+            y += 34.0;
+            for change in release.changes.iter().take(MAX_LINES - lines) {
+                cmds.push(DrawCmd::text(
+                    format!("- {}", change),
+                    Vec2::new(left + 20.0, y),
+                    18.0,
+                    Color::new(1.0, 1.0, 1.0, 1.0),
+                ));
+                y += 28.0;
+                lines += 1;
+            }
+            y += 10.0;
+        }
+
+        cmds.push(DrawCmd::centered_text(
+            lang::tr("whats_new_hint"),
+            Vec2::new(center_x, 560.0),
+            18.0,
+            Color::new(0.8, 0.8, 0.8, 1.0),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_releases_not_seen_yet() {
+        let releases = parse(
+            r#"
+            [[release]]
+            version = "0.10.0"
+            changes = ["Tens"]
+
+            [[release]]
+            version = "0.9.1"
+            changes = ["Nines"]
+            "#,
+        );
+        let versions = |seen| {
+            since(releases.clone(), seen)
+                .into_iter()
+                .map(|release| release.version)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(versions(Some("0.9.1")), vec!["0.10.0"]);
+        assert_eq!(versions(Some("0.10.0")), Vec::<String>::new());
+        assert_eq!(versions(None).len(), 2);
+
+        // The bundled changelog has this version at the top.
+        assert_eq!(parse(CHANGELOG)[0].version, VERSION);
+    }
+}
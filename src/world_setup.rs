@@ -0,0 +1,299 @@
+// Building worlds: registering the components, inserting fresh resources and
+// spawning the entities a run starts with.
+
+use crate::assets::Assets;
+use crate::caves::Caves;
+use crate::engine::{self, Vec2};
+use crate::hazards::Hazards;
+use crate::parallax::{self, Parallax, ParallaxLayer};
+use crate::pipes::PipeSprites;
+use crate::spacing::Spacing;
+use crate::telemetry::Telemetry;
+use crate::theme::{self, PipePaint, ThemeArt};
+use crate::tips::Trail;
+use crate::tuning::{Preset, Tuning};
+use crate::{
+    Backdrop, BackgroundTag, Collectible, CollisionBox, Debris, Difficulty, Direction,
+    FloatingText, Game, GameEvents, GameMode, GameOverReader, GameState, GetReady, GhostBird,
+    Hitbox, Image, ObstacleTag, Particle, PipeColor, PipePool, PlayerId, PlayerTag, Position,
+    ScrollSpeed, SpriteSheet, TimeScale, VerticalOscillator, ZOrder, RACERS,
+};
+use specs::*;
+
+// Where the bird starts, and where it stays outside drift mode.
+pub const BIRD_HOME_X: f32 = 100.0;
+pub const BIRD_START_Y: f32 = 200.0;
+
+// Flock mode flies this many birds, stacked this far apart around the
+// usual start, with one flap moving them all.
+pub const FLOCK_SIZE: usize = 3;
+pub const FLOCK_SPACING: f32 = 60.0;
+
+// The floor runs along the bottom of the screen.
+pub const FLOOR_HEIGHT: f32 = 80.0;
+pub const FLOOR_Y: f32 = engine::SCREEN_HEIGHT - FLOOR_HEIGHT;
+// The lowest the bird goes, its feet on the floor, which ends the run.
+pub const BIRD_FLOOR_Y: f32 = 460.0;
+
+// Everything that needs a ggez context to load. The world can be built
+// without it, in which case entities get no sprites.
+pub struct Sprites {
+    // Every theme's background layers, see `theme::load_backgrounds`.
+    pub backgrounds: Vec<Vec<Image>>,
+    // The scenery's layers with their images, see `parallax`.
+    pub layers: Vec<(ParallaxLayer, Image)>,
+    pub pipes: PipeSprites,
+    pub bird: SpriteSheet,
+}
+
+impl Sprites {
+    // The images `load` needs beyond the manifest's, by path.
+    pub fn extra_images(parallax: &Parallax) -> Vec<&str> {
+        let mut images: Vec<&str> = parallax.images().collect();
+        for path in theme::background_images() {
+            images.push(path);
+        }
+        images
+    }
+
+    pub fn load(assets: &Assets, parallax: &Parallax) -> Self {
+        Sprites {
+            backgrounds: theme::load_backgrounds(assets),
+            layers: parallax.load(assets),
+            pipes: PipeSprites::new(assets),
+            bird: assets.animation("bird").clone(),
+        }
+    }
+}
+
+// Where bird `n` of `birds` starts, the middle one at the usual start.
+pub fn bird_start(n: usize, birds: usize) -> Vec2 {
+    let row = n as f32 - (birds - 1) as f32 / 2.0;
+    Vec2::new(BIRD_HOME_X, BIRD_START_Y + row * FLOCK_SPACING)
+}
+
+// Copies the bird `build_world` spawned until there are `birds` of them,
+// then stacks them at their starts.
+pub fn spawn_flock(world: &mut World, birds: usize) {
+    let (coll_box, hitbox, sheet) = {
+        let boxes = world.read_storage::<CollisionBox>();
+        let hitboxes = world.read_storage::<Hitbox>();
+        let sheets = world.read_storage::<SpriteSheet>();
+        let players = world.read_storage::<PlayerTag>();
+        let entities = world.entities();
+        let (bird, coll_box, _) = (&entities, &boxes, &players).join().next().unwrap();
+        (
+            *coll_box,
+            hitboxes.get(bird).copied(),
+            sheets.get(bird).cloned(),
+        )
+    };
+    for _ in 1..birds {
+        let mut builder = world
+            .create_entity()
+            .with(Position {
+                position: coll_box.origin,
+                speed: Vec2::ZERO,
+            })
+            .with(PlayerTag)
+            .with(coll_box);
+        if let Some(hitbox) = hitbox {
+            builder = builder.with(hitbox);
+        }
+        if let Some(sheet) = sheet.clone() {
+            builder = builder.with(sheet);
+        }
+        builder.build();
+    }
+    place_birds(world);
+}
+
+// Two birds for race mode, each with its player's id.
+pub fn spawn_race(world: &mut World) {
+    spawn_flock(world, RACERS);
+    let entities = world.entities();
+    let players = world.read_storage::<PlayerTag>();
+    let mut ids = world.write_storage::<PlayerId>();
+    for (n, (bird, _)) in (&entities, &players).join().enumerate() {
+        ids.insert(bird, PlayerId(n)).unwrap();
+    }
+}
+
+// Puts every bird back at its start, at rest.
+pub fn place_birds(world: &mut World) {
+    let mut positions = world.write_storage::<Position>();
+    let mut boxes = world.write_storage::<CollisionBox>();
+    let players = world.read_storage::<PlayerTag>();
+    let birds = players.join().count();
+    for (n, (pos, coll_box, _)) in (&mut positions, &mut boxes, &players).join().enumerate() {
+        pos.position = bird_start(n, birds);
+        pos.speed = Vec2::ZERO;
+        coll_box.origin = pos.position;
+    }
+}
+
+// A world with every component registered and fresh resources, but no
+// entities yet.
+pub fn empty_world(tuning: Tuning) -> World {
+    let mut world = World::new();
+    world.register::<Position>();
+    world.register::<Image>();
+    world.register::<SpriteSheet>();
+    world.register::<BackgroundTag>();
+    world.register::<Backdrop>();
+    world.register::<ZOrder>();
+    world.register::<ObstacleTag>();
+    world.register::<PlayerTag>();
+    world.register::<PlayerId>();
+    world.register::<GhostBird>();
+    world.register::<Debris>();
+    world.register::<Particle>();
+    world.register::<FloatingText>();
+    world.register::<CollisionBox>();
+    world.register::<Hitbox>();
+    world.register::<VerticalOscillator>();
+    world.register::<Collectible>();
+
+    world.insert(Direction::new());
+    world.insert(Game::new());
+    world.insert(Difficulty::at(&tuning, 0));
+    world.insert(GameState::Playing);
+    world.insert(GameMode::default());
+    world.insert(GetReady::default());
+    world.insert(tuning);
+    world.insert(TimeScale::default());
+    world.insert(ScrollSpeed::default());
+    world.insert(Caves::default());
+    world.insert(Spacing::default());
+    world.insert(PipePool::default());
+    world.insert(Hazards::default());
+    world.insert(Telemetry::default());
+    world.insert(Trail::default());
+    let mut events = GameEvents::new();
+    world.insert(GameOverReader(events.register_reader()));
+    world.insert(events);
+
+    world
+}
+
+fn pipe_paint(world: &World) -> (PipePaint, Option<Preset>) {
+    let paint = world.try_fetch::<PipePaint>().map(|paint| *paint);
+    let preset = world.try_fetch::<Preset>().map(|preset| *preset);
+    (paint.unwrap_or_default(), preset)
+}
+
+// The first three pipe pairs of a run, painted for the `Preset` if any or
+// as the `PipePaint` says.
+pub fn spawn_obstacles(world: &mut World, tuning: &Tuning) {
+    let (paint, preset) = pipe_paint(world);
+    let colors: Vec<PipeColor> = (0..3).map(|_| paint.pick(preset)).collect();
+    for top in [false, true].iter() {
+        for (n, &color) in colors.iter().enumerate() {
+            let pos_x = (340.0 * n as f32) + 900.0;
+            let pos_y = if *top { -120.0 } else { 360.0 };
+            world
+                .create_entity()
+                .with(Position {
+                    position: Vec2::new(pos_x, pos_y),
+                    speed: Vec2::new(0.0, 0.0),
+                })
+                .with(BackgroundTag {
+                    velocity: tuning.scroll_speed,
+                    width: 64.0,
+                    num_copies: 1,
+                })
+                .with(ObstacleTag {
+                    top: *top,
+                    passed: false,
+                    scored: false,
+                    cave: false,
+                    hazard: None,
+                    color,
+                })
+                .with(CollisionBox {
+                    origin: Vec2::new(pos_x, pos_y),
+                    height: 240.0,
+                    width: 64.0,
+                })
+                .build();
+        }
+    }
+}
+
+// Paints the pipes that are already out again, both of a pair alike, e.g.
+// once the preset is known.
+pub fn repaint_obstacles(world: &mut World) {
+    let (paint, preset) = pipe_paint(world);
+    let positions = world.read_storage::<Position>();
+    let mut obstacles = world.write_storage::<ObstacleTag>();
+    let mut pairs: Vec<(f32, PipeColor)> = Vec::new();
+    for (pos, obstacle) in (&positions, &mut obstacles).join() {
+        if obstacle.cave || obstacle.hazard.is_some() {
+            continue;
+        }
+        let x = pos.position.x;
+        obstacle.color = match pairs.iter().find(|&&(at, _)| at == x) {
+            Some(&(_, color)) => color,
+            None => {
+                let color = paint.pick(preset);
+                pairs.push((x, color));
+                color
+            }
+        };
+    }
+}
+
+// Puts freshly loaded scenery art in, e.g. after its files changed. The
+// scenery starts over from the left edge.
+pub fn replace_scenery(world: &mut World, sprites: &Sprites) {
+    {
+        let entities = world.entities();
+        let scenery = world.read_storage::<BackgroundTag>();
+        let images = world.read_storage::<Image>();
+        for (entity, _, _) in (&entities, &scenery, &images).join() {
+            let _ = entities.delete(entity);
+        }
+    }
+    world.maintain();
+    parallax::spawn_layers(world, &sprites.layers);
+    world.insert(ThemeArt(sprites.backgrounds.clone()));
+}
+
+pub fn build_world(tuning: Tuning, sprites: Option<Sprites>) -> World {
+    let mut world = empty_world(tuning.clone());
+
+    if let Some(sprites) = &sprites {
+        parallax::spawn_layers(&mut world, &sprites.layers);
+    }
+
+    spawn_obstacles(&mut world, &tuning);
+
+    // The bird
+    let bird_height = 72.0;
+    let bird_width = 58.0;
+    let mut builder = world
+        .create_entity()
+        .with(Position {
+            position: Vec2::new(BIRD_HOME_X, BIRD_START_Y),
+            speed: Vec2::new(0.0, 0.0),
+        })
+        .with(PlayerTag)
+        .with(CollisionBox {
+            origin: Vec2::new(BIRD_HOME_X, BIRD_START_Y),
+            height: bird_height,
+            width: bird_width,
+        });
+    if let Some(hitbox) = Hitbox::bird(tuning.hitbox) {
+        builder = builder.with(hitbox);
+    }
+    if let Some(sprites) = sprites {
+        builder = builder.with(sprites.bird);
+        builder.build();
+        world.insert(sprites.pipes);
+        world.insert(ThemeArt(sprites.backgrounds));
+    } else {
+        builder.build();
+    }
+
+    world
+}